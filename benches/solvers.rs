@@ -0,0 +1,59 @@
+//! Every solver on a fixed set of puzzles, run with `cargo bench --features bench`
+//!
+//! The solvers that can't solve a puzzle, because it needs guessing, are left out of its group,
+//! and so are `naive-dfs` and `dfs`, which take minutes on the hard ones.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use nsudoku_solver::{
+    solve::{Registry, SearchOptions},
+    Sudoku,
+};
+
+/// The puzzles of the benchmarks, by name
+const PUZZLES: [(&str, &str); 4] = [
+    (
+        "easy",
+        "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79",
+    ),
+    (
+        "hard",
+        "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..",
+    ),
+    (
+        "17-clue",
+        "......2.1..4.3.............37.....8.6..2........5.....54....6......7..4...2..1...",
+    ),
+    (
+        "16x16",
+        "8....4..B....6.5..2..D.B.A..8.C....1.8..E.29.7.B..7F6.153..........D3.A.9...7.4F.C.A....\
+         F...65..7.B...D..G..2E.92.E.B.4....D....3A..9..8..F.5.7..8.G....D5..3C.A5D1......E....2..\
+         ...1.7...C6..G....3.....1D...5.....8.3G...E..B7.2.....76CA.9.3G1.D....6G...F4..",
+    ),
+];
+
+/// The solvers too slow to benchmark on every puzzle
+const SLOW: [&str; 2] = ["naive-dfs", "dfs"];
+
+fn solvers(c: &mut Criterion) {
+    let registry = Registry::with_builtins(SearchOptions::default());
+    for (name, line) in PUZZLES {
+        let puzzle: Sudoku = line.parse().expect("valid puzzle");
+        let mut group = c.benchmark_group(name);
+        group.throughput(Throughput::Elements(1));
+        for solver in registry.names().filter(|solver| !SLOW.contains(solver)) {
+            let solver = registry.get(solver).expect("registered solver");
+            if solver.solve(&puzzle).0.is_err() {
+                continue;
+            }
+            group.bench_with_input(
+                BenchmarkId::from_parameter(solver.name()),
+                &puzzle,
+                |bench, puzzle| bench.iter(|| solver.solve(puzzle)),
+            );
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, solvers);
+criterion_main!(benches);
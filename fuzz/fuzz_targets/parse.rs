@@ -0,0 +1,38 @@
+//! Parse any text in every puzzle format, the single line of a parsed puzzle must parse back to it
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nsudoku_solver::{formats::Format, Sudoku};
+
+/// The largest order whose values all have a character of their own in the single line
+const MAX_LINE_ORDER: usize = 61;
+
+fuzz_target!(|text: &str| {
+    let formats = [
+        Format::Line,
+        Format::Strict,
+        Format::Sdm,
+        Format::Sdk,
+        Format::Ss,
+        Format::Pretty,
+    ];
+    let mut puzzles: Vec<Sudoku> = formats
+        .into_iter()
+        .chain([Format::detect(None, text)])
+        .flat_map(|format| format.parse(text))
+        .filter_map(|(_, puzzle)| puzzle.ok())
+        .collect();
+    puzzles.extend(text.parse::<Sudoku>());
+    puzzles.extend(Sudoku::parse_lenient(text));
+
+    for puzzle in puzzles {
+        let _ = puzzle.to_string();
+        if puzzle.order() > MAX_LINE_ORDER {
+            continue;
+        }
+        let line = puzzle.to_line();
+        let parsed: Sudoku = line.parse().expect("the line of a puzzle parses");
+        assert_eq!(parsed.to_line(), line);
+    }
+});
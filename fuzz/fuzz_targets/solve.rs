@@ -0,0 +1,46 @@
+//! Solve random puzzles with every solver, which must agree and only answer with solutions
+//!
+//! The searches give up after [`NODES`] nodes, so a hard puzzle doesn't stall the fuzzer, and
+//! `naive-dfs` is left out, it validates the whole grid for every value it tries.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nsudoku_solver::{
+    solve::{Registry, SearchOptions, SolveConfig, SolveError},
+    Sudoku,
+};
+
+/// The nodes a search expands before giving up
+const NODES: u64 = 1_000;
+
+fuzz_target!(|puzzle: Sudoku| {
+    let registry = Registry::with_builtins(SearchOptions::default());
+    let config = SolveConfig {
+        max_nodes: Some(NODES),
+        ..Default::default()
+    };
+
+    let (mut solved, mut unsolvable) = (None, None);
+    for name in registry.names().filter(|&name| name != "naive-dfs") {
+        let solver = registry.get(name).expect("registered solver");
+        match solver.solve_with(&puzzle, &config).0 {
+            Ok(solution) => {
+                assert!(solution.solves(&puzzle), "{name} answered {}", solution.to_line());
+                // A solution is its own only solution
+                let resolved = solver.solve_with(&solution, &config).0;
+                assert_eq!(
+                    resolved.map(|solution| solution.to_line()).ok(),
+                    Some(solution.to_line())
+                );
+                solved = Some(name);
+            }
+            Err(SolveError::Aborted { .. }) => {}
+            Err(SolveError::Unsolved(_)) if !solver.guesses() => {}
+            Err(_) => unsolvable = Some(name),
+        }
+    }
+    if let (Some(solved), Some(unsolvable)) = (solved, unsolvable) {
+        panic!("{solved} solved {} but {unsolvable} found no solution", puzzle.to_line());
+    }
+});
@@ -0,0 +1,68 @@
+/* The C bindings of nsudoku-solver, generated by cbindgen from src/ffi.rs */
+
+#ifndef NSUDOKU_SOLVER_H
+#define NSUDOKU_SOLVER_H
+
+#include <stddef.h>
+
+// The outcome of a call
+typedef enum NsudokuStatus {
+  // The answer was written to the buffer
+  NSUDOKU_STATUS_OK = 0,
+  // A pointer is null or a string isn't valid UTF-8
+  NSUDOKU_STATUS_INVALID_ARGUMENT = -1,
+  // The puzzle is malformed, its givens break the rules or it is too large to solve
+  NSUDOKU_STATUS_INVALID_PUZZLE = -2,
+  // The puzzle has no solution
+  NSUDOKU_STATUS_UNSOLVABLE = -3,
+  // The answer doesn't fit in the buffer, which holds as much of it as fits
+  NSUDOKU_STATUS_BUFFER_TOO_SMALL = -4,
+  // No hint or puzzle was found: the puzzle needs guessing, or no puzzle of the difficulty
+  NSUDOKU_STATUS_NOT_FOUND = -5,
+} NsudokuStatus;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// A description of `status`, as a static NUL-terminated string
+const char *nsudoku_status_message(enum NsudokuStatus status);
+
+// Solve `puzzle`, writing its solution as a single line to `out`
+//
+// # Safety
+//
+// `puzzle` must be a NUL-terminated string and `out` must be valid for writes of `len` bytes.
+enum NsudokuStatus nsudoku_solve(const char *puzzle, char *out, size_t len);
+
+// Rate `puzzle`, storing its score in `score` and writing its difficulty, like `hard`, to `out`
+//
+// # Safety
+//
+// `puzzle` must be a NUL-terminated string, `score` must be valid for writes or null and `out`
+// must be valid for writes of `len` bytes.
+enum NsudokuStatus nsudoku_rate(const char *puzzle, float *score, char *out, size_t len);
+
+// Describe the next logical step towards the solution of `puzzle` in `out`, like
+// `r1c2=2 hidden single in row 1`
+//
+// Answers `NotFound` when the puzzle is solved or needs guessing.
+//
+// # Safety
+//
+// `puzzle` must be a NUL-terminated string and `out` must be valid for writes of `len` bytes.
+enum NsudokuStatus nsudoku_hint(const char *puzzle, char *out, size_t len);
+
+// Generate a 9x9 puzzle of `difficulty`, one of `easy`, `medium`, `hard`, `expert` or
+// `diabolical`, writing it as a single line to `out`
+//
+// # Safety
+//
+// `difficulty` must be a NUL-terminated string and `out` must be valid for writes of `len` bytes.
+enum NsudokuStatus nsudoku_generate(const char *difficulty, char *out, size_t len);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* NSUDOKU_SOLVER_H */
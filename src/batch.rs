@@ -0,0 +1,818 @@
+//! Reports of batch runs over many puzzles
+
+use std::{
+    fs::File,
+    io::{BufWriter, IsTerminal, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use clap::{Args, ValueEnum};
+use indicatif::{ParallelProgressIterator, ProgressStyle};
+use nsudoku_solver::{
+    solve::{interrupted, Registry, SolveError, SolveStats},
+    Sudoku,
+};
+use rayon::prelude::*;
+use serde::Serialize;
+use tracing::warn;
+
+use crate::{
+    expand_globs, interrupt_on_ctrl_c, parse_puzzles, read_input,
+    status::Status,
+    stream::{stream_puzzles, StreamFiles},
+    with_spinner, BudgetArgs, FileArgs, FormatArgs, GridStyle, SearchArgs, SudokuSolver,
+    VariantArgs,
+};
+
+/// Where a puzzle of the batch was read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Source {
+    /// The index of the input file, in the order they were given
+    pub file: usize,
+    /// The line index of the puzzle in its file
+    pub line: usize,
+}
+
+/// The result of solving a single puzzle of the batch
+#[derive(Debug, Clone, Copy)]
+pub struct PuzzleReport {
+    pub source: Source,
+    /// The number of givens of the puzzle
+    pub clues: usize,
+    pub solved: bool,
+    pub stats: SolveStats,
+}
+
+/// The results of solving a batch of puzzles
+#[derive(Debug, Default)]
+pub struct BatchReport {
+    /// The names of the input files, the puzzles are only tagged with their file if there are
+    /// several
+    pub files: Vec<String>,
+    pub puzzles: Vec<PuzzleReport>,
+}
+
+impl BatchReport {
+    /// Add the result of solving the puzzle read from `source`
+    pub fn record(mut self, source: Source, clues: usize, solved: bool, stats: SolveStats) -> Self {
+        self.puzzles.push(PuzzleReport {
+            source,
+            clues,
+            solved,
+            stats,
+        });
+        self
+    }
+
+    /// The statistics of all the solves added together
+    pub fn stats(&self) -> SolveStats {
+        self.puzzles.iter().map(|puzzle| puzzle.stats).sum()
+    }
+
+    /// The puzzle that took the longest to solve
+    pub fn longest(&self) -> Option<&PuzzleReport> {
+        self.puzzles
+            .iter()
+            .max_by_key(|puzzle| puzzle.stats.elapsed)
+    }
+
+    /// The `count` puzzles that took the longest to solve, slowest first
+    pub fn slowest(&self, count: usize) -> Vec<&PuzzleReport> {
+        let mut puzzles: Vec<_> = self.puzzles.iter().collect();
+        puzzles.sort_by_key(|puzzle| std::cmp::Reverse(puzzle.stats.elapsed));
+        puzzles.truncate(count);
+        puzzles
+    }
+
+    /// The worst outcome of the batch, a timeout beating a puzzle without a solution
+    pub fn status(&self) -> Status {
+        self.puzzles
+            .iter()
+            .map(|puzzle| match (puzzle.solved, puzzle.stats.timed_out) {
+                (true, _) => Status::Success,
+                (false, true) => Status::Timeout,
+                (false, false) => Status::Unsolvable,
+            })
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// The locations of the puzzles without a solution
+    pub fn failures(&self) -> Vec<String> {
+        self.locations(|puzzle| !puzzle.solved && !puzzle.stats.timed_out)
+    }
+
+    /// The locations of the puzzles the solver gave up on
+    pub fn timeouts(&self) -> Vec<String> {
+        self.locations(|puzzle| puzzle.stats.timed_out)
+    }
+
+    fn locations(&self, filter: impl Fn(&PuzzleReport) -> bool) -> Vec<String> {
+        self.puzzles
+            .iter()
+            .filter(|puzzle| filter(puzzle))
+            .map(|puzzle| self.location(puzzle.source))
+            .collect()
+    }
+
+    /// The line number of a puzzle, after the name of its file if there are several
+    pub fn location(&self, source: Source) -> String {
+        match self.files.get(source.file) {
+            Some(file) if self.files.len() > 1 => format!("{file}:{}", source.line + 1),
+            _ => (source.line + 1).to_string(),
+        }
+    }
+
+    /// The number of a puzzle like `#12`, followed by its file if there are several
+    pub fn name(&self, source: Source) -> String {
+        match self.file(source) {
+            Some(file) => format!("#{} of {file}", source.line + 1),
+            None => format!("#{}", source.line + 1),
+        }
+    }
+
+    /// The input file of a puzzle if there are several
+    fn file(&self, source: Source) -> Option<&str> {
+        (self.files.len() > 1).then(|| self.files[source.file].as_str())
+    }
+
+    /// Print a summary of the run, `elapsed` is the wall clock time of the whole batch
+    pub fn print_summary(&self, elapsed: Duration) {
+        let num_puzzles = self.puzzles.len();
+        let stats = self.stats();
+
+        let cpu_time = elapsed * rayon::current_num_threads() as u32;
+        let per_puzzle = cpu_time / num_puzzles.max(1) as u32;
+        println!("Took {elapsed:?} [{per_puzzle:?}/sudoku]");
+        if let Some(longest) = self.longest() {
+            println!(
+                "The longest solve was puzzle {} and took {:?}",
+                self.name(longest.source),
+                longest.stats.elapsed
+            );
+        }
+        println!(
+            "Searched {stats} [{} nodes/sudoku]",
+            stats.nodes / num_puzzles.max(1) as u64
+        );
+        self.print_distribution();
+        if self.files.len() > 1 {
+            self.print_files();
+        }
+
+        let failures = self.failures();
+        if !failures.is_empty() {
+            print_failures("Failed to solve", &failures);
+        }
+        let timeouts = self.timeouts();
+        if !timeouts.is_empty() {
+            print_failures("Timed out on", &timeouts);
+        }
+    }
+
+    /// The solve time of every puzzle, from fastest to slowest
+    fn solve_times(&self) -> Vec<Duration> {
+        let mut times: Vec<_> = self
+            .puzzles
+            .iter()
+            .map(|puzzle| puzzle.stats.elapsed)
+            .collect();
+        times.sort_unstable();
+        times
+    }
+
+    /// Print percentiles and a histogram of the solve times
+    ///
+    /// The buckets are spaced logarithmically, as the solve times span several orders of magnitude
+    fn print_distribution(&self) {
+        const BUCKETS: usize = 10;
+        const WIDTH: usize = 50;
+
+        let times = self.solve_times();
+        let (Some(&min), Some(&max)) = (times.first(), times.last()) else {
+            return;
+        };
+
+        println!(
+            "Solve times: min {min:?}, median {:?}, p90 {:?}, p99 {:?}, max {max:?}",
+            percentile(&times, 0.5),
+            percentile(&times, 0.9),
+            percentile(&times, 0.99)
+        );
+
+        let (min, max) = (min.as_secs_f64().max(1e-9), max.as_secs_f64());
+        if max <= min {
+            return;
+        }
+
+        let ratio = (max / min).powf(1.0 / BUCKETS as f64);
+        let mut counts = [0; BUCKETS];
+        for time in &times {
+            let bucket = ((time.as_secs_f64().max(min) / min).ln() / ratio.ln()) as usize;
+            counts[bucket.min(BUCKETS - 1)] += 1;
+        }
+
+        let most = counts.iter().copied().max().unwrap_or(1);
+        for (bucket, count) in counts.into_iter().enumerate() {
+            let upper = Duration::from_secs_f64(min * ratio.powi(bucket as i32 + 1));
+            let bar = "#".repeat((count * WIDTH).div_ceil(most));
+            println!("  <= {upper:>10.1?} {count:>8} {bar}");
+        }
+    }
+
+    /// Print the totals of every input file
+    fn print_files(&self) {
+        println!("Per file:");
+        for (file, name) in self.files.iter().enumerate() {
+            let puzzles: Vec<_> = self
+                .puzzles
+                .iter()
+                .filter(|puzzle| puzzle.source.file == file)
+                .collect();
+            let stats: SolveStats = puzzles.iter().map(|puzzle| puzzle.stats).sum();
+            let solved = puzzles.iter().filter(|puzzle| puzzle.solved).count();
+            println!(
+                "  {name}: solved {solved} of {} sudokus in {:?}, {} nodes",
+                puzzles.len(),
+                stats.elapsed,
+                stats.nodes
+            );
+        }
+    }
+
+    /// Write the report as a JSON object with the results of every puzzle and their totals
+    pub fn write_json(
+        &self,
+        out: &mut impl Write,
+        solver: &str,
+        elapsed: Duration,
+    ) -> serde_json::Result<()> {
+        let puzzles: Vec<_> = self
+            .puzzles
+            .iter()
+            .map(|puzzle| JsonPuzzle {
+                file: self.file(puzzle.source),
+                line: puzzle.source.line + 1,
+                solved: puzzle.solved,
+                timed_out: puzzle.stats.timed_out,
+                stats: puzzle.stats.into(),
+            })
+            .collect();
+
+        let report = JsonReport {
+            solver,
+            elapsed_secs: elapsed.as_secs_f64(),
+            total: JsonTotal {
+                puzzles: puzzles.len(),
+                solved: puzzles.iter().filter(|puzzle| puzzle.solved).count(),
+                failures: self.failures().len(),
+                timeouts: self.timeouts().len(),
+                stats: self.stats().into(),
+            },
+            puzzles,
+        };
+
+        serde_json::to_writer_pretty(&mut *out, &report)?;
+        writeln!(out).map_err(serde_json::Error::io)
+    }
+
+    /// Write one CSV row per puzzle with its clues, solve time and search effort
+    ///
+    /// The rows start with the file of the puzzle if there are several.
+    pub fn write_csv(&self, out: &mut impl Write) -> std::io::Result<()> {
+        if self.files.len() > 1 {
+            write!(out, "file,")?;
+        }
+        writeln!(
+            out,
+            "line,clues,solved,timed_out,solve_secs,nodes,backtracks"
+        )?;
+        for puzzle in &self.puzzles {
+            if let Some(file) = self.file(puzzle.source) {
+                write!(out, "{file},")?;
+            }
+            writeln!(
+                out,
+                "{},{},{},{},{},{},{}",
+                puzzle.source.line + 1,
+                puzzle.clues,
+                puzzle.solved,
+                puzzle.stats.timed_out,
+                puzzle.stats.elapsed.as_secs_f64(),
+                puzzle.stats.nodes,
+                puzzle.stats.backtracks
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    solver: &'a str,
+    /// The wall clock time of the whole batch
+    elapsed_secs: f64,
+    total: JsonTotal,
+    puzzles: Vec<JsonPuzzle<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonTotal {
+    puzzles: usize,
+    solved: usize,
+    failures: usize,
+    timeouts: usize,
+    #[serde(flatten)]
+    stats: JsonStats,
+}
+
+#[derive(Serialize)]
+struct JsonPuzzle<'a> {
+    /// The input file of the puzzle, only with several files
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file: Option<&'a str>,
+    /// The 1-based line of the puzzle in the input
+    line: usize,
+    solved: bool,
+    timed_out: bool,
+    #[serde(flatten)]
+    stats: JsonStats,
+}
+
+#[derive(Serialize)]
+struct JsonStats {
+    /// The time spent solving, summed over every puzzle for the totals
+    solve_secs: f64,
+    nodes: u64,
+    backtracks: u64,
+    propagations: u64,
+    max_depth: usize,
+}
+
+impl From<SolveStats> for JsonStats {
+    fn from(stats: SolveStats) -> Self {
+        Self {
+            solve_secs: stats.elapsed.as_secs_f64(),
+            nodes: stats.nodes,
+            backtracks: stats.backtracks,
+            propagations: stats.propagations,
+            max_depth: stats.max_depth,
+        }
+    }
+}
+
+/// The results of one solver in a comparison
+pub struct SolverRun {
+    pub solver: String,
+    /// The wall clock time of the whole batch
+    pub elapsed: Duration,
+    pub report: BatchReport,
+    /// The solution found for each puzzle of the report, in order
+    pub solutions: Vec<Option<String>>,
+}
+
+/// Print a table comparing the solvers, and the puzzles where they disagree
+///
+/// Two solvers disagree on a puzzle when both finished in time but only one found a solution, or
+/// they found different solutions of a puzzle with several.
+pub fn print_comparison(runs: &[SolverRun]) {
+    println!(
+        "{:<12} {:>12} {:>12} {:>12} {:>9} {:>9}",
+        "solver", "total", "median", "p99", "failures", "timeouts"
+    );
+    for run in runs {
+        let times = run.report.solve_times();
+        println!(
+            "{:<12} {:>12} {:>12} {:>12} {:>9} {:>9}",
+            run.solver,
+            format!("{:.2?}", run.elapsed),
+            format!("{:.2?}", percentile(&times, 0.5)),
+            format!("{:.2?}", percentile(&times, 0.99)),
+            run.report.failures().len(),
+            run.report.timeouts().len()
+        );
+    }
+
+    let Some(first) = runs.first() else {
+        return;
+    };
+    let disagreements: Vec<_> = (0..first.report.puzzles.len())
+        .filter(|&ix| {
+            let mut outcomes = runs
+                .iter()
+                .filter(|run| !run.report.puzzles[ix].stats.timed_out)
+                .map(|run| &run.solutions[ix]);
+            outcomes
+                .next()
+                .is_some_and(|outcome| outcomes.any(|other| other != outcome))
+        })
+        .map(|ix| first.report.location(first.report.puzzles[ix].source))
+        .collect();
+
+    if !disagreements.is_empty() {
+        print_failures("The solvers disagree on", &disagreements);
+    }
+}
+
+/// The value below which a fraction `p` of the sorted `times` fall, zero if there are none
+fn percentile(times: &[Duration], p: f64) -> Duration {
+    if times.is_empty() {
+        return Duration::ZERO;
+    }
+    times[((times.len() - 1) as f64 * p).round() as usize]
+}
+
+/// Warn about the puzzles at the given locations, listing the first few
+fn print_failures(message: &str, lines: &[String]) {
+    const SHOWN: usize = 20;
+
+    let shown: Vec<_> = lines.iter().take(SHOWN).map(String::as_str).collect();
+    let more = match lines.len().saturating_sub(SHOWN) {
+        0 => String::new(),
+        more => format!(" and {more} more"),
+    };
+
+    tracing::warn!(
+        "{message} {} sudokus, on lines {}{more}",
+        lines.len(),
+        shown.join(", ")
+    );
+}
+
+#[derive(Debug, Default, ValueEnum, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    /// A summary of the run
+    #[default]
+    Human,
+    /// A JSON object with the statistics of every puzzle and their totals
+    Json,
+}
+
+#[derive(Debug, Args)]
+pub struct TestArgs {
+    /// The solver strategy to use
+    #[arg(value_enum, default_value_t)]
+    solver: SudokuSolver,
+    #[command(flatten)]
+    search: SearchArgs,
+    /// A file with one sudoku per line, `-` reads from stdin
+    ///
+    /// Repeat it or pass a glob like `puzzles/*.txt` to test several files in one run.
+    #[arg(short, long)]
+    file: Vec<PathBuf>,
+    #[command(flatten)]
+    input: FormatArgs,
+    /// The string representation of a Sudoku
+    #[arg(short, long)]
+    sudoku: Option<Sudoku>,
+    #[command(flatten)]
+    variant: VariantArgs,
+    /// Write the puzzles the solver failed on or timed out on to a file, one per line
+    #[arg(long)]
+    failures: Option<PathBuf>,
+    #[command(flatten)]
+    budget: BudgetArgs,
+    /// How to report the results
+    #[arg(long, value_enum, default_value_t)]
+    output_format: OutputFormat,
+    /// Write the solve time of every puzzle to a CSV file
+    #[arg(long)]
+    timings: Option<PathBuf>,
+    /// List the puzzles that took the longest to solve after the summary
+    #[arg(long, value_name = "COUNT")]
+    worst: Option<usize>,
+    /// Write the solution of every puzzle to a file, one per line in the order of the input
+    ///
+    /// Puzzles that were skipped or not solved get an empty line.
+    #[arg(long, value_name = "FILE")]
+    solutions: Option<PathBuf>,
+    /// Solve the puzzles while reading them, with constant memory however large the input
+    ///
+    /// Only works with one puzzle per line, and only reports the totals of the run.
+    #[arg(long, conflicts_with_all = ["sudoku", "output_format", "timings", "worst"])]
+    stream: bool,
+    /// Save the progress of a streamed run to this file every few seconds
+    #[arg(long, value_name = "FILE", requires = "stream")]
+    checkpoint: Option<PathBuf>,
+    /// Resume the streamed run saved to this checkpoint, which keeps being updated
+    ///
+    /// The failures and solutions files must be the ones of the interrupted run.
+    #[arg(
+        long,
+        value_name = "FILE",
+        requires = "stream",
+        conflicts_with = "checkpoint"
+    )]
+    resume: Option<PathBuf>,
+}
+
+impl TestArgs {
+    /// Test the sudoku solver on a default 9x9 puzzle, or on the puzzles piped through stdin
+    pub fn run(self, style: GridStyle) -> color_eyre::Result<Status> {
+        let Self {
+            solver,
+            search,
+            file,
+            input,
+            sudoku,
+            variant,
+            failures,
+            budget,
+            output_format,
+            timings,
+            worst,
+            solutions,
+            stream,
+            checkpoint,
+            resume,
+        } = self;
+        let status;
+        #[cfg(debug_assertions)]
+        warn!("Running test in debug mode, it will take very long to complete");
+        let registry = Registry::with_builtins(search.options());
+        let strategy = solver.solver(&registry);
+        let config = budget.config(&search, [&solver]);
+
+        if !file.is_empty() && sudoku.is_some() {
+            warn!("Both a file and a sample sudoku provided, ignoring sudoku");
+        }
+
+        // Without a puzzle, read them from stdin if it is being piped into
+        let stdin = file.is_empty() && sudoku.is_none() && !std::io::stdin().is_terminal();
+        let human = output_format == OutputFormat::Human;
+
+        if !file.is_empty() || stdin {
+            let files = if stdin {
+                vec![PathBuf::from("-")]
+            } else {
+                expand_globs(&file)?
+            };
+            for file in files.iter().filter(|_| human) {
+                if file == Path::new("-") {
+                    println!("Reading Sudokus from stdin");
+                } else {
+                    println!("Reading Sudokus from file: {}", file.display());
+                }
+            }
+            interrupt_on_ctrl_c()?;
+            if stream {
+                let [file] = files.as_slice() else {
+                    color_eyre::eyre::bail!("streaming reads a single file, got {}", files.len());
+                };
+                println!("Testing {solver:?}:");
+                let files = StreamFiles {
+                    failures: failures.as_deref(),
+                    solutions: solutions.as_deref(),
+                    checkpoint: checkpoint.as_deref().or(resume.as_deref()),
+                    resume: resume.as_deref(),
+                };
+                return stream_puzzles(
+                    file,
+                    &input,
+                    &variant,
+                    |puzzle| strategy.solve_with(&puzzle, &config),
+                    files,
+                );
+            }
+
+            let start = Instant::now();
+            let mut puzzles = Vec::new();
+            for (ix, file) in files.iter().enumerate() {
+                let text = read_input(Some(file))?;
+                let format = input.resolve(Some(file), &text);
+                puzzles.extend(
+                    parse_puzzles(&text, format, &variant)
+                        .into_iter()
+                        .map(|(line, puzzle)| (Source { file: ix, line }, puzzle)),
+                );
+            }
+            if human {
+                println!("Took {:?} to parse puzzles", start.elapsed());
+                println!("Testing {solver:?}:");
+            }
+
+            let start = Instant::now();
+            // The solutions are only kept when written, collecting keeps them in input order
+            let keep = solutions.is_some();
+            let results: Vec<_> = puzzles
+                .par_iter()
+                .progress_with_style(
+                    ProgressStyle::default_bar()
+                        .template("[{pos:>5}/{len}] {per_sec:>10} {wide_bar} {eta_precise}/{duration_precise}")
+                        .expect("valid template"),
+                )
+                // After Ctrl-C the puzzles left and the ones cut short are dropped
+                .filter_map(|(source, puzzle)| {
+                    if interrupted() {
+                        return None;
+                    }
+                    let _puzzle = tracing::debug_span!("puzzle", line = source.line + 1).entered();
+                    let (solution, stats) = strategy.solve_with(puzzle, &config);
+                    if stats.timed_out && interrupted() {
+                        return None;
+                    }
+                    let solved = solution.is_ok();
+                    let solution = solution.ok().filter(|_| keep).map(|solution| solution.to_line());
+                    Some((*source, puzzle.clues(), solved, solution, stats))
+                })
+                .collect();
+            let end = start.elapsed();
+            if interrupted() {
+                warn!(
+                    "Interrupted, reporting the {} of {} puzzles finished so far",
+                    results.len(),
+                    puzzles.len()
+                );
+            }
+
+            let mut report = BatchReport {
+                files: files
+                    .iter()
+                    .map(|file| file.display().to_string())
+                    .collect(),
+                ..BatchReport::default()
+            };
+            let mut solved = Vec::new();
+            for (source, clues, is_solved, solution, stats) in results {
+                report = report.record(source, clues, is_solved, stats);
+                solved.extend(solution.map(|solution| (source, solution)));
+            }
+            status = if interrupted() {
+                Status::Interrupted
+            } else {
+                report.status()
+            };
+
+            match output_format {
+                OutputFormat::Human => report.print_summary(end),
+                OutputFormat::Json => {
+                    report.write_json(&mut std::io::stdout().lock(), strategy.name(), end)?
+                }
+            }
+
+            if let Some(worst) = worst.filter(|_| human) {
+                println!("The {worst} slowest solves:");
+                for slow in report.slowest(worst) {
+                    let Ok(ix) = puzzles.binary_search_by_key(&slow.source, |(source, _)| *source)
+                    else {
+                        continue;
+                    };
+                    println!(
+                        "  {:<7} {:>10.1?} {}",
+                        report.name(slow.source),
+                        slow.stats.elapsed,
+                        puzzles[ix].1.to_line()
+                    );
+                }
+            }
+
+            if let Some(failures) = failures {
+                let mut out = BufWriter::new(File::create(failures)?);
+                // The puzzles are sorted by source, and so are the ones reported
+                let mut puzzles = puzzles.iter();
+                for failed in report.puzzles.iter().filter(|result| !result.solved) {
+                    if let Some((_, puzzle)) = puzzles.find(|(source, _)| *source == failed.source)
+                    {
+                        writeln!(out, "{}", puzzle.to_line())?;
+                    }
+                }
+                out.flush()?;
+            }
+
+            if let Some(solutions) = solutions {
+                let mut out = BufWriter::new(File::create(solutions)?);
+                let mut solved = solved.into_iter().peekable();
+                for file in 0..files.len() {
+                    // Every file ends with its last puzzle, the lines in between are padded
+                    let end = puzzles
+                        .iter()
+                        .rfind(|(source, _)| source.file == file)
+                        .map_or(0, |(source, _)| source.line + 1);
+                    for line in 0..end {
+                        match solved.next_if(|(source, _)| *source == Source { file, line }) {
+                            Some((_, solution)) => writeln!(out, "{solution}")?,
+                            None => writeln!(out)?,
+                        }
+                    }
+                }
+                out.flush()?;
+            }
+
+            if let Some(timings) = timings {
+                let mut out = BufWriter::new(File::create(timings)?);
+                report.write_csv(&mut out)?;
+                out.flush()?;
+            }
+        } else {
+            let puzzle: Sudoku = if let Some(s) = sudoku {
+                s
+            } else {
+                ".......1.4.........2...........5.4.7..8...3....1.9....3..4..2...5.1........8.6..."
+                    .parse()
+                    .expect("valid 9x9 Sudoku")
+            };
+            let puzzle = variant.apply(puzzle)?;
+
+            if output_format == OutputFormat::Json {
+                let clues = puzzle.clues();
+                let (solution, stats) = strategy.solve_with(&puzzle, &config);
+                status = Status::of_solve(&solution, &stats);
+                let source = Source { file: 0, line: 0 };
+                let report = BatchReport::default().record(source, clues, solution.is_ok(), stats);
+                report.write_json(
+                    &mut std::io::stdout().lock(),
+                    strategy.name(),
+                    stats.elapsed,
+                )?;
+                return Ok(status);
+            }
+
+            println!("Testing {solver:?} on:\n{}", style.draw(&puzzle));
+            let (solution, stats) = with_spinner(|| strategy.solve_with(&puzzle, &config));
+            status = Status::of_solve(&solution, &stats);
+            println!("Took {:?}", stats.elapsed);
+            println!("Searched {stats}");
+
+            match solution {
+                Ok(puzzle) => println!("Solution:\n{}", style.draw(&puzzle)),
+                Err(SolveError::Unsolvable { reason }) => {
+                    println!("The sudoku has no solution, {reason}")
+                }
+                Err(SolveError::Aborted { budget }) => {
+                    println!("Gave up on the sudoku, {budget}")
+                }
+                Err(SolveError::Unsupported(err)) => {
+                    println!("Can't search the sudoku, {err}, try --solver dlx")
+                }
+                Err(_) => println!("No solution found for sudoku"),
+            }
+        }
+        Ok(status)
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct CompareArgs {
+    /// The solver strategies to compare
+    #[arg(value_enum, num_args = 2.., required = true)]
+    solvers: Vec<SudokuSolver>,
+    #[command(flatten)]
+    search: SearchArgs,
+    #[command(flatten)]
+    input: FileArgs,
+    #[command(flatten)]
+    variant: VariantArgs,
+    #[command(flatten)]
+    budget: BudgetArgs,
+}
+
+impl CompareArgs {
+    /// Run several solvers on the same sudokus and compare their speed and results
+    pub fn run(self) -> color_eyre::Result<Status> {
+        let Self {
+            solvers,
+            search,
+            input,
+            variant,
+            budget,
+        } = self;
+        let puzzles = input.read(&variant)?;
+        let registry = Registry::with_builtins(search.options());
+        let config = budget.config(&search, &solvers);
+
+        let runs: Vec<_> = solvers
+            .iter()
+            .map(|solver| {
+                let solver = solver.solver(&registry);
+                let start = Instant::now();
+                let results: Vec<_> = puzzles
+                    .par_iter()
+                    .map(|(ix, puzzle)| {
+                        let (solution, stats) = solver.solve_with(puzzle, &config);
+                        (*ix, puzzle.clues(), solution.ok(), stats)
+                    })
+                    .collect();
+                let elapsed = start.elapsed();
+
+                let mut report = BatchReport::default();
+                let mut solutions = Vec::with_capacity(results.len());
+                for (line, clues, solution, stats) in results {
+                    let source = Source { file: 0, line };
+                    report = report.record(source, clues, solution.is_some(), stats);
+                    solutions.push(solution.as_ref().map(Sudoku::to_line));
+                }
+
+                SolverRun {
+                    solver: solver.name().to_string(),
+                    elapsed,
+                    report,
+                    solutions,
+                }
+            })
+            .collect();
+
+        print_comparison(&runs);
+        Ok(Status::Success)
+    }
+}
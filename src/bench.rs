@@ -0,0 +1,208 @@
+//! Timing a solver on a set of puzzles, for `bench`
+//!
+//! A [`Benchmark`] solves the whole set once per repetition, after a few warmup passes that aren't
+//! timed, and summarizes the times of the passes. Saved as a baseline, it is compared with later
+//! runs of the same set to catch regressions.
+
+use std::{
+    fs::File,
+    hint::black_box,
+    io::{BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use clap::Args;
+use nsudoku_solver::{
+    solve::{Registry, Solver},
+    Sudoku,
+};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{status::Status, FileArgs, SearchArgs, SudokuSolver, VariantArgs};
+
+/// The times of a solver on a set of puzzles
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Benchmark {
+    pub solver: String,
+    pub puzzles: usize,
+    /// The seconds every timed pass over the puzzles took
+    pub passes: Vec<f64>,
+}
+
+impl Benchmark {
+    /// Solve `puzzles` with `solver` `warmup` times, then time `repetitions` more passes
+    ///
+    /// The puzzles are solved one after the other on the current thread, so the other cores
+    /// don't add to the noise.
+    pub fn run(
+        puzzles: &[(usize, Sudoku)],
+        solver: &dyn Solver,
+        warmup: usize,
+        repetitions: usize,
+    ) -> Self {
+        let pass = || {
+            let start = Instant::now();
+            for (_, puzzle) in puzzles {
+                let _ = black_box(solver.solve(black_box(puzzle)));
+            }
+            start.elapsed().as_secs_f64()
+        };
+        for _ in 0..warmup {
+            pass();
+        }
+        Self {
+            solver: solver.name().to_string(),
+            puzzles: puzzles.len(),
+            passes: (0..repetitions).map(|_| pass()).collect(),
+        }
+    }
+
+    /// Read a benchmark saved by [`save`](Self::save)
+    pub fn load(path: &Path) -> color_eyre::Result<Self> {
+        Ok(serde_json::from_reader(BufReader::new(File::open(path)?))?)
+    }
+
+    /// Save the benchmark to `path` as JSON, to compare later runs against it
+    pub fn save(&self, path: &Path) -> color_eyre::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(&mut out, self)?;
+        writeln!(out)?;
+        Ok(out.flush()?)
+    }
+
+    fn mean(&self) -> f64 {
+        self.passes.iter().sum::<f64>() / self.passes.len().max(1) as f64
+    }
+
+    fn median(&self) -> f64 {
+        let mut passes = self.passes.clone();
+        passes.sort_by(f64::total_cmp);
+        match passes.len() {
+            0 => 0.0,
+            len if len % 2 == 0 => (passes[len / 2 - 1] + passes[len / 2]) / 2.0,
+            len => passes[len / 2],
+        }
+    }
+
+    /// The sample standard deviation of the passes
+    fn stddev(&self) -> f64 {
+        if self.passes.len() < 2 {
+            return 0.0;
+        }
+        let mean = self.mean();
+        let squares: f64 = self.passes.iter().map(|pass| (pass - mean).powi(2)).sum();
+        (squares / (self.passes.len() - 1) as f64).sqrt()
+    }
+
+    /// Print the mean, median and spread of the passes
+    pub fn print(&self) {
+        let time = |seconds: f64| format!("{:.2?}", Duration::from_secs_f64(seconds));
+        let min = self.passes.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = self.passes.iter().copied().fold(0.0, f64::max);
+        println!(
+            "{} on {} sudokus, {} passes",
+            self.solver,
+            self.puzzles,
+            self.passes.len()
+        );
+        println!(
+            "mean   {} ± {} ({} per sudoku)",
+            time(self.mean()),
+            time(self.stddev()),
+            time(self.mean() / self.puzzles.max(1) as f64)
+        );
+        println!("median {}", time(self.median()));
+        println!("range  {} to {}", time(min), time(max));
+    }
+
+    /// Compare the median with the one of `baseline`, returning whether it is more than
+    /// `threshold` percent slower
+    pub fn compare(&self, baseline: &Benchmark, threshold: f64) -> bool {
+        if baseline.solver != self.solver || baseline.puzzles != self.puzzles {
+            warn!(
+                "The baseline timed {} on {} sudokus, not {} on {}",
+                baseline.solver, baseline.puzzles, self.solver, self.puzzles
+            );
+        }
+        let change = (self.median() / baseline.median() - 1.0) * 100.0;
+        let verdict = if change > threshold {
+            "a regression"
+        } else if change < -threshold {
+            "an improvement"
+        } else {
+            "within the noise"
+        };
+        println!(
+            "{:+.1}% against the baseline median of {:.2?}, {verdict} at a {threshold}% threshold",
+            change,
+            Duration::from_secs_f64(baseline.median())
+        );
+        change > threshold
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct BenchArgs {
+    #[command(flatten)]
+    input: FileArgs,
+    /// The solver strategy to time
+    #[arg(long, value_enum, default_value_t)]
+    solver: SudokuSolver,
+    #[command(flatten)]
+    search: SearchArgs,
+    #[command(flatten)]
+    variant: VariantArgs,
+    /// The passes over the sudokus before timing, to warm up the caches
+    #[arg(long, default_value_t = 2, value_name = "PASSES")]
+    warmup: usize,
+    /// The timed passes over the sudokus
+    #[arg(short, long, default_value_t = 10, value_name = "PASSES", value_parser = clap::value_parser!(u64).range(1..))]
+    repetitions: u64,
+    /// Save the times to this file, to compare later runs against
+    #[arg(long, value_name = "FILE")]
+    save: Option<PathBuf>,
+    /// Compare the times with a baseline saved with `--save`
+    #[arg(long, value_name = "FILE")]
+    compare: Option<PathBuf>,
+    /// The slowdown of the median, in percent, that counts as a regression
+    #[arg(long, default_value_t = 5.0, requires = "compare")]
+    threshold: f64,
+}
+
+impl BenchArgs {
+    /// Time a solver on a set of sudokus, and compare it with a saved baseline
+    pub fn run(self) -> color_eyre::Result<Status> {
+        let Self {
+            input,
+            solver,
+            search,
+            variant,
+            warmup,
+            repetitions,
+            save,
+            compare,
+            threshold,
+        } = self;
+        // Read the baseline first, so a missing file doesn't waste a whole run
+        let baseline = compare.as_deref().map(Benchmark::load).transpose()?;
+        let puzzles = input.read(&variant)?;
+        let registry = Registry::with_builtins(search.options());
+        let benchmark = Benchmark::run(
+            &puzzles,
+            solver.solver(&registry),
+            warmup,
+            repetitions as usize,
+        );
+
+        benchmark.print();
+        if let Some(save) = save {
+            benchmark.save(&save)?;
+        }
+        if baseline.is_some_and(|baseline| benchmark.compare(&baseline, threshold)) {
+            return Ok(Status::Regression);
+        }
+        Ok(Status::Success)
+    }
+}
@@ -0,0 +1,271 @@
+//! C bindings for the solver, declared in `include/nsudoku_solver.h`
+//!
+//! Every function takes NUL-terminated UTF-8 strings and writes its answer as a NUL-terminated
+//! string into a buffer of `len` bytes owned by the caller, returning an [`NsudokuStatus`]. A
+//! buffer of `order * order + 1` bytes always fits a solution or a generated puzzle.
+//!
+//! The header is generated with `cbindgen --config cbindgen.toml --output include/nsudoku_solver.h`,
+//! and the library built with `cargo rustc --lib --crate-type cdylib --release --features ffi`.
+
+use std::{
+    ffi::{c_char, CStr},
+    panic::{self, AssertUnwindSafe},
+    ptr,
+};
+
+use crate::{
+    generate::{self, Symmetry},
+    rate::{self, Difficulty},
+    solve::{sorted_dfs, SolveError},
+    techniques, Sudoku,
+};
+
+/// The outcome of a call
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NsudokuStatus {
+    /// The answer was written to the buffer
+    Ok = 0,
+    /// A pointer is null or a string isn't valid UTF-8
+    InvalidArgument = -1,
+    /// The puzzle is malformed, its givens break the rules or it is too large to solve
+    InvalidPuzzle = -2,
+    /// The puzzle has no solution
+    Unsolvable = -3,
+    /// The answer doesn't fit in the buffer, which holds as much of it as fits
+    BufferTooSmall = -4,
+    /// No hint or puzzle was found: the puzzle needs guessing, or no puzzle of the difficulty
+    NotFound = -5,
+}
+
+/// A description of `status`, as a static NUL-terminated string
+#[no_mangle]
+pub extern "C" fn nsudoku_status_message(status: NsudokuStatus) -> *const c_char {
+    let message: &'static CStr = match status {
+        NsudokuStatus::Ok => c"success",
+        NsudokuStatus::InvalidArgument => c"a pointer is null or a string isn't valid UTF-8",
+        NsudokuStatus::InvalidPuzzle => {
+            c"the puzzle is malformed, breaks the rules or is too large"
+        }
+        NsudokuStatus::Unsolvable => c"the puzzle has no solution",
+        NsudokuStatus::BufferTooSmall => c"the answer doesn't fit in the buffer",
+        NsudokuStatus::NotFound => c"no hint or puzzle was found",
+    };
+    message.as_ptr()
+}
+
+/// Solve `puzzle`, writing its solution as a single line to `out`
+///
+/// # Safety
+///
+/// `puzzle` must be a NUL-terminated string and `out` must be valid for writes of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn nsudoku_solve(
+    puzzle: *const c_char,
+    out: *mut c_char,
+    len: usize,
+) -> NsudokuStatus {
+    guarded(|| {
+        let puzzle = match read_puzzle(puzzle) {
+            Ok(puzzle) => puzzle,
+            Err(status) => return status,
+        };
+        match sorted_dfs(puzzle).0 {
+            Ok(solution) => write(&solution.to_line(), out, len),
+            Err(SolveError::Unsupported(_)) => NsudokuStatus::InvalidPuzzle,
+            Err(_) => NsudokuStatus::Unsolvable,
+        }
+    })
+}
+
+/// Rate `puzzle`, storing its score in `score` and writing its difficulty, like `hard`, to `out`
+///
+/// # Safety
+///
+/// `puzzle` must be a NUL-terminated string, `score` must be valid for writes or null and `out`
+/// must be valid for writes of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn nsudoku_rate(
+    puzzle: *const c_char,
+    score: *mut f32,
+    out: *mut c_char,
+    len: usize,
+) -> NsudokuStatus {
+    guarded(|| {
+        let puzzle = match read_puzzle(puzzle) {
+            Ok(puzzle) => puzzle,
+            Err(status) => return status,
+        };
+        let rating = match rate::rate(&puzzle) {
+            Ok(Some(rating)) => rating,
+            Ok(None) => return NsudokuStatus::Unsolvable,
+            Err(_) => return NsudokuStatus::InvalidPuzzle,
+        };
+        if !score.is_null() {
+            *score = rating.score;
+        }
+        write(&rating.difficulty.to_string(), out, len)
+    })
+}
+
+/// Describe the next logical step towards the solution of `puzzle` in `out`, like
+/// `r1c2=2 hidden single in row 1`
+///
+/// Answers `NotFound` when the puzzle is solved or needs guessing.
+///
+/// # Safety
+///
+/// `puzzle` must be a NUL-terminated string and `out` must be valid for writes of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn nsudoku_hint(
+    puzzle: *const c_char,
+    out: *mut c_char,
+    len: usize,
+) -> NsudokuStatus {
+    guarded(|| {
+        let puzzle = match read_puzzle(puzzle) {
+            Ok(puzzle) => puzzle,
+            Err(status) => return status,
+        };
+        if puzzle.solved() {
+            return NsudokuStatus::NotFound;
+        }
+        match techniques::hint(&puzzle) {
+            Ok(Some(step)) => write(&step.to_string(), out, len),
+            Ok(None) => NsudokuStatus::NotFound,
+            Err(_) => NsudokuStatus::InvalidPuzzle,
+        }
+    })
+}
+
+/// Generate a 9x9 puzzle of `difficulty`, one of `easy`, `medium`, `hard`, `expert` or
+/// `diabolical`, writing it as a single line to `out`
+///
+/// # Safety
+///
+/// `difficulty` must be a NUL-terminated string and `out` must be valid for writes of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn nsudoku_generate(
+    difficulty: *const c_char,
+    out: *mut c_char,
+    len: usize,
+) -> NsudokuStatus {
+    let Some(difficulty) = read(difficulty).and_then(|text| text.parse::<Difficulty>().ok()) else {
+        return NsudokuStatus::InvalidArgument;
+    };
+    guarded(|| {
+        let rng = &mut rand::thread_rng();
+        let attempts = generate::ATTEMPTS;
+        match generate::puzzle_of_difficulty(
+            9,
+            None,
+            Symmetry::default(),
+            difficulty,
+            attempts,
+            rng,
+        ) {
+            Ok(Some((puzzle, _))) => write(&puzzle.to_line(), out, len),
+            Ok(None) => NsudokuStatus::NotFound,
+            Err(_) => NsudokuStatus::InvalidArgument,
+        }
+    })
+}
+
+/// Run the body of a call, answering `InvalidPuzzle` if the solvers or the generator panic
+///
+/// A panic unwinding out of an `extern "C"` function aborts the whole application.
+fn guarded(call: impl FnOnce() -> NsudokuStatus) -> NsudokuStatus {
+    panic::catch_unwind(AssertUnwindSafe(call)).unwrap_or(NsudokuStatus::InvalidPuzzle)
+}
+
+/// The string behind `text`, `None` if it's null or not UTF-8
+///
+/// # Safety
+///
+/// `text` must be null or a NUL-terminated string.
+unsafe fn read<'a>(text: *const c_char) -> Option<&'a str> {
+    if text.is_null() {
+        return None;
+    }
+    CStr::from_ptr(text).to_str().ok()
+}
+
+/// # Safety
+///
+/// `puzzle` must be null or a NUL-terminated string.
+unsafe fn read_puzzle(puzzle: *const c_char) -> Result<Sudoku, NsudokuStatus> {
+    let puzzle = read(puzzle).ok_or(NsudokuStatus::InvalidArgument)?;
+    Sudoku::parse_lenient(puzzle).map_err(|_| NsudokuStatus::InvalidPuzzle)
+}
+
+/// Write `text` to `out` with a NUL terminator, cutting it short if it doesn't fit in `len` bytes
+///
+/// # Safety
+///
+/// `out` must be null or valid for writes of `len` bytes.
+unsafe fn write(text: &str, out: *mut c_char, len: usize) -> NsudokuStatus {
+    if out.is_null() || len == 0 {
+        return NsudokuStatus::BufferTooSmall;
+    }
+    let written = text.len().min(len - 1);
+    ptr::copy_nonoverlapping(text.as_ptr().cast(), out, written);
+    *out.add(written) = 0;
+    if written < text.len() {
+        NsudokuStatus::BufferTooSmall
+    } else {
+        NsudokuStatus::Ok
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::ffi::{c_char, CStr, CString};
+
+    use super::{nsudoku_hint, nsudoku_rate, nsudoku_solve, NsudokuStatus};
+
+    #[test]
+    fn c_calls() {
+        let mut out = [0 as c_char; 82];
+        let text = |out: &[c_char]| {
+            // SAFETY: every call leaves a NUL terminator in the buffer
+            unsafe { CStr::from_ptr(out.as_ptr()) }
+                .to_str()
+                .expect("utf-8")
+        };
+
+        // SAFETY: the strings are NUL-terminated and the buffer is 82 bytes long
+        unsafe {
+            let status = nsudoku_solve(c"1.34..12.1..4..1".as_ptr(), out.as_mut_ptr(), out.len());
+            assert_eq!(status, NsudokuStatus::Ok);
+            assert_eq!(text(&out), "1234341221434321");
+
+            let status = nsudoku_solve(c"1.34..12.1..4..1".as_ptr(), out.as_mut_ptr(), 5);
+            assert_eq!(status, NsudokuStatus::BufferTooSmall);
+            assert_eq!(text(&out), "1234");
+
+            let status = nsudoku_solve(c"11..............".as_ptr(), out.as_mut_ptr(), out.len());
+            assert_eq!(status, NsudokuStatus::InvalidPuzzle);
+            let large = CString::new(".".repeat(81 * 81)).expect("no NUL");
+            let status = nsudoku_solve(large.as_ptr(), out.as_mut_ptr(), out.len());
+            assert_eq!(status, NsudokuStatus::InvalidPuzzle);
+            let status = nsudoku_hint(large.as_ptr(), out.as_mut_ptr(), out.len());
+            assert_eq!(status, NsudokuStatus::InvalidPuzzle);
+
+            let mut score = 0.0;
+            let status = nsudoku_rate(
+                c"1.34..12.1..4..1".as_ptr(),
+                &mut score,
+                out.as_mut_ptr(),
+                out.len(),
+            );
+            assert_eq!(status, NsudokuStatus::Ok);
+            assert_eq!(text(&out), "easy");
+            assert!(score > 0.0);
+
+            let status = nsudoku_hint(c"1234341221434321".as_ptr(), out.as_mut_ptr(), out.len());
+            assert_eq!(status, NsudokuStatus::NotFound);
+            let status = nsudoku_hint(std::ptr::null(), out.as_mut_ptr(), out.len());
+            assert_eq!(status, NsudokuStatus::InvalidArgument);
+        }
+    }
+}
@@ -0,0 +1,316 @@
+//! Generating puzzles and removing their clues, for `generate`, `book` and `minimize`
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::PathBuf,
+};
+
+use clap::{Args, ValueEnum};
+use nsudoku_solver::{
+    generate::{self, Removal, Symmetry},
+    rate,
+    render::Book,
+    solve::{sorted_dfs_with, SearchOptions},
+};
+use rand::{rngs::StdRng, SeedableRng};
+use rayon::prelude::*;
+use tracing::warn;
+
+use crate::{status::Status, GridFormat, GridStyle, InputArgs, Level, VariantArgs};
+
+/// The order in which `minimize` removes the clues, see [`Removal`]
+#[derive(Debug, Default, ValueEnum, Clone, Copy)]
+enum RemovalOrder {
+    /// A random order, different seeds may reach different minimal puzzles
+    #[default]
+    Random,
+    /// Row by row from the top left
+    RowMajor,
+    /// Row by row from the bottom right
+    Reverse,
+}
+
+impl From<RemovalOrder> for Removal {
+    fn from(order: RemovalOrder) -> Self {
+        match order {
+            RemovalOrder::Random => Removal::Random,
+            RemovalOrder::RowMajor => Removal::RowMajor,
+            RemovalOrder::Reverse => Removal::Reverse,
+        }
+    }
+}
+
+/// The symmetry of the clues of generated puzzles, see [`Symmetry`]
+#[derive(Debug, Default, ValueEnum, Clone, Copy)]
+enum ClueSymmetry {
+    /// No symmetry
+    #[default]
+    None,
+    /// Unchanged by a half turn
+    Rotational,
+    /// Unchanged by a left to right flip
+    Mirror,
+    /// Unchanged by a flip over the main diagonal
+    Diagonal,
+}
+
+impl From<ClueSymmetry> for Symmetry {
+    fn from(symmetry: ClueSymmetry) -> Self {
+        match symmetry {
+            ClueSymmetry::None => Symmetry::None,
+            ClueSymmetry::Rotational => Symmetry::Rotational,
+            ClueSymmetry::Mirror => Symmetry::Mirror,
+            ClueSymmetry::Diagonal => Symmetry::Diagonal,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct GenerateArgs {
+    /// The number of puzzles to generate
+    #[arg(short = 'n', long, default_value_t = 1)]
+    count: usize,
+    /// The number of clues to keep, removes as many as possible if missing
+    #[arg(short, long)]
+    clues: Option<usize>,
+    /// Generate completely solved grids instead of puzzles
+    #[arg(long, conflicts_with = "clues")]
+    full: bool,
+    /// The symmetry kept by the clues
+    #[arg(long, value_enum, default_value_t, conflicts_with = "full")]
+    symmetry: ClueSymmetry,
+    /// Only keep puzzles rated this difficulty, generating new ones until one is
+    #[arg(long, value_enum, conflicts_with = "full")]
+    difficulty: Option<Level>,
+    /// The puzzles generated for each one of `--difficulty` before giving up
+    #[arg(long, default_value_t = generate::ATTEMPTS, requires = "difficulty")]
+    attempts: usize,
+    /// The size of the generated sudokus
+    #[arg(long, default_value_t = 9)]
+    order: usize,
+    /// How to print the puzzles
+    #[arg(long, value_enum, default_value_t)]
+    format: GridFormat,
+    /// Write the puzzles to a file instead of stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+impl GenerateArgs {
+    /// Generate random puzzles with a unique solution
+    pub fn run(self, style: GridStyle) -> color_eyre::Result<Status> {
+        let Self {
+            count,
+            clues,
+            full,
+            symmetry,
+            difficulty,
+            attempts,
+            order,
+            format,
+            output,
+        } = self;
+        let puzzles = (0..count)
+            .into_par_iter()
+            .map(|_| {
+                let rng = &mut rand::thread_rng();
+                match (full, difficulty) {
+                    (true, _) => Ok(generate::full(order, rng)?),
+                    (false, None) => Ok(generate::puzzle_with(order, clues, symmetry.into(), rng)?),
+                    (false, Some(level)) => {
+                        let difficulty = level.into();
+                        generate::puzzle_of_difficulty(
+                            order,
+                            clues,
+                            symmetry.into(),
+                            difficulty,
+                            attempts,
+                            rng,
+                        )?
+                        .map(|(puzzle, _)| puzzle)
+                        .ok_or_else(|| {
+                            color_eyre::eyre::eyre!(
+                                "no {difficulty} puzzle found in {attempts} attempts"
+                            )
+                        })
+                    }
+                }
+            })
+            .collect::<color_eyre::Result<Vec<_>>>()?;
+
+        let mut out: Box<dyn Write> = if let Some(output) = output {
+            Box::new(BufWriter::new(File::create(output)?))
+        } else {
+            Box::new(std::io::stdout().lock())
+        };
+
+        for (ix, puzzle) in puzzles.iter().enumerate() {
+            if clues.is_some_and(|clues| puzzle.clues() > clues) {
+                warn!(
+                    "Puzzle #{} is minimal with {} clues",
+                    ix + 1,
+                    puzzle.clues()
+                );
+            }
+            format.write(&mut out, puzzle, style)?;
+        }
+        out.flush()?;
+        Ok(Status::Success)
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct BookArgs {
+    /// The number of puzzles in the book
+    #[arg(short = 'n', long, default_value_t = 12)]
+    count: usize,
+    /// Only keep puzzles rated this difficulty, any difficulty by default
+    #[arg(long, value_enum)]
+    difficulty: Option<Level>,
+    /// The puzzles generated for each one of `--difficulty` before giving up
+    #[arg(long, default_value_t = generate::ATTEMPTS, requires = "difficulty")]
+    attempts: usize,
+    /// The symmetry kept by the clues
+    #[arg(long, value_enum, default_value_t)]
+    symmetry: ClueSymmetry,
+    /// The size of the generated sudokus
+    #[arg(long, default_value_t = 9)]
+    order: usize,
+    /// The number of puzzles on every page
+    #[arg(long, default_value_t = 4)]
+    per_page: usize,
+    /// The title on the first page
+    #[arg(long, default_value = "Sudoku")]
+    title: String,
+    /// Write the document to a file instead of stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+impl BookArgs {
+    /// Generate a book of rated puzzles as a LaTeX document, with the solutions at the end
+    pub fn run(self) -> color_eyre::Result<Status> {
+        let Self {
+            count,
+            difficulty,
+            attempts,
+            symmetry,
+            order,
+            per_page,
+            title,
+            output,
+        } = self;
+        let mut puzzles = (0..count)
+            .into_par_iter()
+            .map(|_| {
+                let rng = &mut rand::thread_rng();
+                let (puzzle, rating) = match difficulty {
+                    Some(level) => {
+                        let difficulty = level.into();
+                        generate::puzzle_of_difficulty(
+                            order,
+                            None,
+                            symmetry.into(),
+                            difficulty,
+                            attempts,
+                            rng,
+                        )?
+                        .ok_or_else(|| {
+                            color_eyre::eyre::eyre!(
+                                "no {difficulty} puzzle found in {attempts} attempts"
+                            )
+                        })?
+                    }
+                    None => {
+                        let puzzle = generate::puzzle_with(order, None, symmetry.into(), rng)?;
+                        let rating =
+                            rate::rate(&puzzle)?.expect("generated puzzles have a solution");
+                        (puzzle, rating)
+                    }
+                };
+                let solution = sorted_dfs_with(puzzle.clone(), SearchOptions::default()).0?;
+                Ok((puzzle, solution, rating))
+            })
+            .collect::<color_eyre::Result<Vec<_>>>()?;
+        puzzles.sort_by(|(_, _, a), (_, _, b)| a.score.total_cmp(&b.score));
+
+        let mut book = Book::new(&title).with_per_page(per_page);
+        for (puzzle, solution, rating) in &puzzles {
+            book.push(puzzle, solution, &rating.difficulty.to_string())?;
+        }
+        match output {
+            Some(output) => std::fs::write(output, book.to_latex())?,
+            None => std::io::stdout()
+                .lock()
+                .write_all(book.to_latex().as_bytes())?,
+        }
+        Ok(Status::Success)
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct MinimizeArgs {
+    #[command(flatten)]
+    input: InputArgs,
+    /// The order in which the clues are removed
+    #[arg(long, value_enum, default_value_t)]
+    removal: RemovalOrder,
+    /// The seed of the random removal order
+    #[arg(long)]
+    seed: Option<u64>,
+    /// How to print the minimal puzzles
+    #[arg(long, value_enum, default_value_t)]
+    format: GridFormat,
+    #[command(flatten)]
+    variant: VariantArgs,
+}
+
+impl MinimizeArgs {
+    /// Remove clues from puzzles while their solution stays unique, until none can be removed
+    pub fn run(self, style: GridStyle) -> color_eyre::Result<Status> {
+        let Self {
+            input,
+            removal,
+            seed,
+            format,
+            variant,
+        } = self;
+        let puzzles = input.read(&variant)?;
+
+        let seed = seed.unwrap_or_else(rand::random);
+        let minimal: Vec<_> = puzzles
+            .par_iter()
+            .map(|(ix, puzzle)| {
+                let mut rng = StdRng::seed_from_u64(seed.wrapping_add(*ix as u64));
+                (
+                    ix,
+                    puzzle,
+                    generate::minimize(puzzle, removal.into(), &mut rng),
+                )
+            })
+            .collect();
+
+        let mut out = std::io::stdout().lock();
+        for (ix, puzzle, minimal) in minimal {
+            let Some(minimal) = minimal else {
+                writeln!(
+                    out,
+                    "Sudoku on line {} doesn't have a unique solution",
+                    ix + 1
+                )?;
+                continue;
+            };
+            writeln!(
+                out,
+                "Sudoku on line {}: minimal with {} clues, removed {} of {}",
+                ix + 1,
+                minimal.clues(),
+                puzzle.clues() - minimal.clues(),
+                puzzle.clues()
+            )?;
+            format.write(&mut out, &minimal, style)?;
+        }
+        Ok(Status::Success)
+    }
+}
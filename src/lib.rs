@@ -0,0 +1,33 @@
+//! A generalized N-Sudoku solver
+//!
+//! Parse a [`Sudoku`] from its single line representation and solve it with any of the
+//! strategies in [`solve`]:
+//!
+//! ```
+//! use nsudoku_solver::{solve::sorted_dfs, Sudoku};
+//!
+//! let sudoku: Sudoku =
+//!     "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79"
+//!         .parse()
+//!         .expect("valid sudoku");
+//!
+//! assert!(sorted_dfs(sudoku).0.expect("solvable sudoku").solved());
+//! ```
+//!
+//! The default `cli` feature builds the `nsudoku-solver` binary, turn it off to only depend on the
+//! solver. The `parallel` feature searches on several threads, `png` rasterizes drawings, `ffi`
+//! adds the C bindings of the `ffi` module and `wasm` the JavaScript bindings of the `wasm` module.
+//! `arbitrary` generates random puzzles for fuzzing.
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod sudoku;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[cfg(feature = "serde")]
+pub use sudoku::serialize;
+pub use sudoku::{
+    canonical, explain, formats, generate, rate, render, rules, savegame, solve, techniques,
+    CellValue, Conflict, Difference, Sudoku, SudokuError, SudokuValue,
+};
@@ -0,0 +1,126 @@
+//! Solving puzzles step by step with the human-style techniques, for `rate`, `hint` and `explain`
+
+use clap::Args;
+use nsudoku_solver::{explain, rate, techniques, Sudoku, SudokuError};
+use rayon::prelude::*;
+
+use crate::{status::Status, InputArgs, TechniqueArgs, VariantArgs};
+
+#[derive(Debug, Args)]
+pub struct RateArgs {
+    #[command(flatten)]
+    input: InputArgs,
+    #[command(flatten)]
+    techniques: TechniqueArgs,
+    #[command(flatten)]
+    variant: VariantArgs,
+}
+
+impl RateArgs {
+    /// Grade the difficulty of sudokus
+    pub fn run(self) -> color_eyre::Result<Status> {
+        let Self {
+            input,
+            techniques,
+            variant,
+        } = self;
+        let puzzles = input.read(&variant)?;
+
+        let selected = techniques.selected();
+        let ratings = puzzles
+            .par_iter()
+            .map(|(ix, puzzle)| Ok((ix, rate::rate_with(puzzle, &selected)?)))
+            .collect::<Result<Vec<_>, SudokuError>>()?;
+
+        for (ix, rating) in ratings {
+            match rating {
+                Some(rating) => println!("Sudoku on line {}: {rating}", ix + 1),
+                None => println!("Sudoku on line {} has no solution", ix + 1),
+            }
+        }
+        Ok(Status::Success)
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct HintArgs {
+    /// The string representation of a Sudoku
+    puzzle: Option<Sudoku>,
+    /// The string representation of a Sudoku
+    #[arg(
+        short,
+        long,
+        conflicts_with = "puzzle",
+        required_unless_present = "puzzle"
+    )]
+    sudoku: Option<Sudoku>,
+    #[command(flatten)]
+    techniques: TechniqueArgs,
+    #[command(flatten)]
+    variant: VariantArgs,
+}
+
+impl HintArgs {
+    /// Show the next logical step towards solving a sudoku
+    pub fn run(self) -> color_eyre::Result<Status> {
+        let Self {
+            puzzle,
+            sudoku,
+            techniques,
+            variant,
+        } = self;
+        let puzzle = variant.apply(puzzle.or(sudoku).expect("required by clap"))?;
+
+        if puzzle.solved() {
+            println!("The sudoku is already solved");
+        } else if let Some(step) = techniques::hint_with(&puzzle, &techniques.selected())? {
+            println!("{step}");
+        } else {
+            println!("No logical step found, guessing is required");
+        }
+        Ok(Status::Success)
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct ExplainArgs {
+    /// The string representation of a Sudoku
+    puzzle: Option<Sudoku>,
+    /// The string representation of a Sudoku
+    #[arg(
+        short,
+        long,
+        conflicts_with = "puzzle",
+        required_unless_present = "puzzle"
+    )]
+    sudoku: Option<Sudoku>,
+    #[command(flatten)]
+    techniques: TechniqueArgs,
+    #[command(flatten)]
+    variant: VariantArgs,
+}
+
+impl ExplainArgs {
+    /// Print every step of a solve, guessing only when no technique applies
+    pub fn run(self) -> color_eyre::Result<Status> {
+        let Self {
+            puzzle,
+            sudoku,
+            techniques,
+            variant,
+        } = self;
+        let puzzle = variant.apply(puzzle.or(sudoku).expect("required by clap"))?;
+
+        let Some(path) = explain::explain_with(&puzzle, &techniques.selected())? else {
+            println!("The sudoku has no solution");
+            return Ok(Status::Unsolvable);
+        };
+
+        for (ix, step) in path.iter().enumerate() {
+            println!("{:>4}. {step}", ix + 1);
+        }
+        let guesses = path.iter().filter(|step| step.is_guess()).count();
+        println!("Solved in {} steps with {guesses} guesses", path.len());
+        Ok(Status::Success)
+    }
+}
@@ -1,39 +1,342 @@
 use std::{
-    num::ParseIntError,
-    path::PathBuf,
-    str::FromStr,
-    time::{Duration, Instant},
+    fs::File,
+    io::{BufRead, BufReader, IsTerminal, Read, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::{
+        mpsc::{self, RecvTimeoutError},
+        Arc,
+    },
+    time::Duration,
 };
 
-use clap::{Parser, Subcommand, ValueEnum};
-use indicatif::{ParallelProgressIterator, ProgressStyle};
-use rayon::prelude::*;
-use sudoku::Sudoku;
+use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum};
+use flate2::bufread::MultiGzDecoder;
+use indicatif::{HumanCount, ProgressBar, ProgressStyle};
+use nsudoku_solver::{
+    formats::Format,
+    rate::Difficulty,
+    render::Style,
+    rules::{self, BoxShape, Cage, Regions},
+    solve::{
+        interrupt, interrupted, with_progress, Heuristic, Progress, PropagationLevel, Registry,
+        SearchOptions, SolveConfig, Solver, ValueOrder,
+    },
+    techniques::{self, Technique},
+    Sudoku, SudokuError,
+};
+use tracing::warn;
 
-use crate::sudoku::solve::{dfs, naive_dfs, sorted_dfs};
+use crate::status::Status;
 
-mod sudoku;
+mod batch;
+mod bench;
+mod generator;
+mod logic;
+mod play;
+mod print;
+mod replay;
+mod rpc;
+mod serve;
+mod solve;
+mod stats;
+mod status;
+mod stream;
+mod trace;
+mod transform;
+mod verify;
+mod visualize;
 
 #[derive(Debug, Parser)]
 struct Cli {
+    /// The number of threads solving puzzles in parallel, all the cores by default
+    #[arg(long, global = true)]
+    threads: Option<usize>,
+    /// Log more details, `-v` for the events of the solvers and `-vv` for everything
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    verbose: u8,
+    /// Only log errors, `-qq` logs nothing at all
+    #[arg(short, long, global = true, action = ArgAction::Count, conflicts_with = "verbose")]
+    quiet: u8,
+    /// How to draw the pretty grids
+    #[arg(long, global = true, value_enum, default_value_t)]
+    style: GridStyle,
     #[command(subcommand)]
     mode: Mode,
 }
 
 #[derive(Debug, Subcommand)]
 enum Mode {
-    /// Test the sudoku solver on a default 9x9 puzzle
-    Test {
-        /// The solver strategy to use
-        #[arg(value_enum, default_value_t)]
-        solver: SudokuSolver,
-        /// A file with one sudoku per line
-        #[arg(short, long)]
-        file: Option<PathBuf>,
-        /// The string represeentation of a Sudoku
-        #[arg(short, long)]
-        sudoku: Option<Sudoku>,
-    },
+    /// Test the sudoku solver on a default 9x9 puzzle, or on the puzzles piped through stdin
+    Test(batch::TestArgs),
+    /// Run several solvers on the same sudokus and compare their speed and results
+    Compare(batch::CompareArgs),
+    /// Time a solver on a set of sudokus, and compare it with a saved baseline
+    Bench(bench::BenchArgs),
+    /// Solve sudokus with several solvers and report the puzzles they disagree on
+    ///
+    /// A solver disagrees when its grid isn't a solution of the puzzle, or when it finds no
+    /// solution while another solver finds one.
+    Verify(verify::VerifyArgs),
+    /// Solve sudokus and print their solutions
+    Solve(solve::SolveArgs),
+    /// Print sudokus without solving them, `--style blank` draws them to solve on paper
+    Print(print::PrintArgs),
+    /// Write the constraints of a sudoku for external solvers
+    Export(print::ExportArgs),
+    /// Check a filled grid against its puzzle, listing the cells that are wrong
+    Check(solve::CheckArgs),
+    /// Check whether sudokus have no solution, a unique solution or multiple solutions
+    CheckUnique(solve::CheckUniqueArgs),
+    /// Report the clues and solutions of sudokus
+    Analyze(solve::AnalyzeArgs),
+    /// Turn sudokus into the canonical form shared by every equivalent puzzle
+    ///
+    /// Puzzles that only differ by relabeling the values, moving bands, stacks, rows and columns
+    /// around or transposing the grid have the same canonical form. Variant rules are ignored.
+    Canonicalize(transform::CanonicalizeArgs),
+    /// Check whether two sudokus are the same puzzle with its values and lines moved around
+    ///
+    /// Prints a transformation turning the first puzzle into the second if they are equivalent.
+    Equivalent(transform::EquivalentArgs),
+    /// Turn, flip and relabel sudokus into equivalent puzzles
+    ///
+    /// The transformations are applied in the order of the flags: rotation, transposition,
+    /// reflection, the random swaps and the digit permutation. Variant rules are dropped.
+    Transform(transform::TransformArgs),
+    /// Drop the sudokus equivalent to an earlier one, keeping the first of each
+    ///
+    /// Equivalent puzzles share their canonical form, see `canonicalize`.
+    Dedup(transform::DedupArgs),
+    /// Report how the clues of the sudokus in a file are distributed
+    ///
+    /// Prints histograms of the number of clues, the values given and the empty rows, columns and
+    /// boxes of each puzzle, and how many puzzles have symmetric clues.
+    Stats(stats::StatsArgs),
+    /// Generate random puzzles with a unique solution
+    Generate(generator::GenerateArgs),
+    /// Generate a book of rated puzzles as a LaTeX document, with the solutions at the end
+    ///
+    /// The puzzles get harder as the book goes, compile it with `pdflatex book.tex`.
+    Book(generator::BookArgs),
+    /// Remove clues from puzzles while their solution stays unique, until none can be removed
+    Minimize(generator::MinimizeArgs),
+    /// Grade the difficulty of sudokus
+    Rate(logic::RateArgs),
+    /// Show the next logical step towards solving a sudoku
+    Hint(logic::HintArgs),
+    /// Play a sudoku in the terminal, with pencil marks, undo and hints from the techniques
+    Play(play::PlayArgs),
+    /// Step through a trace recorded with `solve --trace` in the terminal
+    Replay(replay::ReplayArgs),
+    /// Print every step of a solve, guessing only when no technique applies
+    Explain(logic::ExplainArgs),
+    /// Answer solve, rate and generate requests over HTTP with JSON
+    Serve(serve::ServeArgs),
+    /// Answer solve, hint, validate and rate requests with JSON-RPC on stdin and stdout
+    Rpc(rpc::RpcArgs),
+}
+
+#[derive(Debug, Args)]
+struct InputArgs {
+    /// The string representation of a Sudoku, read from stdin if missing
+    puzzle: Option<Sudoku>,
+    /// The string representation of a Sudoku
+    #[arg(short, long, conflicts_with = "puzzle")]
+    sudoku: Option<Sudoku>,
+    /// A file with one sudoku per line, `-` reads from stdin
+    #[arg(short, long, conflicts_with_all = ["puzzle", "sudoku"])]
+    file: Option<PathBuf>,
+    #[command(flatten)]
+    format: FormatArgs,
+}
+
+impl InputArgs {
+    /// The sudoku given, or the sudokus of the file or stdin
+    fn read(self, variant: &VariantArgs) -> color_eyre::Result<Vec<(usize, Sudoku)>> {
+        read_puzzles(
+            self.puzzle.or(self.sudoku),
+            self.file,
+            &self.format,
+            variant,
+        )
+    }
+}
+
+#[derive(Debug, Args)]
+struct FileArgs {
+    /// A file with one sudoku per line, read from stdin if missing or `-`
+    #[arg(short, long)]
+    file: Option<PathBuf>,
+    #[command(flatten)]
+    format: FormatArgs,
+}
+
+impl FileArgs {
+    /// The sudokus of the file or stdin
+    fn read(self, variant: &VariantArgs) -> color_eyre::Result<Vec<(usize, Sudoku)>> {
+        read_puzzles(None, self.file, &self.format, variant)
+    }
+}
+
+#[derive(Debug, Args)]
+struct FormatArgs {
+    /// The format of the puzzle file
+    #[arg(long, value_enum, default_value_t)]
+    input_format: InputFormat,
+    /// Only accept one sudoku per line, with `.` for empty cells and nothing else
+    #[arg(long, conflicts_with = "input_format")]
+    strict: bool,
+}
+
+impl FormatArgs {
+    /// The format of the input read from `file`, see [`InputFormat::resolve`]
+    fn resolve(&self, file: Option<&Path>, input: &str) -> Format {
+        self.input_format.resolve(self.strict, file, input)
+    }
+}
+
+#[derive(Debug, Args)]
+struct VariantArgs {
+    /// The rules the sudokus follow
+    #[arg(long, value_enum, default_value_t)]
+    variant: Variant,
+    /// A file with one killer cage per line: its sum followed by its cells, like `3 r1c1 r1c2`
+    #[arg(long, value_parser = read_cages, required_if_eq("variant", "killer"))]
+    cages: Option<Cages>,
+    /// The jigsaw regions as one region id per cell, either inline or in a file
+    #[arg(long, value_parser = read_layout, required_if_eq("variant", "jigsaw"))]
+    layout: Option<Regions>,
+    /// The rows and columns of the boxes, like `3x2`, as square as possible by default
+    #[arg(long, value_name = "ROWSxCOLS")]
+    box_shape: Option<BoxShape>,
+}
+
+#[derive(Debug, Args)]
+struct TechniqueArgs {
+    /// Only use these techniques, all of them but the uniqueness ones by default
+    #[arg(long, value_enum, value_delimiter = ',')]
+    techniques: Vec<TechniqueGroup>,
+    /// Also use the techniques relying on the puzzle having a single solution
+    #[arg(long)]
+    assume_unique: bool,
+}
+
+impl TechniqueArgs {
+    /// The techniques the human-style solver may use
+    fn selected(&self) -> Vec<Technique> {
+        let mut selected: Vec<_> = if self.techniques.is_empty() {
+            Technique::ALL
+                .into_iter()
+                .filter(|technique| !technique.assumes_unique())
+                .collect()
+        } else {
+            self.techniques
+                .iter()
+                .flat_map(|group| group.techniques())
+                .copied()
+                .collect()
+        };
+        if self.assume_unique {
+            selected.extend(TechniqueGroup::Uniqueness.techniques());
+        }
+        selected
+    }
+}
+
+/// Families of techniques selected with `--techniques`
+#[derive(Debug, ValueEnum, Clone, Copy)]
+enum TechniqueGroup {
+    /// Naked and hidden singles
+    Singles,
+    /// Locked candidates, pointing and claiming
+    Locked,
+    /// Naked and hidden pairs
+    Pairs,
+    /// Naked and hidden triples
+    Triples,
+    /// Naked and hidden quads
+    Quads,
+    /// X-Wing, Swordfish and Jellyfish
+    Fish,
+    /// Unique rectangles and BUG+1, only valid on puzzles with a single solution
+    Uniqueness,
+    /// XY-chains
+    Chains,
+}
+
+impl TechniqueGroup {
+    fn techniques(self) -> &'static [Technique] {
+        match self {
+            TechniqueGroup::Singles => &[Technique::HiddenSingle, Technique::NakedSingle],
+            TechniqueGroup::Locked => &[Technique::LockedCandidates],
+            TechniqueGroup::Pairs => &[Technique::NakedPair, Technique::HiddenPair],
+            TechniqueGroup::Triples => &[Technique::NakedTriple, Technique::HiddenTriple],
+            TechniqueGroup::Quads => &[Technique::NakedQuad, Technique::HiddenQuad],
+            TechniqueGroup::Fish => &[Technique::XWing, Technique::Swordfish, Technique::Jellyfish],
+            TechniqueGroup::Uniqueness => &[
+                Technique::UniqueRectangle1,
+                Technique::UniqueRectangle2,
+                Technique::UniqueRectangle3,
+                Technique::UniqueRectangle4,
+                Technique::BugPlusOne,
+            ],
+            TechniqueGroup::Chains => &[Technique::XYChain],
+        }
+    }
+}
+
+#[derive(Debug, Default, ValueEnum, Clone, Copy, PartialEq, Eq)]
+enum Variant {
+    /// Distinct values in every row, column and box
+    #[default]
+    Classic,
+    /// Cages of cells with distinct values adding up to a sum, read from `--cages`
+    Killer,
+    /// Both main diagonals also hold distinct values
+    X,
+    /// Irregular regions read from `--layout` replace the boxes
+    Jigsaw,
+}
+
+/// The killer cages parsed from `--cages`
+#[derive(Debug, Clone)]
+struct Cages(Vec<Cage>);
+
+fn read_cages(file: &str) -> color_eyre::Result<Cages> {
+    let input = read_input(Some(Path::new(file)))?;
+    Ok(Cages(rules::parse_cages(&input)?))
+}
+
+fn read_layout(layout: &str) -> color_eyre::Result<Regions> {
+    let path = Path::new(layout);
+    if path.is_file() {
+        Ok(read_input(Some(path))?.parse()?)
+    } else {
+        Ok(layout.parse()?)
+    }
+}
+
+impl VariantArgs {
+    /// Add the variant constraints to a puzzle
+    fn apply(&self, puzzle: Sudoku) -> Result<Sudoku, SudokuError> {
+        let puzzle = match self.box_shape {
+            Some(shape) => puzzle.with_box_shape(shape)?,
+            None => puzzle,
+        };
+        match self.variant {
+            Variant::Classic => Ok(puzzle),
+            Variant::Killer => {
+                let Cages(cages) = self.cages.as_ref().expect("required by clap");
+                puzzle.with_cages(cages.clone())
+            }
+            Variant::X => puzzle.with_diagonals(),
+            Variant::Jigsaw => {
+                let regions = self.layout.as_ref().expect("required by clap");
+                puzzle.with_regions(regions.clone())
+            }
+        }
+    }
 }
 
 #[derive(Debug, Default, ValueEnum, Clone)]
@@ -45,102 +348,528 @@ enum SudokuSolver {
     /// Sorts possibilities by ammount
     #[default]
     SortedDfs,
+    /// Exact cover search using Dancing Links (Algorithm X), only checks killer cages on full grids
+    Dlx,
+    /// Only fixes naked and hidden singles, fails if guessing is required
+    Propagate,
+    /// Only applies human-style techniques, fails if guessing is required
+    Logic,
+    /// Races sorted-dfs, dlx and a randomized sorted-dfs on separate threads, keeping the first
+    Portfolio,
+    /// Encodes the puzzle as a boolean formula for an embedded SAT solver, ignores --max-nodes
+    Sat,
 }
 
-fn main() -> color_eyre::Result<()> {
-    color_eyre::install()?;
+impl SudokuSolver {
+    /// The solver of `registry` with the same name as the strategy on the command line
+    fn solver<'a>(&self, registry: &'a Registry) -> &'a dyn Solver {
+        let value = self.to_possible_value().expect("no solver is skipped");
+        registry
+            .get(value.get_name())
+            .expect("every strategy is a built-in solver")
+    }
+}
 
-    let cli = Cli::parse();
+/// The budgets of the solvers, see [`SolveConfig`]
+#[derive(Debug, Args)]
+struct BudgetArgs {
+    /// Give up on puzzles taking longer than this, like `500ms`, `2s` or `1m`
+    #[arg(long, value_parser = parse_duration)]
+    timeout: Option<Duration>,
+    /// Give up on puzzles after expanding this many search nodes
+    #[arg(long, value_name = "NODES")]
+    max_nodes: Option<u64>,
+}
 
-    match cli.mode {
-        Mode::Test {
-            solver,
-            file,
-            sudoku,
-        } => {
-            #[cfg(debug_assertions)]
-            println!("[WARN] Running test in debug mode, it will take very long to complete");
-
-            if file.is_some() && sudoku.is_some() {
-                println!("[WARN] Both a file and a sample sudoku provided, ignoring sudoku");
+impl BudgetArgs {
+    fn config<'a>(
+        &self,
+        search: &SearchArgs,
+        solvers: impl IntoIterator<Item = &'a SudokuSolver>,
+    ) -> SolveConfig {
+        let mut solvers = solvers.into_iter();
+        if self.max_nodes.is_some() && solvers.any(|solver| matches!(solver, SudokuSolver::Sat)) {
+            warn!("The sat solver doesn't expand search nodes, it ignores --max-nodes");
+        }
+        SolveConfig {
+            max_nodes: self.max_nodes,
+            max_time: self.timeout,
+            random_seed: search.seed,
+            propagation_level: Some(search.propagation_level.into()),
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+struct SearchArgs {
+    /// The logic applied before searching, only used by dfs and sorted-dfs
+    #[arg(long, value_enum, default_value_t)]
+    propagation_level: Propagation,
+    /// How sorted-dfs picks the cell to branch on
+    #[arg(long, value_enum, default_value_t)]
+    heuristic: CellHeuristic,
+    /// The order in which dfs and sorted-dfs try the candidates of a cell
+    #[arg(long, value_enum, default_value_t)]
+    value_order: Values,
+    /// Make dfs and sorted-dfs jump back to the guesses behind each dead end
+    #[arg(long)]
+    backjump: bool,
+    /// Make sorted-dfs search the candidates of its first guess on several threads
+    #[arg(long)]
+    parallel: bool,
+    /// The seed of `--value-order random`, making the searches reproducible
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Make dfs and sorted-dfs start over after this many nodes, doubling it every time
+    #[arg(long, value_name = "NODES")]
+    restarts: Option<u64>,
+}
+
+impl SearchArgs {
+    fn options(&self) -> SearchOptions {
+        SearchOptions {
+            level: self.propagation_level.into(),
+            heuristic: self.heuristic.into(),
+            value_order: self.value_order.into(),
+            backjump: self.backjump,
+            parallel: self.parallel,
+            seed: self.seed,
+            restarts: self.restarts,
+        }
+    }
+}
+
+/// How sorted-dfs picks the cell to branch on, see [`Heuristic`]
+#[derive(Debug, Default, ValueEnum, Clone, Copy)]
+enum CellHeuristic {
+    /// The first cell with the fewest candidates
+    Mrv,
+    /// The cell with the fewest candidates and the most empty peers
+    #[default]
+    MrvDegree,
+}
+
+impl From<CellHeuristic> for Heuristic {
+    fn from(heuristic: CellHeuristic) -> Self {
+        match heuristic {
+            CellHeuristic::Mrv => Heuristic::Mrv,
+            CellHeuristic::MrvDegree => Heuristic::MrvDegree,
+        }
+    }
+}
+
+/// The order in which the candidates of a cell are tried, see [`ValueOrder`]
+#[derive(Debug, Default, ValueEnum, Clone, Copy)]
+enum Values {
+    /// From the smallest to the largest
+    #[default]
+    Ascending,
+    /// The value removing the fewest candidates from its peers first
+    Lcv,
+    /// Shuffled at every branch
+    Random,
+}
+
+impl From<Values> for ValueOrder {
+    fn from(order: Values) -> Self {
+        match order {
+            Values::Ascending => ValueOrder::Ascending,
+            Values::Lcv => ValueOrder::Lcv,
+            Values::Random => ValueOrder::Random,
+        }
+    }
+}
+
+/// The difficulty of generated puzzles, see [`Difficulty`]
+#[derive(Debug, ValueEnum, Clone, Copy)]
+enum Level {
+    /// Solvable with singles
+    Easy,
+    /// Requires locked candidates
+    Medium,
+    /// Requires naked or hidden subsets, or fish
+    Hard,
+    /// Requires chains or a little guessing
+    Expert,
+    /// Requires extensive guessing
+    Diabolical,
+}
+
+impl From<Level> for Difficulty {
+    fn from(level: Level) -> Self {
+        match level {
+            Level::Easy => Difficulty::Easy,
+            Level::Medium => Difficulty::Medium,
+            Level::Hard => Difficulty::Hard,
+            Level::Expert => Difficulty::Expert,
+            Level::Diabolical => Difficulty::Diabolical,
+        }
+    }
+}
+
+/// The logic applied before searching, see [`PropagationLevel`]
+#[derive(Debug, Default, ValueEnum, Clone, Copy)]
+enum Propagation {
+    /// Naked and hidden singles
+    #[default]
+    Singles,
+    /// Singles and locked candidates
+    Locked,
+    /// Singles, locked candidates, naked and hidden subsets up to quads and fish up to jellyfish
+    Subsets,
+}
+
+impl From<Propagation> for PropagationLevel {
+    fn from(level: Propagation) -> Self {
+        match level {
+            Propagation::Singles => PropagationLevel::Singles,
+            Propagation::Locked => PropagationLevel::Locked,
+            Propagation::Subsets => PropagationLevel::Subsets,
+        }
+    }
+}
+
+/// The characters a pretty grid is drawn with, see [`Style`]
+#[derive(Debug, Default, ValueEnum, Clone, Copy)]
+enum GridStyle {
+    /// Borders drawn with `+`, `-` and `|`
+    #[default]
+    Ascii,
+    /// Borders drawn with box-drawing characters
+    Unicode,
+    /// No borders, only spaces between the boxes
+    Compact,
+    /// The single line form, one character per cell
+    Line,
+    /// Large cells with only the givens, to print and solve on paper
+    Blank,
+}
+
+impl GridStyle {
+    /// Draw a sudoku in this style
+    fn draw(self, sudoku: &Sudoku) -> String {
+        match self {
+            GridStyle::Ascii => sudoku.to_grid(Style::Ascii),
+            GridStyle::Unicode => sudoku.to_grid(Style::Unicode),
+            GridStyle::Compact => sudoku.to_grid(Style::Compact),
+            GridStyle::Line => sudoku.to_line(),
+            GridStyle::Blank => sudoku.to_grid(Style::Blank),
+        }
+    }
+}
+
+#[derive(Debug, Default, ValueEnum, Clone)]
+enum GridFormat {
+    /// A grid with the boxes outlined
+    #[default]
+    Pretty,
+    /// A single line per sudoku, empty cells are represented with `.`
+    Line,
+    /// A grid with the remaining candidates of every empty cell
+    Candidates,
+    /// A single line per sudoku, empty cells are represented with `0`
+    Sdm,
+    /// One row per line, with a blank line after each sudoku
+    Sdk,
+    /// The Simple Sudoku grid, with the boxes outlined
+    Ss,
+    /// A GitHub flavored Markdown table, to paste in issues and wikis
+    Markdown,
+}
+
+impl GridFormat {
+    fn write(
+        &self,
+        out: &mut impl Write,
+        sudoku: &Sudoku,
+        style: GridStyle,
+    ) -> std::io::Result<()> {
+        match self {
+            GridFormat::Pretty => match style {
+                GridStyle::Line => writeln!(out, "{}", sudoku.to_line()),
+                style => writeln!(out, "{}\n", style.draw(sudoku)),
+            },
+            GridFormat::Line => writeln!(out, "{}", sudoku.to_line()),
+            GridFormat::Candidates => {
+                let grid = techniques::Grid::new(sudoku).map_err(std::io::Error::other)?;
+                writeln!(out, "{grid}")
             }
+            GridFormat::Sdm => Format::Sdm.write(out, sudoku),
+            GridFormat::Sdk => Format::Sdk.write(out, sudoku),
+            GridFormat::Ss => Format::Ss.write(out, sudoku),
+            GridFormat::Markdown => writeln!(out, "{}\n", sudoku.to_markdown()),
+        }
+    }
+}
 
-            if let Some(file) = file {
-                println!("Reading Sudokus from file: {}", file.display());
-                let start = Instant::now();
-                let puzzles = String::from_utf8(std::fs::read(&file)?)?
-                    .lines()
-                    .enumerate()
-                    .map(|(ix, s)| Ok((ix, Sudoku::from_str(s)?)))
-                    .collect::<Result<Vec<(usize, Sudoku)>, ParseIntError>>()?;
-                println!("Took {:?} to parse puzzles", start.elapsed());
-
-                println!("Testing {solver:?}:");
-                let num_puzzles = puzzles.len();
-                let start = Instant::now();
-                let (ix, longest) = puzzles
-                    .into_par_iter()
-                    .progress_with_style(
-                        ProgressStyle::default_bar()
-                            .template("[{pos:>5}/{len}] {per_sec:>10} {wide_bar} {eta_precise}/{duration_precise}")
-                            .expect("valid template"),
-                    )
-                    .fold(
-                        || (0, Duration::from_secs(0)),
-                        |(ix, longest), (iy, puzzle)| {
-                            let start = Instant::now();
-                            let solution = match solver {
-                                SudokuSolver::Dfs => dfs(puzzle),
-                                SudokuSolver::NaiveDfs => naive_dfs(puzzle),
-                                SudokuSolver::SortedDfs => sorted_dfs(puzzle),
-                            };
-                            let end = start.elapsed();
-
-                            if let Err(puzzle) = solution {
-                                panic!("Failed to solve {puzzle}");
-                            }
-
-                            if end > longest {
-                                (iy, end)
-                            } else {
-                                (ix, longest)
-                            }
-                        },
-                    )
-                    .reduce(|| (0, Duration::from_secs(0)), |(ix, tx), (iy, ty)| if ty > tx { (iy, ty)} else {(ix, tx)});
-                let end = start.elapsed();
-                let cpu_time = end * num_cpus::get() as u32;
-                let per_puzzle = cpu_time / num_puzzles as u32;
-                println!("Took {end:?} [{per_puzzle:?}/sudoku]");
-                println!(
-                    "The longest solve was puzzle #{} and took {longest:?}",
-                    ix + 1
-                );
-            } else {
-                let puzzle: Sudoku = if let Some(s) = sudoku {
-                    s
-                } else {
-                    ".......1.4.........2...........5.4.7..8...3....1.9....3..4..2...5.1........8.6..."
-                    .parse()
-                    .expect("valid 9x9 Sudoku")
-                };
-
-                println!("Testing {solver:?} on:\n{puzzle}");
-                let start = Instant::now();
-                let solution = match solver {
-                    SudokuSolver::NaiveDfs => naive_dfs(puzzle),
-                    SudokuSolver::Dfs => dfs(puzzle),
-                    SudokuSolver::SortedDfs => sorted_dfs(puzzle),
-                };
-                println!("Took {:?}", start.elapsed());
-
-                if let Ok(puzzle) = solution {
-                    println!("Solution:\n{puzzle}")
-                } else {
-                    println!("No solution found for sudoku")
+#[derive(Debug, Default, ValueEnum, Clone, Copy, PartialEq, Eq)]
+enum InputFormat {
+    /// Guess the format from the file extension and its contents
+    #[default]
+    Auto,
+    /// One sudoku per line, empty cells are represented with `.`
+    Line,
+    /// One sudoku per line, empty cells are represented with `0`
+    Sdm,
+    /// One row per line, sudokus separated by blank or `#` metadata lines
+    Sdk,
+    /// The Simple Sudoku grid, with the boxes outlined by `|` and `-`
+    Ss,
+    /// The grids printed by the pretty and candidates output formats
+    Pretty,
+}
+
+impl InputFormat {
+    /// The format of the input read from `file`, `strict` only accepts the line format
+    fn resolve(self, strict: bool, file: Option<&Path>, input: &str) -> Format {
+        match self {
+            _ if strict => Format::Strict,
+            // Look past the extension of compressed files, `puzzles.sdk.gz` holds sdk puzzles
+            InputFormat::Auto => match file {
+                Some(file)
+                    if file
+                        .extension()
+                        .is_some_and(|ext| ext == "gz" || ext == "zst") =>
+                {
+                    Format::detect(Some(&file.with_extension("")), input)
                 }
-            }
+                _ => Format::detect(file, input),
+            },
+            InputFormat::Line => Format::Line,
+            InputFormat::Sdm => Format::Sdm,
+            InputFormat::Sdk => Format::Sdk,
+            InputFormat::Ss => Format::Ss,
+            InputFormat::Pretty => Format::Pretty,
         }
     }
+}
+
+/// Parse a duration made of a number and a unit (`ms`, `s` or `m`), seconds by default
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(s.len());
+    let (value, unit) = s.split_at(split);
+
+    let value: f64 = value
+        .parse()
+        .map_err(|_| format!("invalid duration `{s}`"))?;
+    let seconds = match unit.trim() {
+        "ms" => value / 1000.0,
+        "" | "s" => value,
+        "m" => value * 60.0,
+        unit => return Err(format!("unknown unit `{unit}`, expected `ms`, `s` or `m`")),
+    };
+
+    Duration::try_from_secs_f64(seconds).map_err(|err| err.to_string())
+}
+
+/// Use the given sudoku, or read the sudokus in a file or stdin
+fn read_puzzles(
+    sudoku: Option<Sudoku>,
+    file: Option<PathBuf>,
+    format: &FormatArgs,
+    variant: &VariantArgs,
+) -> color_eyre::Result<Vec<(usize, Sudoku)>> {
+    if let Some(sudoku) = sudoku {
+        return Ok(vec![(0, variant.apply(sudoku)?)]);
+    }
+
+    let input = read_input(file.as_deref())?;
+    let format = format.resolve(file.as_deref(), &input);
+    Ok(parse_puzzles(&input, format, variant))
+}
+
+/// The files matching each pattern in turn, `-` and paths without wildcards are kept as is
+fn expand_globs(patterns: &[PathBuf]) -> color_eyre::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for pattern in patterns {
+        let Some(glob) = pattern.to_str().filter(|s| s.contains(['*', '?', '['])) else {
+            files.push(pattern.clone());
+            continue;
+        };
+        let start = files.len();
+        for file in glob::glob(glob)? {
+            files.push(file?);
+        }
+        if files.len() == start {
+            color_eyre::eyre::bail!("no files match `{glob}`");
+        }
+    }
+    Ok(files)
+}
+
+/// Read a file, or stdin if the file is missing or `-`, see [`open_input`]
+fn read_input(file: Option<&Path>) -> color_eyre::Result<String> {
+    let mut input = Vec::new();
+    open_input(file)?.read_to_end(&mut input)?;
+    Ok(String::from_utf8(input)?)
+}
+
+/// The magic bytes starting a gzip stream
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+
+/// The magic bytes starting a zstd frame
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// How often [`with_spinner`] redraws the progress of a solve
+const SPINNER_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Open a file, or stdin if the file is missing or `-`
+///
+/// Gzip and zstd compressed input is recognized by its magic bytes and decompressed on the fly.
+fn open_input(file: Option<&Path>) -> color_eyre::Result<Box<dyn BufRead + Send>> {
+    let mut input: Box<dyn BufRead + Send> = match file {
+        Some(file) if file != Path::new("-") => Box::new(BufReader::new(File::open(file)?)),
+        _ => Box::new(BufReader::new(std::io::stdin())),
+    };
+
+    let start = input.fill_buf()?;
+    if start.starts_with(GZIP_MAGIC) {
+        input = Box::new(BufReader::new(MultiGzDecoder::new(input)));
+    } else if start.starts_with(ZSTD_MAGIC) {
+        input = Box::new(BufReader::new(zstd::Decoder::with_buffer(input)?));
+    }
+    Ok(input)
+}
+
+/// Make the first Ctrl-C stop the solvers so the results so far are reported, see [`interrupt`]
+///
+/// A second Ctrl-C exits right away.
+fn interrupt_on_ctrl_c() -> color_eyre::Result<()> {
+    ctrlc::set_handler(|| {
+        if interrupted() {
+            std::process::exit(Status::Interrupted as i32);
+        }
+        warn!("Interrupted, finishing up, press Ctrl-C again to exit right away");
+        interrupt();
+    })?;
     Ok(())
 }
+
+/// Run the solve of a single puzzle, drawing a spinner with the nodes searched per second, the
+/// depth of the search and the time spent on stderr until it returns
+///
+/// The spinner is hidden when stderr isn't a terminal.
+fn with_spinner<T>(solve: impl FnOnce() -> T) -> T {
+    let progress = Arc::new(Progress::default());
+    let spinner = ProgressBar::new_spinner().with_style(
+        ProgressStyle::default_spinner()
+            .template("{spinner} {elapsed_precise} {msg}")
+            .expect("valid template"),
+    );
+    let (done, finished) = mpsc::channel::<()>();
+
+    let result = std::thread::scope(|scope| {
+        let (spinner, progress) = (&spinner, &progress);
+        scope.spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = finished.recv_timeout(SPINNER_INTERVAL) {
+                let nodes = progress.nodes();
+                let rate = nodes as f64 / spinner.elapsed().as_secs_f64();
+                spinner.set_message(format!(
+                    "{} nodes, {} nodes/s, depth {} (deepest {})",
+                    HumanCount(nodes),
+                    HumanCount(rate as u64),
+                    progress.depth(),
+                    progress.max_depth()
+                ));
+            }
+        });
+        let result = with_progress(progress, solve);
+        drop(done);
+        result
+    });
+    spinner.finish_and_clear();
+    result
+}
+
+/// Parse the sudokus along with the line index they start on, skipping malformed ones
+fn parse_puzzles(input: &str, format: Format, variant: &VariantArgs) -> Vec<(usize, Sudoku)> {
+    format
+        .parse(input)
+        .into_iter()
+        .filter_map(|(ix, sudoku)| Some((ix, apply_variant(ix, sudoku, variant)?)))
+        .collect()
+}
+
+/// Apply the variant rules to the sudoku parsed on line `ix`, warning about malformed ones
+fn apply_variant(
+    ix: usize,
+    sudoku: Result<Sudoku, SudokuError>,
+    variant: &VariantArgs,
+) -> Option<Sudoku> {
+    match sudoku.and_then(|sudoku| variant.apply(sudoku)) {
+        Ok(sudoku) => Some(sudoku),
+        Err(err) => {
+            warn!("Skipping line {}: {err}", ix + 1);
+            None
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(status) => status.into(),
+        Err(report) => {
+            eprintln!("Error: {report:?}");
+            Status::of_error(&report).into()
+        }
+    }
+}
+
+fn run() -> color_eyre::Result<Status> {
+    color_eyre::install()?;
+
+    let cli = Cli::parse();
+    let level = match (cli.verbose, cli.quiet) {
+        (_, 2..) => None,
+        (_, 1) => Some(tracing::Level::ERROR),
+        (0, _) => Some(tracing::Level::INFO),
+        (1, _) => Some(tracing::Level::DEBUG),
+        (2.., _) => Some(tracing::Level::TRACE),
+    };
+    if let Some(level) = level {
+        tracing_subscriber::fmt()
+            .with_max_level(level)
+            .with_writer(std::io::stderr)
+            .with_ansi(std::io::stderr().is_terminal())
+            .without_time()
+            .with_target(false)
+            .init();
+    }
+    if let Some(threads) = cli.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()?;
+    }
+
+    let style = cli.style;
+    match cli.mode {
+        Mode::Test(args) => args.run(style),
+        Mode::Compare(args) => args.run(),
+        Mode::Bench(args) => args.run(),
+        Mode::Verify(args) => args.run(),
+        Mode::Solve(args) => args.run(style),
+        Mode::Print(args) => args.run(style),
+        Mode::Export(args) => args.run(),
+        Mode::Check(args) => args.run(),
+        Mode::CheckUnique(args) => args.run(),
+        Mode::Analyze(args) => args.run(),
+        Mode::Canonicalize(args) => args.run(style),
+        Mode::Equivalent(args) => args.run(),
+        Mode::Transform(args) => args.run(style),
+        Mode::Dedup(args) => args.run(style),
+        Mode::Stats(args) => args.run(),
+        Mode::Generate(args) => args.run(style),
+        Mode::Book(args) => args.run(),
+        Mode::Minimize(args) => args.run(style),
+        Mode::Rate(args) => args.run(),
+        Mode::Hint(args) => args.run(),
+        Mode::Play(args) => args.run(),
+        Mode::Replay(args) => args.run(),
+        Mode::Explain(args) => args.run(),
+        Mode::Serve(args) => args.run(),
+        Mode::Rpc(args) => args.run(),
+    }
+}
@@ -0,0 +1,587 @@
+//! Playing a sudoku in the terminal
+//!
+//! The [`Game`] keeps the values and pencil marks entered by the player on top of the givens,
+//! along with the history of edits to undo and redo. [`play`] draws it with ratatui and maps the
+//! keys to its actions until the player quits. Games are saved and resumed as a [`SaveGame`].
+
+use std::{
+    io::IsTerminal,
+    num::NonZeroU16,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use clap::Args;
+use nsudoku_solver::{
+    savegame::SaveGame,
+    solve::{has_unique_solution, sorted_dfs},
+    techniques::{self, Cell, Deduction, Technique},
+    Sudoku,
+};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Paragraph, Wrap},
+    DefaultTerminal, Frame,
+};
+
+use crate::{status::Status, TechniqueArgs, VariantArgs};
+
+/// The largest puzzles that can be played, every value has a key of its own
+pub const MAX_ORDER: usize = 9;
+
+/// The pencil marks of a cell are drawn in rows of this many values
+const MARKS_PER_LINE: usize = 3;
+
+/// How often the clock is redrawn while waiting for a key
+const TICK: Duration = Duration::from_secs(1);
+
+/// What the player wrote in a cell
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Entry {
+    value: Option<NonZeroU16>,
+    /// The values marked as candidates, value `v` being bit `v - 1`
+    marks: u16,
+}
+
+/// A change to a cell, undone by writing `before` back
+#[derive(Debug, Clone, Copy)]
+struct Edit {
+    cell: Cell,
+    before: Entry,
+    after: Entry,
+}
+
+/// A sudoku being played
+#[derive(Debug)]
+pub struct Game {
+    givens: Sudoku,
+    /// The solution, only known when it is unique
+    solution: Option<Sudoku>,
+    techniques: Vec<Technique>,
+    /// The entries of every cell in row-major order, those of the givens stay empty
+    entries: Vec<Entry>,
+    cursor: Cell,
+    /// Whether digits toggle pencil marks instead of filling the cell
+    pencil: bool,
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+    /// The cells of the last hint
+    hinted: Vec<Cell>,
+    message: String,
+    /// The time played before this session started
+    played: Duration,
+    started: Instant,
+    /// The time it took to solve the sudoku, once it is solved
+    finished: Option<Duration>,
+    /// Where the game is saved to
+    file: Option<PathBuf>,
+}
+
+impl Game {
+    /// Resume a saved game, or start a new one from [`SaveGame::new`], saving it to `file`
+    pub fn new(save: SaveGame, techniques: Vec<Technique>, file: Option<PathBuf>) -> Self {
+        let SaveGame {
+            givens,
+            entries,
+            marks,
+            elapsed,
+        } = save;
+        let solution = has_unique_solution(&givens)
+            .then(|| sorted_dfs(givens.clone()).0.ok())
+            .flatten();
+        let message = match solution {
+            Some(_) => String::new(),
+            None => "The sudoku doesn't have a unique solution".to_string(),
+        };
+        let entries = entries
+            .into_iter()
+            .zip(marks)
+            .map(|(value, marks)| Entry {
+                value,
+                marks: marks
+                    .iter()
+                    .fold(0, |all, mark| all | 1 << (mark.get() - 1)),
+            })
+            .collect();
+        let mut game = Self {
+            givens,
+            solution,
+            techniques,
+            entries,
+            cursor: (0, 0),
+            pencil: false,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            hinted: Vec::new(),
+            message,
+            played: elapsed,
+            started: Instant::now(),
+            finished: None,
+            file,
+        };
+        if game.solved() {
+            game.finished = Some(elapsed);
+        }
+        game
+    }
+
+    /// The time the sudoku has been played for, stopping once it is solved
+    fn elapsed(&self) -> Duration {
+        self.finished
+            .unwrap_or_else(|| self.played + self.started.elapsed())
+    }
+
+    /// The state of the game, without its history
+    fn to_save(&self) -> SaveGame {
+        let mut save = SaveGame::new(self.givens.clone());
+        for (ix, entry) in self.entries.iter().enumerate() {
+            save.entries[ix] = entry.value;
+            save.marks[ix] = (1..=self.order() as u16)
+                .filter(|value| entry.marks & (1 << (value - 1)) != 0)
+                .filter_map(NonZeroU16::new)
+                .collect();
+        }
+        save.elapsed = self.elapsed();
+        save
+    }
+
+    fn save(&mut self) {
+        let Some(file) = &self.file else {
+            self.message = "Pass --save to choose where to save the game".to_string();
+            return;
+        };
+        self.message = match std::fs::write(file, self.to_save().to_string()) {
+            Ok(()) => format!("Saved to {}", file.display()),
+            Err(err) => format!("Couldn't save to {}: {err}", file.display()),
+        };
+    }
+
+    fn order(&self) -> usize {
+        self.givens.order()
+    }
+
+    fn entry(&self, (row, col): Cell) -> Entry {
+        self.entries[row * self.order() + col]
+    }
+
+    /// The value shown in a cell, given or entered
+    fn value(&self, (row, col): Cell) -> Option<NonZeroU16> {
+        self.givens.get(row, col).or(self.entry((row, col)).value)
+    }
+
+    /// Move the cursor by `rows` and `cols`, wrapping around the edges
+    fn move_cursor(&mut self, rows: isize, cols: isize) {
+        let order = self.order() as isize;
+        let (row, col) = self.cursor;
+        self.cursor = (
+            (row as isize + rows).rem_euclid(order) as usize,
+            (col as isize + cols).rem_euclid(order) as usize,
+        );
+    }
+
+    /// Fill the cell under the cursor with `value`, or toggle its mark in pencil mode
+    fn enter(&mut self, value: NonZeroU16) {
+        if value.get() as usize > self.order() {
+            return;
+        }
+        let before = self.entry(self.cursor);
+        let after = match (self.pencil, before.value) {
+            (true, None) => Entry {
+                marks: before.marks ^ (1 << (value.get() - 1)),
+                ..before
+            },
+            (true, Some(_)) => {
+                self.message = "Clear the cell before marking it".to_string();
+                return;
+            }
+            (false, _) => Entry {
+                value: Some(value),
+                ..before
+            },
+        };
+        self.edit(after);
+    }
+
+    /// Empty the cell under the cursor, its value first and then its marks
+    fn clear(&mut self) {
+        let before = self.entry(self.cursor);
+        let after = match before.value {
+            Some(_) => Entry {
+                value: None,
+                ..before
+            },
+            None => Entry::default(),
+        };
+        self.edit(after);
+    }
+
+    fn edit(&mut self, after: Entry) {
+        let cell = self.cursor;
+        if self.givens.get(cell.0, cell.1).is_some() {
+            self.message = format!("{} is a given", name(cell));
+            return;
+        }
+        let before = self.entry(cell);
+        if before == after {
+            return;
+        }
+        self.write(cell, after);
+        self.undo.push(Edit {
+            cell,
+            before,
+            after,
+        });
+        self.redo.clear();
+        if self.finished.is_none() && self.solved() {
+            self.finished = Some(self.elapsed());
+            self.message = "Solved!".to_string();
+        }
+    }
+
+    fn undo(&mut self) {
+        match self.undo.pop() {
+            Some(edit) => {
+                self.write(edit.cell, edit.before);
+                self.cursor = edit.cell;
+                self.redo.push(edit);
+            }
+            None => self.message = "Nothing to undo".to_string(),
+        }
+    }
+
+    fn redo(&mut self) {
+        match self.redo.pop() {
+            Some(edit) => {
+                self.write(edit.cell, edit.after);
+                self.cursor = edit.cell;
+                self.undo.push(edit);
+            }
+            None => self.message = "Nothing to redo".to_string(),
+        }
+    }
+
+    fn write(&mut self, (row, col): Cell, entry: Entry) {
+        let order = self.order();
+        self.entries[row * order + col] = entry;
+        self.hinted.clear();
+        self.message.clear();
+    }
+
+    /// The cells repeating a value of one of their peers
+    fn conflicts(&self) -> Vec<Cell> {
+        let order = self.order();
+        (0..order)
+            .flat_map(|row| (0..order).map(move |col| (row, col)))
+            .filter(|&cell| {
+                self.value(cell).is_some_and(|value| {
+                    self.givens
+                        .peers(cell.0, cell.1)
+                        .expect("cells of the grid")
+                        .any(|(peer, _)| self.value(peer) == Some(value))
+                })
+            })
+            .collect()
+    }
+
+    /// The givens with the entered values, `None` if they break the rules
+    fn board(&self) -> Option<Sudoku> {
+        let mut board = self.givens.clone();
+        for (ix, entry) in self.entries.iter().enumerate() {
+            if let Some(value) = entry.value {
+                board
+                    .set(ix / self.order(), ix % self.order(), value)
+                    .ok()?;
+            }
+        }
+        Some(board)
+    }
+
+    fn solved(&self) -> bool {
+        self.board().is_some_and(|board| board.solved())
+    }
+
+    /// Point out a mistake, or the next step of the technique engine
+    fn hint(&mut self) {
+        self.hinted.clear();
+        if self.solved() {
+            self.message = "The sudoku is already solved".to_string();
+            return;
+        }
+
+        let conflicts = self.conflicts();
+        if !conflicts.is_empty() {
+            self.message = "Fix the repeated values first".to_string();
+            self.hinted = conflicts;
+            return;
+        }
+        let Some(board) = self.board() else {
+            self.message = "The values break the rules of the sudoku".to_string();
+            return;
+        };
+
+        let mistake = self.solution.as_ref().and_then(|solution| {
+            self.entries.iter().enumerate().find_map(|(ix, entry)| {
+                let cell = (ix / self.order(), ix % self.order());
+                entry
+                    .value
+                    .filter(|&value| Some(value) != solution.get(cell.0, cell.1))
+                    .map(|_| cell)
+            })
+        });
+        if let Some(cell) = mistake {
+            self.message = format!("{} doesn't lead to the solution", name(cell));
+            self.hinted = vec![cell];
+            return;
+        }
+
+        match techniques::hint_with(&board, &self.techniques) {
+            Ok(Some(step)) => {
+                self.message = step.to_string();
+                self.hinted = match step.deduction {
+                    Deduction::Place { cell, .. } => vec![cell],
+                    Deduction::Eliminate(_) => step.cells,
+                };
+            }
+            Ok(None) => self.message = "No logical step found, guessing is required".to_string(),
+            Err(err) => self.message = format!("No hints, {err}"),
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let order = self.order();
+        let lines = order.div_ceil(MARKS_PER_LINE);
+        let boxes = self.givens.box_shape();
+        let width = order * 4 + (order / boxes.cols - 1) * 2 + 1;
+        let height = order * lines + order / boxes.rows - 1;
+
+        let [board, side] =
+            Layout::horizontal([Constraint::Length(width as u16 + 2), Constraint::Min(20)])
+                .areas(frame.area());
+        let [board, _] =
+            Layout::vertical([Constraint::Length(height as u16 + 2), Constraint::Min(0)])
+                .areas(board);
+
+        let conflicts = self.conflicts();
+        let mut text = Vec::with_capacity(height);
+        for row in 0..order {
+            if row > 0 && row % boxes.rows == 0 {
+                text.push(Line::from(separator(order, boxes.cols)));
+            }
+            for line in 0..lines {
+                let mut spans = Vec::new();
+                for col in 0..order {
+                    if col > 0 && col % boxes.cols == 0 {
+                        spans.push(Span::raw(" │"));
+                    }
+                    spans.push(Span::raw(" "));
+                    let cell = (row, col);
+                    let content = self.cell_line(cell, line, lines);
+                    spans.push(Span::styled(content, self.style(cell, &conflicts)));
+                }
+                text.push(Line::from(spans));
+            }
+        }
+        frame.render_widget(
+            Paragraph::new(text).block(Block::bordered().title(" Sudoku ")),
+            board,
+        );
+
+        let mode = if self.pencil {
+            "pencil marks"
+        } else {
+            "values"
+        };
+        let elapsed = self.elapsed().as_secs();
+        let mut help = vec![
+            Line::from(format!("{} entering {mode}", name(self.cursor))),
+            Line::from(format!(
+                "Time {:02}:{:02}:{:02}",
+                elapsed / 3600,
+                elapsed / 60 % 60,
+                elapsed % 60
+            )),
+            Line::from(""),
+            Line::from("arrows/hjkl  move"),
+            Line::from(format!("1-{order}          enter a value")),
+            Line::from("0/del        clear the cell"),
+            Line::from("p            toggle pencil marks"),
+            Line::from("u/ctrl-r     undo/redo"),
+            Line::from("?            hint"),
+            Line::from("s            save"),
+            Line::from("q/esc        quit"),
+            Line::from(""),
+        ];
+        help.push(Line::styled(
+            self.message.as_str(),
+            Style::new().add_modifier(Modifier::BOLD),
+        ));
+        frame.render_widget(
+            Paragraph::new(help)
+                .wrap(Wrap { trim: true })
+                .block(Block::bordered().title(" Play ")),
+            side,
+        );
+    }
+
+    /// Line `line` of the `lines` a cell is drawn on, its value in the middle or its marks
+    fn cell_line(&self, cell: Cell, line: usize, lines: usize) -> String {
+        if let Some(value) = self.value(cell) {
+            return if line == lines / 2 {
+                format!(" {value} ")
+            } else {
+                "   ".to_string()
+            };
+        }
+        let marks = self.entry(cell).marks;
+        (line * MARKS_PER_LINE..(line + 1) * MARKS_PER_LINE)
+            .map(|ix| {
+                if ix < self.order() && marks & (1 << ix) != 0 {
+                    char::from_digit(ix as u32 + 1, 10).expect("a single digit")
+                } else {
+                    ' '
+                }
+            })
+            .collect()
+    }
+
+    fn style(&self, cell: Cell, conflicts: &[Cell]) -> Style {
+        let mut style = match self.givens.get(cell.0, cell.1) {
+            Some(_) => Style::new().add_modifier(Modifier::BOLD),
+            None if self.entry(cell).value.is_some() => Style::new().fg(Color::Cyan),
+            None => Style::new().fg(Color::DarkGray),
+        };
+        if conflicts.contains(&cell) {
+            style = style.fg(Color::Red);
+        }
+        if self.hinted.contains(&cell) {
+            style = style.bg(Color::Yellow).fg(Color::Black);
+        }
+        if cell == self.cursor {
+            style = style.add_modifier(Modifier::REVERSED);
+        }
+        style
+    }
+}
+
+/// The line between two bands of boxes
+fn separator(order: usize, stack: usize) -> String {
+    let mut line = String::new();
+    for col in 0..order {
+        if col > 0 && col % stack == 0 {
+            line.push_str("─┼");
+        }
+        line.push_str("────");
+    }
+    line.push('─');
+    line
+}
+
+fn name((row, col): Cell) -> String {
+    format!("r{}c{}", row + 1, col + 1)
+}
+
+/// Play `game` until the player quits
+pub fn play(mut game: Game) -> color_eyre::Result<()> {
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, &mut game);
+    ratatui::restore();
+    result
+}
+
+fn run(terminal: &mut DefaultTerminal, game: &mut Game) -> color_eyre::Result<()> {
+    loop {
+        terminal.draw(|frame| game.draw(frame))?;
+        if !event::poll(TICK)? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Char('c') if ctrl => return Ok(()),
+            KeyCode::Char('r') if ctrl => game.redo(),
+            KeyCode::Up | KeyCode::Char('k') => game.move_cursor(-1, 0),
+            KeyCode::Down | KeyCode::Char('j') => game.move_cursor(1, 0),
+            KeyCode::Left | KeyCode::Char('h') => game.move_cursor(0, -1),
+            KeyCode::Right | KeyCode::Char('l') => game.move_cursor(0, 1),
+            KeyCode::Char('0' | '.') | KeyCode::Delete | KeyCode::Backspace => game.clear(),
+            KeyCode::Char('p') => game.pencil = !game.pencil,
+            KeyCode::Char('u') => game.undo(),
+            KeyCode::Char('?') => game.hint(),
+            KeyCode::Char('s') => game.save(),
+            KeyCode::Char(digit) => {
+                if let Some(value) = digit
+                    .to_digit(10)
+                    .and_then(|digit| NonZeroU16::new(digit as u16))
+                {
+                    game.enter(value);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct PlayArgs {
+    /// The string representation of a Sudoku
+    puzzle: Option<Sudoku>,
+    /// The string representation of a Sudoku
+    #[arg(
+        short,
+        long,
+        conflicts_with = "puzzle",
+        required_unless_present_any = ["puzzle", "load"]
+    )]
+    sudoku: Option<Sudoku>,
+    /// Resume a saved game, keeping the rules it was saved with
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["puzzle", "sudoku"])]
+    load: Option<PathBuf>,
+    /// Where `s` saves the game to, the file it was loaded from by default
+    #[arg(long, value_name = "FILE")]
+    save: Option<PathBuf>,
+    #[command(flatten)]
+    techniques: TechniqueArgs,
+    #[command(flatten)]
+    variant: VariantArgs,
+}
+
+impl PlayArgs {
+    /// Play a sudoku in the terminal, with pencil marks, undo and hints from the techniques
+    pub fn run(self) -> color_eyre::Result<Status> {
+        let Self {
+            puzzle,
+            sudoku,
+            load,
+            save,
+            techniques,
+            variant,
+        } = self;
+        let game: SaveGame = match &load {
+            Some(file) => std::fs::read_to_string(file)?.parse()?,
+            None => SaveGame::new(variant.apply(puzzle.or(sudoku).expect("required by clap"))?),
+        };
+        let puzzle = &game.givens;
+        if puzzle.order() > MAX_ORDER {
+            color_eyre::eyre::bail!(
+                "only puzzles up to {0}x{0} can be played, got {1}x{1}",
+                MAX_ORDER,
+                puzzle.order()
+            );
+        }
+        if !std::io::stdout().is_terminal() {
+            color_eyre::eyre::bail!("playing needs a terminal");
+        }
+        play(Game::new(game, techniques.selected(), save.or(load)))?;
+        Ok(Status::Success)
+    }
+}
@@ -0,0 +1,140 @@
+//! Printing puzzles without solving them, and exporting them for other tools
+
+use std::{
+    io::{IsTerminal, Write},
+    path::PathBuf,
+};
+
+use clap::{Args, ValueEnum};
+use nsudoku_solver::{
+    render::{html, Drawing},
+    solve::{sorted_dfs_with, Cnf, ExactCover, SearchOptions},
+};
+
+use crate::{status::Status, GridFormat, GridStyle, InputArgs, VariantArgs};
+
+/// The encodings written by `export`
+#[derive(Debug, ValueEnum, Clone, Copy)]
+enum ExportFormat {
+    /// The 0/1 exact cover matrix, one row per candidate placement and one column per constraint
+    ExactCover,
+    /// A boolean formula in the DIMACS CNF format read by SAT solvers
+    Cnf,
+    /// A vector image of the grid, with the givens in bold
+    Svg,
+    /// The SVG image rasterized to `--size` pixels
+    Png,
+    /// A standalone web page with the grid to fill in
+    Html,
+}
+
+#[derive(Debug, Args)]
+pub struct PrintArgs {
+    #[command(flatten)]
+    input: InputArgs,
+    #[command(flatten)]
+    variant: VariantArgs,
+    /// How to print the puzzles
+    #[arg(long, value_enum, default_value_t)]
+    format: GridFormat,
+}
+
+impl PrintArgs {
+    /// Print sudokus without solving them, `--style blank` draws them to solve on paper
+    pub fn run(self, style: GridStyle) -> color_eyre::Result<Status> {
+        let Self {
+            input,
+            variant,
+            format,
+        } = self;
+        let puzzles = input.read(&variant)?;
+
+        let mut out = std::io::stdout().lock();
+        for (_, puzzle) in puzzles {
+            format.write(&mut out, &puzzle, style)?;
+        }
+        Ok(Status::Success)
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct ExportArgs {
+    #[command(flatten)]
+    input: InputArgs,
+    #[command(flatten)]
+    variant: VariantArgs,
+    /// What to write
+    #[arg(long, value_enum)]
+    format: ExportFormat,
+    /// Draw the solution in the empty cells of an image, or hide it behind a toggle on a page
+    #[arg(long)]
+    solution: bool,
+    /// Draw the candidates left in the empty cells of an image
+    #[arg(long, conflicts_with = "solution")]
+    candidates: bool,
+    /// The width and height of a PNG image in pixels
+    #[arg(long, default_value_t = 1024)]
+    size: u32,
+    /// The title of an HTML page
+    #[arg(long, default_value = "Sudoku")]
+    title: String,
+    /// Write to a file instead of stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+impl ExportArgs {
+    /// Write the constraints of a sudoku for external solvers
+    pub fn run(self) -> color_eyre::Result<Status> {
+        let Self {
+            input,
+            variant,
+            format,
+            solution,
+            candidates,
+            size,
+            title,
+            output,
+        } = self;
+        let puzzles = input.read(&variant)?;
+        let [(_, puzzle)] = &puzzles[..] else {
+            color_eyre::eyre::bail!("export takes a single puzzle, got {}", puzzles.len());
+        };
+
+        let drawing = || -> color_eyre::Result<Drawing> {
+            let mut drawing = Drawing::new(puzzle);
+            if solution {
+                let solved = sorted_dfs_with(puzzle.clone(), SearchOptions::default()).0?;
+                drawing = drawing.with_solution(&solved)?;
+            }
+            if candidates {
+                drawing = drawing.with_candidates();
+            }
+            Ok(drawing)
+        };
+        let encoded = match format {
+            ExportFormat::ExactCover => ExactCover::new(puzzle).to_string().into_bytes(),
+            ExportFormat::Cnf => Cnf::new(puzzle).to_string().into_bytes(),
+            ExportFormat::Svg => drawing()?.to_svg().into_bytes(),
+            ExportFormat::Png => {
+                if output.is_none() && std::io::stdout().is_terminal() {
+                    color_eyre::eyre::bail!(
+                        "not writing a PNG image to the terminal, pass --output"
+                    );
+                }
+                drawing()?.to_png(size)?
+            }
+            ExportFormat::Html => {
+                let solved = solution
+                    .then(|| sorted_dfs_with(puzzle.clone(), SearchOptions::default()).0)
+                    .transpose()?;
+                html::page(puzzle, solved.as_ref(), &title)?.into_bytes()
+            }
+        };
+        match output {
+            Some(output) => std::fs::write(output, encoded)?,
+            None => std::io::stdout().lock().write_all(&encoded)?,
+        }
+        Ok(Status::Success)
+    }
+}
@@ -0,0 +1,209 @@
+//! Stepping through a trace recorded with `solve --trace` in the terminal
+//!
+//! The [`Viewer`] rebuilds the board after any number of events of the [`Trace`] with a
+//! [`Replay`], and [`replay`] draws its pencil marks with ratatui, stepping on the keys or playing
+//! the events back at a steady speed.
+
+use std::{
+    io::IsTerminal,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use clap::Args;
+use nsudoku_solver::{solve::Replay, SudokuError};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, Paragraph, Wrap},
+    DefaultTerminal, Frame,
+};
+
+use crate::{
+    status::Status,
+    trace::{self, Trace},
+};
+
+/// The events per second played back when no `--speed` was given
+const DEFAULT_SPEED: f64 = 20.0;
+
+/// The board is redrawn at most this often while playing, several events apply per frame above it
+const FRAME: Duration = Duration::from_millis(40);
+
+/// How long to wait for a key while paused
+const TICK: Duration = Duration::from_secs(1);
+
+/// A trace being replayed
+#[derive(Debug)]
+pub struct Viewer {
+    trace: Trace,
+    replay: Replay,
+    /// The number of events applied to the board
+    position: usize,
+    /// The events per second played back
+    speed: f64,
+    playing: bool,
+}
+
+impl Viewer {
+    /// Start at the givens of the trace, playing it back at `speed` events per second if given
+    pub fn new(trace: Trace, speed: Option<f64>) -> Result<Self, SudokuError> {
+        Ok(Self {
+            replay: Replay::new(&trace.puzzle)?,
+            trace,
+            position: 0,
+            speed: speed.unwrap_or(DEFAULT_SPEED),
+            playing: speed.is_some(),
+        })
+    }
+
+    /// Apply up to `count` more events
+    fn forward(&mut self, count: usize) {
+        let end = (self.position + count).min(self.trace.events.len());
+        for event in &self.trace.events[self.position..end] {
+            self.replay.apply(event);
+        }
+        self.position = end;
+        if self.position == self.trace.events.len() {
+            self.playing = false;
+        }
+    }
+
+    /// Go back `count` events, replaying the trace from the start
+    fn back(&mut self, count: usize) {
+        let position = self.position.saturating_sub(count);
+        self.replay = Replay::new(&self.trace.puzzle).expect("the viewer replayed the givens");
+        self.position = 0;
+        self.forward(position);
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let board = self.replay.grid().to_string();
+        let width = board.lines().map(str::len).max().unwrap_or_default();
+        let [board_area, side] =
+            Layout::horizontal([Constraint::Length(width as u16 + 2), Constraint::Min(20)])
+                .areas(frame.area());
+        frame.render_widget(
+            Paragraph::new(board).block(Block::bordered().title(" Trace ")),
+            board_area,
+        );
+
+        let last = self.position.checked_sub(1).map(|ix| self.trace.events[ix]);
+        let state = if self.playing {
+            format!("playing at {} events/s", self.speed)
+        } else {
+            "paused".to_string()
+        };
+        let mut help = vec![
+            Line::from(format!("Solver {}", self.trace.solver)),
+            Line::from(format!(
+                "Event {} of {}, {state}",
+                self.position,
+                self.trace.events.len()
+            )),
+            Line::from(""),
+            Line::from("right/l      next event"),
+            Line::from("left/h       previous event"),
+            Line::from("pgup/pgdn    100 events back/forward"),
+            Line::from("home/end     first/last event"),
+            Line::from("space        play/pause"),
+            Line::from("+/-          faster/slower"),
+            Line::from("q/esc        quit"),
+            Line::from(""),
+        ];
+        help.push(Line::styled(
+            last.map_or_else(|| "givens".to_string(), |event| event.to_string()),
+            Style::new().add_modifier(Modifier::BOLD),
+        ));
+        frame.render_widget(
+            Paragraph::new(help)
+                .wrap(Wrap { trim: true })
+                .block(Block::bordered().title(" Replay ")),
+            side,
+        );
+    }
+}
+
+/// Replay the trace of `viewer` until the user quits
+pub fn replay(mut viewer: Viewer) -> color_eyre::Result<()> {
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, &mut viewer);
+    ratatui::restore();
+    result
+}
+
+fn run(terminal: &mut DefaultTerminal, viewer: &mut Viewer) -> color_eyre::Result<()> {
+    let mut played = Instant::now();
+    loop {
+        terminal.draw(|frame| viewer.draw(frame))?;
+        // The events due since the last frame, so high speeds aren't capped by the frame rate
+        let interval = Duration::from_secs_f64(1.0 / viewer.speed);
+        if viewer.playing && played.elapsed() >= interval {
+            let due = played.elapsed().as_secs_f64() * viewer.speed;
+            viewer.forward(due as usize);
+            played = Instant::now();
+            continue;
+        }
+
+        let timeout = if viewer.playing {
+            interval.saturating_sub(played.elapsed()).max(FRAME)
+        } else {
+            TICK
+        };
+        if !event::poll(timeout)? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+            KeyCode::Right | KeyCode::Char('l') => viewer.forward(1),
+            KeyCode::Left | KeyCode::Char('h') => viewer.back(1),
+            KeyCode::PageDown => viewer.forward(100),
+            KeyCode::PageUp => viewer.back(100),
+            KeyCode::End => viewer.forward(viewer.trace.events.len()),
+            KeyCode::Home => viewer.back(viewer.position),
+            KeyCode::Char(' ') => {
+                viewer.playing = !viewer.playing && viewer.position < viewer.trace.events.len();
+                played = Instant::now();
+            }
+            KeyCode::Char('+') => viewer.speed *= 2.0,
+            KeyCode::Char('-') => viewer.speed = (viewer.speed / 2.0).max(0.25),
+            _ => {}
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct ReplayArgs {
+    /// The trace written by `solve --trace`
+    #[arg(value_name = "FILE")]
+    trace: PathBuf,
+    /// Play the events back at this many per second instead of waiting for the keys
+    #[arg(long, value_name = "EVENTS")]
+    speed: Option<f64>,
+}
+
+impl ReplayArgs {
+    /// Step through a trace recorded with `solve --trace` in the terminal
+    pub fn run(self) -> color_eyre::Result<Status> {
+        let Self { trace, speed } = self;
+        if speed.is_some_and(|speed| !(speed.is_finite() && speed > 0.0)) {
+            color_eyre::eyre::bail!("--speed must be a positive number of events per second");
+        }
+        let trace = trace::load(&trace)?;
+        if !std::io::stdout().is_terminal() {
+            color_eyre::eyre::bail!("replaying needs a terminal");
+        }
+        replay(Viewer::new(trace, speed)?)?;
+        Ok(Status::Success)
+    }
+}
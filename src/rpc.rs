@@ -0,0 +1,301 @@
+//! A JSON-RPC 2.0 server on stdin and stdout, one message per line
+//!
+//! Every method takes `{"puzzle": "..."}` as its params:
+//!
+//! - `solve` answers `{"solution": "..."}`
+//! - `hint` answers the next logical step, `{"technique": "...", "description": "...",
+//!   "place": {"cell": "r1c1", "value": 5}}` or `"eliminate"` with a list of such cells and
+//!   values, or `null` when the puzzle is solved or needs guessing
+//! - `validate` answers `{"valid": true, "solutions": "unique"}`, with `"none"` or `"multiple"`
+//!   solutions, or `{"valid": false, "error": "..."}` when the givens break the rules
+//! - `rate` answers the rating of the puzzle like `serve`
+//!
+//! Errors use the codes of the specification, and `1` when a puzzle has no solution or `2` when
+//! solving, counting the solutions or rating it gave up after the timeout. Batches are answered
+//! with a batch, notifications aren't answered.
+
+use std::{
+    io::{BufRead, Write},
+    time::Duration,
+};
+
+use clap::Args;
+use nsudoku_solver::{
+    rate,
+    solve::{
+        count_solutions, out_of_time, sorted_dfs_with, with_timeout, SearchOptions, SolveError,
+    },
+    techniques::{self, Deduction},
+    Sudoku,
+};
+use serde_json::{json, Value};
+use tracing::debug;
+
+use crate::{parse_duration, status::Status};
+
+/// The message isn't valid JSON
+const PARSE_ERROR: i64 = -32700;
+/// The message isn't a request object
+const INVALID_REQUEST: i64 = -32600;
+/// There's no method with this name
+const METHOD_NOT_FOUND: i64 = -32601;
+/// The params aren't `{"puzzle": "..."}` with a valid puzzle
+const INVALID_PARAMS: i64 = -32602;
+/// The puzzle has no solution
+const UNSOLVABLE: i64 = 1;
+/// The solver gave up on the puzzle after running out of time
+const TIMEOUT: i64 = 2;
+
+/// An error answering a request, with its code and message
+struct RpcError(i64, String);
+
+/// Answer the requests read from `input` on `output` until the end of the input
+pub fn serve(
+    input: impl BufRead,
+    mut output: impl Write,
+    timeout: Duration,
+) -> color_eyre::Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(Value::Array(batch)) if !batch.is_empty() => {
+                let responses: Vec<_> = batch
+                    .into_iter()
+                    .filter_map(|request| respond(request, timeout))
+                    .collect();
+                (!responses.is_empty()).then_some(Value::Array(responses))
+            }
+            Ok(request) => respond(request, timeout),
+            Err(err) => Some(failure(Value::Null, RpcError(PARSE_ERROR, err.to_string()))),
+        };
+        if let Some(response) = response {
+            writeln!(output, "{response}")?;
+            output.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// The response to a request, `None` for notifications
+fn respond(request: Value, timeout: Duration) -> Option<Value> {
+    let id = request.get("id").cloned();
+    let method = request.get("method").and_then(Value::as_str);
+    let (Some(method), Some("2.0")) = (method, request.get("jsonrpc").and_then(Value::as_str))
+    else {
+        let message = "expected a JSON-RPC 2.0 request".to_string();
+        return Some(failure(
+            id.unwrap_or(Value::Null),
+            RpcError(INVALID_REQUEST, message),
+        ));
+    };
+    debug!(method, "answering a request");
+
+    let result = call(method, request.get("params"), timeout);
+    let id = id?;
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(err) => failure(id, err),
+    })
+}
+
+fn call(method: &str, params: Option<&Value>, timeout: Duration) -> Result<Value, RpcError> {
+    let text = || {
+        params
+            .and_then(|params| params.get("puzzle"))
+            .and_then(Value::as_str)
+            .ok_or_else(|| RpcError(INVALID_PARAMS, "expected {\"puzzle\": \"...\"}".into()))
+    };
+    let puzzle =
+        || Sudoku::parse_lenient(text()?).map_err(|err| RpcError(INVALID_PARAMS, err.to_string()));
+
+    match method {
+        "solve" => {
+            let puzzle = puzzle()?;
+            let (solution, stats) = with_timeout(timeout, || {
+                sorted_dfs_with(puzzle, SearchOptions::default())
+            });
+            match solution {
+                Ok(solution) => Ok(json!({ "solution": solution.to_line() })),
+                Err(_) if stats.timed_out => Err(gave_up(timeout)),
+                Err(SolveError::Unsupported(err)) => Err(RpcError(INVALID_PARAMS, err.to_string())),
+                Err(err) => Err(RpcError(
+                    UNSOLVABLE,
+                    format!("the sudoku has no solution, {err}"),
+                )),
+            }
+        }
+        "hint" => {
+            let puzzle = puzzle()?;
+            let step = if puzzle.solved() {
+                None
+            } else {
+                techniques::hint(&puzzle)
+                    .map_err(|err| RpcError(INVALID_PARAMS, err.to_string()))?
+            };
+            let Some(step) = step else {
+                return Ok(Value::Null);
+            };
+            let cell = |(row, col): (usize, usize), value: u16| json!({ "cell": format!("r{}c{}", row + 1, col + 1), "value": value });
+            let deduction = match &step.deduction {
+                Deduction::Place { cell: at, value } => ("place", cell(*at, value.get())),
+                Deduction::Eliminate(eliminations) => (
+                    "eliminate",
+                    eliminations
+                        .iter()
+                        .map(|&(at, value)| cell(at, value.get()))
+                        .collect(),
+                ),
+            };
+            Ok(json!({
+                "technique": step.technique.to_string(),
+                "description": step.to_string(),
+                deduction.0: deduction.1,
+            }))
+        }
+        // A puzzle breaking the rules is a valid question with a negative answer
+        "validate" => match Sudoku::parse_lenient(text()?) {
+            Ok(puzzle) => {
+                let (count, timed_out) =
+                    with_timeout(timeout, || (count_solutions(&puzzle, 2), out_of_time()));
+                let solutions = match count {
+                    _ if timed_out => return Err(gave_up(timeout)),
+                    0 => "none",
+                    1 => "unique",
+                    _ => "multiple",
+                };
+                Ok(json!({ "valid": true, "solutions": solutions }))
+            }
+            Err(err) => Ok(json!({ "valid": false, "error": err.to_string() })),
+        },
+        "rate" => {
+            let puzzle = puzzle()?;
+            let (rating, timed_out) =
+                with_timeout(timeout, || (rate::rate(&puzzle), out_of_time()));
+            match rating {
+                Ok(Some(rating)) => Ok(crate::serve::rating(&rating)),
+                Ok(None) if timed_out => Err(gave_up(timeout)),
+                Ok(None) => Err(RpcError(UNSOLVABLE, "the sudoku has no solution".into())),
+                Err(err) => Err(RpcError(INVALID_PARAMS, err.to_string())),
+            }
+        }
+        method => Err(RpcError(
+            METHOD_NOT_FOUND,
+            format!("no method called {method:?}"),
+        )),
+    }
+}
+
+fn gave_up(timeout: Duration) -> RpcError {
+    RpcError(TIMEOUT, format!("gave up after {timeout:?}"))
+}
+
+fn failure(id: Value, RpcError(code, message): RpcError) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+#[derive(Debug, Args)]
+pub struct RpcArgs {
+    /// Give up on puzzles taking longer than this, like `500ms`, `2s` or `1m`
+    #[arg(long, value_parser = parse_duration, default_value = "10s")]
+    timeout: Duration,
+}
+
+impl RpcArgs {
+    /// Answer solve, hint, validate and rate requests with JSON-RPC on stdin and stdout
+    pub fn run(self) -> color_eyre::Result<Status> {
+        let Self { timeout } = self;
+        serve(std::io::stdin().lock(), std::io::stdout().lock(), timeout)?;
+        Ok(Status::Success)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use serde_json::{json, Value};
+
+    use super::{serve, INVALID_REQUEST, METHOD_NOT_FOUND, PARSE_ERROR, TIMEOUT};
+
+    #[test]
+    fn answers_requests_batches_and_errors() {
+        let puzzle = json!({ "puzzle": "12.4341.2.434.21" });
+        let input = [
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "solve", "params": puzzle }).to_string(),
+            // A notification, and a blank line
+            json!({ "jsonrpc": "2.0", "method": "solve", "params": puzzle }).to_string(),
+            String::new(),
+            json!([
+                {
+                    "jsonrpc": "2.0",
+                    "id": 2,
+                    "method": "validate",
+                    "params": { "puzzle": "11.............." },
+                },
+                { "jsonrpc": "2.0", "method": "rate", "params": puzzle },
+                { "jsonrpc": "2.0", "id": 3, "method": "guess" },
+                { "id": 4, "method": "solve" },
+            ])
+            .to_string(),
+            "{\"jsonrpc\": ".to_string(),
+        ]
+        .join("\n");
+        let mut output = Vec::new();
+        serve(input.as_bytes(), &mut output, Duration::from_secs(10)).expect("in-memory io");
+
+        let responses: Vec<Value> = String::from_utf8(output)
+            .expect("JSON is UTF-8")
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("one response per line"))
+            .collect();
+        let [solved, batch, unparsed] = &responses[..] else {
+            panic!("expected three responses, got {responses:?}");
+        };
+
+        assert_eq!(
+            solved,
+            &json!({ "jsonrpc": "2.0", "id": 1, "result": { "solution": "1234341221434321" } })
+        );
+
+        let Value::Array(batch) = batch else {
+            panic!("expected a batch, got {batch}");
+        };
+        let [invalid, missing, malformed] = &batch[..] else {
+            panic!("the notification of the batch was answered: {batch:?}");
+        };
+        assert_eq!(invalid["id"], 2);
+        assert_eq!(invalid["result"]["valid"], false);
+        assert!(invalid["result"]["error"].is_string());
+        assert_eq!(missing["id"], 3);
+        assert_eq!(missing["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(malformed["id"], 4);
+        assert_eq!(malformed["error"]["code"], INVALID_REQUEST);
+
+        assert_eq!(unparsed["id"], Value::Null);
+        assert_eq!(unparsed["error"]["code"], PARSE_ERROR);
+    }
+
+    #[test]
+    fn gives_up_after_the_timeout() {
+        // The empty grid needs a search to count its solutions or rate it
+        let empty = json!({ "puzzle": ".".repeat(25 * 25) });
+        let input = ["validate", "rate"]
+            .map(|method| {
+                json!({ "jsonrpc": "2.0", "id": method, "method": method, "params": empty })
+                    .to_string()
+            })
+            .join("\n");
+        let mut output = Vec::new();
+        serve(input.as_bytes(), &mut output, Duration::ZERO).expect("in-memory io");
+
+        let output = String::from_utf8(output).expect("JSON is UTF-8");
+        for line in output.lines() {
+            let response: Value = serde_json::from_str(line).expect("one response per line");
+            assert_eq!(response["error"]["code"], TIMEOUT, "{response}");
+        }
+        assert_eq!(output.lines().count(), 2);
+    }
+}
@@ -0,0 +1,514 @@
+//! A small HTTP server answering solve, rate and generate requests with JSON
+//!
+//! - `POST /solve` takes `{"puzzle": "..."}` and answers `{"solution": "..."}`
+//! - `POST /rate` takes `{"puzzle": "..."}` and answers its score, difficulty, hardest technique
+//!   and backtracks
+//! - `GET /generate?difficulty=hard&order=9` answers `{"puzzle": "...", "difficulty": "hard"}`,
+//!   both parameters are optional
+//!
+//! Failed requests are answered with their status code and `{"error": "..."}`. Every response
+//! allows any origin, so web pages can call the server directly.
+//!
+//! Long solves and generations can be followed as server-sent events instead, from
+//! `GET /solve/events?puzzle=...` and `GET /generate/events?difficulty=hard`. A `progress` event
+//! is sent every quarter of a second, then the answer of the plain endpoint as a `done` event, or
+//! an `error` event if it failed. The progress of a solve holds the nodes searched, the deepest
+//! guess and the time left before it gives up, and the progress of a generation the attempts
+//! made and the time left until all of them are used up.
+
+use std::{
+    io::{Read, Write},
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use clap::{Args, ValueEnum};
+use nsudoku_solver::{
+    generate::{self, Symmetry},
+    rate::{self, Rating},
+    solve::{
+        interrupted, out_of_time, sorted_dfs_with, with_progress, with_timeout, Progress,
+        SearchOptions, SolveError,
+    },
+    Sudoku,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Response, Server};
+use tracing::{debug, info, warn};
+
+use crate::{interrupt_on_ctrl_c, parse_duration, status::Status, Level};
+
+/// The largest request body read, puzzles are far smaller
+const MAX_BODY: u64 = 64 * 1024;
+/// The largest puzzles generated, larger ones take too long to answer
+const MAX_ORDER: usize = 16;
+/// How often the server checks for Ctrl-C while waiting for requests
+const POLL: Duration = Duration::from_millis(200);
+/// How often the progress of a streamed request is sent
+const TICK: Duration = Duration::from_millis(250);
+
+/// The status of a response and its JSON body, `None` answers with an empty body
+type Answer = (u16, Option<Value>);
+
+/// The settings shared by every request
+#[derive(Debug, Clone, Copy)]
+pub struct Settings {
+    /// How long a puzzle is searched before giving up
+    pub timeout: Duration,
+    /// The puzzles generated looking for one of the requested difficulty
+    pub attempts: usize,
+}
+
+/// The body of the solve and rate requests
+#[derive(Deserialize)]
+struct PuzzleRequest {
+    puzzle: String,
+}
+
+/// Answer requests on `address` until Ctrl-C, each on a thread of the rayon pool
+pub fn serve(address: &str, settings: Settings) -> color_eyre::Result<()> {
+    let server = Server::http(address).map_err(|err| color_eyre::eyre::eyre!(err))?;
+    info!("Listening on http://{address}");
+
+    while !interrupted() {
+        if let Some(request) = server.recv_timeout(POLL)? {
+            // Rayon aborts the process when a job panics, taking every other request with it
+            rayon::spawn(move || {
+                if panic::catch_unwind(AssertUnwindSafe(|| respond(request, settings))).is_err() {
+                    warn!("Failed to answer a request, it panicked");
+                }
+            });
+        }
+    }
+    Ok(())
+}
+
+fn respond(mut request: Request, settings: Settings) {
+    let url = request.url();
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    if *request.method() == Method::Get && path.ends_with("/events") {
+        let (path, query) = (path.to_string(), query.to_string());
+        return stream(request, &path, &query, settings);
+    }
+
+    let mut body = String::new();
+    let (status, answer) = match request.as_reader().take(MAX_BODY).read_to_string(&mut body) {
+        Ok(_) => guarded(|| handle(request.method(), request.url(), &body, settings)),
+        Err(err) => error(400, format!("can't read the request body: {err}")),
+    };
+    debug!(method = %request.method(), url = request.url(), status, "answered a request");
+    answer_with(request, status, answer);
+}
+
+fn answer_with(request: Request, status: u16, answer: Option<Value>) {
+    let response = match answer {
+        Some(answer) => Response::from_string(answer.to_string())
+            .with_header(header("Content-Type", "application/json")),
+        None => Response::from_string(""),
+    };
+    let response = response
+        .with_status_code(status)
+        .with_header(header("Access-Control-Allow-Origin", "*"))
+        .with_header(header("Access-Control-Allow-Methods", "GET, POST, OPTIONS"))
+        .with_header(header("Access-Control-Allow-Headers", "Content-Type"));
+    if let Err(err) = request.respond(response) {
+        warn!("Failed to answer a request: {err}");
+    }
+}
+
+/// The answer to a request
+fn handle(method: &Method, url: &str, body: &str, settings: Settings) -> Answer {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    match (method, path) {
+        // The preflight requests of browsers
+        (Method::Options, _) => (204, None),
+        (Method::Post, "/solve") => match puzzle(body) {
+            Ok(puzzle) => solve(puzzle, settings),
+            Err(err) => err,
+        },
+        (Method::Post, "/rate") => match puzzle(body) {
+            Ok(puzzle) => rate(puzzle, settings),
+            Err(err) => err,
+        },
+        (Method::Get, "/generate") => match generation(query) {
+            Ok((level, order)) => generate(level, order, settings, &AtomicUsize::new(0)),
+            Err(err) => err,
+        },
+        (_, "/solve" | "/rate" | "/generate" | "/solve/events" | "/generate/events") => {
+            error(405, format!("{method} is not allowed"))
+        }
+        _ => error(404, format!("nothing at {path}")),
+    }
+}
+
+/// Stream the progress of a request to an `/events` endpoint, see the [module docs](self)
+fn stream(request: Request, path: &str, query: &str, settings: Settings) {
+    let started = Instant::now();
+    debug!(path, "streaming the progress of a request");
+    match path {
+        "/solve/events" => {
+            let puzzle = match parameters(query).and_then(|parameters| {
+                let puzzle = parameters
+                    .iter()
+                    .find(|(key, _)| key == "puzzle")
+                    .ok_or_else(|| error(400, "expected a puzzle parameter"))?;
+                Sudoku::parse_lenient(&puzzle.1).map_err(|err| error(400, err.to_string()))
+            }) {
+                Ok(puzzle) => puzzle,
+                Err((status, answer)) => return answer_with(request, status, answer),
+            };
+            let progress = Arc::new(Progress::default());
+            events(
+                request,
+                || with_progress(&progress, || solve(puzzle, settings)),
+                || {
+                    let elapsed = started.elapsed();
+                    json!({
+                        "nodes": progress.nodes(),
+                        "depth": progress.max_depth(),
+                        "elapsed_ms": elapsed.as_millis(),
+                        "remaining_ms": settings.timeout.saturating_sub(elapsed).as_millis(),
+                    })
+                },
+            );
+        }
+        "/generate/events" => {
+            let (level, order) = match generation(query) {
+                Ok(generation) => generation,
+                Err((status, answer)) => return answer_with(request, status, answer),
+            };
+            let attempts = if level.is_some() {
+                settings.attempts
+            } else {
+                1
+            };
+            let tried = AtomicUsize::new(0);
+            events(
+                request,
+                || generate(level, order, settings, &tried),
+                || {
+                    let elapsed = started.elapsed();
+                    let tried = tried.load(Ordering::Relaxed);
+                    // The slowest case, where every attempt is used up
+                    let remaining = (tried > 0).then(|| {
+                        elapsed.mul_f64(attempts.saturating_sub(tried) as f64 / tried as f64)
+                    });
+                    json!({
+                        "attempts": tried,
+                        "of": attempts,
+                        "elapsed_ms": elapsed.as_millis(),
+                        "remaining_ms": remaining.map(|remaining| remaining.as_millis()),
+                    })
+                },
+            );
+        }
+        path => {
+            let (status, answer) = error(404, format!("nothing at {path}"));
+            answer_with(request, status, answer);
+        }
+    }
+}
+
+/// Run `work` on another thread, sending `progress` every tick and then its answer
+fn events(request: Request, work: impl FnOnce() -> Answer + Send, progress: impl Fn() -> Value) {
+    let mut out = request.into_writer();
+    // The client going away is the usual way for a stream to fail
+    if let Err(err) = write_events(&mut out, work, progress) {
+        debug!("Stopped streaming the answer to a request: {err}");
+    }
+}
+
+/// Write the response streaming the events, chunk by chunk since `tiny_http` buffers its own
+/// responses
+fn write_events(
+    out: &mut impl Write,
+    work: impl FnOnce() -> Answer + Send,
+    progress: impl Fn() -> Value,
+) -> std::io::Result<()> {
+    write!(
+        out,
+        "HTTP/1.1 200 OK\r\n\
+        Content-Type: text/event-stream\r\n\
+        Cache-Control: no-cache\r\n\
+        Access-Control-Allow-Origin: *\r\n\
+        Transfer-Encoding: chunked\r\n\r\n"
+    )?;
+    let mut send = |event: &str, data: &Value| {
+        let message = format!("event: {event}\ndata: {data}\n\n");
+        write!(out, "{:x}\r\n{message}\r\n", message.len())?;
+        out.flush()
+    };
+
+    thread::scope(|scope| {
+        let (sender, receiver) = mpsc::channel();
+        scope.spawn(move || {
+            // Nobody is listening once the client went away
+            let _ = sender.send(guarded(work));
+        });
+
+        let (status, answer) = loop {
+            match receiver.recv_timeout(TICK) {
+                Ok(answered) => break answered,
+                Err(RecvTimeoutError::Timeout) => send("progress", &progress())?,
+                Err(RecvTimeoutError::Disconnected) => break error(500, "the request failed"),
+            }
+        };
+        let event = if status == 200 { "done" } else { "error" };
+        send(event, &answer.unwrap_or(Value::Null))
+    })?;
+    write!(out, "0\r\n\r\n")?;
+    out.flush()
+}
+
+/// The puzzle in the body of a request, or the error answering it
+fn puzzle(body: &str) -> Result<Sudoku, Answer> {
+    let request: PuzzleRequest = serde_json::from_str(body)
+        .map_err(|err| error(400, format!("expected {{\"puzzle\": \"...\"}}: {err}")))?;
+    Sudoku::parse_lenient(&request.puzzle).map_err(|err| error(400, err.to_string()))
+}
+
+fn solve(puzzle: Sudoku, settings: Settings) -> Answer {
+    let (solution, stats) = with_timeout(settings.timeout, || {
+        sorted_dfs_with(puzzle, SearchOptions::default())
+    });
+    match solution {
+        Ok(solution) => (200, Some(json!({ "solution": solution.to_line() }))),
+        Err(_) if stats.timed_out => error(503, format!("gave up after {:?}", settings.timeout)),
+        Err(SolveError::Unsupported(err)) => error(400, err.to_string()),
+        Err(err) => error(422, format!("the sudoku has no solution, {err}")),
+    }
+}
+
+fn rate(puzzle: Sudoku, settings: Settings) -> Answer {
+    let (rating, timed_out) =
+        with_timeout(settings.timeout, || (rate::rate(&puzzle), out_of_time()));
+    match rating {
+        Ok(Some(rating)) => (200, Some(self::rating(&rating))),
+        Ok(None) if timed_out => error(503, format!("gave up after {:?}", settings.timeout)),
+        Ok(None) => error(422, "the sudoku has no solution"),
+        Err(err) => error(400, err.to_string()),
+    }
+}
+
+/// The difficulty and order of the puzzle asked for by the query of a generate request
+fn generation(query: &str) -> Result<(Option<Level>, usize), Answer> {
+    let mut level = None;
+    let mut order = 9;
+    for (key, value) in parameters(query)? {
+        match key.as_str() {
+            "difficulty" => match Level::from_str(&value, true) {
+                Ok(parsed) => level = Some(parsed),
+                Err(_) => return Err(error(400, format!("unknown difficulty {value:?}"))),
+            },
+            "order" => match value.parse() {
+                Ok(parsed) if parsed <= MAX_ORDER => order = parsed,
+                _ => return Err(error(400, format!("expected an order up to {MAX_ORDER}"))),
+            },
+            _ => return Err(error(400, format!("unknown parameter {key:?}"))),
+        }
+    }
+    Ok((level, order))
+}
+
+/// Generate a puzzle of `level`, counting the puzzles generated in `tried`
+fn generate(level: Option<Level>, order: usize, settings: Settings, tried: &AtomicUsize) -> Answer {
+    let rng = &mut rand::thread_rng();
+    let attempts = if level.is_some() {
+        settings.attempts
+    } else {
+        1
+    };
+    for _ in 0..attempts {
+        if interrupted() {
+            break;
+        }
+        let generated = match level {
+            None => generate::puzzle_with(order, None, Symmetry::default(), rng)
+                .and_then(|puzzle| Ok(rate::rate(&puzzle)?.map(|rating| (puzzle, rating)))),
+            Some(level) => generate::puzzle_of_difficulty(
+                order,
+                None,
+                Symmetry::default(),
+                level.into(),
+                1,
+                rng,
+            ),
+        };
+        tried.fetch_add(1, Ordering::Relaxed);
+        match generated {
+            Ok(Some((puzzle, rating))) => {
+                return (
+                    200,
+                    Some(json!({
+                        "puzzle": puzzle.to_line(),
+                        "difficulty": rating.difficulty.to_string(),
+                    })),
+                )
+            }
+            Ok(None) => {}
+            Err(err) => return error(400, err.to_string()),
+        }
+    }
+    error(503, format!("no puzzle found in {attempts} attempts"))
+}
+
+/// The decoded key and value pairs of a query string
+fn parameters(query: &str) -> Result<Vec<(String, String)>, Answer> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            Ok((decode(key)?, decode(value)?))
+        })
+        .collect()
+}
+
+/// Undo the percent-encoding of a query parameter, where `+` also stands for a space
+fn decode(text: &str) -> Result<String, Answer> {
+    let malformed = || error(400, format!("malformed query parameter {text:?}"));
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        rest = tail;
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                // `from_str_radix` would also take a sign
+                let hex = rest
+                    .get(..2)
+                    .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+                    .ok_or_else(malformed)?;
+                let hex = std::str::from_utf8(hex).expect("hex digits are ASCII");
+                bytes.push(u8::from_str_radix(hex, 16).expect("two hex digits fit a byte"));
+                rest = &rest[2..];
+            }
+            byte => bytes.push(byte),
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| malformed())
+}
+
+/// The rating of a puzzle as answered by `/rate`, also used by the `rate` method of [`rpc`](crate::rpc)
+pub fn rating(rating: &Rating) -> Value {
+    json!({
+        "score": rating.score,
+        "difficulty": rating.difficulty.to_string(),
+        "hardest": rating.hardest.map(|technique| technique.to_string()),
+        "backtracks": rating.backtracks,
+    })
+}
+
+/// Run `work`, answering with a 500 if it panics
+fn guarded(work: impl FnOnce() -> Answer) -> Answer {
+    panic::catch_unwind(AssertUnwindSafe(work))
+        .unwrap_or_else(|_| error(500, "the request failed unexpectedly"))
+}
+
+fn error(status: u16, message: impl Into<String>) -> Answer {
+    (status, Some(json!({ "error": message.into() })))
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name, value).expect("valid header")
+}
+
+#[derive(Debug, Args)]
+pub struct ServeArgs {
+    /// The port to listen on
+    #[arg(short, long, default_value_t = 8080)]
+    port: u16,
+    /// The address to listen on, `0.0.0.0` accepts requests from other machines
+    #[arg(long, default_value = "127.0.0.1")]
+    host: String,
+    /// Give up on puzzles taking longer than this, like `500ms`, `2s` or `1m`
+    #[arg(long, value_parser = parse_duration, default_value = "10s")]
+    timeout: Duration,
+    /// The puzzles generated looking for one of the requested difficulty
+    #[arg(long, default_value_t = generate::ATTEMPTS)]
+    attempts: usize,
+}
+
+impl ServeArgs {
+    /// Answer solve, rate and generate requests over HTTP with JSON
+    pub fn run(self) -> color_eyre::Result<Status> {
+        let Self {
+            port,
+            host,
+            timeout,
+            attempts,
+        } = self;
+        interrupt_on_ctrl_c()?;
+        serve(&format!("{host}:{port}"), Settings { timeout, attempts })?;
+        Ok(Status::Success)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use tiny_http::Method;
+
+    use super::{decode, handle, parameters, Settings};
+
+    #[test]
+    fn decodes_query_parameters() {
+        assert_eq!(decode("very+hard").expect("plain text"), "very hard");
+        assert_eq!(decode("a%20b%2Bc").expect("escapes"), "a b+c");
+        assert_eq!(decode("%e2%9C%93").expect("UTF-8 escapes"), "\u{2713}");
+        for malformed in ["%", "%2", "%zz", "%+1", "%ff"] {
+            let (status, _) = decode(malformed).expect_err(malformed);
+            assert_eq!(status, 400, "{malformed}");
+        }
+
+        let parsed = parameters("difficulty=very+hard&&order").expect("valid query");
+        assert_eq!(
+            parsed,
+            [
+                ("difficulty".to_string(), "very hard".to_string()),
+                ("order".to_string(), String::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn routes_requests() {
+        let settings = Settings {
+            timeout: Duration::from_secs(10),
+            attempts: 1,
+        };
+        let status = |method, url| handle(&method, url, "", settings).0;
+
+        assert_eq!(status(Method::Options, "/solve"), 204);
+        assert_eq!(status(Method::Get, "/solve"), 405);
+        assert_eq!(status(Method::Delete, "/rate"), 405);
+        assert_eq!(status(Method::Post, "/generate/events"), 405);
+        assert_eq!(status(Method::Get, "/nowhere"), 404);
+        assert_eq!(status(Method::Post, "/solve"), 400);
+
+        let (status, body) = handle(
+            &Method::Post,
+            "/solve?ignored=1",
+            r#"{"puzzle": "12.4341.2.434.21"}"#,
+            settings,
+        );
+        assert_eq!(status, 200);
+        assert_eq!(body.expect("a JSON body")["solution"], "1234341221434321");
+
+        // Rating the empty grid needs a search, which gives up before it starts
+        let impatient = Settings {
+            timeout: Duration::ZERO,
+            ..settings
+        };
+        let empty = format!(r#"{{"puzzle": "{}"}}"#, ".".repeat(25 * 25));
+        assert_eq!(handle(&Method::Post, "/rate", &empty, impatient).0, 503);
+    }
+}
@@ -0,0 +1,329 @@
+//! Solving and checking puzzles, for `solve`, `check`, `check-unique` and `analyze`
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::PathBuf,
+};
+
+use clap::Args;
+use nsudoku_solver::{
+    generate,
+    solve::{
+        count_solutions, has_unique_solution, sorted_dfs_with, Cnf, Registry, SearchOptions,
+        SolveError,
+    },
+    Sudoku, SudokuError,
+};
+use rayon::prelude::*;
+use tracing::warn;
+
+use crate::{
+    status::Status, trace, visualize, with_spinner, BudgetArgs, GridFormat, GridStyle, InputArgs,
+    SearchArgs, SudokuSolver, VariantArgs,
+};
+
+#[derive(Debug, Args)]
+pub struct SolveArgs {
+    #[command(flatten)]
+    input: InputArgs,
+    /// The solver strategy to use
+    #[arg(long, value_enum, default_value_t)]
+    solver: SudokuSolver,
+    #[command(flatten)]
+    search: SearchArgs,
+    #[command(flatten)]
+    variant: VariantArgs,
+    #[command(flatten)]
+    budget: BudgetArgs,
+    /// How to print the solutions
+    #[arg(long, value_enum, default_value_t)]
+    format: GridFormat,
+    /// Write the solutions to a file instead of stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Also write the puzzle as a DIMACS CNF formula for external SAT solvers
+    #[arg(long, value_name = "FILE")]
+    export_cnf: Option<PathBuf>,
+    /// Record every step of the solver to a file as JSON lines, for a single puzzle
+    ///
+    /// Only dfs, sorted-dfs, propagate and logic report their steps.
+    #[arg(long, value_name = "FILE")]
+    trace: Option<PathBuf>,
+    /// Redraw the candidates on stderr as the solver changes them, for a single puzzle
+    #[arg(long, conflicts_with = "trace")]
+    visualize: bool,
+}
+
+impl SolveArgs {
+    /// Solve sudokus and print their solutions
+    pub fn run(self, style: GridStyle) -> color_eyre::Result<Status> {
+        let Self {
+            input,
+            solver,
+            search,
+            variant,
+            budget,
+            format,
+            output,
+            export_cnf,
+            trace,
+            visualize,
+        } = self;
+        let mut status = Status::Success;
+        let puzzles = input.read(&variant)?;
+
+        if let Some(export) = export_cnf {
+            let [(_, puzzle)] = &puzzles[..] else {
+                color_eyre::eyre::bail!(
+                    "--export-cnf takes a single puzzle, got {}",
+                    puzzles.len()
+                );
+            };
+            std::fs::write(export, Cnf::new(puzzle).to_string())?;
+        }
+
+        let registry = Registry::with_builtins(search.options());
+        let strategy = solver.solver(&registry);
+        let config = budget.config(&search, [&solver]);
+        // A single puzzle may take a while, show that the search is getting somewhere
+        let solutions: Vec<_> = match &puzzles[..] {
+            [(ix, puzzle)] => {
+                let solve = || strategy.solve_with(puzzle, &config).0;
+                let solution = match &trace {
+                    Some(trace) => {
+                        trace::record(trace, puzzle, strategy.name(), || with_spinner(solve))?
+                    }
+                    None if visualize => visualize::visualize(puzzle, solve)?,
+                    None => with_spinner(solve),
+                };
+                vec![(*ix, solution)]
+            }
+            _ if trace.is_some() || visualize => color_eyre::eyre::bail!(
+                "--trace and --visualize take a single puzzle, got {}",
+                puzzles.len()
+            ),
+            _ => puzzles
+                .into_par_iter()
+                .map(|(ix, puzzle)| (ix, strategy.solve_with(&puzzle, &config).0))
+                .collect(),
+        };
+
+        let mut out: Box<dyn Write> = if let Some(output) = output {
+            Box::new(BufWriter::new(File::create(output)?))
+        } else {
+            Box::new(std::io::stdout().lock())
+        };
+
+        for (ix, solution) in solutions {
+            match solution {
+                Ok(_) => {}
+                Err(SolveError::Aborted { .. }) => status = status.max(Status::Timeout),
+                Err(SolveError::Unsupported(_)) => status = status.max(Status::InvalidInput),
+                Err(_) => status = status.max(Status::Unsolvable),
+            }
+            match solution {
+                Ok(solution) => format.write(&mut out, &solution, style)?,
+                Err(SolveError::Unsolved(partial)) if !strategy.guesses() && partial.valid() => {
+                    warn!("Stuck on sudoku on line {}, guessing is required", ix + 1);
+                    format.write(&mut out, &partial, style)?;
+                }
+                Err(SolveError::Unsolvable { reason }) => {
+                    warn!("Sudoku on line {} has no solution, {reason}", ix + 1)
+                }
+                Err(SolveError::Aborted { budget }) => {
+                    warn!("Gave up on sudoku on line {}, {budget}", ix + 1)
+                }
+                Err(SolveError::Unsupported(err)) => {
+                    warn!(
+                        "Can't search sudoku on line {}, {err}, try --solver dlx",
+                        ix + 1
+                    )
+                }
+                Err(_) => warn!("No solution found for sudoku on line {}", ix + 1),
+            }
+        }
+        out.flush()?;
+        Ok(status)
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct CheckArgs {
+    /// The givens of the puzzle
+    #[arg(long)]
+    puzzle: Sudoku,
+    /// The grid filled by the player, repeated values are reported as mistakes
+    #[arg(long)]
+    candidate: String,
+    #[command(flatten)]
+    variant: VariantArgs,
+}
+
+impl CheckArgs {
+    /// Check a filled grid against its puzzle, listing the cells that are wrong
+    pub fn run(self) -> color_eyre::Result<Status> {
+        let Self {
+            puzzle,
+            candidate,
+            variant,
+        } = self;
+        let puzzle = variant.apply(puzzle)?;
+        let candidate =
+            match Sudoku::parse_lenient(&candidate).and_then(|filled| variant.apply(filled)) {
+                Ok(candidate) => candidate,
+                Err(SudokuError::RepeatedGivens(conflicts)) => {
+                    println!("The candidate repeats values:");
+                    for conflict in conflicts {
+                        println!("  {conflict}");
+                    }
+                    return Ok(Status::Incorrect);
+                }
+                Err(SudokuError::InconsistentGivens) => {
+                    println!("The candidate breaks the rules of the sudoku");
+                    return Ok(Status::Incorrect);
+                }
+                Err(err) => return Err(err.into()),
+            };
+
+        let cell = |(row, col): (usize, usize)| format!("r{}c{}", row + 1, col + 1);
+        let mut mistakes = Vec::new();
+        for difference in puzzle.diff(&candidate)? {
+            let name = cell(difference.cell);
+            match (difference.left, difference.right) {
+                (Some(given), Some(value)) => {
+                    mistakes.push(format!("{name} holds {value} instead of the given {given}"))
+                }
+                (Some(given), None) => {
+                    mistakes.push(format!("{name} is missing the given {given}"))
+                }
+                (None, _) => {}
+            }
+        }
+        // Only a unique solution tells which of the values entered are wrong
+        if has_unique_solution(&puzzle) {
+            let solution = sorted_dfs_with(puzzle.clone(), SearchOptions::default())
+                .0
+                .expect("the sudoku has a solution");
+            for difference in solution.diff(&candidate)? {
+                let (row, col) = difference.cell;
+                if let (None, Some(value)) = (puzzle.get(row, col), difference.right) {
+                    mistakes.push(format!(
+                        "{} holds {value}, which is not the solution",
+                        cell(difference.cell)
+                    ));
+                }
+            }
+        }
+        let order = candidate.order();
+        let empty: Vec<_> = (0..order)
+            .flat_map(|row| (0..order).map(move |col| (row, col)))
+            .filter(|&(row, col)| {
+                candidate.get(row, col).is_none() && puzzle.get(row, col).is_none()
+            })
+            .map(cell)
+            .collect();
+        if !empty.is_empty() {
+            mistakes.push(format!("{} empty cells: {}", empty.len(), empty.join(" ")));
+        }
+
+        if mistakes.is_empty() {
+            println!("The candidate solves the sudoku");
+            Ok(Status::Success)
+        } else {
+            println!("The candidate is not a solution:");
+            for mistake in mistakes {
+                println!("  {mistake}");
+            }
+            Ok(Status::Incorrect)
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct CheckUniqueArgs {
+    #[command(flatten)]
+    input: InputArgs,
+    #[command(flatten)]
+    variant: VariantArgs,
+}
+
+impl CheckUniqueArgs {
+    /// Check whether sudokus have no solution, a unique solution or multiple solutions
+    pub fn run(self) -> color_eyre::Result<Status> {
+        let Self { input, variant } = self;
+        let mut status = Status::Success;
+        let puzzles = input.read(&variant)?;
+
+        let counts: Vec<_> = puzzles
+            .par_iter()
+            .map(|(ix, puzzle)| (ix, count_solutions(puzzle, 2)))
+            .collect();
+
+        for (ix, count) in counts {
+            let (solutions, outcome) = match count {
+                0 => ("no solution", Status::Unsolvable),
+                1 => ("a unique solution", Status::Success),
+                _ => ("multiple solutions", Status::MultipleSolutions),
+            };
+            status = status.max(outcome);
+            println!("Sudoku on line {} has {solutions}", ix + 1);
+        }
+        Ok(status)
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct AnalyzeArgs {
+    #[command(flatten)]
+    input: InputArgs,
+    /// Check whether every clue is necessary, listing the ones that can be removed
+    #[arg(long)]
+    minimal: bool,
+    #[command(flatten)]
+    variant: VariantArgs,
+}
+
+impl AnalyzeArgs {
+    /// Report the clues and solutions of sudokus
+    pub fn run(self) -> color_eyre::Result<Status> {
+        let Self {
+            input,
+            minimal,
+            variant,
+        } = self;
+        let puzzles = input.read(&variant)?;
+
+        let reports: Vec<_> = puzzles
+            .par_iter()
+            .map(|(ix, puzzle)| {
+                let solutions = count_solutions(puzzle, 2);
+                let redundant = (minimal && solutions == 1)
+                    .then(|| generate::redundant_clues(puzzle))
+                    .flatten();
+                (ix, puzzle.clues(), solutions, redundant)
+            })
+            .collect();
+
+        for (ix, clues, solutions, redundant) in reports {
+            let solutions = match solutions {
+                0 => "no solution",
+                1 => "a unique solution",
+                _ => "multiple solutions",
+            };
+            print!("Sudoku on line {}: {clues} clues, {solutions}", ix + 1);
+            match redundant {
+                Some(redundant) if redundant.is_empty() => println!(", minimal"),
+                Some(redundant) => {
+                    let cells: Vec<_> = redundant
+                        .iter()
+                        .map(|(row, col)| format!("r{}c{}", row + 1, col + 1))
+                        .collect();
+                    println!(", {} redundant clues: {}", redundant.len(), cells.join(" "));
+                }
+                None => println!(),
+            }
+        }
+        Ok(Status::Success)
+    }
+}
@@ -0,0 +1,171 @@
+//! Statistics of the clues of many puzzles
+
+use std::{collections::BTreeMap, fmt::Display, path::PathBuf};
+
+use clap::Args;
+use nsudoku_solver::{generate::Symmetry, Sudoku};
+use rayon::prelude::*;
+
+use crate::{read_puzzles, status::Status, FormatArgs, VariantArgs};
+
+/// The symmetries counted by [`ClueStats`]
+const SYMMETRIES: [Symmetry; 3] = [Symmetry::Rotational, Symmetry::Mirror, Symmetry::Diagonal];
+
+/// The clues of a batch of puzzles
+#[derive(Debug, Default)]
+pub struct ClueStats {
+    pub puzzles: usize,
+    /// The number of puzzles with each number of clues
+    clues: BTreeMap<usize, usize>,
+    /// How many times each value is given, over every puzzle
+    values: BTreeMap<u16, usize>,
+    /// The number of puzzles with each number of empty rows
+    empty_rows: BTreeMap<usize, usize>,
+    /// The number of puzzles with each number of empty columns
+    empty_cols: BTreeMap<usize, usize>,
+    /// The number of puzzles with each number of empty boxes
+    empty_boxes: BTreeMap<usize, usize>,
+    /// The number of puzzles keeping each of [`SYMMETRIES`]
+    symmetries: [usize; SYMMETRIES.len()],
+    /// The number of puzzles keeping none of them
+    asymmetric: usize,
+}
+
+impl ClueStats {
+    /// Add the clues of a puzzle
+    pub fn record(mut self, sudoku: &Sudoku) -> Self {
+        self.puzzles += 1;
+        add(&mut self.clues, sudoku.clues(), 1);
+        for (value, count) in (1..).zip(sudoku.value_counts()) {
+            add(&mut self.values, value, count);
+        }
+        add(&mut self.empty_rows, sudoku.empty_rows().len(), 1);
+        add(&mut self.empty_cols, sudoku.empty_cols().len(), 1);
+        add(&mut self.empty_boxes, sudoku.empty_boxes().len(), 1);
+
+        let mut symmetric = false;
+        for (count, symmetry) in self.symmetries.iter_mut().zip(SYMMETRIES) {
+            if sudoku.has_symmetry(symmetry) {
+                *count += 1;
+                symmetric = true;
+            }
+        }
+        if !symmetric {
+            self.asymmetric += 1;
+        }
+        self
+    }
+
+    pub fn merge(mut self, other: Self) -> Self {
+        self.puzzles += other.puzzles;
+        for (own, other) in [
+            (&mut self.clues, other.clues),
+            (&mut self.empty_rows, other.empty_rows),
+            (&mut self.empty_cols, other.empty_cols),
+            (&mut self.empty_boxes, other.empty_boxes),
+        ] {
+            for (key, count) in other {
+                add(own, key, count);
+            }
+        }
+        for (value, count) in other.values {
+            add(&mut self.values, value, count);
+        }
+        for (own, other) in self.symmetries.iter_mut().zip(other.symmetries) {
+            *own += other;
+        }
+        self.asymmetric += other.asymmetric;
+        self
+    }
+
+    /// Print histograms of the clue counts, values and empty units, and the symmetries found
+    pub fn print_summary(&self) {
+        println!("Read {} puzzles", self.puzzles);
+        let (Some(min), Some(max)) = (self.clues.keys().next(), self.clues.keys().last()) else {
+            return;
+        };
+
+        let total: usize = self.clues.iter().map(|(clues, count)| clues * count).sum();
+        println!(
+            "Clues: min {min}, mean {:.1}, max {max}",
+            total as f64 / self.puzzles as f64
+        );
+        print_histogram(&self.clues, |count| count.to_string());
+
+        println!("Values, as a share of all the clues:");
+        print_histogram(&self.values, |count| {
+            format!("{:.1}%", percent(count, total))
+        });
+
+        for (units, counts) in [
+            ("rows", &self.empty_rows),
+            ("columns", &self.empty_cols),
+            ("boxes", &self.empty_boxes),
+        ] {
+            println!("Empty {units} per puzzle:");
+            print_histogram(counts, |count| count.to_string());
+        }
+
+        let symmetries: Vec<_> = SYMMETRIES
+            .iter()
+            .map(|symmetry| symmetry.to_string())
+            .chain(["none".to_string()])
+            .zip(self.symmetries.iter().chain([&self.asymmetric]))
+            .map(|(symmetry, &count)| {
+                format!("{symmetry} {count} ({:.1}%)", percent(count, self.puzzles))
+            })
+            .collect();
+        println!("Symmetric clues: {}", symmetries.join(", "));
+    }
+}
+
+fn add<K: Ord>(counts: &mut BTreeMap<K, usize>, key: K, count: usize) {
+    *counts.entry(key).or_default() += count;
+}
+
+fn percent(count: usize, total: usize) -> f64 {
+    100.0 * count as f64 / total.max(1) as f64
+}
+
+/// Print one bar per key, labeled with `label(count)`
+fn print_histogram<K: Display>(counts: &BTreeMap<K, usize>, label: impl Fn(usize) -> String) {
+    const WIDTH: usize = 50;
+
+    let most = counts.values().copied().max().unwrap_or(1).max(1);
+    for (key, &count) in counts {
+        let bar = "#".repeat((count * WIDTH).div_ceil(most));
+        println!("  {key:>6} {:>8} {bar}", label(count));
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct StatsArgs {
+    /// A file with one sudoku per line, `-` reads from stdin
+    #[arg(short, long)]
+    file: PathBuf,
+    #[command(flatten)]
+    input: FormatArgs,
+    #[command(flatten)]
+    variant: VariantArgs,
+}
+
+impl StatsArgs {
+    /// Report how the clues of the sudokus in a file are distributed
+    pub fn run(self) -> color_eyre::Result<Status> {
+        let Self {
+            file,
+            input,
+            variant,
+        } = self;
+        let puzzles = read_puzzles(None, Some(file), &input, &variant)?;
+
+        puzzles
+            .par_iter()
+            .fold(ClueStats::default, |stats, (_, puzzle)| {
+                stats.record(puzzle)
+            })
+            .reduce(ClueStats::default, ClueStats::merge)
+            .print_summary();
+        Ok(Status::Success)
+    }
+}
@@ -0,0 +1,63 @@
+//! The exit status of the CLI, so scripts can branch on the outcome of a run
+
+use std::process::ExitCode;
+
+use nsudoku_solver::{
+    solve::{SolveError, SolveStats, SudokuResult},
+    SudokuError,
+};
+
+/// How a run ended, a batch ends with the worst status of its puzzles
+///
+/// Invalid arguments exit with 2 like invalid puzzles, and a second Ctrl-C exits right away
+/// with 130.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Status {
+    /// Every puzzle was solved, or the command didn't solve any
+    #[default]
+    Success = 0,
+    /// A puzzle has no solution, or the solver couldn't find one without guessing
+    Unsolvable = 1,
+    /// A puzzle or an argument is malformed
+    InvalidInput = 2,
+    /// The solver gave up on a puzzle after running out of time or nodes
+    Timeout = 3,
+    /// A puzzle has more than one solution
+    MultipleSolutions = 4,
+    /// Anything else went wrong, like failing to read or write a file
+    Error = 5,
+    /// The grid given to `check` is not a solution of its puzzle, or the solvers disagree
+    Incorrect = 6,
+    /// `bench --compare` was slower than the baseline by more than the threshold
+    Regression = 7,
+    /// The run was stopped with Ctrl-C, the results are partial
+    Interrupted = 130,
+}
+
+impl Status {
+    /// The status of solving a single puzzle
+    pub fn of_solve(solution: &SudokuResult, stats: &SolveStats) -> Self {
+        match solution {
+            Ok(_) => Self::Success,
+            Err(SolveError::Aborted { .. }) => Self::Timeout,
+            Err(_) if stats.timed_out => Self::Timeout,
+            Err(SolveError::Unsolvable { .. } | SolveError::Unsolved(_)) => Self::Unsolvable,
+            Err(SolveError::Unsupported(_)) => Self::InvalidInput,
+        }
+    }
+
+    /// The status of a run that failed with `report`
+    pub fn of_error(report: &color_eyre::Report) -> Self {
+        if report.downcast_ref::<SudokuError>().is_some() {
+            Self::InvalidInput
+        } else {
+            Self::Error
+        }
+    }
+}
+
+impl From<Status> for ExitCode {
+    fn from(status: Status) -> Self {
+        ExitCode::from(status as u8)
+    }
+}
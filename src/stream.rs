@@ -0,0 +1,506 @@
+//! Solving puzzles while they are read, for files too large to load at once
+//!
+//! A thread reads the input in chunks of [`CHUNK`] lines, the chunks are solved in parallel as
+//! they arrive, and another thread records and writes the results in the order of the input.
+//! Every stage waits once [`QUEUE`] chunks are pending, so memory stays bounded by the size of a
+//! few chunks whatever the size of the input.
+//!
+//! A [`Checkpoint`] saves the progress every [`CHECKPOINT_INTERVAL`], so an interrupted run can
+//! resume after the last lines it finished.
+
+use std::{
+    fs::File,
+    io::{BufRead, BufWriter, Seek, SeekFrom, Write},
+    path::Path,
+    sync::mpsc::{sync_channel, Receiver},
+    thread,
+    time::{Duration, Instant},
+};
+
+use indicatif::{ProgressBar, ProgressStyle};
+use nsudoku_solver::{
+    formats::Format,
+    solve::{interrupted, SolveOutcome, SolveStats, SudokuResult},
+    Sudoku,
+};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{apply_variant, open_input, status::Status, FormatArgs, VariantArgs};
+
+/// The lines solved in parallel at a time
+const CHUNK: usize = 4096;
+
+/// The chunks waiting between each stage of the pipeline
+const QUEUE: usize = 2;
+
+/// How often the progress is saved to the checkpoint
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The result of a puzzle of the input
+struct Solved {
+    puzzle: Sudoku,
+    solution: SudokuResult,
+    stats: SolveStats,
+}
+
+/// The totals of a streamed batch, without the results of every puzzle
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StreamReport {
+    pub puzzles: usize,
+    pub solved: usize,
+    pub timeouts: usize,
+    /// The lines that didn't hold a puzzle
+    pub skipped: usize,
+    #[serde(with = "SolveStatsDef")]
+    pub stats: SolveStats,
+    /// The line index and solve time of the puzzle that took the longest
+    pub longest: Option<(usize, Duration)>,
+}
+
+/// The fields of [`SolveStats`], which has no serde support without the library's `serde` feature
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "SolveStats")]
+struct SolveStatsDef {
+    nodes: u64,
+    backtracks: u64,
+    max_depth: usize,
+    propagations: u64,
+    elapsed: Duration,
+    timed_out: bool,
+}
+
+/// The progress of a streamed run, enough to resume it
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// The file being solved
+    pub input: String,
+    /// The lines finished, every line before it was solved or skipped
+    pub lines: usize,
+    /// The length of the failures file once those lines were written
+    pub failures_len: u64,
+    /// The length of the solutions file once those lines were written
+    pub solutions_len: u64,
+    pub report: StreamReport,
+}
+
+impl Checkpoint {
+    /// Read a checkpoint saved by [`save`](Self::save)
+    pub fn load(path: &Path) -> color_eyre::Result<Self> {
+        Ok(serde_json::from_reader(std::io::BufReader::new(
+            File::open(path)?,
+        ))?)
+    }
+
+    /// Save the checkpoint to `path` through a temporary file, so it is never left half written
+    fn save(&self, path: &Path) -> std::io::Result<()> {
+        let partial = path.with_extension("partial");
+        let mut out = BufWriter::new(File::create(&partial)?);
+        serde_json::to_writer_pretty(&mut out, self)?;
+        out.flush()?;
+        drop(out);
+        std::fs::rename(partial, path)
+    }
+}
+
+/// The files a streamed run writes to
+pub struct Outputs {
+    pub failures: Option<BufWriter<File>>,
+    pub solutions: Option<BufWriter<File>>,
+    /// Where to save the progress of the run
+    pub checkpoint: Option<Box<Path>>,
+}
+
+impl StreamReport {
+    fn record(&mut self, line: usize, solved: bool, stats: SolveStats) {
+        self.puzzles += 1;
+        self.solved += solved as usize;
+        self.timeouts += stats.timed_out as usize;
+        self.stats += stats;
+        if self
+            .longest
+            .is_none_or(|(_, elapsed)| elapsed < stats.elapsed)
+        {
+            self.longest = Some((line, stats.elapsed));
+        }
+    }
+
+    /// The puzzles without a solution that didn't time out
+    pub fn failures(&self) -> usize {
+        self.puzzles - self.solved - self.timeouts
+    }
+
+    /// The worst outcome of the run, like [`BatchReport::status`](crate::batch::BatchReport::status)
+    pub fn status(&self) -> Status {
+        if self.timeouts > 0 {
+            Status::Timeout
+        } else if self.failures() > 0 {
+            Status::Unsolvable
+        } else {
+            Status::Success
+        }
+    }
+
+    /// Print a summary of the run, `elapsed` is the wall clock time of the whole batch
+    pub fn print_summary(&self, elapsed: Duration) {
+        let num_puzzles = self.puzzles.max(1);
+
+        let cpu_time = elapsed * rayon::current_num_threads() as u32;
+        println!(
+            "Took {elapsed:?} [{:?}/sudoku]",
+            cpu_time / num_puzzles as u32
+        );
+        if let Some((line, elapsed)) = self.longest {
+            println!(
+                "The longest solve was puzzle #{} and took {elapsed:?}",
+                line + 1
+            );
+        }
+        println!(
+            "Searched {} [{} nodes/sudoku]",
+            self.stats,
+            self.stats.nodes / num_puzzles as u64
+        );
+        println!(
+            "Solved {} of {} puzzles, {} failed and {} timed out, skipped {} lines",
+            self.solved,
+            self.puzzles,
+            self.failures(),
+            self.timeouts,
+            self.skipped
+        );
+    }
+}
+
+/// Open an output of a run to append to, dropping whatever was written after its first `len` bytes
+///
+/// Resumed runs pass the lengths saved in the [`Checkpoint`], so the lines written after it are
+/// written again instead of twice.
+pub fn reopen(path: &Path, len: u64) -> std::io::Result<BufWriter<File>> {
+    let mut file = File::options()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)?;
+    file.set_len(len)?;
+    file.seek(SeekFrom::End(0))?;
+    Ok(BufWriter::new(file))
+}
+
+/// Solve the puzzles of a line format as they are read, after the lines finished in `resume`
+///
+/// The puzzles without a solution are written to `failures`, and every line gets a line in
+/// `solutions` with its solution, or an empty one if it wasn't solved. The files are appended to,
+/// they must hold what was written up to the checkpoint.
+pub fn run(
+    input: impl BufRead + Send,
+    format: Format,
+    variant: &VariantArgs,
+    solve: impl Fn(Sudoku) -> (SudokuResult, SolveStats) + Sync,
+    outputs: Outputs,
+    resume: Checkpoint,
+) -> color_eyre::Result<StreamReport> {
+    let skip = resume.lines;
+    let (lines, chunks) = sync_channel(QUEUE);
+    let (outcomes, results) = sync_channel(QUEUE);
+
+    let progress = ProgressBar::new_spinner().with_style(
+        ProgressStyle::default_spinner()
+            .template("[{pos:>8}] {per_sec:>10} {spinner} {elapsed_precise}")
+            .expect("valid template"),
+    );
+
+    thread::scope(|scope| {
+        let reader = scope.spawn(move || -> std::io::Result<()> {
+            let mut chunk = Vec::with_capacity(CHUNK);
+            for (ix, line) in input.lines().enumerate().skip(skip) {
+                chunk.push((ix, line?));
+                if chunk.len() == CHUNK {
+                    let full = std::mem::replace(&mut chunk, Vec::with_capacity(CHUNK));
+                    if lines.send(full).is_err() {
+                        return Ok(());
+                    }
+                }
+            }
+            if !chunk.is_empty() {
+                let _ = lines.send(chunk);
+            }
+            Ok(())
+        });
+        let writer = scope.spawn(move || write_results(results, outputs, resume));
+
+        for chunk in chunks {
+            let solved: Vec<_> = chunk
+                .into_par_iter()
+                .map(|(ix, line)| {
+                    if interrupted() {
+                        return None;
+                    }
+                    let sudoku = format
+                        .parse(&line)
+                        .pop()
+                        .and_then(|(_, sudoku)| apply_variant(ix, sudoku, variant));
+                    // Blank and malformed lines are skipped
+                    let solved = sudoku.map(|puzzle| {
+                        let _puzzle = tracing::debug_span!("puzzle", line = ix + 1).entered();
+                        let (solution, stats) = solve(puzzle.clone());
+                        Solved {
+                            puzzle,
+                            solution,
+                            stats,
+                        }
+                    });
+                    match solved {
+                        Some(solved) if solved.stats.timed_out && interrupted() => None,
+                        solved => Some((ix, solved)),
+                    }
+                })
+                .collect();
+
+            // After Ctrl-C only the lines before the first one left unfinished are written
+            let finished: Vec<_> = solved.into_iter().map_while(|solved| solved).collect();
+            let complete = !interrupted();
+            progress.inc(finished.len() as u64);
+            if outcomes.send(finished).is_err() || !complete {
+                break;
+            }
+        }
+        drop(outcomes);
+        progress.finish_and_clear();
+
+        let report = writer.join().expect("the writer doesn't panic")?;
+        reader.join().expect("the reader doesn't panic")?;
+        Ok(report)
+    })
+}
+
+fn write_results(
+    results: Receiver<Vec<(usize, Option<Solved>)>>,
+    mut outputs: Outputs,
+    mut checkpoint: Checkpoint,
+) -> std::io::Result<StreamReport> {
+    let mut saved = Instant::now();
+    for chunk in results {
+        checkpoint.lines += chunk.len();
+        write_chunk(chunk, &mut outputs, &mut checkpoint.report)?;
+        if saved.elapsed() >= CHECKPOINT_INTERVAL {
+            save(&mut outputs, &mut checkpoint)?;
+            saved = Instant::now();
+        }
+    }
+    save(&mut outputs, &mut checkpoint)?;
+    Ok(checkpoint.report)
+}
+
+/// Flush the outputs and save the checkpoint if there is one
+fn save(outputs: &mut Outputs, checkpoint: &mut Checkpoint) -> std::io::Result<()> {
+    let len = |out: &mut Option<BufWriter<File>>| -> std::io::Result<u64> {
+        let Some(out) = out else {
+            return Ok(0);
+        };
+        out.flush()?;
+        Ok(out.get_ref().metadata()?.len())
+    };
+    checkpoint.failures_len = len(&mut outputs.failures)?;
+    checkpoint.solutions_len = len(&mut outputs.solutions)?;
+    match &outputs.checkpoint {
+        Some(path) => checkpoint.save(path),
+        None => Ok(()),
+    }
+}
+
+fn write_chunk(
+    chunk: Vec<(usize, Option<Solved>)>,
+    outputs: &mut Outputs,
+    report: &mut StreamReport,
+) -> std::io::Result<()> {
+    let Outputs {
+        failures,
+        solutions,
+        ..
+    } = outputs;
+    for (ix, solved) in chunk {
+        let Some(Solved {
+            puzzle,
+            solution,
+            stats,
+        }) = solved
+        else {
+            report.skipped += 1;
+            if let Some(out) = solutions.as_mut() {
+                writeln!(out)?;
+            }
+            continue;
+        };
+
+        report.record(ix, solution.is_ok(), stats);
+        match (&solution, solutions.as_mut()) {
+            (Ok(solution), Some(out)) => writeln!(out, "{}", solution.to_line())?,
+            (Err(_), Some(out)) => writeln!(out)?,
+            _ => {}
+        }
+        if let (Err(_), Some(out)) = (&solution, failures.as_mut()) {
+            writeln!(out, "{}", puzzle.to_line())?;
+        }
+    }
+    Ok(())
+}
+
+/// The files written by [`stream_puzzles`]
+pub struct StreamFiles<'a> {
+    pub failures: Option<&'a Path>,
+    pub solutions: Option<&'a Path>,
+    pub checkpoint: Option<&'a Path>,
+    /// The checkpoint to resume from
+    pub resume: Option<&'a Path>,
+}
+
+/// Solve the sudokus of a file or stdin as they are read, see [`run`]
+pub fn stream_puzzles(
+    file: &Path,
+    format: &FormatArgs,
+    variant: &VariantArgs,
+    solve: impl Fn(Sudoku) -> SolveOutcome + Sync,
+    files: StreamFiles,
+) -> color_eyre::Result<Status> {
+    let name = file.display().to_string();
+    let resume = match files.resume {
+        Some(path) => {
+            let checkpoint = Checkpoint::load(path)?;
+            if checkpoint.input != name {
+                color_eyre::eyre::bail!("the checkpoint is for {}, not {name}", checkpoint.input);
+            }
+            info!("Resuming after line {}", checkpoint.lines);
+            checkpoint
+        }
+        None => Checkpoint {
+            input: name,
+            ..Default::default()
+        },
+    };
+
+    let file = Some(file);
+    let mut input = open_input(file)?;
+
+    // Detect the format from the first line without consuming it
+    let first = String::from_utf8_lossy(input.fill_buf()?);
+    let first = first.lines().next().unwrap_or_default();
+    let format = format.resolve(file, first);
+    if !matches!(format, Format::Line | Format::Strict | Format::Sdm) {
+        color_eyre::eyre::bail!(
+            "streaming only supports one sudoku per line, found the {format:?} format"
+        );
+    }
+
+    let outputs = Outputs {
+        failures: files
+            .failures
+            .map(|path| reopen(path, resume.failures_len))
+            .transpose()?,
+        solutions: files
+            .solutions
+            .map(|path| reopen(path, resume.solutions_len))
+            .transpose()?,
+        checkpoint: files.checkpoint.map(Box::from),
+    };
+
+    let start = Instant::now();
+    let report = run(input, format, variant, solve, outputs, resume)?;
+    report.print_summary(start.elapsed());
+    if interrupted() {
+        warn!(
+            "Interrupted, reporting the {} puzzles finished so far",
+            report.puzzles
+        );
+        return Ok(Status::Interrupted);
+    }
+    Ok(report.status())
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        fs,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use nsudoku_solver::{
+        formats::Format,
+        solve::{sorted_dfs, SolveError, SolveStats},
+        Sudoku,
+    };
+
+    use super::{reopen, run, Checkpoint, Outputs, StreamReport};
+    use crate::VariantArgs;
+
+    #[test]
+    fn resumes_after_the_checkpoint() {
+        let dir = std::env::temp_dir().join(format!("nsudoku-stream-{}", std::process::id()));
+        fs::create_dir_all(&dir).expect("writable temporary directory");
+        let (failures, solutions) = (dir.join("failures"), dir.join("solutions"));
+
+        // The first two lines were finished, a line more was written after the checkpoint
+        let finished = "1234341221434321\n\n";
+        fs::write(&solutions, format!("{finished}4321123434122143\n")).expect("writable file");
+        fs::write(&failures, ".2.4...........3\n").expect("writable file");
+        let resume = Checkpoint {
+            input: "puzzles".into(),
+            lines: 2,
+            failures_len: 0,
+            solutions_len: finished.len() as u64,
+            report: StreamReport {
+                puzzles: 1,
+                solved: 1,
+                skipped: 1,
+                ..Default::default()
+            },
+        };
+
+        let input = "12.4341.2.434.21\nnot a puzzle\n.2.4...........3\n432.1.3434.22143\n";
+        let variant = VariantArgs {
+            variant: Default::default(),
+            cages: None,
+            layout: None,
+            box_shape: None,
+        };
+        // Give up on one of the puzzles, to have a failure
+        let solved = AtomicUsize::new(0);
+        let solve = |puzzle: Sudoku| {
+            solved.fetch_add(1, Ordering::Relaxed);
+            if puzzle.clues() == 3 {
+                return (Err(SolveError::Unsolved(puzzle)), SolveStats::default());
+            }
+            sorted_dfs(puzzle)
+        };
+        let checkpoint = dir.join("checkpoint.json");
+        let outputs = Outputs {
+            failures: Some(reopen(&failures, resume.failures_len).expect("writable file")),
+            solutions: Some(reopen(&solutions, resume.solutions_len).expect("writable file")),
+            checkpoint: Some(checkpoint.clone().into()),
+        };
+        let report = run(
+            input.as_bytes(),
+            Format::Line,
+            &variant,
+            solve,
+            outputs,
+            resume,
+        )
+        .expect("run");
+
+        assert_eq!(solved.load(Ordering::Relaxed), 2);
+        assert_eq!((report.puzzles, report.solved), (3, 2));
+        assert_eq!((report.failures(), report.skipped), (1, 1));
+        let solutions = fs::read_to_string(&solutions).expect("written file");
+        assert_eq!(solutions, format!("{finished}\n4321123434122143\n"));
+        let failures = fs::read_to_string(&failures).expect("written file");
+        assert_eq!(failures, ".2.4...........3\n");
+
+        let saved = Checkpoint::load(&checkpoint).expect("saved checkpoint");
+        assert_eq!(saved.lines, 4);
+        assert_eq!(saved.solutions_len, solutions.len() as u64);
+        assert_eq!(saved.report.puzzles, 3);
+        fs::remove_dir_all(dir).expect("removable temporary directory");
+    }
+}
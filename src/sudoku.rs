@@ -1,43 +1,250 @@
-use std::{fmt::Display, num::NonZeroU8, ops::Deref, str::FromStr};
+use std::{fmt::Display, num::NonZeroU16, ops::Deref, str::FromStr};
 
 use ndarray::{Array2, ArrayView, Dimension};
 
+mod analysis;
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+mod candidates;
+pub mod canonical;
+mod error;
+pub mod explain;
+pub mod formats;
+pub mod generate;
+mod peers;
+#[cfg(test)]
+mod properties;
+pub mod rate;
+pub mod render;
+pub mod rules;
+pub mod savegame;
+#[cfg(feature = "serde")]
+pub mod serialize;
 pub mod solve;
+pub mod techniques;
+mod transform;
 
+pub use error::SudokuError;
+
+use render::Style;
+use rules::{BoxShape, Cage, Regions, Rules};
+use techniques::{Cell, Unit};
+
+/// A cell position as (row, column) along with its value, `None` if it is empty
+pub type CellValue = (Cell, Option<NonZeroU16>);
+
+/// Two cells of a unit holding the same value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub unit: Unit,
+    pub cells: (Cell, Cell),
+    pub value: NonZeroU16,
+}
+
+/// A cell holding different values in two Sudokus, see [`Sudoku::diff`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Difference {
+    pub cell: Cell,
+    /// The value of the Sudoku compared, `None` if the cell is empty
+    pub left: Option<NonZeroU16>,
+    /// The value of the other Sudoku
+    pub right: Option<NonZeroU16>,
+}
+
+/// The value of a cell, `None` if the cell is empty
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
-struct SudokuValue(Option<NonZeroU8>);
+pub struct SudokuValue(Option<NonZeroU16>);
 
+/// A Sudoku of size order * order along with the rules of its variant
 #[derive(Debug, Clone)]
-pub struct Sudoku(Array2<SudokuValue>);
+pub struct Sudoku(Array2<SudokuValue>, Rules);
 
 impl Sudoku {
     /// Create a new empty 9x9 Sudoku
-    #[allow(dead_code)]
     pub fn new() -> Self {
         Self::from_order(9)
     }
 
     /// Create a new empty Sudoku of size order * order
-    #[allow(dead_code)]
     fn from_order(order: usize) -> Self {
         Self::from_order_vec(order, vec![SudokuValue::default(); order * order])
+            .expect("valid order")
     }
 
     /// Create a new Sudoku with size order * order and select values
-    fn from_order_vec(order: usize, values: Vec<SudokuValue>) -> Self {
-        assert!(
-            (2..16).map(|x| x * x).any(|x| x == order),
-            "The maximum supported Sudoku size is 225"
-        );
-        assert_eq!(
-            values.len(),
-            order * order,
-            "Values is not of size order * order"
-        );
+    fn from_order_vec(order: usize, values: Vec<SudokuValue>) -> Result<Self, SudokuError> {
+        if !supported(order) {
+            return Err(SudokuError::UnsupportedOrder(order));
+        }
+        if values.len() != order * order {
+            return Err(SudokuError::WrongLength(values.len()));
+        }
+        let out_of_range = values.iter().enumerate().find_map(|(position, value)| {
+            let value = value.0?.get();
+            (value as usize > order).then_some((position, value))
+        });
+        if let Some((position, value)) = out_of_range {
+            return Err(SudokuError::OutOfRange {
+                value,
+                position,
+                order,
+            });
+        }
+
+        Self(
+            Array2::from_shape_vec((order, order), values).expect("valid values size"),
+            Rules::default(),
+        )
+        .check_givens()
+    }
+
+    /// Turn the Sudoku into a killer Sudoku with the given cages
+    pub fn with_cages(self, cages: Vec<Cage>) -> Result<Self, SudokuError> {
+        let order = self.order();
+        let rules = self.1.with_cages(order, cages)?;
+        Self(self.0, rules).check_givens()
+    }
 
-        Self(Array2::from_shape_vec((order, order), values).expect("valid values size"))
+    /// Turn the Sudoku into a Sudoku X, where both main diagonals hold distinct values
+    pub fn with_diagonals(self) -> Result<Self, SudokuError> {
+        Self(self.0, self.1.with_diagonals()).check_givens()
+    }
+
+    /// Turn the Sudoku into a jigsaw Sudoku, where irregular regions replace the boxes
+    pub fn with_regions(self, regions: Regions) -> Result<Self, SudokuError> {
+        let order = self.order();
+        let rules = self.1.with_regions(order, regions)?;
+        Self(self.0, rules).check_givens()
+    }
+
+    /// Use boxes of `shape` instead of the default ones, like `3x2` boxes on a 6x6 Sudoku
+    pub fn with_box_shape(self, shape: BoxShape) -> Result<Self, SudokuError> {
+        let order = self.order();
+        let rules = self.1.with_boxes(order, shape)?;
+        Self(self.0, rules).check_givens()
     }
 
+    /// The shape of the boxes, `2x3` for a 6x6 Sudoku unless set otherwise
+    pub fn box_shape(&self) -> BoxShape {
+        self.1.box_shape(self.order())
+    }
+
+    /// The rules of the Sudoku's variant
+    pub fn rules(&self) -> &Rules {
+        &self.1
+    }
+
+    /// The single line representation of the Sudoku, as accepted by [`FromStr`]
+    ///
+    /// Sudokus with more than 61 values have no single line representation, their values past 61
+    /// are written as `?`. Write them as a grid with [`Display`] instead.
+    pub fn to_line(&self) -> String {
+        let order = self.order();
+        self.0.iter().map(|value| value.to_char(order)).collect()
+    }
+
+    /// Parse a Sudoku ignoring whitespace and punctuation, with `.` or `0` for empty cells
+    ///
+    /// Grids split over several lines or outlined with `|`, `-` and `+` parse as well, while
+    /// [`FromStr`] only accepts the single line representation.
+    pub fn parse_lenient(s: &str) -> Result<Self, SudokuError> {
+        lenient_cells(s).collect::<String>().parse()
+    }
+
+    /// The value in row `row` and column `col`, counting from 0
+    ///
+    /// Returns `None` for empty cells and cells outside the grid.
+    pub fn get(&self, row: usize, col: usize) -> Option<NonZeroU16> {
+        self.0.get((row, col)).and_then(|value| value.0)
+    }
+
+    /// Fill the cell in row `row` and column `col` with `value`
+    ///
+    /// The Sudoku is left unchanged if the value is out of range or breaks the rules.
+    pub fn set(&mut self, row: usize, col: usize, value: NonZeroU16) -> Result<(), SudokuError> {
+        let order = self.order();
+        self.check_bounds(row, col)?;
+        if value.get() as usize > order {
+            return Err(SudokuError::OutOfRange {
+                value: value.get(),
+                position: row * order + col,
+                order,
+            });
+        }
+
+        let previous = std::mem::replace(&mut self.0[(row, col)], SudokuValue(Some(value)));
+        if !self.valid() {
+            self.0[(row, col)] = previous;
+            return Err(SudokuError::Conflict { row, col, value });
+        }
+
+        Ok(())
+    }
+
+    /// Empty the cell in row `row` and column `col`
+    pub fn clear(&mut self, row: usize, col: usize) -> Result<(), SudokuError> {
+        self.check_bounds(row, col)?;
+        self.0[(row, col)] = SudokuValue(None);
+        Ok(())
+    }
+
+    /// The cells of every row with their values, from top to bottom
+    pub fn iter_rows(&self) -> impl Iterator<Item = impl Iterator<Item = CellValue> + '_> + '_ {
+        (0..self.order()).map(|row| self.unit(Unit::Row(row)))
+    }
+
+    /// The cells of every column with their values, from left to right
+    pub fn iter_cols(&self) -> impl Iterator<Item = impl Iterator<Item = CellValue> + '_> + '_ {
+        (0..self.order()).map(|col| self.unit(Unit::Column(col)))
+    }
+
+    /// The cells of every box with their values, or of every region in a jigsaw Sudoku
+    ///
+    /// Boxes are numbered in row-major order, and so are the cells of each box.
+    pub fn iter_boxes(&self) -> impl Iterator<Item = impl Iterator<Item = CellValue> + '_> + '_ {
+        (0..self.order()).map(|cell_box| self.unit(Unit::Box(cell_box)))
+    }
+
+    /// The cells sharing a row, column, box or diagonal with the cell in row `row` and column
+    /// `col`, in row-major order
+    ///
+    /// Returns `None` for cells outside the grid.
+    pub fn peers(&self, row: usize, col: usize) -> Option<impl Iterator<Item = CellValue> + '_> {
+        let order = self.order();
+        if row >= order || col >= order {
+            return None;
+        }
+
+        let mut peers: Vec<_> = Unit::containing(order, &self.1, (row, col))
+            .into_iter()
+            .flat_map(|unit| unit.cells(order, &self.1))
+            .filter(|&cell| cell != (row, col))
+            .collect();
+        peers.sort_unstable();
+        peers.dedup();
+
+        Some(peers.into_iter().map(|cell| (cell, self.0[cell].0)))
+    }
+
+    fn unit(&self, unit: Unit) -> impl Iterator<Item = CellValue> + '_ {
+        unit.cells(self.order(), &self.1)
+            .map(|cell| (cell, self.0[cell].0))
+    }
+
+    fn check_bounds(&self, row: usize, col: usize) -> Result<(), SudokuError> {
+        let order = self.order();
+        if row >= order || col >= order {
+            return Err(SudokuError::OutOfBounds { row, col, order });
+        }
+        Ok(())
+    }
+
+    /// The number of filled cells
+    pub fn clues(&self) -> usize {
+        self.0.iter().filter(|value| value.is_some()).count()
+    }
+
+    /// Whether every cell is filled without repeating values in a row, column or box
     pub fn solved(&self) -> bool {
         self.filled() && self.valid()
     }
@@ -46,27 +253,109 @@ impl Sudoku {
         self.0.iter().all(|value| value.is_some())
     }
 
-    fn valid(&self) -> bool {
-        let cell_size = self.cell_size();
+    /// Whether no value is repeated in a row, column or box and the variant rules can still hold
+    pub fn valid(&self) -> bool {
+        let boxes = self.box_shape();
         Self::valid_set(self.0.rows())
             && Self::valid_set(self.0.columns())
-            && Self::valid_set(self.0.exact_chunks((cell_size, cell_size)))
+            // Jigsaw regions are checked by the rules instead of the boxes
+            && (self.1.regions.is_some()
+                || Self::valid_set(self.0.exact_chunks((boxes.rows, boxes.cols))))
+            && self.1.valid(&self.0)
     }
 
-    fn order(&self) -> usize {
-        self.0.dim().0
+    /// Every pair of cells repeating a value in a row, column, box or diagonal
+    ///
+    /// A pair is listed once for every unit it shares. Killer cages exceeding their sum are not
+    /// conflicts, but still make the Sudoku invalid.
+    pub fn conflicts(&self) -> Vec<Conflict> {
+        let order = self.order();
+        let mut conflicts = Vec::new();
+
+        for unit in Unit::all(order, &self.1) {
+            let cells: Vec<_> = self.unit(unit).collect();
+            for (ix, &(first, value)) in cells.iter().enumerate() {
+                let Some(value) = value else {
+                    continue;
+                };
+                for &(second, _) in cells[ix + 1..]
+                    .iter()
+                    .filter(|(_, other)| *other == Some(value))
+                {
+                    conflicts.push(Conflict {
+                        unit,
+                        cells: (first, second),
+                        value,
+                    });
+                }
+            }
+        }
+
+        conflicts
+    }
+
+    /// The cells holding a different value in `other`, in row-major order
+    ///
+    /// Only the values are compared, not the rules of the variants.
+    pub fn diff(&self, other: &Sudoku) -> Result<Vec<Difference>, SudokuError> {
+        if self.order() != other.order() {
+            return Err(SudokuError::OrderMismatch(self.order(), other.order()));
+        }
+
+        Ok(self
+            .0
+            .indexed_iter()
+            .zip(other.0.iter())
+            .filter(|((_, left), right)| left != right)
+            .map(|((cell, left), right)| Difference {
+                cell,
+                left: left.0,
+                right: right.0,
+            })
+            .collect())
+    }
+
+    /// Whether the Sudoku is a solution of `puzzle`, filling its empty cells by its rules without
+    /// changing its givens
+    pub fn solves(&self, puzzle: &Sudoku) -> bool {
+        self.order() == puzzle.order()
+            && Sudoku(self.0.clone(), puzzle.1.clone()).solved()
+            && puzzle
+                .0
+                .iter()
+                .zip(self.0.iter())
+                .all(|(given, value)| given.is_none() || given == value)
     }
 
-    fn cell_size(&self) -> usize {
-        (self.order() as f64).sqrt() as usize
+    /// Reject the givens if they break the rules, listing the repeated values if any
+    fn check_givens(self) -> Result<Self, SudokuError> {
+        if self.valid() {
+            return Ok(self);
+        }
+
+        match self.conflicts() {
+            conflicts if conflicts.is_empty() => Err(SudokuError::InconsistentGivens),
+            conflicts => Err(SudokuError::RepeatedGivens(conflicts)),
+        }
+    }
+
+    /// The number of rows (and columns) of the Sudoku
+    pub fn order(&self) -> usize {
+        self.0.dim().0
     }
 
     fn valid_set<'a, D: Dimension>(
         set: impl IntoIterator<Item = ArrayView<'a, SudokuValue, D>>,
     ) -> bool {
+        let mut seen = Vec::new();
         set.into_iter().all(|subset| {
-            subset.iter().enumerate().all(|(ix, value)| {
-                value.is_none() || !subset.iter().skip(ix + 1).any(|val| val == value)
+            seen.clear();
+            subset.iter().filter_map(|value| value.0).all(|value| {
+                let ix = value.get() as usize - 1;
+                if ix >= seen.len() {
+                    seen.resize(ix + 1, false);
+                }
+                !std::mem::replace(&mut seen[ix], true)
             })
         })
     }
@@ -84,78 +373,107 @@ impl Sudoku {
     }
 }
 
+/// Whether `order * order` Sudokus are supported, their order must be split into boxes
+fn supported(order: usize) -> bool {
+    order <= u16::MAX as usize && BoxShape::of_order(order).is_some()
+}
+
+/// The order of a supported Sudoku with `cells` cells
+pub(crate) fn order_of(cells: usize) -> Option<usize> {
+    let order = cells.isqrt();
+    (order * order == cells && supported(order)).then_some(order)
+}
+
+/// The cells of a leniently formatted Sudoku, with `0` replaced by `.`
+pub(crate) fn lenient_cells(s: &str) -> impl Iterator<Item = char> + '_ {
+    s.chars()
+        .filter(|&c| c == '.' || !(c.is_whitespace() || c.is_ascii_punctuation()))
+        .map(|c| if c == '0' { '.' } else { c })
+}
+
+impl Default for Sudoku {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<Option<NonZeroU16>> for SudokuValue {
+    fn from(value: Option<NonZeroU16>) -> Self {
+        Self(value)
+    }
+}
+
 impl FromStr for Sudoku {
-    type Err = std::num::ParseIntError;
+    type Err = SudokuError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        assert!(
-            s.len() == 4 * 4 || s.len() == 9 * 9,
-            "Only works with 4x4 and 9x9 Sudoku puzzles"
-        );
+        let len = s.chars().count();
+        let order = order_of(len).ok_or(SudokuError::WrongLength(len))?;
         let vals: Result<Vec<_>, _> = s
             .chars()
-            .map(|c| {
-                let mut buffer = [0; 4];
-                c.encode_utf8(&mut buffer).parse::<SudokuValue>()
+            .enumerate()
+            .map(|(position, c)| {
+                SudokuValue::from_char(c, order).ok_or(SudokuError::InvalidCharacter {
+                    character: c,
+                    position,
+                })
             })
             .collect();
 
-        let vals = vals?;
-        Ok(Self::from_order_vec(
-            match vals.len() {
-                16 => 4,
-                81 => 9,
-                _ => unreachable!(),
-            },
-            vals,
-        ))
+        Self::from_order_vec(order, vals?)
     }
 }
 
-impl FromStr for SudokuValue {
-    type Err = std::num::ParseIntError;
+/// The characters of the values from 1 on in the single line representation
+const DIGITS: &[u8] = b"123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        assert_eq!(s.len(), 1, "Only works with 4x4 and 9x9 Sudoku puzzles");
-        if s == "." {
-            Ok(SudokuValue(None))
-        } else {
-            s.parse::<NonZeroU8>().map(|val| SudokuValue(Some(val)))
+impl SudokuValue {
+    /// Parse the single character representation of a value in a Sudoku of size `order`
+    ///
+    /// Empty cells are `.` and values past 9 are letters from `A` for 10 on (`A`-`G` for 10-16 on
+    /// a 16x16 Sudoku), except on 25x25 Sudokus which use `A`-`Y` for 1-25. Letters are case
+    /// insensitive up to 35x35 Sudokus, larger ones use `a`-`z` for 36-61.
+    fn from_char(c: char, order: usize) -> Option<Self> {
+        let value = match (order, c) {
+            (_, '.') => return Some(SudokuValue(None)),
+            (25, 'A'..='Y' | 'a'..='y') => c.to_ascii_uppercase() as u16 - 'A' as u16 + 1,
+            (_, '1'..='9') => c as u16 - '0' as u16,
+            _ => {
+                let c = if order < 36 {
+                    c.to_ascii_uppercase()
+                } else {
+                    c
+                };
+                let value = DIGITS.iter().position(|&digit| digit as char == c)? + 1;
+                (value <= order).then_some(value as u16)?
+            }
+        };
+
+        Some(SudokuValue(NonZeroU16::new(value)))
+    }
+
+    /// The single character representation of a value in a Sudoku of size `order`
+    ///
+    /// Values past 61 have none and are written as `?`.
+    fn to_char(self, order: usize) -> char {
+        match (order, self.0.map(NonZeroU16::get)) {
+            (_, None) => '.',
+            (25, Some(value)) => (b'A' + value as u8 - 1) as char,
+            (_, Some(value)) => DIGITS
+                .get(value as usize - 1)
+                .map_or('?', |&digit| digit as char),
         }
     }
 }
 
 impl Display for Sudoku {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let cell_size = self.cell_size();
-        let padding = self.required_padding() - 1;
-
-        let horizontal_line = format!(
-            "{}+",
-            format!("+{}", "-".repeat(cell_size * padding + 1)).repeat(cell_size)
-        );
-
-        for (ix, row) in self.0.rows().into_iter().enumerate() {
-            if (ix) % cell_size == 0 {
-                writeln!(f, "{horizontal_line}")?;
-            }
-
-            write!(f, "|")?;
-            for (ix, ele) in row.into_iter().enumerate() {
-                write!(f, "{:>padding$}", format!("{ele}"))?;
-                if ix % cell_size == cell_size - 1 {
-                    write!(f, " |")?;
-                }
-            }
-            writeln!(f)?;
-        }
-
-        write!(f, "{horizontal_line}")
+        self.draw(f, Style::Ascii)
     }
 }
 
 impl Deref for SudokuValue {
-    type Target = Option<NonZeroU8>;
+    type Target = Option<NonZeroU16>;
 
     fn deref(&self) -> &Self::Target {
         &self.0
@@ -171,3 +489,264 @@ impl Display for SudokuValue {
         }
     }
 }
+
+impl Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (first, second) = self.cells;
+        write!(
+            f,
+            "{} and {} both hold {} in {}",
+            techniques::CellName(first),
+            techniques::CellName(second),
+            self.value,
+            self.unit
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::num::NonZeroU16;
+
+    use rand::SeedableRng;
+
+    use super::{formats::Format, BoxShape, Conflict, Sudoku, SudokuError, Unit};
+
+    #[test]
+    fn line_round_trip() {
+        let lines = [
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79",
+            &format!("{}{}", "123456789ABCDEFG", ".".repeat(240)),
+            &format!("{}{}", "ABCDEFGHIJKLMNOPQRSTUVWXY", ".".repeat(600)),
+            &format!(
+                "{}{}",
+                "123456789ABCDEFGHIJKLMNOPQRSTUVWXYZa",
+                ".".repeat(1260)
+            ),
+        ];
+
+        for line in lines {
+            let sudoku: Sudoku = line.parse().expect("Successful parse");
+            assert_eq!(sudoku.to_line(), line);
+        }
+    }
+
+    #[test]
+    fn parse_errors() {
+        let parse = |s: &str| s.parse::<Sudoku>().unwrap_err();
+
+        assert_eq!(parse("123"), SudokuError::WrongLength(3));
+        assert_eq!(
+            parse("1234x..........."),
+            SudokuError::InvalidCharacter {
+                character: 'x',
+                position: 4
+            }
+        );
+        assert_eq!(
+            parse("5..............."),
+            SudokuError::OutOfRange {
+                value: 5,
+                position: 0,
+                order: 4
+            }
+        );
+        assert_eq!(
+            parse("11.............."),
+            SudokuError::RepeatedGivens(vec![
+                Conflict {
+                    unit: Unit::Row(0),
+                    cells: ((0, 0), (0, 1)),
+                    value: NonZeroU16::new(1).expect("non-zero value"),
+                },
+                Conflict {
+                    unit: Unit::Box(0),
+                    cells: ((0, 0), (0, 1)),
+                    value: NonZeroU16::new(1).expect("non-zero value"),
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn solutions_keep_the_givens() {
+        let puzzle: Sudoku = "1.34..12.1..4..1".parse().expect("Successful parse");
+        let solution: Sudoku = "1234341221434321".parse().expect("Successful parse");
+        let other: Sudoku = "4321124334122134".parse().expect("Successful parse");
+
+        assert!(solution.solves(&puzzle));
+        assert!(!puzzle.solves(&puzzle));
+        assert!(other.solved() && !other.solves(&puzzle));
+        let larger: Sudoku = "1.......6..44...1..2.......243......"
+            .parse()
+            .expect("Successful parse");
+        assert!(!solution.solves(&larger));
+    }
+
+    #[test]
+    fn diff_lists_changed_cells() {
+        let puzzle: Sudoku = "1.......6..44...1..2.......243......"
+            .parse()
+            .expect("Successful parse");
+        let mut filled = puzzle.clone();
+        filled.clear(0, 0).expect("inside the grid");
+        filled
+            .set(0, 1, NonZeroU16::new(5).expect("non zero"))
+            .expect("valid value");
+
+        let cells: Vec<_> = puzzle
+            .diff(&filled)
+            .expect("same order")
+            .into_iter()
+            .map(|difference| (difference.cell, difference.left, difference.right))
+            .collect();
+        assert_eq!(
+            cells,
+            [
+                ((0, 0), NonZeroU16::new(1), None),
+                ((0, 1), None, NonZeroU16::new(5))
+            ]
+        );
+        assert_eq!(
+            puzzle.diff(&Sudoku::new()),
+            Err(SudokuError::OrderMismatch(6, 9))
+        );
+    }
+
+    #[test]
+    fn values_past_u8() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let solved = crate::sudoku::generate::solved(256, &mut rng).expect("valid order");
+        assert!(solved.solved());
+
+        let parsed = Format::Pretty
+            .parse(&solved.to_string())
+            .remove(0)
+            .1
+            .expect("Successful parse");
+        assert_eq!(parsed.0, solved.0);
+        assert!(solved.to_line().contains('?'));
+    }
+
+    #[test]
+    fn rectangular_boxes() {
+        let sudoku: Sudoku = "1.......6..44...1..2.......243......"
+            .parse()
+            .expect("Successful parse");
+        assert_eq!(sudoku.box_shape(), BoxShape { rows: 2, cols: 3 });
+        assert!(sudoku
+            .to_string()
+            .starts_with("+-------+-------+\n| 1 . . | . . . |"));
+        assert!(crate::sudoku::solve::sorted_dfs(sudoku.clone())
+            .0
+            .expect("solvable")
+            .solved());
+        assert!(sudoku
+            .with_box_shape(BoxShape { rows: 2, cols: 2 })
+            .is_err());
+
+        let tall = Sudoku::from_order(6)
+            .with_box_shape(BoxShape { rows: 3, cols: 2 })
+            .expect("valid shape");
+        let solved = crate::sudoku::solve::sorted_dfs(tall).0.expect("solvable");
+        let mut first_box: Vec<_> = (0..3)
+            .flat_map(|row| (0..2).map(move |col| (row, col)))
+            .filter_map(|(row, col)| solved.get(row, col))
+            .map(NonZeroU16::get)
+            .collect();
+        first_box.sort_unstable();
+        assert_eq!(first_box, [1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn parse_lenient_grids() {
+        let line = "1.3..2..4..1.3..";
+        let grid = "1 0 | 3 0\n0 2 | 0 0\n----+----\n4 0 | 0 1\n0 3 | 0 0\n";
+
+        assert_eq!(
+            Sudoku::parse_lenient(grid)
+                .expect("Successful parse")
+                .to_line(),
+            line
+        );
+        assert_eq!(
+            Sudoku::parse_lenient(&line.replace('.', "0"))
+                .expect("Successful parse")
+                .to_line(),
+            line
+        );
+        assert_eq!(
+            "1030020040010300".parse::<Sudoku>().unwrap_err(),
+            SudokuError::InvalidCharacter {
+                character: '0',
+                position: 1
+            }
+        );
+    }
+
+    #[test]
+    fn cell_access() {
+        let mut sudoku: Sudoku = "1.3..2..4..1.3..".parse().expect("Successful parse");
+        let value = |v| NonZeroU16::new(v).expect("non-zero value");
+
+        assert_eq!(sudoku.get(0, 0), Some(value(1)));
+        assert_eq!(sudoku.get(0, 1), None);
+        assert_eq!(sudoku.get(4, 0), None);
+
+        sudoku.set(0, 1, value(4)).expect("valid placement");
+        assert_eq!(sudoku.get(0, 1), Some(value(4)));
+        assert_eq!(
+            sudoku.set(0, 3, value(4)),
+            Err(SudokuError::Conflict {
+                row: 0,
+                col: 3,
+                value: value(4)
+            })
+        );
+        assert_eq!(sudoku.get(0, 3), None);
+        assert!(matches!(
+            sudoku.set(0, 3, value(5)),
+            Err(SudokuError::OutOfRange { .. })
+        ));
+        assert!(matches!(
+            sudoku.set(0, 4, value(2)),
+            Err(SudokuError::OutOfBounds { .. })
+        ));
+
+        sudoku.clear(0, 0).expect("cell in bounds");
+        assert_eq!(sudoku.get(0, 0), None);
+    }
+
+    #[test]
+    fn unit_iterators() {
+        let sudoku: Sudoku = "1.3..2..4..1.3..".parse().expect("Successful parse");
+        let cells = |unit: &mut dyn Iterator<Item = super::CellValue>| {
+            unit.map(|(cell, _)| cell).collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            cells(&mut sudoku.iter_rows().nth(2).expect("third row")),
+            [(2, 0), (2, 1), (2, 2), (2, 3)]
+        );
+        assert_eq!(
+            cells(&mut sudoku.iter_cols().nth(1).expect("second column")),
+            [(0, 1), (1, 1), (2, 1), (3, 1)]
+        );
+        assert_eq!(
+            cells(&mut sudoku.iter_boxes().nth(1).expect("second box")),
+            [(0, 2), (0, 3), (1, 2), (1, 3)]
+        );
+        assert_eq!(
+            cells(&mut sudoku.peers(0, 0).expect("cell in the grid")),
+            [(0, 1), (0, 2), (0, 3), (1, 0), (1, 1), (2, 0), (3, 0)]
+        );
+        assert!(sudoku.peers(0, 4).is_none());
+
+        let values: Vec<_> = sudoku
+            .peers(0, 0)
+            .expect("cell in the grid")
+            .filter_map(|(_, value)| value)
+            .collect();
+        assert_eq!(values.len(), 3);
+    }
+}
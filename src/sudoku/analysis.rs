@@ -0,0 +1,75 @@
+//! Properties of the clues of a puzzle, regardless of its solution
+
+use super::{generate::Symmetry, CellValue, Sudoku};
+
+impl Sudoku {
+    /// How many times each value is given, `counts[value - 1]` for `value`
+    pub fn value_counts(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.order()];
+        for value in self.0.iter().filter_map(|value| value.0) {
+            counts[value.get() as usize - 1] += 1;
+        }
+        counts
+    }
+
+    /// The rows without clues, from top to bottom
+    pub fn empty_rows(&self) -> Vec<usize> {
+        empty(self.iter_rows())
+    }
+
+    /// The columns without clues, from left to right
+    pub fn empty_cols(&self) -> Vec<usize> {
+        empty(self.iter_cols())
+    }
+
+    /// The boxes without clues, or regions in a jigsaw Sudoku, in row-major order
+    pub fn empty_boxes(&self) -> Vec<usize> {
+        empty(self.iter_boxes())
+    }
+
+    /// Whether every clue has a clue on its partner cell, the values may differ
+    pub fn has_symmetry(&self, symmetry: Symmetry) -> bool {
+        let order = self.order();
+        self.0
+            .indexed_iter()
+            .filter(|(_, value)| value.is_some())
+            .all(|(cell, _)| self.0[symmetry.partner(order, cell)].is_some())
+    }
+
+    /// The symmetries kept by the clues, [`Symmetry::None`] is left out
+    pub fn clue_symmetries(&self) -> Vec<Symmetry> {
+        [Symmetry::Rotational, Symmetry::Mirror, Symmetry::Diagonal]
+            .into_iter()
+            .filter(|&symmetry| self.has_symmetry(symmetry))
+            .collect()
+    }
+}
+
+fn empty<I: Iterator<Item = CellValue>>(units: impl Iterator<Item = I>) -> Vec<usize> {
+    units
+        .enumerate()
+        .filter_map(|(ix, mut unit)| unit.all(|(_, value)| value.is_none()).then_some(ix))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::sudoku::{generate::Symmetry, Sudoku};
+
+    #[test]
+    fn clue_patterns() {
+        let sudoku: Sudoku = "1..............4".parse().expect("Successful parse");
+        assert_eq!(sudoku.value_counts(), [1, 0, 0, 1]);
+        assert_eq!(sudoku.empty_rows(), [1, 2]);
+        assert_eq!(sudoku.empty_cols(), [1, 2]);
+        assert_eq!(sudoku.empty_boxes(), [1, 2]);
+        assert_eq!(
+            sudoku.clue_symmetries(),
+            [Symmetry::Rotational, Symmetry::Diagonal]
+        );
+
+        let empty = Sudoku::from_order(4);
+        assert_eq!(empty.empty_boxes(), [0, 1, 2, 3]);
+        assert_eq!(empty.clue_symmetries().len(), 3);
+    }
+}
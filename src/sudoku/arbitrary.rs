@@ -0,0 +1,29 @@
+//! Random puzzles for fuzzing, behind the `arbitrary` feature
+
+use std::num::NonZeroU16;
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use super::Sudoku;
+
+/// The orders of the arbitrary puzzles, with square and rectangular boxes
+///
+/// Larger puzzles are mostly empty when drawn from a few bytes, and take too long to search.
+const ORDERS: [usize; 4] = [4, 6, 8, 9];
+
+/// A puzzle with valid givens, which may still have no solution or many of them
+///
+/// The drawn givens breaking the rules are skipped, so the fuzzers spend their time on the
+/// solvers instead of the checks of the givens.
+impl<'a> Arbitrary<'a> for Sudoku {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let order = *u.choose(&ORDERS)?;
+        let mut sudoku = Sudoku::from_order(order);
+        for _ in 0..u.int_in_range(0..=order * order)? {
+            let (row, col) = (u.choose_index(order)?, u.choose_index(order)?);
+            let value = u.int_in_range(1..=order as u16)?;
+            let _ = sudoku.set(row, col, NonZeroU16::new(value).expect("values start at 1"));
+        }
+        Ok(sudoku)
+    }
+}
@@ -0,0 +1,174 @@
+//! Bitmask set of the values a cell can still take
+
+use std::{
+    num::NonZeroU16,
+    ops::{BitAnd, BitOr, Sub},
+};
+
+use super::SudokuError;
+
+/// Set of candidate values stored as a bitmask, bit `n - 1` is set if `n` is a candidate
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Candidates(u64);
+
+impl Candidates {
+    /// The largest value a [`Candidates`] set can hold
+    pub(crate) const MAX: usize = u64::BITS as usize;
+
+    /// Reject the orders with more values than a [`Candidates`] set can hold
+    pub(crate) fn check(order: usize) -> Result<(), SudokuError> {
+        if order > Self::MAX {
+            return Err(SudokuError::TooLarge(order));
+        }
+        Ok(())
+    }
+
+    /// All the values from `1` to `order`, which must pass [`check`](Self::check)
+    pub(crate) fn full(order: usize) -> Self {
+        assert!(
+            order <= Self::MAX,
+            "Candidates support values up to {}",
+            Self::MAX
+        );
+        Self(u64::MAX >> (Self::MAX - order))
+    }
+
+    fn bit(value: NonZeroU16) -> u64 {
+        1 << (value.get() - 1)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub(crate) fn contains(&self, value: NonZeroU16) -> bool {
+        self.0 & Self::bit(value) != 0
+    }
+
+    pub(crate) fn insert(&mut self, value: NonZeroU16) -> bool {
+        let bit = Self::bit(value);
+        let missing = self.0 & bit == 0;
+        self.0 |= bit;
+        missing
+    }
+
+    /// Remove `value` from the set, returns true if it was present
+    pub(crate) fn remove(&mut self, value: NonZeroU16) -> bool {
+        let bit = Self::bit(value);
+        let present = self.0 & bit != 0;
+        self.0 &= !bit;
+        present
+    }
+
+    /// Remove and return the smallest value in the set
+    pub(crate) fn pop(&mut self) -> Option<NonZeroU16> {
+        let value = self.iter().next()?;
+        self.0 &= self.0 - 1;
+        Some(value)
+    }
+
+    pub(crate) fn iter(&self) -> Iter {
+        Iter(self.0)
+    }
+}
+
+impl BitOr for Candidates {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitAnd for Candidates {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        Self(self.0 & rhs.0)
+    }
+}
+
+/// Set difference
+impl Sub for Candidates {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self(self.0 & !rhs.0)
+    }
+}
+
+impl IntoIterator for Candidates {
+    type Item = NonZeroU16;
+    type IntoIter = Iter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl FromIterator<NonZeroU16> for Candidates {
+    fn from_iter<T: IntoIterator<Item = NonZeroU16>>(iter: T) -> Self {
+        let mut candidates = Self::default();
+        for value in iter {
+            candidates.insert(value);
+        }
+        candidates
+    }
+}
+
+/// Iterator over the values of a [`Candidates`] set in ascending order
+pub(crate) struct Iter(u64);
+
+impl Iterator for Iter {
+    type Item = NonZeroU16;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0 == 0 {
+            return None;
+        }
+
+        let value = self.0.trailing_zeros() as u16 + 1;
+        self.0 &= self.0 - 1;
+        NonZeroU16::new(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.0.count_ones() as usize;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for Iter {}
+
+#[cfg(test)]
+mod test {
+    use std::num::NonZeroU16;
+
+    use super::Candidates;
+
+    #[test]
+    fn full_set() {
+        let candidates = Candidates::full(9);
+
+        assert_eq!(candidates.len(), 9);
+        assert_eq!(
+            candidates.iter().map(NonZeroU16::get).collect::<Vec<_>>(),
+            (1..=9).collect::<Vec<_>>()
+        );
+        assert_eq!(Candidates::full(Candidates::MAX).len(), Candidates::MAX);
+    }
+
+    #[test]
+    fn remove_values() {
+        let mut candidates = Candidates::full(4);
+        let two = NonZeroU16::new(2).unwrap();
+
+        assert!(candidates.remove(two));
+        assert!(!candidates.remove(two));
+        assert_eq!(candidates.len(), 3);
+    }
+}
@@ -0,0 +1,459 @@
+//! Canonical forms of puzzles, to find the ones that are the same puzzle in disguise
+//!
+//! Relabeling the values, permuting the bands, the rows in a band, the stacks and the columns in a
+//! stack, and transposing a grid with square boxes all turn a puzzle into an equivalent one, with
+//! as many solutions and the same difficulty. The canonical form is the equivalent puzzle with the
+//! smallest line representation: empty cells sort first and the values are numbered in the order
+//! they appear in. Two puzzles are equivalent exactly when their canonical forms are equal.
+//!
+//! Only the grid is transformed, the constraints of a variant other than the shape of the boxes
+//! are dropped. The search goes through every order of the columns, so it fails for the boxes
+//! with millions of them, like the 4x4 boxes of 16x16 puzzles.
+
+use std::{cmp::Ordering, collections::HashSet, fmt::Display, num::NonZeroU16};
+
+use ndarray::Array2;
+
+use super::{
+    rules::{BoxShape, Rules},
+    Sudoku, SudokuError, SudokuValue,
+};
+
+/// The most orders of the columns searched, the 82944 of 3x4 boxes
+const MAX_COLUMN_ORDERS: usize = 82944;
+
+/// A transformation turning a puzzle into an equivalent one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transformation {
+    /// Whether the grid is transposed before moving the rows and columns
+    pub transpose: bool,
+    /// The row of the (transposed) grid moved to each row
+    pub rows: Vec<usize>,
+    /// The column of the (transposed) grid moved to each column
+    pub cols: Vec<usize>,
+    /// The value replacing each value, `values[value - 1]` replaces `value`
+    pub values: Vec<u16>,
+}
+
+impl Transformation {
+    /// The transformation leaving an `order * order` puzzle unchanged
+    pub fn identity(order: usize) -> Self {
+        Self {
+            transpose: false,
+            rows: (0..order).collect(),
+            cols: (0..order).collect(),
+            values: (1..=order as u16).collect(),
+        }
+    }
+
+    /// Transform a puzzle, which must have the order of the transformation
+    pub fn apply(&self, sudoku: &Sudoku) -> Sudoku {
+        let order = sudoku.order();
+        let grid = Array2::from_shape_fn((order, order), |(row, col)| {
+            let cell = (self.rows[row], self.cols[col]);
+            let cell = if self.transpose {
+                (cell.1, cell.0)
+            } else {
+                cell
+            };
+            let value = sudoku.0[cell]
+                .0
+                .map(|value| self.values[value.get() as usize - 1]);
+            SudokuValue(value.and_then(NonZeroU16::new))
+        });
+        Sudoku(grid, grid_rules(sudoku))
+    }
+
+    /// The transformation undoing this one
+    pub fn inverse(&self) -> Self {
+        let (rows, cols) = (invert(&self.rows), invert(&self.cols));
+        let values = invert_values(&self.values);
+        if self.transpose {
+            Self {
+                transpose: true,
+                rows: cols,
+                cols: rows,
+                values,
+            }
+        } else {
+            Self {
+                transpose: false,
+                rows,
+                cols,
+                values,
+            }
+        }
+    }
+
+    /// The transformation applying this one and then `next`
+    pub fn then(&self, next: &Self) -> Self {
+        let compose = |first: &[usize], second: &[usize]| -> Vec<usize> {
+            second.iter().map(|&line| first[line]).collect()
+        };
+        let (rows, cols) = if next.transpose {
+            (
+                compose(&self.cols, &next.rows),
+                compose(&self.rows, &next.cols),
+            )
+        } else {
+            (
+                compose(&self.rows, &next.rows),
+                compose(&self.cols, &next.cols),
+            )
+        };
+
+        Self {
+            transpose: self.transpose != next.transpose,
+            rows,
+            cols,
+            values: self
+                .values
+                .iter()
+                .map(|&value| next.values[value as usize - 1])
+                .collect(),
+        }
+    }
+}
+
+/// Lists the row and column of the (transposed) grid moved to each one, counting from 1, and the
+/// value replacing each value
+impl Display for Transformation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let lines = |lines: &[usize]| {
+            let lines: Vec<_> = lines.iter().map(|line| (line + 1).to_string()).collect();
+            lines.join(" ")
+        };
+        let values: Vec<_> = self
+            .values
+            .iter()
+            .enumerate()
+            .map(|(ix, value)| format!("{}->{value}", ix + 1))
+            .collect();
+
+        writeln!(
+            f,
+            "transpose: {}",
+            if self.transpose { "yes" } else { "no" }
+        )?;
+        writeln!(f, "rows: {}", lines(&self.rows))?;
+        writeln!(f, "columns: {}", lines(&self.cols))?;
+        write!(f, "values: {}", values.join(" "))
+    }
+}
+
+/// The inverse of a permutation of lines
+fn invert(lines: &[usize]) -> Vec<usize> {
+    let mut inverse = vec![0; lines.len()];
+    for (ix, &line) in lines.iter().enumerate() {
+        inverse[line] = ix;
+    }
+    inverse
+}
+
+/// The inverse of a relabeling, whose values start at 1
+fn invert_values(values: &[u16]) -> Vec<u16> {
+    let mut inverse = vec![0; values.len()];
+    for (ix, &value) in values.iter().enumerate() {
+        inverse[value as usize - 1] = ix as u16 + 1;
+    }
+    inverse
+}
+
+/// A transformation turning `first` into `second`, `None` if the puzzles aren't equivalent
+///
+/// Puzzles of different sizes or box shapes are never equivalent.
+pub fn equivalence(first: &Sudoku, second: &Sudoku) -> Result<Option<Transformation>, SudokuError> {
+    if first.order() != second.order() || first.box_shape() != second.box_shape() {
+        return Ok(None);
+    }
+
+    let (form, to_form) = canonicalize(first)?;
+    let (other, from_form) = canonicalize(second)?;
+    Ok((form.0 == other.0).then(|| to_form.then(&from_form.inverse())))
+}
+
+/// The canonical form of a puzzle, see the [module docs](self)
+pub fn canonical(sudoku: &Sudoku) -> Result<Sudoku, SudokuError> {
+    Ok(canonicalize(sudoku)?.0)
+}
+
+/// The canonical form of a puzzle along with a transformation turning the puzzle into it
+///
+/// Searches the transformations row by row, only keeping the ones whose rows so far are the
+/// smallest. This is fast unless the first rows of every transformation are empty, like for
+/// puzzles with very few clues, and grows with the number of ways to order the columns: 1296 for
+/// 9x9 puzzles and 82944 for 12x12 ones with 3x4 boxes. Fails with
+/// [`SudokuError::TooManyOrders`] for the box shapes with more, like the eight million of 16x16.
+pub fn canonicalize(sudoku: &Sudoku) -> Result<(Sudoku, Transformation), SudokuError> {
+    let order = sudoku.order();
+    let boxes = sudoku.box_shape();
+    // Bands are `boxes.rows` tall and stacks `boxes.cols` wide
+    if count_line_orders(boxes.rows, boxes.cols).is_none_or(|orders| orders > MAX_COLUMN_ORDERS) {
+        let BoxShape { rows, cols } = boxes;
+        return Err(SudokuError::TooManyOrders { rows, cols });
+    }
+    let columns = line_orders(boxes.rows, boxes.cols);
+    let value = |transpose: bool, (row, col): (usize, usize)| {
+        let cell = if transpose { (col, row) } else { (row, col) };
+        sudoku.0[cell].0.map_or(0, |value| value.get())
+    };
+
+    let transposes: &[bool] = if boxes.rows == boxes.cols {
+        &[false, true]
+    } else {
+        &[false]
+    };
+    let mut states: Vec<_> = transposes
+        .iter()
+        .flat_map(|&transpose| {
+            (0..columns.len()).map(move |cols| State {
+                transpose,
+                rows: Vec::with_capacity(order),
+                cols,
+                values: vec![0; order],
+                labels: 0,
+            })
+        })
+        .collect();
+    let mut grid = Vec::with_capacity(order * order);
+
+    for depth in 0..order {
+        let mut best: Option<Vec<u16>> = None;
+        let mut next = Vec::new();
+        let mut seen = HashSet::new();
+
+        for state in &states {
+            for row in state.next_rows(order, boxes.rows, depth) {
+                let mut values = state.values.clone();
+                let mut labels = state.labels;
+                let mut line = Vec::with_capacity(order);
+                let mut ordering = best.as_ref().map(|_| Ordering::Equal);
+
+                for (pos, &col) in columns[state.cols].iter().enumerate() {
+                    let label = match value(state.transpose, (row, col)) {
+                        0 => 0,
+                        value => {
+                            let label = &mut values[value as usize - 1];
+                            if *label == 0 {
+                                labels += 1;
+                                *label = labels;
+                            }
+                            *label
+                        }
+                    };
+                    line.push(label);
+
+                    if let (Some(best), Some(Ordering::Equal)) = (&best, ordering) {
+                        ordering = Some(label.cmp(&best[pos]));
+                    }
+                    if ordering == Some(Ordering::Greater) {
+                        break;
+                    }
+                }
+
+                match ordering {
+                    Some(Ordering::Greater) => continue,
+                    Some(Ordering::Equal) => {}
+                    None | Some(Ordering::Less) => {
+                        best = Some(line);
+                        next.clear();
+                        seen.clear();
+                    }
+                }
+
+                let mut rows = state.rows.clone();
+                rows.push(row);
+                let next_state = State {
+                    transpose: state.transpose,
+                    rows,
+                    cols: state.cols,
+                    values,
+                    labels,
+                };
+                // Equal states lead to the same rows further down, only one of them is searched
+                if seen.insert(next_state.key()) {
+                    next.push(next_state);
+                }
+            }
+        }
+
+        grid.extend(best.expect("every state has a next row"));
+        states = next;
+    }
+
+    let state = states.swap_remove(0);
+    let mut values = state.values;
+    let mut labels = state.labels;
+    // Values missing from the puzzle take the remaining labels
+    for value in values.iter_mut().filter(|value| **value == 0) {
+        labels += 1;
+        *value = labels;
+    }
+
+    let grid = Array2::from_shape_vec(
+        (order, order),
+        grid.into_iter()
+            .map(|label| SudokuValue(NonZeroU16::new(label)))
+            .collect(),
+    )
+    .expect("a value for every cell");
+    let transformation = Transformation {
+        transpose: state.transpose,
+        rows: state.rows,
+        cols: columns[state.cols].clone(),
+        values,
+    };
+    Ok((Sudoku(grid, grid_rules(sudoku)), transformation))
+}
+
+/// A partial transformation, with the first rows chosen
+#[derive(Debug, Clone)]
+struct State {
+    transpose: bool,
+    rows: Vec<usize>,
+    /// The index of the column order
+    cols: usize,
+    /// The label of each value, `0` if it hasn't appeared yet
+    values: Vec<u16>,
+    /// The number of labels given
+    labels: u16,
+}
+
+impl State {
+    /// The rows that may come next, keeping the rows of every band together
+    fn next_rows(&self, order: usize, band: usize, depth: usize) -> Vec<usize> {
+        if depth.is_multiple_of(band) {
+            (0..order)
+                .filter(|row| !self.rows.iter().any(|used| used / band == row / band))
+                .collect()
+        } else {
+            let current = self.rows[depth - 1] / band;
+            (current * band..(current + 1) * band)
+                .filter(|row| !self.rows.contains(row))
+                .collect()
+        }
+    }
+
+    /// What decides the rows further down: the rows left and the columns and labels so far
+    fn key(&self) -> (bool, Vec<usize>, usize, Vec<u16>) {
+        let mut rows = self.rows.clone();
+        rows.sort_unstable();
+        (self.transpose, rows, self.cols, self.values.clone())
+    }
+}
+
+/// The number of [`line_orders`], `groups! * size!^groups`, `None` if it overflows
+fn count_line_orders(groups: usize, size: usize) -> Option<usize> {
+    let factorial = |n: usize| (1..=n).try_fold(1usize, |product, k| product.checked_mul(k));
+    factorial(size)?
+        .checked_pow(groups.try_into().ok()?)?
+        .checked_mul(factorial(groups)?)
+}
+
+/// Every order of `groups * size` lines keeping the lines of each group together
+fn line_orders(groups: usize, size: usize) -> Vec<Vec<usize>> {
+    let mut orders = Vec::new();
+    for groups in permutations(groups) {
+        let mut partial = vec![Vec::new()];
+        for group in groups {
+            partial = partial
+                .into_iter()
+                .flat_map(|prefix| {
+                    permutations(size).into_iter().map(move |lines| {
+                        let mut order = prefix.clone();
+                        order.extend(lines.into_iter().map(|line| group * size + line));
+                        order
+                    })
+                })
+                .collect();
+        }
+        orders.extend(partial);
+    }
+    orders
+}
+
+/// Every permutation of `0..len` in lexicographic order
+fn permutations(len: usize) -> Vec<Vec<usize>> {
+    if len == 0 {
+        return vec![Vec::new()];
+    }
+
+    permutations(len - 1)
+        .into_iter()
+        .flat_map(|rest| {
+            (0..len).map(move |first| {
+                let mut permutation = vec![first];
+                permutation.extend(rest.iter().map(|&x| x + (x >= first) as usize));
+                permutation
+            })
+        })
+        .collect()
+}
+
+/// The rules kept by a transformed puzzle, only the shape of the boxes
+fn grid_rules(sudoku: &Sudoku) -> Rules {
+    Rules {
+        boxes: sudoku.1.boxes,
+        ..Rules::default()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::sudoku::{Sudoku, SudokuError};
+
+    use super::{
+        canonical, canonicalize, count_line_orders, equivalence, line_orders, Transformation,
+        MAX_COLUMN_ORDERS,
+    };
+
+    #[test]
+    fn orders_keep_groups_together() {
+        let orders = line_orders(2, 3);
+        assert_eq!(orders.len(), 72);
+        assert_eq!(count_line_orders(2, 3), Some(72));
+        assert_eq!(count_line_orders(3, 4), Some(MAX_COLUMN_ORDERS));
+        assert!(orders.contains(&vec![5, 3, 4, 1, 0, 2]));
+        assert!(!orders.contains(&vec![0, 1, 3, 2, 4, 5]));
+    }
+
+    #[test]
+    fn equivalent_puzzles_share_their_canonical_form() {
+        let sudoku: Sudoku =
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4.."
+                .parse()
+                .expect("Successful parse");
+        let (form, transformation) = canonicalize(&sudoku).expect("9x9 sudoku");
+        assert_eq!(transformation.apply(&sudoku).to_line(), form.to_line());
+        assert_eq!(form.clues(), sudoku.clues());
+        assert!(form.to_line() < sudoku.to_line());
+
+        let shuffled = Transformation {
+            transpose: true,
+            rows: vec![5, 3, 4, 0, 1, 2, 8, 6, 7],
+            cols: vec![2, 1, 0, 6, 7, 8, 3, 4, 5],
+            values: vec![3, 1, 2, 9, 8, 7, 4, 5, 6],
+        }
+        .apply(&sudoku);
+        assert_ne!(shuffled.to_line(), sudoku.to_line());
+        let canonical = |sudoku| canonical(sudoku).expect("9x9 sudoku");
+        assert_eq!(canonical(&shuffled).to_line(), form.to_line());
+        assert_eq!(canonical(&form).to_line(), form.to_line());
+
+        let found = equivalence(&sudoku, &shuffled)
+            .expect("9x9 sudokus")
+            .expect("equivalent puzzles");
+        assert_eq!(found.apply(&sudoku).to_line(), shuffled.to_line());
+        assert_eq!(found.inverse().apply(&shuffled).to_line(), sudoku.to_line());
+        let easy: Sudoku =
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79"
+                .parse()
+                .expect("Successful parse");
+        assert_eq!(equivalence(&sudoku, &easy), Ok(None));
+
+        assert_eq!(
+            canonicalize(&Sudoku::from_order(16)).map(|_| ()),
+            Err(SudokuError::TooManyOrders { rows: 4, cols: 4 })
+        );
+    }
+}
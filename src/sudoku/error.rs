@@ -0,0 +1,88 @@
+use std::num::NonZeroU16;
+
+use thiserror::Error;
+
+use super::Conflict;
+
+/// Errors when parsing or building a [`Sudoku`](super::Sudoku)
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum SudokuError {
+    #[error("expected a square number of cells like 36, 81 or 256, found {0}")]
+    WrongLength(usize),
+    #[error("invalid character {character:?} at position {position}")]
+    InvalidCharacter { character: char, position: usize },
+    #[error("value {value} at position {position} is out of range for a {order}x{order} sudoku")]
+    OutOfRange {
+        value: u16,
+        position: usize,
+        order: usize,
+    },
+    #[error(
+        "the givens repeat a value in a row, column, box, region or diagonal, or break a cage"
+    )]
+    InconsistentGivens,
+    #[error("the givens repeat values: {}", list_conflicts(.0))]
+    RepeatedGivens(Vec<Conflict>),
+    #[error("r{}c{} is outside of a {order}x{order} sudoku", .row + 1, .col + 1)]
+    OutOfBounds {
+        row: usize,
+        col: usize,
+        order: usize,
+    },
+    #[error("placing {value} in r{}c{} breaks the rules", .row + 1, .col + 1)]
+    Conflict {
+        row: usize,
+        col: usize,
+        value: NonZeroU16,
+    },
+    #[error(
+        "sudokus of order {0} are not supported, the order must be up to 65535 and split into boxes"
+    )]
+    UnsupportedOrder(usize),
+    #[error(
+        "sudokus of order {0} are too large for the candidates of the techniques and the searches, \
+         which go up to {}",
+        super::candidates::Candidates::MAX
+    )]
+    TooLarge(usize),
+    #[error("malformed cage on line {}, expected a sum followed by cells like `r1c1`", .line + 1)]
+    MalformedCage { line: usize },
+    #[error("cage {} {reason}", .cage + 1)]
+    InvalidCage { cage: usize, reason: &'static str },
+    #[error("invalid region layout, {0}")]
+    InvalidRegions(&'static str),
+    #[error("invalid box shape {0:?}, expected the rows and columns of a box like `2x3`")]
+    InvalidBoxShape(String),
+    #[error("invalid transformation, {0}")]
+    InvalidTransformation(&'static str),
+    #[error(
+        "the columns of sudokus with {rows}x{cols} boxes can be ordered in too many ways to find \
+         their canonical form, 12x12 sudokus with 3x4 boxes are the largest supported"
+    )]
+    TooManyOrders { rows: usize, cols: usize },
+    #[error("a {0}x{0} sudoku can't be compared with a {1}x{1} one")]
+    OrderMismatch(usize, usize),
+    #[error("malformed savegame on line {}, {reason}", .line + 1)]
+    MalformedSaveGame { line: usize, reason: &'static str },
+    #[error(
+        "savegames of version {0} are not supported, the latest is {}",
+        super::savegame::VERSION
+    )]
+    UnsupportedSaveGame(u32),
+    #[error("can't draw an image of {0}x{0} pixels")]
+    InvalidImageSize(u32),
+    #[error("unknown difficulty {0:?}, expected easy, medium, hard, expert or diabolical")]
+    UnknownDifficulty(String),
+    #[error(
+        "unknown reason {0:?}, expected guess, peer, cage or a technique like `hidden single`"
+    )]
+    UnknownReason(String),
+}
+
+fn list_conflicts(conflicts: &[Conflict]) -> String {
+    conflicts
+        .iter()
+        .map(|conflict| conflict.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,137 @@
+//! Step by step solve paths
+//!
+//! A path applies the simplest technique from [`techniques`](super::techniques) at every step.
+//! Once no technique applies, it guesses the value of the cell with the fewest candidates, taking
+//! the value from the solution so the path never has to backtrack.
+
+use std::{fmt::Display, num::NonZeroU16};
+
+use super::{
+    solve::sorted_dfs,
+    techniques::{Cell, CellName, Deduction, Grid, Step, Technique},
+    Sudoku, SudokuError,
+};
+
+/// A single step of a solve path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathStep {
+    /// A logical deduction
+    Logic(Step),
+    /// A value filled in because no technique applied
+    Guess {
+        cell: Cell,
+        value: NonZeroU16,
+        /// The candidates of the cell when the guess was made
+        candidates: Vec<NonZeroU16>,
+    },
+}
+
+impl PathStep {
+    /// Whether the step was a guess
+    pub fn is_guess(&self) -> bool {
+        matches!(self, PathStep::Guess { .. })
+    }
+}
+
+/// The ordered steps solving the Sudoku, `None` if it has no solution
+///
+/// Fails if the Sudoku is too large for the candidates of the techniques.
+pub fn explain(sudoku: &Sudoku) -> Result<Option<Vec<PathStep>>, SudokuError> {
+    Ok(explain_grid(Grid::new(sudoku)?))
+}
+
+/// [`explain`], only using the given techniques
+pub fn explain_with(
+    sudoku: &Sudoku,
+    techniques: &[Technique],
+) -> Result<Option<Vec<PathStep>>, SudokuError> {
+    Ok(explain_grid(Grid::new(sudoku)?.with_techniques(techniques)))
+}
+
+fn explain_grid(mut grid: Grid) -> Option<Vec<PathStep>> {
+    let solution = sorted_dfs(grid.sudoku()).0.ok()?;
+    let mut path = Vec::new();
+
+    while !grid.is_filled() {
+        if let Some(step) = grid.next_step() {
+            grid.apply(&step.deduction);
+            path.push(PathStep::Logic(step));
+            continue;
+        }
+
+        let (cell, candidates) = solution
+            .0
+            .indexed_iter()
+            .filter(|&(cell, _)| grid.value(cell).is_none())
+            .map(|(cell, _)| (cell, grid.candidates(cell).collect::<Vec<_>>()))
+            .min_by_key(|(_, candidates)| candidates.len())?;
+        let value = solution.0[cell].0?;
+
+        grid.apply(&Deduction::Place { cell, value });
+        path.push(PathStep::Guess {
+            cell,
+            value,
+            candidates,
+        });
+    }
+
+    Some(path)
+}
+
+impl Display for PathStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PathStep::Logic(step) => write!(f, "{step}"),
+            PathStep::Guess {
+                cell,
+                value,
+                candidates,
+            } => {
+                let candidates = candidates
+                    .iter()
+                    .map(NonZeroU16::to_string)
+                    .collect::<Vec<_>>()
+                    .join(",");
+                write!(
+                    f,
+                    "{}={value} guess among {{{candidates}}}",
+                    CellName(*cell)
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::sudoku::Sudoku;
+
+    use super::explain;
+
+    #[test]
+    fn explain_solves_puzzles() {
+        let easy: Sudoku =
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79"
+                .parse()
+                .expect("Successful parse");
+        let hard: Sudoku =
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4.."
+                .parse()
+                .expect("Successful parse");
+
+        let path = explain(&easy)
+            .expect("small sudoku")
+            .expect("solvable sudoku");
+        assert!(path.iter().all(|step| !step.is_guess()));
+
+        let path = explain(&hard)
+            .expect("small sudoku")
+            .expect("solvable sudoku");
+        assert!(path.iter().any(|step| step.is_guess()));
+
+        let unsolvable: Sudoku = format!("12345678.........9{}", ".".repeat(63))
+            .parse()
+            .expect("Successful parse");
+        assert_eq!(explain(&unsolvable), Ok(None));
+    }
+}
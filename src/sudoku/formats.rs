@@ -0,0 +1,328 @@
+//! Common puzzle interchange formats
+//!
+//! - [`Format::Line`]: one puzzle per line, `.` for empty cells
+//! - [`Format::Strict`]: one puzzle per line, exactly as accepted by
+//!   [`FromStr`](std::str::FromStr)
+//! - [`Format::Sdm`]: one puzzle per line, `0` for empty cells
+//! - [`Format::Sdk`]: a grid with one row per line, preceded by `#` metadata lines
+//! - [`Format::Ss`]: the Simple Sudoku grid, with the boxes outlined by `|` and `-`
+//! - [`Format::Pretty`]: the grid printed by [`Sudoku`]'s `Display`, see [`parse_pretty`]
+//!
+//! The grid formats store several puzzles separated by blank lines. Every format but
+//! [`Format::Strict`] parses puzzles with [`Sudoku::parse_lenient`], accepting either `.` or `0`
+//! for empty cells.
+
+use std::{num::NonZeroU16, path::Path};
+
+use super::{lenient_cells, order_of, Sudoku, SudokuError, SudokuValue};
+
+/// A puzzle file format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Line,
+    Strict,
+    Sdm,
+    Sdk,
+    Ss,
+    Pretty,
+}
+
+impl Format {
+    /// The format matching the file extension, or guessed from the contents
+    pub fn detect(path: Option<&Path>, input: &str) -> Self {
+        let extension = path
+            .and_then(Path::extension)
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("sdm") => return Format::Sdm,
+            Some("sdk") => return Format::Sdk,
+            Some("ss") => return Format::Ss,
+            _ => {}
+        }
+
+        let Some(first) = input
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !is_metadata(line))
+        else {
+            return Format::Line;
+        };
+
+        if first.starts_with('+') || first.starts_with('┌') {
+            Format::Pretty
+        } else if input.contains('|') {
+            Format::Ss
+        } else if order_of(lenient_cells(first).count()).is_some() {
+            if first.contains('0') {
+                Format::Sdm
+            } else {
+                Format::Line
+            }
+        } else {
+            Format::Sdk
+        }
+    }
+
+    /// Parse every puzzle in the input along with the line index it starts on
+    pub fn parse(self, input: &str) -> Vec<(usize, Result<Sudoku, SudokuError>)> {
+        match self {
+            Format::Line | Format::Sdm => parse_lines(input, Sudoku::parse_lenient),
+            Format::Strict => parse_lines(input, str::parse),
+            Format::Sdk | Format::Ss => parse_grids(input, Sudoku::parse_lenient),
+            Format::Pretty => parse_grids(input, parse_pretty),
+        }
+    }
+
+    /// Write a puzzle in this format
+    pub fn write(self, out: &mut impl std::io::Write, sudoku: &Sudoku) -> std::io::Result<()> {
+        let line = sudoku.to_line();
+        let order = sudoku.order();
+        let boxes = sudoku.box_shape();
+        let rows = line.as_bytes().chunks(order).map(String::from_utf8_lossy);
+
+        match self {
+            Format::Line | Format::Strict => writeln!(out, "{line}"),
+            Format::Sdm => writeln!(out, "{}", line.replace('.', "0")),
+            Format::Sdk => {
+                for row in rows {
+                    writeln!(out, "{row}")?;
+                }
+                writeln!(out)
+            }
+            Format::Ss => {
+                let separator = vec!["-".repeat(boxes.cols); boxes.rows].join("+");
+                for (ix, row) in rows.enumerate() {
+                    if ix > 0 && ix % boxes.rows == 0 {
+                        writeln!(out, "{separator}")?;
+                    }
+                    let boxes: Vec<_> = row
+                        .as_bytes()
+                        .chunks(boxes.cols)
+                        .map(String::from_utf8_lossy)
+                        .collect();
+                    writeln!(out, "{}", boxes.join("|"))?;
+                }
+                writeln!(out)
+            }
+            Format::Pretty => writeln!(out, "{sudoku}\n"),
+        }
+    }
+}
+
+/// Whether a line holds metadata instead of cells, like `#A author` or `[Puzzle]`
+fn is_metadata(line: &str) -> bool {
+    line.starts_with('#') || line.starts_with('[')
+}
+
+/// Parse one puzzle per line, skipping blank lines
+fn parse_lines(
+    input: &str,
+    parse: impl Fn(&str) -> Result<Sudoku, SudokuError>,
+) -> Vec<(usize, Result<Sudoku, SudokuError>)> {
+    input
+        .lines()
+        .map(str::trim)
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(ix, line)| (ix, parse(line)))
+        .collect()
+}
+
+/// Parse the puzzles of a grid format, separated by blank or metadata lines
+fn parse_grids(
+    input: &str,
+    parse: impl Fn(&str) -> Result<Sudoku, SudokuError>,
+) -> Vec<(usize, Result<Sudoku, SudokuError>)> {
+    let mut puzzles = Vec::new();
+    let mut grid = String::new();
+    let mut start = 0;
+
+    for (ix, line) in input.lines().map(str::trim).enumerate() {
+        if line.is_empty() || is_metadata(line) {
+            if !grid.is_empty() {
+                puzzles.push((start, parse(&grid)));
+                grid.clear();
+            }
+            continue;
+        }
+
+        if grid.is_empty() {
+            start = ix;
+        }
+        grid.push_str(line);
+        grid.push('\n');
+    }
+
+    if !grid.is_empty() {
+        puzzles.push((start, parse(&grid)));
+    }
+
+    puzzles
+}
+
+/// Parse a grid as printed by [`Sudoku`]'s `Display`, or a grid of candidates
+///
+/// Lines starting with `|` hold the cells, and lines starting with `+` outline the boxes. In a grid
+/// of candidates, where every cell spans several lines separated by `|` or `:`, only the values
+/// in brackets like `[5]` are kept. Grids drawn with box-drawing characters, like
+/// [`Style::Unicode`](super::render::Style::Unicode), are read the same way.
+pub fn parse_pretty(grid: &str) -> Result<Sudoku, SudokuError> {
+    let grid = ascii_borders(grid);
+    let lines: Vec<_> = grid
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with('|') || line.starts_with('+'))
+        .collect();
+
+    let tokens = if lines
+        .iter()
+        .any(|line| line.contains('[') || line.contains(':'))
+    {
+        candidate_tokens(&lines)
+    } else {
+        lines
+            .iter()
+            .filter(|line| line.starts_with('|'))
+            .flat_map(|line| line.split(|c: char| c == '|' || c.is_whitespace()))
+            .filter(|token| !token.is_empty())
+            .collect()
+    };
+
+    let order = order_of(tokens.len()).ok_or(SudokuError::WrongLength(tokens.len()))?;
+    let values = tokens
+        .iter()
+        .enumerate()
+        .map(|(position, token)| {
+            parse_token(token, order).ok_or(SudokuError::InvalidCharacter {
+                character: token.chars().next().unwrap_or(' '),
+                position,
+            })
+        })
+        .collect::<Result<_, _>>()?;
+
+    Sudoku::from_order_vec(order, values)
+}
+
+/// Replace the box-drawing characters of a grid by the `+`, `-` and `|` they stand for
+fn ascii_borders(grid: &str) -> String {
+    grid.chars()
+        .map(|c| match c {
+            '│' => '|',
+            '─' => '-',
+            '\u{2500}'..='\u{257F}' => '+',
+            c => c,
+        })
+        .collect()
+}
+
+/// The value of every cell of a grid of candidates, `.` for the empty cells
+fn candidate_tokens<'a>(lines: &[&'a str]) -> Vec<&'a str> {
+    let mut tokens = Vec::new();
+
+    // Every row of cells is outlined by lines starting with `+`
+    for row in lines.split(|line| line.starts_with('+')) {
+        let cells: Vec<Vec<_>> = row
+            .iter()
+            .map(|line| {
+                let cells: Vec<_> = line.split(['|', ':']).collect();
+                cells[1..cells.len() - 1].to_vec()
+            })
+            .collect();
+        let Some(width) = cells.first().map(Vec::len) else {
+            continue;
+        };
+
+        for col in 0..width {
+            let value = cells
+                .iter()
+                .filter_map(|line| line.get(col))
+                .find_map(|cell| {
+                    let (_, value) = cell.split_once('[')?;
+                    Some(value.split_once(']')?.0.trim())
+                });
+            tokens.push(value.unwrap_or("."));
+        }
+    }
+
+    tokens
+}
+
+/// Parse a single value, written as a number or as its character
+fn parse_token(token: &str, order: usize) -> Option<SudokuValue> {
+    if let Ok(value) = token.parse::<u16>() {
+        return Some(SudokuValue(Some(NonZeroU16::new(value)?)));
+    }
+
+    let mut chars = token.chars();
+    let value = SudokuValue::from_char(chars.next()?, order)?;
+    chars.next().is_none().then_some(value)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::sudoku::{render::Style, techniques::Grid, Sudoku};
+
+    use super::{parse_pretty, Format};
+
+    #[test]
+    fn formats_round_trip() {
+        let sudoku: Sudoku =
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79"
+                .parse()
+                .expect("Successful parse");
+
+        for format in [
+            Format::Line,
+            Format::Sdm,
+            Format::Sdk,
+            Format::Ss,
+            Format::Pretty,
+        ] {
+            let mut out = Vec::new();
+            format.write(&mut out, &sudoku).expect("write to memory");
+            format.write(&mut out, &sudoku).expect("write to memory");
+            let out = String::from_utf8(out).expect("valid utf-8");
+
+            assert_eq!(Format::detect(None, &out), format);
+            let puzzles = format.parse(&out);
+            assert_eq!(puzzles.len(), 2);
+            for (_, puzzle) in puzzles {
+                assert_eq!(
+                    puzzle.expect("Successful parse").to_line(),
+                    sudoku.to_line()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn pretty_round_trip() {
+        let lines = [
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79",
+            &format!("{}{}", "123456789ABCDEFG", ".".repeat(240)),
+        ];
+
+        for line in lines {
+            let sudoku: Sudoku = line.parse().expect("Successful parse");
+            let pretty = parse_pretty(&sudoku.to_string()).expect("Successful parse");
+            let unicode = parse_pretty(&sudoku.to_grid(Style::Unicode)).expect("Successful parse");
+            let candidates = parse_pretty(&Grid::new(&sudoku).expect("small sudoku").to_string())
+                .expect("Successful parse");
+
+            assert_eq!(pretty.to_line(), line);
+            assert_eq!(unicode.to_line(), line);
+            assert_eq!(candidates.to_line(), line);
+        }
+    }
+
+    #[test]
+    fn parse_sdk_metadata() {
+        let sdk = "#Aauthor\n#Ddescription\n[Puzzle]\n53..7....\n6..195...\n.98....6.\n8...6...3\n4..8.3..1\n7...2...6\n.6....28.\n...419..5\n....8..79\n";
+        let puzzles = Format::Sdk.parse(sdk);
+
+        assert_eq!(puzzles.len(), 1);
+        assert_eq!(puzzles[0].0, 3);
+        assert!(puzzles[0].1.is_ok());
+    }
+}
@@ -0,0 +1,415 @@
+//! Random puzzle generation
+
+use std::{fmt::Display, num::NonZeroU16};
+
+use rand::{seq::SliceRandom, Rng};
+
+use super::{
+    candidates::Candidates,
+    rate::{rate, Difficulty, Rating},
+    rules::BoxShape,
+    solve::{has_unique_solution, sorted_dfs_with, SearchOptions, ValueOrder},
+    techniques::Cell,
+    Sudoku, SudokuError, SudokuValue,
+};
+
+/// The nodes searched before [`full`] starts over, as unlucky guesses may never be undone
+const RESTART_NODES: u64 = 1000;
+
+/// The puzzles generated by default looking for one of the requested difficulty, see
+/// [`puzzle_of_difficulty`]
+pub const ATTEMPTS: usize = 100;
+
+/// Generate a random solved Sudoku of size order * order
+///
+/// Shuffles the values, the bands, the rows in each band, the stacks and the columns in each
+/// stack of a pattern solution.
+pub fn solved(order: usize, rng: &mut impl Rng) -> Result<Sudoku, SudokuError> {
+    let BoxShape { rows, cols } =
+        BoxShape::of_order(order).ok_or(SudokuError::UnsupportedOrder(order))?;
+
+    let mut values: Vec<_> = (1..=order as u16).filter_map(NonZeroU16::new).collect();
+    values.shuffle(rng);
+
+    // Bands are `rows` tall and stacks `cols` wide
+    let lines = shuffled_lines(cols, rows, rng);
+    let columns = shuffled_lines(rows, cols, rng);
+
+    let values = &values;
+    let grid = lines
+        .iter()
+        .flat_map(|row| {
+            columns.iter().map(move |col| {
+                let pattern = (cols * (row % rows) + row / rows + col) % order;
+                SudokuValue(Some(values[pattern]))
+            })
+        })
+        .collect();
+
+    Sudoku::from_order_vec(order, grid)
+}
+
+/// Generate a random solved Sudoku of size order * order by filling an empty grid
+///
+/// Searches the empty grid trying the values in a random order, which reaches far more grids
+/// than the shuffled pattern of [`solved`], if not quite uniformly. Fails for orders too large
+/// for the candidates of the search, which [`solved`] still generates, and falls back to
+/// [`solved`] if the search gives up, like after [`interrupt`](super::solve::interrupt).
+pub fn full(order: usize, rng: &mut impl Rng) -> Result<Sudoku, SudokuError> {
+    let empty = Sudoku::from_order_vec(order, vec![SudokuValue::default(); order * order])?;
+    Candidates::check(order)?;
+    let options = SearchOptions {
+        value_order: ValueOrder::Random,
+        seed: Some(rng.gen()),
+        restarts: Some(RESTART_NODES),
+        ..SearchOptions::default()
+    };
+
+    match sorted_dfs_with(empty, options).0 {
+        Ok(solved) => Ok(solved),
+        Err(_) => solved(order, rng),
+    }
+}
+
+/// Generate a random grid of size order * order holding `clues` values of a random solution
+///
+/// The grid always has a solution, and usually many of them unless most of the cells are filled.
+pub fn partial(order: usize, clues: usize, rng: &mut impl Rng) -> Result<Sudoku, SudokuError> {
+    let mut sudoku = full(order, rng)?;
+    let mut cells: Vec<_> = (0..order)
+        .flat_map(|row| (0..order).map(move |col| (row, col)))
+        .collect();
+    cells.shuffle(rng);
+    for (row, col) in cells.into_iter().skip(clues) {
+        sudoku.0[(row, col)] = SudokuValue::default();
+    }
+    Ok(sudoku)
+}
+
+/// Generate a random grid of size order * order by filling `attempts` random cells with random
+/// values, skipping the ones breaking the rules
+///
+/// The givens are valid, but unlike [`partial`] the grid may have no solution.
+pub fn scattered(order: usize, attempts: usize, rng: &mut impl Rng) -> Result<Sudoku, SudokuError> {
+    let mut sudoku = Sudoku::from_order_vec(order, vec![SudokuValue::default(); order * order])?;
+    for _ in 0..attempts {
+        let (row, col) = (rng.gen_range(0..order), rng.gen_range(0..order));
+        let value = NonZeroU16::new(rng.gen_range(1..=order as u16)).expect("values start at 1");
+        let _ = sudoku.set(row, col, value);
+    }
+    Ok(sudoku)
+}
+
+/// Random line order that keeps lines in the same band (or stack) of `size` lines
+fn shuffled_lines(bands: usize, size: usize, rng: &mut impl Rng) -> Vec<usize> {
+    let mut bands: Vec<_> = (0..bands).collect();
+    bands.shuffle(rng);
+
+    bands
+        .into_iter()
+        .flat_map(|band| {
+            let mut lines: Vec<_> = (band * size..(band + 1) * size).collect();
+            lines.shuffle(rng);
+            lines
+        })
+        .collect()
+}
+
+/// The symmetry kept by the clues of a generated puzzle
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Symmetry {
+    /// Any clue may be removed on its own
+    #[default]
+    None,
+    /// Unchanged by a half turn, every clue has a partner on the opposite side of the center
+    Rotational,
+    /// Unchanged by flipping the grid left to right
+    Mirror,
+    /// Unchanged by flipping the grid over its main diagonal
+    Diagonal,
+}
+
+impl Symmetry {
+    /// The cell a clue is paired with, itself if it lies on the axis or in the center
+    pub(crate) fn partner(self, order: usize, (row, col): (usize, usize)) -> (usize, usize) {
+        match self {
+            Symmetry::None => (row, col),
+            Symmetry::Rotational => (order - 1 - row, order - 1 - col),
+            Symmetry::Mirror => (row, order - 1 - col),
+            Symmetry::Diagonal => (col, row),
+        }
+    }
+}
+
+impl Display for Symmetry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Symmetry::None => "none",
+            Symmetry::Rotational => "rotational",
+            Symmetry::Mirror => "mirror",
+            Symmetry::Diagonal => "diagonal",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Generate a random puzzle of size order * order with a unique solution
+///
+/// Clues are removed from a random [`full`] grid as long as the solution stays unique, until
+/// only `clues` remain. The puzzle will have more clues than requested if no more clues can be
+/// removed, with `None` clues are removed until the puzzle is minimal.
+pub fn puzzle(
+    order: usize,
+    clues: Option<usize>,
+    rng: &mut impl Rng,
+) -> Result<Sudoku, SudokuError> {
+    puzzle_with(order, clues, Symmetry::None, rng)
+}
+
+/// Generate a random puzzle like [`puzzle`] whose clues keep the given symmetry
+///
+/// A clue is only removed along with its partner, so the puzzle may keep one clue more than
+/// requested.
+pub fn puzzle_with(
+    order: usize,
+    clues: Option<usize>,
+    symmetry: Symmetry,
+    rng: &mut impl Rng,
+) -> Result<Sudoku, SudokuError> {
+    // The shuffled pattern only for orders too large to fill by searching
+    let mut sudoku = match Candidates::check(order) {
+        Ok(()) => full(order, rng)?,
+        Err(_) => solved(order, rng)?,
+    };
+
+    let mut cells: Vec<_> = (0..order)
+        .flat_map(|row| (0..order).map(move |col| (row, col)))
+        .collect();
+    cells.shuffle(rng);
+    remove_clues(&mut sudoku, cells, clues, symmetry);
+
+    Ok(sudoku)
+}
+
+/// The order in which [`minimize`] tries to remove the clues
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Removal {
+    /// A random order, different ones may reach different minimal puzzles
+    #[default]
+    Random,
+    /// From the top left to the bottom right, row by row
+    RowMajor,
+    /// From the bottom right to the top left, row by row
+    Reverse,
+}
+
+/// Remove clues from a puzzle as long as its solution stays unique, until none can be removed
+///
+/// Returns `None` if the puzzle doesn't have a unique solution to begin with.
+pub fn minimize(sudoku: &Sudoku, removal: Removal, rng: &mut impl Rng) -> Option<Sudoku> {
+    if !has_unique_solution(sudoku) {
+        return None;
+    }
+
+    let mut cells: Vec<_> = sudoku
+        .0
+        .indexed_iter()
+        .filter(|(_, value)| value.is_some())
+        .map(|(cell, _)| cell)
+        .collect();
+    match removal {
+        Removal::Random => cells.shuffle(rng),
+        Removal::RowMajor => {}
+        Removal::Reverse => cells.reverse(),
+    }
+
+    let mut minimal = sudoku.clone();
+    remove_clues(&mut minimal, cells, None, Symmetry::None);
+    Some(minimal)
+}
+
+/// The clues that can be removed on their own without losing the unique solution
+///
+/// A puzzle is minimal when it has none. Removing one redundant clue may make others necessary,
+/// use [`minimize`] to remove as many as possible. Returns `None` if the puzzle doesn't have a
+/// unique solution to begin with.
+pub fn redundant_clues(sudoku: &Sudoku) -> Option<Vec<Cell>> {
+    if !has_unique_solution(sudoku) {
+        return None;
+    }
+
+    let mut without = sudoku.clone();
+    let redundant = sudoku
+        .0
+        .indexed_iter()
+        .filter(|(_, value)| value.is_some())
+        .filter_map(|(cell, &value)| {
+            without.0[cell] = SudokuValue(None);
+            let unique = has_unique_solution(&without);
+            without.0[cell] = value;
+            unique.then_some(cell)
+        })
+        .collect();
+    Some(redundant)
+}
+
+/// Remove the clues of `cells` in order as long as the solution stays unique
+///
+/// Stops once only `clues` remain, and removes the partners of every clue along with it.
+fn remove_clues(
+    sudoku: &mut Sudoku,
+    cells: Vec<(usize, usize)>,
+    clues: Option<usize>,
+    symmetry: Symmetry,
+) {
+    let order = sudoku.order();
+    let mut remaining = sudoku.clues();
+    for ix in cells {
+        if clues.is_some_and(|clues| remaining <= clues) {
+            break;
+        }
+        // The partner of a cell tried before was tried along with it
+        if sudoku.0[ix].is_none() {
+            continue;
+        }
+
+        let partner = symmetry.partner(order, ix);
+        let removed = if partner == ix { 1 } else { 2 };
+        if clues.is_some_and(|clues| remaining - removed < clues) {
+            continue;
+        }
+        let values = (
+            std::mem::take(&mut sudoku.0[ix]),
+            std::mem::take(&mut sudoku.0[partner]),
+        );
+        if has_unique_solution(sudoku) {
+            remaining -= removed;
+        } else {
+            sudoku.0[partner] = values.1;
+            sudoku.0[ix] = values.0;
+        }
+    }
+}
+
+/// Generate puzzles like [`puzzle_with`] until one is rated `difficulty` by [`rate`]
+///
+/// Gives up after `attempts` puzzles, returning `None`.
+pub fn puzzle_of_difficulty(
+    order: usize,
+    clues: Option<usize>,
+    symmetry: Symmetry,
+    difficulty: Difficulty,
+    attempts: usize,
+    rng: &mut impl Rng,
+) -> Result<Option<(Sudoku, Rating)>, SudokuError> {
+    for _ in 0..attempts {
+        let sudoku = puzzle_with(order, clues, symmetry, rng)?;
+        if let Some(rating) = rate(&sudoku)?.filter(|rating| rating.difficulty == difficulty) {
+            return Ok(Some((sudoku, rating)));
+        }
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::sudoku::solve::{count_solutions, has_unique_solution};
+
+    use crate::sudoku::{
+        canonical::canonical,
+        rate::{rate, Difficulty},
+        solve::sorted_dfs,
+        Sudoku,
+    };
+
+    use super::{
+        full, minimize, partial, puzzle, puzzle_of_difficulty, puzzle_with, redundant_clues,
+        scattered, solved, Removal, Symmetry,
+    };
+
+    #[test]
+    fn solved_grids_are_valid() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        for order in [4, 6, 9, 16] {
+            assert!(solved(order, &mut rng).expect("valid order").solved());
+            assert!(full(order, &mut rng).expect("valid order").solved());
+        }
+        assert_ne!(
+            full(9, &mut rng).expect("valid order").to_line(),
+            full(9, &mut rng).expect("valid order").to_line()
+        );
+    }
+
+    #[test]
+    fn partial_grids_are_valid() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let sudoku = partial(9, 30, &mut rng).expect("valid order");
+        assert_eq!(sudoku.clues(), 30);
+        assert_eq!(count_solutions(&sudoku, 1), 1);
+        assert!(scattered(9, 40, &mut rng).expect("valid order").valid());
+        assert!(scattered(7, 40, &mut rng).is_err());
+    }
+
+    #[test]
+    fn puzzles_are_unique() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let sudoku = puzzle(9, Some(30), &mut rng).expect("valid order");
+
+        assert_eq!(sudoku.clues(), 30);
+        assert!(has_unique_solution(&sudoku));
+
+        let symmetric = puzzle_with(9, None, Symmetry::Rotational, &mut rng).expect("valid order");
+        assert!(has_unique_solution(&symmetric));
+        for (row, col) in (0..9).flat_map(|row| (0..9).map(move |col| (row, col))) {
+            assert_eq!(
+                symmetric.get(row, col).is_some(),
+                symmetric.get(8 - row, 8 - col).is_some()
+            );
+        }
+
+        // Not the same solution in disguise
+        let solution = |puzzle: Sudoku| {
+            canonical(&sorted_dfs(puzzle).0.expect("unique solution")).expect("9x9 sudoku")
+        };
+        assert_ne!(solution(symmetric).to_line(), solution(sudoku).to_line());
+    }
+
+    #[test]
+    fn minimized_puzzles_are_minimal() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let sudoku = puzzle(9, Some(40), &mut rng).expect("valid order");
+        let minimal = minimize(&sudoku, Removal::RowMajor, &mut rng).expect("unique solution");
+
+        assert!(minimal.clues() < 40);
+        assert!(has_unique_solution(&minimal));
+        let again = minimize(&minimal, Removal::Reverse, &mut rng).expect("unique solution");
+        assert_eq!(again.to_line(), minimal.to_line());
+
+        let ambiguous: Sudoku = "1...............".parse().expect("Successful parse");
+        assert!(minimize(&ambiguous, Removal::Random, &mut rng).is_none());
+
+        assert_eq!(redundant_clues(&minimal), Some(Vec::new()));
+        let redundant = redundant_clues(&sudoku).expect("unique solution");
+        assert!(!redundant.is_empty());
+        assert!(redundant
+            .iter()
+            .all(|&(row, col)| sudoku.get(row, col).is_some()));
+    }
+
+    #[test]
+    fn puzzles_of_difficulty() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let (sudoku, rating) =
+            puzzle_of_difficulty(9, None, Symmetry::None, Difficulty::Medium, 50, &mut rng)
+                .expect("valid order")
+                .expect("a medium puzzle within 50 attempts");
+
+        assert_eq!(rating.difficulty, Difficulty::Medium);
+        assert_eq!(rate(&sudoku), Ok(Some(rating)));
+    }
+}
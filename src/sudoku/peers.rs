@@ -0,0 +1,113 @@
+//! Precomputed peer tables
+//!
+//! The peers of a cell are the other cells that can't hold the same value: its row, column, box
+//! or region, diagonals and killer cage. The solvers eliminate a value from every peer each time
+//! they fix a cell, so the peers are listed once per board instead of being recomputed from the
+//! rules on every elimination.
+
+use std::{cell::RefCell, sync::Arc};
+
+use super::{rules::Rules, techniques::Unit};
+
+/// The peers of every cell of a board, flattened in row-major order
+#[derive(Debug)]
+pub(crate) struct Peers {
+    order: usize,
+    /// Where the peers of each cell start in `cells`, with a final entry for the end
+    offsets: Vec<usize>,
+    cells: Vec<(usize, usize)>,
+}
+
+thread_local! {
+    /// The tables of the classic rules, shared by every board of the same order
+    static CLASSIC: RefCell<Vec<Arc<Peers>>> = const { RefCell::new(Vec::new()) };
+}
+
+impl Peers {
+    /// The peers of every cell of an `order * order` board following `rules`
+    ///
+    /// The tables of boards with the classic rules are cached, as they only depend on the order.
+    pub(crate) fn new(order: usize, rules: &Rules) -> Arc<Self> {
+        let classic = rules.cages.is_empty()
+            && !rules.diagonals
+            && rules.regions.is_none()
+            && rules.boxes.is_none();
+        if !classic {
+            return Arc::new(Self::build(order, rules));
+        }
+
+        CLASSIC.with_borrow_mut(|cache| {
+            if let Some(peers) = cache.iter().find(|peers| peers.order == order) {
+                return Arc::clone(peers);
+            }
+
+            let peers = Arc::new(Self::build(order, rules));
+            cache.push(Arc::clone(&peers));
+            peers
+        })
+    }
+
+    fn build(order: usize, rules: &Rules) -> Self {
+        let mut cage_of = vec![None; order * order];
+        for (cage, cells) in rules.cages.iter().map(|cage| &cage.cells).enumerate() {
+            for &(row, col) in cells {
+                cage_of[row * order + col] = Some(cage);
+            }
+        }
+
+        let mut offsets = vec![0];
+        let mut cells = Vec::new();
+        for row in 0..order {
+            for col in 0..order {
+                let mut peers: Vec<_> = Unit::containing(order, rules, (row, col))
+                    .into_iter()
+                    .flat_map(|unit| unit.cells(order, rules))
+                    .collect();
+                if let Some(cage) = cage_of[row * order + col] {
+                    peers.extend_from_slice(&rules.cages[cage].cells);
+                }
+                peers.sort_unstable();
+                peers.dedup();
+                peers.retain(|&peer| peer != (row, col));
+
+                cells.extend(peers);
+                offsets.push(cells.len());
+            }
+        }
+
+        Self {
+            order,
+            offsets,
+            cells,
+        }
+    }
+
+    /// The peers of a cell in row-major order
+    pub(crate) fn of(&self, (row, col): (usize, usize)) -> &[(usize, usize)] {
+        let ix = row * self.order + col;
+        &self.cells[self.offsets[ix]..self.offsets[ix + 1]]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::sudoku::rules::Rules;
+
+    use super::Peers;
+
+    #[test]
+    fn classic_peers() {
+        let peers = Peers::new(9, &Rules::default());
+
+        assert_eq!(peers.of((0, 0)).len(), 20);
+        assert!(peers.of((4, 4)).contains(&(3, 3)));
+        assert!(!peers.of((4, 4)).contains(&(4, 4)));
+        assert!(!peers.of((0, 0)).contains(&(4, 4)));
+        assert!(Arc::ptr_eq(&Peers::new(9, &Rules::default()), &peers));
+
+        let diagonal = Peers::new(9, &Rules::default().with_diagonals());
+        assert!(diagonal.of((0, 0)).contains(&(4, 4)));
+    }
+}
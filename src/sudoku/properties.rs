@@ -0,0 +1,97 @@
+//! Invariants of the solvers, the canonical forms and the transformations on random grids
+//!
+//! The grids are generated from a seed with [`partial`] and [`scattered`], so proptest shrinks
+//! the seed and the number of clues rather than the grid itself.
+
+use proptest::prelude::*;
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use super::{
+    canonical::canonical,
+    generate::{partial, scattered},
+    solve::{count_solutions, Registry, SearchOptions, SolveError},
+    Sudoku,
+};
+
+/// The orders of the grids, with square and rectangular boxes
+const ORDERS: [usize; 3] = [4, 6, 9];
+
+/// The solvers too slow to run on every grid
+const SLOW: [&str; 2] = ["naive-dfs", "dfs"];
+
+/// Grids holding some of the values of a random solution, so they always have one
+fn solvable() -> impl Strategy<Value = Sudoku> {
+    prop::sample::select(&ORDERS[..]).prop_flat_map(|order| {
+        (0..=order * order, any::<u64>()).prop_map(move |(clues, seed)| {
+            partial(order, clues, &mut StdRng::seed_from_u64(seed)).expect("supported order")
+        })
+    })
+}
+
+/// Grids with random valid givens, which may have no solution
+fn valid() -> impl Strategy<Value = Sudoku> {
+    prop::sample::select(&ORDERS[..]).prop_flat_map(|order| {
+        (0..=order * order, any::<u64>()).prop_map(move |(attempts, seed)| {
+            scattered(order, attempts, &mut StdRng::seed_from_u64(seed)).expect("supported order")
+        })
+    })
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn solutions_keep_the_givens(puzzle in solvable()) {
+        let registry = Registry::with_builtins(SearchOptions::default());
+        for name in registry.names().filter(|name| !SLOW.contains(name)) {
+            let solver = registry.get(name).expect("registered solver");
+            match solver.solve(&puzzle).0 {
+                Ok(solution) => {
+                    prop_assert!(solution.solves(&puzzle), "{name} answered {}", solution.to_line());
+                }
+                Err(SolveError::Unsolved(_)) if !solver.guesses() => {}
+                Err(error) => prop_assert!(false, "{name} failed on {}: {error}", puzzle.to_line()),
+            }
+        }
+    }
+
+    #[test]
+    fn canonical_forms_are_canonical(puzzle in solvable(), seed in any::<u64>()) {
+        let canonical = |sudoku: &Sudoku| canonical(sudoku).expect("small sudoku");
+        let form = canonical(&puzzle);
+        prop_assert_eq!(canonical(&form).to_line(), form.to_line());
+        prop_assert_eq!(form.clues(), puzzle.clues());
+
+        let mut values: Vec<_> = (1..=puzzle.order() as u16).collect();
+        values.shuffle(&mut StdRng::seed_from_u64(seed));
+        let last = puzzle.order() / puzzle.box_shape().cols - 1;
+        let shuffled = puzzle
+            .permute_values(&values)
+            .and_then(|sudoku| sudoku.swap_stacks(0, last))
+            .and_then(|sudoku| sudoku.swap_cols_in_stack(0, 1))
+            .expect("valid transformations");
+        prop_assert_eq!(canonical(&shuffled).to_line(), form.to_line());
+    }
+
+    #[test]
+    fn transformations_keep_the_solutions(puzzle in valid(), seed in any::<u64>()) {
+        let solutions = count_solutions(&puzzle, 2);
+        let last = puzzle.order() / puzzle.box_shape().rows - 1;
+        let mut values: Vec<_> = (1..=puzzle.order() as u16).collect();
+        values.shuffle(&mut StdRng::seed_from_u64(seed));
+
+        let transformed = [
+            puzzle.transpose(),
+            puzzle.rotate90(),
+            puzzle.rotate180(),
+            puzzle.reflect_horizontal(),
+            puzzle.reflect_vertical(),
+            puzzle.permute_values(&values).expect("a permutation"),
+            puzzle.swap_rows_in_band(0, 1).expect("rows of the first band"),
+            puzzle.swap_bands(0, last).expect("existing bands"),
+        ];
+        for sudoku in transformed {
+            prop_assert_eq!(count_solutions(&sudoku, 2), solutions, "{}", sudoku.to_line());
+        }
+    }
+}
@@ -0,0 +1,191 @@
+//! Difficulty ratings
+//!
+//! A puzzle is rated by the hardest technique from [`techniques`](super::techniques) needed to
+//! solve it, and by the backtracking effort once no technique applies.
+
+use std::{fmt::Display, str::FromStr};
+
+use super::{
+    solve::sorted_dfs,
+    techniques::{Grid, Technique},
+    Sudoku, SudokuError,
+};
+
+/// The score given to puzzles that require guessing, before accounting for backtracking
+const GUESSING: f32 = 7.0;
+
+/// Difficulty categories, from easiest to hardest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Difficulty {
+    /// Solvable with singles
+    Easy,
+    /// Requires locked candidates
+    Medium,
+    /// Requires naked or hidden subsets, or fish
+    Hard,
+    /// Requires chains or a little guessing
+    Expert,
+    /// Requires extensive guessing
+    Diabolical,
+}
+
+/// The difficulty of a puzzle
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rating {
+    /// The numeric difficulty, higher is harder
+    pub score: f32,
+    pub difficulty: Difficulty,
+    /// The hardest technique used, `None` if the puzzle was already solved
+    pub hardest: Option<Technique>,
+    /// The backtracks needed after the techniques got stuck
+    pub backtracks: u64,
+}
+
+/// Rate a puzzle, `None` if it has no solution
+///
+/// Fails if the puzzle is too large for the candidates of the techniques.
+pub fn rate(sudoku: &Sudoku) -> Result<Option<Rating>, SudokuError> {
+    Ok(rate_grid(Grid::new(sudoku)?))
+}
+
+/// [`rate`], only using the given techniques
+pub fn rate_with(sudoku: &Sudoku, techniques: &[Technique]) -> Result<Option<Rating>, SudokuError> {
+    Ok(rate_grid(Grid::new(sudoku)?.with_techniques(techniques)))
+}
+
+fn rate_grid(mut grid: Grid) -> Option<Rating> {
+    let mut hardest = None;
+
+    while let Some(step) = grid.next_step() {
+        hardest = hardest.max(Some(step.technique));
+        grid.apply(&step.deduction);
+    }
+
+    if grid.is_broken() {
+        return None;
+    }
+
+    let score = hardest.map_or(0.0, Technique::difficulty);
+    if grid.is_filled() {
+        let difficulty = match hardest {
+            None | Some(Technique::HiddenSingle | Technique::NakedSingle) => Difficulty::Easy,
+            Some(Technique::LockedCandidates) => Difficulty::Medium,
+            Some(Technique::XYChain) => Difficulty::Expert,
+            Some(_) => Difficulty::Hard,
+        };
+
+        return Some(Rating {
+            score,
+            difficulty,
+            hardest,
+            backtracks: 0,
+        });
+    }
+
+    let (solution, stats) = sorted_dfs(grid.sudoku());
+    solution.ok()?;
+
+    // Every doubling of the backtracks adds a point
+    let score = GUESSING + (stats.backtracks as f32 + 1.0).log2();
+    let difficulty = if score < GUESSING + 4.0 {
+        Difficulty::Expert
+    } else {
+        Difficulty::Diabolical
+    };
+
+    Some(Rating {
+        score,
+        difficulty,
+        hardest,
+        backtracks: stats.backtracks,
+    })
+}
+
+impl Display for Difficulty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+            Difficulty::Expert => "expert",
+            Difficulty::Diabolical => "diabolical",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl Difficulty {
+    /// Every difficulty, from easiest to hardest
+    pub const ALL: [Difficulty; 5] = [
+        Difficulty::Easy,
+        Difficulty::Medium,
+        Difficulty::Hard,
+        Difficulty::Expert,
+        Difficulty::Diabolical,
+    ];
+}
+
+/// Parse a difficulty from its name, ignoring case
+impl FromStr for Difficulty {
+    type Err = SudokuError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|difficulty| difficulty.to_string().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| SudokuError::UnknownDifficulty(s.to_string()))
+    }
+}
+
+impl Display for Rating {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:.1} {}", self.score, self.difficulty)?;
+        match self.hardest {
+            Some(technique) if self.backtracks > 0 => {
+                write!(f, " ({technique}, {} backtracks)", self.backtracks)
+            }
+            Some(technique) => write!(f, " ({technique})"),
+            None if self.backtracks > 0 => write!(f, " ({} backtracks)", self.backtracks),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::sudoku::Sudoku;
+
+    use super::{rate, Difficulty};
+
+    #[test]
+    fn rate_puzzles() {
+        let rating = |s: &str| {
+            let sudoku: Sudoku = s.parse().expect("Successful parse");
+            rate(&sudoku)
+                .expect("small sudoku")
+                .expect("solvable sudoku")
+                .difficulty
+        };
+
+        assert_eq!(
+            rating(
+                "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79"
+            ),
+            Difficulty::Easy
+        );
+        assert!(
+            rating(
+                "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4.."
+            ) >= Difficulty::Expert
+        );
+    }
+
+    #[test]
+    fn parse_difficulties() {
+        for difficulty in Difficulty::ALL {
+            assert_eq!(difficulty.to_string().parse(), Ok(difficulty));
+        }
+        assert_eq!(" Hard".parse(), Ok(Difficulty::Hard));
+        assert!("impossible".parse::<Difficulty>().is_err());
+    }
+}
@@ -0,0 +1,341 @@
+//! Drawing a sudoku as a text grid
+//!
+//! Every [`Style`] shares the same layout: the borders are drawn between the cells of different
+//! boxes, or of different regions in a jigsaw, and only the characters drawing them change.
+//!
+//! Images start from a [`Drawing`], the strokes and labels of a sudoku laid out on a canvas, which
+//! is then written as an SVG with [`Drawing::to_svg`], rasterized with `Drawing::to_png` with the `png` feature or
+//! drawn in LaTeX with [`Drawing::to_tikz`], which [`Book`] uses to print a book of puzzles.
+//! Web pages are tables instead, see [`html::page`], and so is [`Sudoku::to_markdown`].
+
+use std::fmt::Write;
+
+use super::{techniques::Cell, Sudoku};
+
+mod drawing;
+pub mod html;
+mod latex;
+#[cfg(feature = "png")]
+mod png;
+mod svg;
+
+pub use drawing::{Drawing, Ink, Label, Stroke, CELL};
+pub use latex::Book;
+
+/// The characters a grid is drawn with
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    /// Borders drawn with `+`, `-` and `|`, read back by
+    /// [`parse_pretty`](super::formats::parse_pretty)
+    #[default]
+    Ascii,
+    /// Borders drawn with box-drawing characters like `┌─┬─┐`
+    Unicode,
+    /// No borders, only a space between the boxes and a blank line between the bands
+    Compact,
+    /// Large cells outlined with thin lines and thick box borders, with only the givens filled in
+    ///
+    /// Meant to be printed and solved on paper.
+    Blank,
+}
+
+/// The box-drawing character joining the borders leaving a corner, indexed by the weight of the
+/// borders going up, down, left and right as `up + 3 * down + 9 * left + 27 * right`
+///
+/// A weight of 0 is no border, 1 a thin one and 2 a thick one.
+const CORNERS: [char; 81] = [
+    ' ', '╵', '╹', '╷', '│', '╿', '╻', '╽', '┃', '╴', '┘', '┚', '┐', '┤', '┦', '┒', '┧', '┨', '╸',
+    '┙', '┛', '┑', '┥', '┩', '┓', '┪', '┫', '╶', '└', '┖', '┌', '├', '┞', '┎', '┟', '┠', '─', '┴',
+    '┸', '┬', '┼', '╀', '┰', '╁', '╂', '╾', '┵', '┹', '┭', '┽', '╃', '┱', '╅', '╉', '╺', '┕', '┗',
+    '┍', '┝', '┡', '┏', '┢', '┣', '╼', '┶', '┺', '┮', '┾', '╄', '┲', '╆', '╊', '━', '┷', '┻', '┯',
+    '┿', '╇', '┳', '╈', '╋',
+];
+
+impl Style {
+    /// The weight of the borders between two cells, thicker between different regions
+    fn weight(self, regions: bool) -> usize {
+        match (self, regions) {
+            (Style::Blank, true) => 2,
+            (Style::Blank, false) | (_, true) => 1,
+            (_, false) => 0,
+        }
+    }
+
+    fn horizontal(self, weight: usize) -> char {
+        match (self, weight) {
+            (_, 0) | (Style::Compact, _) => ' ',
+            (Style::Ascii, _) => '-',
+            (_, 1) => '─',
+            (_, _) => '━',
+        }
+    }
+
+    fn vertical(self, weight: usize) -> char {
+        match (self, weight) {
+            (_, 0) | (Style::Compact, _) => ' ',
+            (Style::Ascii, _) => '|',
+            (_, 1) => '│',
+            (_, _) => '┃',
+        }
+    }
+
+    /// The character joining the borders leaving a corner, weighted `[up, down, left, right]`
+    fn corner(self, [up, down, left, right]: [usize; 4]) -> char {
+        match self {
+            Style::Ascii if up + down + left + right > 0 => '+',
+            Style::Ascii | Style::Compact => ' ',
+            Style::Unicode | Style::Blank => CORNERS[up + 3 * down + 9 * left + 27 * right],
+        }
+    }
+}
+
+impl Sudoku {
+    /// The grid of the Sudoku outlined with the borders of `style`
+    ///
+    /// The `Display` of a Sudoku is the [`Style::Ascii`] grid.
+    pub fn to_grid(&self, style: Style) -> String {
+        let mut grid = String::new();
+        self.draw(&mut grid, style)
+            .expect("writing to a string can't fail");
+        grid
+    }
+
+    /// The grid of the Sudoku as a GitHub flavored Markdown table
+    ///
+    /// The header holds the number of every column and the first column the number of every row,
+    /// the empty cells are left blank. Tables can't outline the boxes.
+    pub fn to_markdown(&self) -> String {
+        let order = self.order();
+        let row = |label: String, cells: Vec<String>| {
+            std::iter::once(label)
+                .chain(cells)
+                .map(|cell| format!("| {cell} "))
+                .collect::<String>()
+                + "|"
+        };
+        let mut lines = vec![
+            row(
+                " ".to_string(),
+                (1..=order).map(|col| col.to_string()).collect(),
+            ),
+            row(":-:".to_string(), vec![":-:".to_string(); order]),
+        ];
+        for r in 0..order {
+            let cells = (0..order)
+                .map(|col| {
+                    self.get(r, col)
+                        .map_or(" ".to_string(), |value| value.to_string())
+                })
+                .collect();
+            lines.push(row(format!("**{}**", r + 1), cells));
+        }
+        lines.join("\n")
+    }
+
+    pub(crate) fn draw(&self, out: &mut impl Write, style: Style) -> std::fmt::Result {
+        let order = self.order();
+        let boxes = self.box_shape();
+        let region = |cell| self.1.box_of(boxes, cell);
+        let padding = self.required_padding() - 1;
+        match style {
+            // Three lines per cell, with the given in the middle and room left to write on
+            Style::Blank => draw(out, order, style, region, |cell| {
+                let value = self.0[cell]
+                    .0
+                    .map_or(String::new(), |value| value.to_string());
+                format!("\n   {value:^width$}  \n", width = padding - 1)
+            }),
+            // Single digits are packed together without borders, larger values keep a space
+            Style::Compact if order < 10 => draw(out, order, style, region, |cell| {
+                format!("{}", self.0[cell])
+            }),
+            _ => draw(out, order, style, region, |cell| {
+                format!("{:>padding$}", format!("{}", self.0[cell]))
+            }),
+        }
+    }
+}
+
+/// Draw an `order` sized grid, with borders between the cells of a different `region`
+///
+/// The cells are drawn as returned by `cell`, padded to the widest of them, and may span several
+/// lines. A column or row of borders is drawn when any border runs through it, or for
+/// [`Style::Compact`] when it splits the whole grid.
+pub(crate) fn draw(
+    out: &mut impl Write,
+    order: usize,
+    style: Style,
+    region: impl Fn(Cell) -> usize,
+    cell: impl Fn(Cell) -> String,
+) -> std::fmt::Result {
+    let region = |row: Option<usize>, col: Option<usize>| {
+        let (row, col) = (row?, col?);
+        (row < order && col < order).then(|| region((row, col)))
+    };
+    // The weight of the border left of `col` in `row`
+    let vertical = |row: Option<usize>, col: usize| match row {
+        Some(row) if row < order => {
+            style.weight(region(Some(row), col.checked_sub(1)) != region(Some(row), Some(col)))
+        }
+        _ => 0,
+    };
+    // The weight of the border above `row` in `col`
+    let horizontal = |row: usize, col: Option<usize>| match col {
+        Some(col) if col < order => {
+            style.weight(region(row.checked_sub(1), Some(col)) != region(Some(row), Some(col)))
+        }
+        _ => 0,
+    };
+    let shown = |line: usize, borders: Vec<usize>| match style {
+        Style::Compact => 0 < line && line < order && !borders.contains(&0),
+        Style::Ascii | Style::Unicode | Style::Blank => borders.iter().any(|&border| border > 0),
+    };
+    let columns: Vec<_> = (0..=order)
+        .map(|col| {
+            shown(
+                col,
+                (0..order).map(|row| vertical(Some(row), col)).collect(),
+            )
+        })
+        .collect();
+    let rows: Vec<_> = (0..=order)
+        .map(|row| {
+            shown(
+                row,
+                (0..order).map(|col| horizontal(row, Some(col))).collect(),
+            )
+        })
+        .collect();
+
+    let cells: Vec<Vec<String>> = (0..order)
+        .flat_map(|row| (0..order).map(move |col| (row, col)))
+        .map(|ix| cell(ix).split('\n').map(str::to_string).collect())
+        .collect();
+    let lines = cells.iter().flatten();
+    let width = lines.map(|line| line.chars().count()).max().unwrap_or(0);
+    let height = cells.iter().map(Vec::len).max().unwrap_or(0);
+    // The borders after the first column are spaced from the cell before them
+    let spacing = |col: usize| col > 0 && style != Style::Compact;
+
+    let mut lines = Vec::new();
+    for (row, &shown) in rows.iter().enumerate() {
+        if shown {
+            let mut line = String::new();
+            for (col, &column) in columns.iter().enumerate() {
+                let fill = |col: usize| style.horizontal(horizontal(row, Some(col)));
+                if column {
+                    if spacing(col) {
+                        line.push(fill(col - 1));
+                    }
+                    line.push(style.corner([
+                        vertical(row.checked_sub(1), col),
+                        vertical(Some(row), col),
+                        horizontal(row, col.checked_sub(1)),
+                        horizontal(row, Some(col)),
+                    ]));
+                }
+                if col < order {
+                    line.extend(std::iter::repeat_n(fill(col), width));
+                }
+            }
+            lines.push(line);
+        }
+
+        if row == order {
+            break;
+        }
+        for part in 0..height {
+            let mut line = String::new();
+            for (col, &column) in columns.iter().enumerate() {
+                if column {
+                    if spacing(col) {
+                        line.push(' ');
+                    }
+                    line.push(style.vertical(vertical(Some(row), col)));
+                }
+                if col < order {
+                    let text = cells[row * order + col].get(part);
+                    write!(line, "{:>width$}", text.map_or("", String::as_str))?;
+                }
+            }
+            lines.push(line);
+        }
+    }
+
+    for (ix, line) in lines.iter().enumerate() {
+        if ix > 0 {
+            writeln!(out)?;
+        }
+        write!(out, "{}", line.trim_end())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::Style;
+    use crate::sudoku::Sudoku;
+
+    #[test]
+    fn grid_styles() {
+        let sudoku: Sudoku = "1.34..12.1..4..1".parse().expect("Successful parse");
+
+        assert_eq!(
+            sudoku.to_grid(Style::Ascii),
+            [
+                "+-----+-----+",
+                "| 1 . | 3 4 |",
+                "| . . | 1 2 |",
+                "+-----+-----+",
+                "| . 1 | . . |",
+                "| 4 . | . 1 |",
+                "+-----+-----+",
+            ]
+            .join("\n")
+        );
+        assert_eq!(sudoku.to_grid(Style::Ascii), sudoku.to_string());
+        assert_eq!(
+            sudoku.to_grid(Style::Unicode),
+            [
+                "┌─────┬─────┐",
+                "│ 1 . │ 3 4 │",
+                "│ . . │ 1 2 │",
+                "├─────┼─────┤",
+                "│ . 1 │ . . │",
+                "│ 4 . │ . 1 │",
+                "└─────┴─────┘",
+            ]
+            .join("\n")
+        );
+        assert_eq!(
+            sudoku.to_grid(Style::Compact),
+            ["1. 34", ".. 12", "", ".1 ..", "4. .1"].join("\n")
+        );
+
+        let blank = sudoku.to_grid(Style::Blank);
+        let lines: Vec<_> = blank.lines().collect();
+        assert_eq!(lines.len(), 17);
+        assert_eq!(lines[0], "┏━━━━━━━┯━━━━━━━┳━━━━━━━┯━━━━━━━┓");
+        assert_eq!(lines[2], "┃   1   │       ┃   3   │   4   ┃");
+        assert_eq!(lines[4], "┠───────┼───────╂───────┼───────┨");
+        assert_eq!(lines[8], "┣━━━━━━━┿━━━━━━━╋━━━━━━━┿━━━━━━━┫");
+    }
+
+    #[test]
+    fn markdown_table() {
+        let sudoku: Sudoku = "1.34..12.1..4..1".parse().expect("Successful parse");
+
+        assert_eq!(
+            sudoku.to_markdown(),
+            [
+                "|   | 1 | 2 | 3 | 4 |",
+                "| :-: | :-: | :-: | :-: | :-: |",
+                "| **1** | 1 |   | 3 | 4 |",
+                "| **2** |   |   | 1 | 2 |",
+                "| **3** |   | 1 |   |   |",
+                "| **4** | 4 |   |   | 1 |",
+            ]
+            .join("\n")
+        );
+    }
+}
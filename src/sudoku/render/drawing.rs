@@ -0,0 +1,228 @@
+//! A sudoku laid out on a canvas, shared by the renderers writing images
+
+use crate::sudoku::{techniques::Grid, Sudoku, SudokuError};
+
+/// The side of a cell on the canvas
+pub const CELL: f32 = 48.0;
+/// The width of the lines between the cells of a box
+const THIN: f32 = 1.0;
+/// The width of the borders of the boxes and of the grid
+const THICK: f32 = 3.0;
+/// The blank space around the grid
+const MARGIN: f32 = 2.0 * THICK;
+
+/// How a label is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ink {
+    /// A given of the puzzle, in bold
+    Given,
+    /// A value of the solution found for an empty cell
+    Solved,
+    /// A candidate of an empty cell, small and at its own spot in the cell
+    Candidate,
+}
+
+/// A straight line between two points of the canvas
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stroke {
+    pub from: (f32, f32),
+    pub to: (f32, f32),
+    pub width: f32,
+}
+
+/// A value written centered on a point of the canvas
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub text: String,
+    pub center: (f32, f32),
+    /// The height of the font
+    pub size: f32,
+    pub ink: Ink,
+}
+
+/// A sudoku laid out as strokes and labels on a square canvas
+///
+/// The coordinates grow right and down from the top left corner of the canvas, every cell is
+/// [`CELL`] wide. The boxes, or the regions of a jigsaw, are outlined with thick lines.
+#[derive(Debug, Clone)]
+pub struct Drawing {
+    puzzle: Sudoku,
+    solution: Option<Sudoku>,
+    candidates: bool,
+}
+
+impl Drawing {
+    /// Lay out the givens of a puzzle
+    pub fn new(puzzle: &Sudoku) -> Self {
+        Self {
+            puzzle: puzzle.clone(),
+            solution: None,
+            candidates: false,
+        }
+    }
+
+    /// Also write the values of `solution` in the empty cells of the puzzle
+    pub fn with_solution(mut self, solution: &Sudoku) -> Result<Self, SudokuError> {
+        if solution.order() != self.puzzle.order() {
+            return Err(SudokuError::OrderMismatch(
+                self.puzzle.order(),
+                solution.order(),
+            ));
+        }
+        self.solution = Some(solution.clone());
+        Ok(self)
+    }
+
+    /// Write the candidates left in the empty cells once the givens are removed from their peers
+    ///
+    /// The candidates are only known up to 64x64 sudokus, larger ones are drawn without them.
+    pub fn with_candidates(mut self) -> Self {
+        self.candidates = true;
+        self
+    }
+
+    /// The side of the canvas
+    pub fn size(&self) -> f32 {
+        self.puzzle.order() as f32 * CELL + 2.0 * MARGIN
+    }
+
+    /// The lines of the grid, the thin ones first so the borders of the boxes are drawn over them
+    pub fn strokes(&self) -> Vec<Stroke> {
+        let order = self.puzzle.order();
+        let boxes = self.puzzle.box_shape();
+        let region = |row: Option<usize>, col: Option<usize>| {
+            let (row, col) = (row?, col?);
+            (row < order && col < order).then(|| self.puzzle.1.box_of(boxes, (row, col)))
+        };
+        let at = |ix: usize| MARGIN + ix as f32 * CELL;
+
+        let mut strokes = Vec::new();
+        for line in 0..=order {
+            // The widths of the borders along the line, merged into a stroke while they match
+            let horizontal: Vec<_> = (0..order)
+                .map(|col| region(line.checked_sub(1), Some(col)) != region(Some(line), Some(col)))
+                .collect();
+            let vertical: Vec<_> = (0..order)
+                .map(|row| region(Some(row), line.checked_sub(1)) != region(Some(row), Some(line)))
+                .collect();
+
+            for (thick, start, end) in runs(&horizontal) {
+                strokes.push(Stroke {
+                    from: (at(start), at(line)),
+                    to: (at(end), at(line)),
+                    width: if thick { THICK } else { THIN },
+                });
+            }
+            for (thick, start, end) in runs(&vertical) {
+                strokes.push(Stroke {
+                    from: (at(line), at(start)),
+                    to: (at(line), at(end)),
+                    width: if thick { THICK } else { THIN },
+                });
+            }
+        }
+
+        strokes.sort_by(|a, b| a.width.total_cmp(&b.width));
+        strokes
+    }
+
+    /// The values written in the cells
+    pub fn labels(&self) -> Vec<Label> {
+        let order = self.puzzle.order();
+        let candidates = self
+            .candidates
+            .then(|| Grid::new(&self.puzzle).ok())
+            .flatten();
+        // The candidates are spread over a square of spots in the cell
+        let side = (1..).find(|side| side * side >= order).unwrap_or(1);
+        let spot = CELL / side as f32;
+
+        let mut labels = Vec::new();
+        for row in 0..order {
+            for col in 0..order {
+                let (x, y) = (MARGIN + col as f32 * CELL, MARGIN + row as f32 * CELL);
+                let center = (x + CELL / 2.0, y + CELL / 2.0);
+                let solved = self
+                    .solution
+                    .as_ref()
+                    .and_then(|solution| solution.get(row, col));
+
+                if let Some(value) = self.puzzle.get(row, col) {
+                    labels.push(Label {
+                        text: value.to_string(),
+                        center,
+                        size: CELL * 0.6,
+                        ink: Ink::Given,
+                    });
+                } else if let Some(value) = solved {
+                    labels.push(Label {
+                        text: value.to_string(),
+                        center,
+                        size: CELL * 0.6,
+                        ink: Ink::Solved,
+                    });
+                } else if let Some(grid) = &candidates {
+                    for value in grid.candidates((row, col)) {
+                        let ix = value.get() as usize - 1;
+                        let (spot_row, spot_col) = (ix / side, ix % side);
+                        labels.push(Label {
+                            text: value.to_string(),
+                            center: (
+                                x + (spot_col as f32 + 0.5) * spot,
+                                y + (spot_row as f32 + 0.5) * spot,
+                            ),
+                            size: spot * 0.7,
+                            ink: Ink::Candidate,
+                        });
+                    }
+                }
+            }
+        }
+
+        labels
+    }
+}
+
+/// The runs of equal values in `borders` with their first and past the end indices
+fn runs(borders: &[bool]) -> Vec<(bool, usize, usize)> {
+    let mut runs: Vec<(bool, usize, usize)> = Vec::new();
+    for (ix, &border) in borders.iter().enumerate() {
+        match runs.last_mut() {
+            Some((thick, _, end)) if *thick == border => *end = ix + 1,
+            _ => runs.push((border, ix, ix + 1)),
+        }
+    }
+    runs
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Drawing, Ink, THICK, THIN};
+    use crate::sudoku::Sudoku;
+
+    #[test]
+    fn drawing_layout() {
+        let sudoku: Sudoku = "1.34..12.1..4..1".parse().expect("Successful parse");
+        let drawing = Drawing::new(&sudoku);
+
+        // Every line of a 4x4 grid is a single stroke, thick around the boxes
+        let strokes = drawing.strokes();
+        assert_eq!(strokes.len(), 10);
+        assert_eq!(strokes.iter().filter(|s| s.width == THIN).count(), 4);
+        assert_eq!(strokes.iter().filter(|s| s.width == THICK).count(), 6);
+        assert!(strokes[..4].iter().all(|s| s.width == THIN));
+
+        let labels = drawing.labels();
+        assert_eq!(labels.len(), 8);
+        assert!(labels.iter().all(|label| label.ink == Ink::Given));
+
+        let solution: Sudoku = "1234341221434321".parse().expect("Successful parse");
+        let solved = drawing
+            .clone()
+            .with_solution(&solution)
+            .expect("same order");
+        assert_eq!(solved.labels().len(), 16);
+        let candidates = drawing.with_candidates().labels();
+        assert!(candidates.iter().any(|label| label.ink == Ink::Candidate));
+    }
+}
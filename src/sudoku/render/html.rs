@@ -0,0 +1,156 @@
+//! Writing a puzzle as a standalone HTML page
+//!
+//! The page is filled from a template, where `{{name}}` is replaced by the value called `name`
+//! and `{{#name}}...{{/name}}` is only kept when that value isn't empty.
+
+use std::fmt::Write;
+
+use crate::sudoku::{Sudoku, SudokuError};
+
+/// The template of the page, with the `title`, the `puzzle` and an optional `solution`
+const PAGE: &str = include_str!("page.html");
+
+/// A page showing the puzzle as a grid to fill in, and the solution behind a toggle if given
+pub fn page(
+    puzzle: &Sudoku,
+    solution: Option<&Sudoku>,
+    title: &str,
+) -> Result<String, SudokuError> {
+    if let Some(solution) = solution.filter(|solution| solution.order() != puzzle.order()) {
+        return Err(SudokuError::OrderMismatch(puzzle.order(), solution.order()));
+    }
+    let puzzle_table = table(puzzle, None);
+    let solution_table = solution.map_or(String::new(), |solution| table(puzzle, Some(solution)));
+    Ok(fill(
+        PAGE,
+        &[
+            ("title", &escape(title)),
+            ("puzzle", &puzzle_table),
+            ("solution", &solution_table),
+        ],
+    ))
+}
+
+/// The grid as a table, the empty cells are inputs unless they hold the value of a solution
+fn table(puzzle: &Sudoku, solution: Option<&Sudoku>) -> String {
+    let order = puzzle.order();
+    let boxes = puzzle.box_shape();
+    let region = |row: Option<usize>, col: Option<usize>| {
+        let (row, col) = (row?, col?);
+        (row < order && col < order).then(|| puzzle.1.box_of(boxes, (row, col)))
+    };
+
+    let mut table = String::from("<table class=\"sudoku\">\n");
+    for row in 0..order {
+        table.push_str("<tr>");
+        for col in 0..order {
+            let here = region(Some(row), Some(col));
+            let borders = [
+                ("top", region(row.checked_sub(1), Some(col))),
+                ("left", region(Some(row), col.checked_sub(1))),
+                ("bottom", region(Some(row + 1), Some(col))),
+                ("right", region(Some(row), Some(col + 1))),
+            ];
+            let mut classes: Vec<_> = borders
+                .iter()
+                .filter(|(_, other)| *other != here)
+                .map(|(class, _)| *class)
+                .collect();
+
+            let solved = solution.and_then(|solution| solution.get(row, col));
+            let content = match (puzzle.get(row, col), solved) {
+                (Some(value), _) => {
+                    classes.push("given");
+                    value.to_string()
+                }
+                (None, Some(value)) => {
+                    classes.push("solved");
+                    value.to_string()
+                }
+                (None, None) => format!(
+                    "<input inputmode=\"numeric\" maxlength=\"{}\" aria-label=\"r{}c{}\">",
+                    order.to_string().len(),
+                    row + 1,
+                    col + 1
+                ),
+            };
+            let cell = match classes.join(" ").as_str() {
+                "" => format!("<td>{content}</td>"),
+                classes => format!("<td class=\"{classes}\">{content}</td>"),
+            };
+            table.push_str(&cell);
+        }
+        table.push_str("</tr>\n");
+    }
+    table.push_str("</table>");
+    table
+}
+
+/// Fill the placeholders and sections of a template with `values`
+fn fill(template: &str, values: &[(&str, &str)]) -> String {
+    let mut text = template.to_string();
+    for (name, value) in values {
+        let (open, close) = (format!("{{{{#{name}}}}}\n"), format!("{{{{/{name}}}}}\n"));
+        while let Some(start) = text.find(&open) {
+            let Some(end) = text[start..].find(&close).map(|end| start + end) else {
+                break;
+            };
+            let inner = text[start + open.len()..end].to_string();
+            let kept = if value.is_empty() { "" } else { &inner };
+            text.replace_range(start..end + close.len(), kept);
+        }
+    }
+
+    let mut filled = String::new();
+    let mut rest = text.as_str();
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}").map(|end| start + end) else {
+            break;
+        };
+        filled.push_str(&rest[..start]);
+        let name = &rest[start + 2..end];
+        match values.iter().find(|(key, _)| *key == name) {
+            Some((_, value)) => filled.push_str(value),
+            None => {
+                let _ = write!(filled, "{{{{{name}}}}}");
+            }
+        }
+        rest = &rest[end + 2..];
+    }
+    filled.push_str(rest);
+    filled
+}
+
+/// Escape the characters with a special meaning in HTML
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod test {
+    use super::{fill, page};
+    use crate::sudoku::Sudoku;
+
+    #[test]
+    fn html_page() {
+        let puzzle: Sudoku = "1.34..12.1..4..1".parse().expect("Successful parse");
+        let solution: Sudoku = "1234341221434321".parse().expect("Successful parse");
+
+        let html = page(&puzzle, None, "<Daily> puzzle").expect("same order");
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>&lt;Daily&gt; puzzle</title>"));
+        assert_eq!(html.matches("<input").count(), 8);
+        assert!(!html.contains("<details>"));
+        assert!(!html.contains("{{"));
+
+        let html = page(&puzzle, Some(&solution), "Puzzle").expect("same order");
+        assert!(html.contains("<summary>Show the solution</summary>"));
+        assert_eq!(html.matches("solved\">").count(), 8);
+        assert!(html.contains("<td class=\"top left given\">1</td>"));
+
+        assert_eq!(fill("{{a}} and {{b}}", &[("a", "x")]), "x and {{b}}");
+    }
+}
@@ -0,0 +1,224 @@
+//! Writing puzzles as a LaTeX document, to print a book of them
+
+use std::fmt::Write;
+
+use super::{Drawing, Ink};
+use crate::sudoku::{Sudoku, SudokuError};
+
+/// The width of the text of an A4 page with 2cm margins, in centimeters
+const TEXT_WIDTH: f32 = 17.0;
+/// The height of the text of an A4 page with 2cm margins, in centimeters
+const TEXT_HEIGHT: f32 = 25.7;
+/// The height left below every grid for its caption and the space to the next one
+const CAPTION: f32 = 1.5;
+/// The solutions printed on every page at the end, unless the puzzles are denser
+const SOLUTIONS_PER_PAGE: usize = 6;
+/// Points per centimeter, the unit of the font sizes
+const POINTS: f32 = 28.4528;
+
+impl Drawing {
+    /// The drawing as a TikZ picture `side` centimeters wide and tall
+    pub fn to_tikz(&self, side: f32) -> String {
+        let mut tikz = String::new();
+        self.write_tikz(&mut tikz, side)
+            .expect("writing to a string can't fail");
+        tikz
+    }
+
+    fn write_tikz(&self, out: &mut impl Write, side: f32) -> std::fmt::Result {
+        // The drawing grows down from the top left corner, TikZ grows up
+        let unit = side / self.size();
+        writeln!(
+            out,
+            r"\begin{{tikzpicture}}[x={unit:.5}cm, y=-{unit:.5}cm, line cap=rect]"
+        )?;
+        for stroke in self.strokes() {
+            let ((x1, y1), (x2, y2)) = (stroke.from, stroke.to);
+            writeln!(
+                out,
+                r"\draw[line width={:.4}cm] ({x1}, {y1}) -- ({x2}, {y2});",
+                stroke.width * unit
+            )?;
+        }
+        for label in self.labels() {
+            let (x, y) = label.center;
+            let size = label.size * unit * POINTS;
+            let (color, weight) = match label.ink {
+                Ink::Given => ("black", r"\bfseries"),
+                Ink::Solved => ("solved", ""),
+                Ink::Candidate => ("gray", ""),
+            };
+            writeln!(
+                out,
+                r"\node[text={color}, font=\fontsize{{{size:.1}}}{{{size:.1}}}\selectfont\sffamily{weight}] at ({x}, {y}) {{{}}};",
+                label.text
+            )?;
+        }
+        writeln!(out, r"\end{{tikzpicture}}")
+    }
+}
+
+/// A book of puzzles laid out on A4 pages, with their solutions at the end
+#[derive(Debug, Clone)]
+pub struct Book {
+    title: String,
+    per_page: usize,
+    /// The drawings of every puzzle and of its solution, with the note written under them
+    puzzles: Vec<(Drawing, Drawing, String)>,
+}
+
+impl Book {
+    /// An empty book with four puzzles on every page
+    pub fn new(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            per_page: 4,
+            puzzles: Vec::new(),
+        }
+    }
+
+    /// Lay out `count` puzzles on every page, at least one
+    pub fn with_per_page(mut self, count: usize) -> Self {
+        self.per_page = count.max(1);
+        self
+    }
+
+    /// Add a puzzle with its solution, `note` is written under the puzzle next to its number
+    pub fn push(
+        &mut self,
+        puzzle: &Sudoku,
+        solution: &Sudoku,
+        note: &str,
+    ) -> Result<(), SudokuError> {
+        let drawing = Drawing::new(puzzle);
+        let solved = drawing.clone().with_solution(solution)?;
+        self.puzzles.push((drawing, solved, note.to_string()));
+        Ok(())
+    }
+
+    /// The book as a LaTeX document, to compile with `pdflatex`
+    pub fn to_latex(&self) -> String {
+        let mut latex = String::new();
+        self.write_latex(&mut latex)
+            .expect("writing to a string can't fail");
+        latex
+    }
+
+    fn write_latex(&self, out: &mut impl Write) -> std::fmt::Result {
+        let title = escape(&self.title);
+        writeln!(out, r"\documentclass[a4paper]{{article}}")?;
+        writeln!(out, r"\usepackage[margin=2cm]{{geometry}}")?;
+        writeln!(out, r"\usepackage[T1]{{fontenc}}")?;
+        writeln!(out, r"\usepackage{{lmodern}}")?;
+        writeln!(out, r"\usepackage{{tikz}}")?;
+        writeln!(out, r"\definecolor{{solved}}{{HTML}}{{2A5DB0}}")?;
+        writeln!(out, r"\begin{{document}}")?;
+        writeln!(out, r"\begin{{titlepage}}")?;
+        writeln!(out, r"\centering\vspace*{{8cm}}")?;
+        writeln!(out, r"{{\Huge {title}\par}}\vspace{{1cm}}")?;
+        writeln!(out, r"{{\Large {} puzzles\par}}", self.puzzles.len())?;
+        writeln!(out, r"\end{{titlepage}}")?;
+
+        let puzzles: Vec<_> = self
+            .puzzles
+            .iter()
+            .enumerate()
+            .map(|(ix, (drawing, _, note))| match note.as_str() {
+                "" => (drawing, format!(r"\textbf{{{}}}", ix + 1)),
+                note => (
+                    drawing,
+                    format!(r"\textbf{{{}}} --- {}", ix + 1, escape(note)),
+                ),
+            })
+            .collect();
+        pages(out, &puzzles, self.per_page)?;
+
+        let solutions: Vec<_> = self
+            .puzzles
+            .iter()
+            .enumerate()
+            .map(|(ix, (_, solution, _))| (solution, format!("Solution {}", ix + 1)))
+            .collect();
+        pages(out, &solutions, self.per_page.max(SOLUTIONS_PER_PAGE))?;
+
+        writeln!(out, r"\end{{document}}")
+    }
+}
+
+/// Lay out the drawings `per_page` on every page, in rows as wide as they are tall
+fn pages(
+    out: &mut impl Write,
+    drawings: &[(&Drawing, String)],
+    per_page: usize,
+) -> std::fmt::Result {
+    let cols = (1..).find(|cols| cols * cols >= per_page).unwrap_or(1);
+    let rows = per_page.div_ceil(cols);
+    let width = TEXT_WIDTH / cols as f32;
+    let side = (width - 0.5).min(TEXT_HEIGHT / rows as f32 - CAPTION);
+
+    for page in drawings.chunks(per_page) {
+        for row in page.chunks(cols) {
+            write!(out, r"\noindent")?;
+            for (ix, (drawing, caption)) in row.iter().enumerate() {
+                if ix > 0 {
+                    write!(out, r"\hfill")?;
+                }
+                writeln!(
+                    out,
+                    r"\begin{{minipage}}[t]{{{:.2}cm}}\centering",
+                    width - 0.2
+                )?;
+                write!(out, "{}", drawing.to_tikz(side))?;
+                writeln!(out, r"\\[1mm]{caption}")?;
+                writeln!(out, r"\end{{minipage}}")?;
+            }
+            writeln!(out, r"\par\vfill")?;
+        }
+        writeln!(out, r"\clearpage")?;
+    }
+    Ok(())
+}
+
+/// Escape the characters with a special meaning in LaTeX
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str(r"\textbackslash{}"),
+            '^' => escaped.push_str(r"\textasciicircum{}"),
+            '~' => escaped.push_str(r"\textasciitilde{}"),
+            '{' | '}' | '$' | '&' | '#' | '_' | '%' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use super::Book;
+    use crate::sudoku::Sudoku;
+
+    #[test]
+    fn book_layout() {
+        let puzzle: Sudoku = "1.34..12.1..4..1".parse().expect("Successful parse");
+        let solution: Sudoku = "1234341221434321".parse().expect("Successful parse");
+
+        let mut book = Book::new("Puzzles & 100% solutions").with_per_page(1);
+        for note in ["easy", "hard"] {
+            book.push(&puzzle, &solution, note).expect("same order");
+        }
+        let latex = book.to_latex();
+
+        assert!(latex.starts_with(r"\documentclass"));
+        assert!(latex.contains(r"{\Huge Puzzles \& 100\% solutions\par}"));
+        assert!(latex.contains(r"\textbf{2} --- hard"));
+        assert_eq!(latex.matches(r"\begin{tikzpicture}").count(), 4);
+        // One page per puzzle, and both solutions on a single page
+        assert_eq!(latex.matches(r"\clearpage").count(), 3);
+        assert!(latex.trim_end().ends_with(r"\end{document}"));
+    }
+}
@@ -0,0 +1,77 @@
+//! Rasterizing a [`Drawing`] into a PNG image
+
+use resvg::{
+    tiny_skia,
+    usvg::{
+        self,
+        fontdb::{Family, Query},
+    },
+};
+use tracing::warn;
+
+use super::Drawing;
+use crate::sudoku::SudokuError;
+
+impl Drawing {
+    /// The drawing as a PNG image `size` pixels wide and tall
+    ///
+    /// The values are written with the sans-serif font of the system, without any font installed
+    /// the image only has the grid.
+    pub fn to_png(&self, size: u32) -> Result<Vec<u8>, SudokuError> {
+        let mut pixmap =
+            tiny_skia::Pixmap::new(size, size).ok_or(SudokuError::InvalidImageSize(size))?;
+
+        let mut options = usvg::Options::default();
+        let fonts = options.fontdb_mut();
+        fonts.load_system_fonts();
+        let sans = Query {
+            families: &[Family::SansSerif],
+            ..Query::default()
+        };
+        if fonts.query(&sans).is_none() {
+            // The system doesn't say which font is sans-serif, take any font that looks like one
+            let families: Vec<_> = fonts
+                .faces()
+                .flat_map(|face| &face.families)
+                .map(|(family, _)| family.clone())
+                .collect();
+            match families
+                .iter()
+                .find(|family| family.contains("Sans"))
+                .or(families.first())
+            {
+                Some(family) => fonts.set_sans_serif_family(family),
+                None => warn!("no fonts are installed, the values are left out of the image"),
+            }
+        }
+        let tree =
+            usvg::Tree::from_str(&self.to_svg(), &options).expect("the drawing is a valid SVG");
+
+        let scale = size as f32 / self.size();
+        resvg::render(
+            &tree,
+            tiny_skia::Transform::from_scale(scale, scale),
+            &mut pixmap.as_mut(),
+        );
+        Ok(pixmap
+            .encode_png()
+            .expect("encoding an image in memory can't fail"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::sudoku::{render::Drawing, Sudoku, SudokuError};
+
+    #[test]
+    fn png_image() {
+        let sudoku: Sudoku = "1.34..12.1..4..1".parse().expect("Successful parse");
+        let drawing = Drawing::new(&sudoku);
+
+        let png = drawing.to_png(64).expect("valid size");
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        // The width and height are the first fields of the header chunk
+        assert_eq!(png[16..24], [0, 0, 0, 64, 0, 0, 0, 64]);
+        assert_eq!(drawing.to_png(0), Err(SudokuError::InvalidImageSize(0)));
+    }
+}
@@ -0,0 +1,72 @@
+//! Writing a [`Drawing`] as an SVG image
+
+use std::fmt::Write;
+
+use super::{Drawing, Ink};
+
+impl Drawing {
+    /// The drawing as a standalone SVG document on a white background
+    pub fn to_svg(&self) -> String {
+        let mut svg = String::new();
+        self.write_svg(&mut svg)
+            .expect("writing to a string can't fail");
+        svg
+    }
+
+    fn write_svg(&self, out: &mut impl Write) -> std::fmt::Result {
+        let size = self.size();
+        writeln!(
+            out,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{size}" height="{size}" viewBox="0 0 {size} {size}">"#
+        )?;
+        writeln!(out, r#"<rect width="100%" height="100%" fill="white"/>"#)?;
+
+        writeln!(out, r#"<g stroke="black" stroke-linecap="square">"#)?;
+        for stroke in self.strokes() {
+            let ((x1, y1), (x2, y2)) = (stroke.from, stroke.to);
+            writeln!(
+                out,
+                r#"<line x1="{x1}" y1="{y1}" x2="{x2}" y2="{y2}" stroke-width="{}"/>"#,
+                stroke.width
+            )?;
+        }
+        writeln!(out, "</g>")?;
+
+        writeln!(
+            out,
+            r#"<g font-family="sans-serif" text-anchor="middle" dominant-baseline="central">"#
+        )?;
+        for label in self.labels() {
+            let (x, y) = label.center;
+            let style = match label.ink {
+                Ink::Given => r#"font-weight="bold""#,
+                Ink::Solved => r##"fill="#2a5db0""##,
+                Ink::Candidate => r##"fill="#707070""##,
+            };
+            writeln!(
+                out,
+                r#"<text x="{x}" y="{y}" font-size="{}" {style}>{}</text>"#,
+                label.size, label.text
+            )?;
+        }
+        writeln!(out, "</g>")?;
+        writeln!(out, "</svg>")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::sudoku::{render::Drawing, Sudoku};
+
+    #[test]
+    fn svg_document() {
+        let sudoku: Sudoku = "1.34..12.1..4..1".parse().expect("Successful parse");
+        let svg = Drawing::new(&sudoku).with_candidates().to_svg();
+
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<line ").count(), 10);
+        assert_eq!(svg.matches("font-weight=\"bold\"").count(), 8);
+        assert!(svg.contains("fill=\"#707070\""));
+    }
+}
@@ -0,0 +1,427 @@
+//! Variant constraints on top of the classic rows, columns and boxes
+
+use std::{str::FromStr, sync::Arc};
+
+use ndarray::Array2;
+
+use super::{candidates::Candidates, SudokuError, SudokuValue};
+
+/// A killer cage: its cells hold distinct values adding up to `sum`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cage {
+    pub sum: u32,
+    pub cells: Vec<(usize, usize)>,
+}
+
+/// Irregular regions replacing the boxes of a jigsaw Sudoku
+///
+/// Parsed from a layout with one region id per cell, ignoring whitespace. Cells with the same
+/// character belong to the same region, so a 9x9 layout has 9 distinct ids used 9 times each:
+///
+/// ```text
+/// 111222333
+/// 111222333
+/// ...
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Regions {
+    /// The region containing each cell
+    region_of: Array2<usize>,
+    /// The cells of each region in row-major order
+    cells: Vec<Vec<(usize, usize)>>,
+}
+
+/// The number of rows and columns of the boxes, which multiply to the order of the Sudoku
+///
+/// Parsed from `2x3` for boxes two rows tall and three columns wide.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoxShape {
+    pub rows: usize,
+    pub cols: usize,
+}
+
+/// The extra constraints of a Sudoku variant, the classic rules have none
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Rules {
+    pub(crate) cages: Arc<[Cage]>,
+    /// Whether both main diagonals hold distinct values, as in Sudoku X
+    pub(crate) diagonals: bool,
+    /// The regions used instead of the boxes
+    pub(crate) regions: Option<Arc<Regions>>,
+    /// The shape of the boxes, the default one of the order if `None`
+    pub(crate) boxes: Option<BoxShape>,
+}
+
+impl BoxShape {
+    /// The boxes of an `order * order` Sudoku, as square as possible and wider than tall
+    ///
+    /// Returns `None` if the boxes would have a single row, like for prime orders.
+    pub fn of_order(order: usize) -> Option<Self> {
+        (2..=order.isqrt())
+            .rev()
+            .find(|rows| order.is_multiple_of(*rows))
+            .map(|rows| Self {
+                rows,
+                cols: order / rows,
+            })
+    }
+
+    /// The number of cells of a box
+    pub fn order(self) -> usize {
+        self.rows * self.cols
+    }
+
+    /// The box containing a cell, boxes are numbered in row-major order
+    fn box_of(self, (row, col): (usize, usize)) -> usize {
+        // Each band of boxes is `rows` tall and holds `order / cols = rows` boxes
+        (row / self.rows) * self.rows + col / self.cols
+    }
+
+    /// The `pos`th cell of a box in row-major order
+    fn box_cell(self, cell_box: usize, pos: usize) -> (usize, usize) {
+        (
+            (cell_box / self.rows) * self.rows + pos / self.cols,
+            (cell_box % self.rows) * self.cols + pos % self.cols,
+        )
+    }
+}
+
+impl std::fmt::Display for BoxShape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x{}", self.rows, self.cols)
+    }
+}
+
+impl FromStr for BoxShape {
+    type Err = SudokuError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let malformed = || SudokuError::InvalidBoxShape(s.to_string());
+        let (rows, cols) = s.split_once(['x', 'X']).ok_or_else(malformed)?;
+        let (rows, cols) = (
+            rows.trim().parse().map_err(|_| malformed())?,
+            cols.trim().parse().map_err(|_| malformed())?,
+        );
+        if rows == 0 || cols == 0 {
+            return Err(malformed());
+        }
+
+        Ok(Self { rows, cols })
+    }
+}
+
+impl Cage {
+    /// Whether the filled cells can still reach the sum without repeating a value
+    pub(crate) fn valid(&self, grid: &Array2<SudokuValue>) -> bool {
+        let mut seen = Candidates::default();
+        let mut sum = 0;
+        let mut empty = 0;
+
+        for &cell in &self.cells {
+            match grid[cell].0 {
+                Some(value) if !seen.insert(value) => return false,
+                Some(value) => sum += value.get() as u32,
+                None => empty += 1,
+            }
+        }
+
+        if empty == 0 {
+            sum == self.sum
+        } else {
+            sum < self.sum
+        }
+    }
+
+    /// Check the cage fits in a Sudoku of size order * order
+    fn check(&self, order: usize) -> Result<(), &'static str> {
+        if order > Candidates::MAX {
+            return Err("needs a sudoku with at most 64 values");
+        }
+        let len = self.cells.len();
+        if len == 0 {
+            return Err("has no cells");
+        }
+        if self
+            .cells
+            .iter()
+            .any(|&(row, col)| row >= order || col >= order)
+        {
+            return Err("has a cell outside the grid");
+        }
+        if len > order {
+            return Err("has more cells than values");
+        }
+
+        let (min, max) = sum_bounds(Candidates::full(order), len);
+        if !(min..=max).contains(&self.sum) {
+            return Err("has an unreachable sum");
+        }
+
+        Ok(())
+    }
+}
+
+impl Rules {
+    /// Add killer cages to the rules, every cell can be in at most one cage
+    pub(crate) fn with_cages(self, order: usize, cages: Vec<Cage>) -> Result<Self, SudokuError> {
+        let mut caged = Array2::from_elem((order, order), false);
+
+        for (ix, cage) in cages.iter().enumerate() {
+            cage.check(order)
+                .map_err(|reason| SudokuError::InvalidCage { cage: ix, reason })?;
+
+            for &cell in &cage.cells {
+                if std::mem::replace(&mut caged[cell], true) {
+                    return Err(SudokuError::InvalidCage {
+                        cage: ix,
+                        reason: "overlaps another cage",
+                    });
+                }
+            }
+        }
+
+        Ok(Self {
+            cages: cages.into(),
+            ..self
+        })
+    }
+
+    /// Add both main diagonals as units
+    pub(crate) fn with_diagonals(self) -> Self {
+        Self {
+            diagonals: true,
+            ..self
+        }
+    }
+
+    /// Use boxes of `shape` instead of the default ones of the order
+    pub(crate) fn with_boxes(self, order: usize, shape: BoxShape) -> Result<Self, SudokuError> {
+        if shape.order() != order {
+            return Err(SudokuError::InvalidBoxShape(format!(
+                "{shape} boxes don't fit a {order}x{order} sudoku"
+            )));
+        }
+
+        Ok(Self {
+            boxes: Some(shape),
+            ..self
+        })
+    }
+
+    /// The shape of the boxes of an `order * order` Sudoku following these rules
+    pub(crate) fn box_shape(&self, order: usize) -> BoxShape {
+        self.boxes
+            .or_else(|| BoxShape::of_order(order))
+            .expect("the order has boxes")
+    }
+
+    /// Replace the boxes with irregular regions
+    pub(crate) fn with_regions(self, order: usize, regions: Regions) -> Result<Self, SudokuError> {
+        if regions.order() != order {
+            return Err(SudokuError::InvalidRegions(
+                "the layout and the sudoku differ in size",
+            ));
+        }
+
+        Ok(Self {
+            regions: Some(Arc::new(regions)),
+            ..self
+        })
+    }
+
+    /// Whether the grid satisfies the extra constraints so far
+    pub(crate) fn valid(&self, grid: &Array2<SudokuValue>) -> bool {
+        let order = grid.dim().0;
+
+        self.cages.iter().all(|cage| cage.valid(grid))
+            && (!self.diagonals
+                || (0..2).all(|diagonal| distinct(grid, diagonal_cells(order, diagonal))))
+            && self.regions.iter().all(|regions| {
+                regions
+                    .cells
+                    .iter()
+                    .all(|cells| distinct(grid, cells.iter().copied()))
+            })
+    }
+
+    /// The box (or region) containing a cell, `boxes` being [`Rules::box_shape`]
+    pub(crate) fn box_of(&self, boxes: BoxShape, cell: (usize, usize)) -> usize {
+        match &self.regions {
+            Some(regions) => regions.region_of[cell],
+            None => boxes.box_of(cell),
+        }
+    }
+
+    /// The `pos`th cell of a box (or region), `boxes` being [`Rules::box_shape`]
+    pub(crate) fn box_cell(&self, boxes: BoxShape, cell_box: usize, pos: usize) -> (usize, usize) {
+        match &self.regions {
+            Some(regions) => regions.cells[cell_box][pos],
+            None => boxes.box_cell(cell_box, pos),
+        }
+    }
+}
+
+impl Regions {
+    /// The number of regions, which is also the size of every region
+    pub fn order(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// The region containing a cell
+    pub fn region_of(&self, cell: (usize, usize)) -> usize {
+        self.region_of[cell]
+    }
+
+    /// The cells of a region
+    pub fn cells(&self, region: usize) -> &[(usize, usize)] {
+        &self.cells[region]
+    }
+}
+
+impl FromStr for Regions {
+    type Err = SudokuError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let ids: Vec<_> = s.chars().filter(|c| !c.is_whitespace()).collect();
+        let order = (ids.len() as f64).sqrt() as usize;
+        if order * order != ids.len() || order == 0 {
+            return Err(SudokuError::InvalidRegions("the layout is not square"));
+        }
+
+        let mut names = Vec::with_capacity(order);
+        let mut cells: Vec<Vec<_>> = Vec::with_capacity(order);
+        let mut region_of = Array2::zeros((order, order));
+        for (ix, id) in ids.into_iter().enumerate() {
+            let region = names
+                .iter()
+                .position(|&name| name == id)
+                .unwrap_or_else(|| {
+                    names.push(id);
+                    cells.push(Vec::with_capacity(order));
+                    names.len() - 1
+                });
+
+            let cell = (ix / order, ix % order);
+            region_of[cell] = region;
+            cells[region].push(cell);
+        }
+
+        if cells.len() != order || cells.iter().any(|cells| cells.len() != order) {
+            return Err(SudokuError::InvalidRegions(
+                "every region must have as many cells as there are regions",
+            ));
+        }
+
+        Ok(Self { region_of, cells })
+    }
+}
+
+/// The cells of the main diagonal (`0`) or the anti-diagonal (`1`)
+pub(crate) fn diagonal_cells(
+    order: usize,
+    diagonal: usize,
+) -> impl Iterator<Item = (usize, usize)> {
+    (0..order).map(move |pos| match diagonal {
+        0 => (pos, pos),
+        _ => (pos, order - 1 - pos),
+    })
+}
+
+/// Whether the filled cells hold distinct values
+fn distinct(grid: &Array2<SudokuValue>, cells: impl IntoIterator<Item = (usize, usize)>) -> bool {
+    let mut seen = Candidates::default();
+    cells
+        .into_iter()
+        .all(|cell| grid[cell].0.is_none_or(|value| seen.insert(value)))
+}
+
+/// The smallest and largest sums of `count` distinct values taken from `values`
+pub(crate) fn sum_bounds(values: Candidates, count: usize) -> (u32, u32) {
+    let values: Vec<_> = values.iter().map(|value| value.get() as u32).collect();
+    if values.len() < count {
+        return (u32::MAX, 0);
+    }
+
+    let min = values[..count].iter().sum();
+    let max = values[values.len() - count..].iter().sum();
+    (min, max)
+}
+
+/// Parse killer cages, one per line as a sum followed by its cells
+///
+/// Cells are written as `r1c1` (1-based), blank lines and lines starting with `#` are skipped:
+///
+/// ```text
+/// # The top left corner
+/// 3 r1c1 r1c2
+/// ```
+pub fn parse_cages(input: &str) -> Result<Vec<Cage>, SudokuError> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(line, text)| (line, text.trim()))
+        .filter(|(_, text)| !text.is_empty() && !text.starts_with('#'))
+        .map(|(line, text)| parse_cage(text).ok_or(SudokuError::MalformedCage { line }))
+        .collect()
+}
+
+pub(crate) fn parse_cage(text: &str) -> Option<Cage> {
+    let mut words = text.split_whitespace();
+    let sum = words.next()?.parse().ok()?;
+    let cells = words.map(parse_cell).collect::<Option<_>>()?;
+
+    Some(Cage { sum, cells })
+}
+
+/// Parse a 1-based `r1c1` cell into a 0-based (row, column)
+pub(crate) fn parse_cell(cell: &str) -> Option<(usize, usize)> {
+    let cell = cell.to_ascii_lowercase();
+    let (row, col) = cell.strip_prefix('r')?.split_once('c')?;
+    let (row, col): (usize, usize) = (row.parse().ok()?, col.parse().ok()?);
+
+    Some((row.checked_sub(1)?, col.checked_sub(1)?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_cages, BoxShape, Cage};
+    use crate::sudoku::SudokuError;
+
+    #[test]
+    fn box_shapes() {
+        let six = BoxShape::of_order(6).expect("6x6 has boxes");
+        assert_eq!(six, BoxShape { rows: 2, cols: 3 });
+        assert_eq!(BoxShape::of_order(12), Some(BoxShape { rows: 3, cols: 4 }));
+        assert_eq!(BoxShape::of_order(9), Some(BoxShape { rows: 3, cols: 3 }));
+        assert_eq!(BoxShape::of_order(7), None);
+        assert_eq!("3x2".parse(), Ok(BoxShape { rows: 3, cols: 2 }));
+        assert!("3x".parse::<BoxShape>().is_err());
+
+        assert_eq!(six.box_of((1, 4)), 1);
+        assert_eq!(six.box_of((2, 0)), 2);
+        assert_eq!(six.box_cell(3, 5), (3, 5));
+    }
+
+    #[test]
+    fn parse_cage_file() {
+        let cages = parse_cages("# comment\n\n3 r1c1 R1C2\n9 r9c9\n").expect("valid cages");
+        assert_eq!(
+            cages,
+            [
+                Cage {
+                    sum: 3,
+                    cells: vec![(0, 0), (0, 1)]
+                },
+                Cage {
+                    sum: 9,
+                    cells: vec![(8, 8)]
+                }
+            ]
+        );
+
+        assert_eq!(
+            parse_cages("3 r1c1\n4 r0c1"),
+            Err(SudokuError::MalformedCage { line: 1 })
+        );
+    }
+}
@@ -0,0 +1,284 @@
+//! Saving a game in progress to resume it later
+//!
+//! A [`SaveGame`] is written as text, one record per line after a header with the version of the
+//! format. Every row of the givens and of the entered values gets a line, with `.` for the empty
+//! cells, followed by the rules of the variant, the pencil marks of each cell and the time played:
+//!
+//! ```text
+//! nsudoku-savegame 1
+//! givens 5 3 . . 7 . . . .
+//! ...
+//! entries . . 4 . . . . . .
+//! ...
+//! diagonals
+//! cage 15 r1c1 r1c2
+//! marks r1c4 1 2 6
+//! elapsed 93125
+//! ```
+//!
+//! The time played is in milliseconds. A box shape is written as `boxes 2x3` and irregular regions
+//! as `regions` followed by their layout, see [`Regions`].
+
+use std::{fmt::Display, num::NonZeroU16, str::FromStr, time::Duration};
+
+use super::{
+    rules::{parse_cage, parse_cell, Cage, Regions},
+    Sudoku, SudokuError, SudokuValue,
+};
+
+/// The version of the format written by [`SaveGame`], older ones are read as long as they parse
+pub const VERSION: u32 = 1;
+
+/// The header starting every savegame, followed by its version
+const HEADER: &str = "nsudoku-savegame";
+
+/// A game in progress: the puzzle and what the player wrote on it
+#[derive(Debug, Clone)]
+pub struct SaveGame {
+    pub givens: Sudoku,
+    /// The values entered in every cell in row-major order, `None` for the empty cells
+    ///
+    /// The entries may repeat values, the player hasn't necessarily found the solution yet.
+    pub entries: Vec<Option<NonZeroU16>>,
+    /// The pencil marks of every cell in row-major order
+    pub marks: Vec<Vec<NonZeroU16>>,
+    /// How long the game was played for
+    pub elapsed: Duration,
+}
+
+impl SaveGame {
+    /// A game that hasn't started yet
+    pub fn new(givens: Sudoku) -> Self {
+        let cells = givens.order() * givens.order();
+        Self {
+            givens,
+            entries: vec![None; cells],
+            marks: vec![Vec::new(); cells],
+            elapsed: Duration::ZERO,
+        }
+    }
+}
+
+impl Display for SaveGame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let order = self.givens.order();
+        let value = |value: Option<NonZeroU16>| value.map_or(".".to_string(), |v| v.to_string());
+
+        writeln!(f, "{HEADER} {VERSION}")?;
+        for row in self.givens.0.rows() {
+            let values: Vec<_> = row.iter().map(|cell| value(cell.0)).collect();
+            writeln!(f, "givens {}", values.join(" "))?;
+        }
+        for row in self.entries.chunks(order) {
+            let values: Vec<_> = row.iter().map(|&cell| value(cell)).collect();
+            writeln!(f, "entries {}", values.join(" "))?;
+        }
+
+        let rules = &self.givens.1;
+        if let Some(boxes) = rules.boxes {
+            writeln!(f, "boxes {boxes}")?;
+        }
+        if let Some(regions) = &rules.regions {
+            let layout: String = (0..order)
+                .flat_map(|row| (0..order).map(move |col| (row, col)))
+                .map(|cell| region_id(regions.region_of(cell)))
+                .collect();
+            writeln!(f, "regions {layout}")?;
+        }
+        if rules.diagonals {
+            writeln!(f, "diagonals")?;
+        }
+        for Cage { sum, cells } in rules.cages.iter() {
+            write!(f, "cage {sum}")?;
+            for (row, col) in cells {
+                write!(f, " r{}c{}", row + 1, col + 1)?;
+            }
+            writeln!(f)?;
+        }
+
+        for (ix, marks) in self.marks.iter().enumerate() {
+            if marks.is_empty() {
+                continue;
+            }
+            let marks: Vec<_> = marks.iter().map(NonZeroU16::to_string).collect();
+            writeln!(
+                f,
+                "marks r{}c{} {}",
+                ix / order + 1,
+                ix % order + 1,
+                marks.join(" ")
+            )?;
+        }
+        writeln!(f, "elapsed {}", self.elapsed.as_millis())
+    }
+}
+
+impl FromStr for SaveGame {
+    type Err = SudokuError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s
+            .lines()
+            .enumerate()
+            .map(|(line, text)| (line, text.trim()))
+            .filter(|(_, text)| !text.is_empty());
+
+        let malformed = |line: usize, reason| SudokuError::MalformedSaveGame { line, reason };
+        let version = lines
+            .next()
+            .and_then(|(_, header)| header.strip_prefix(HEADER))
+            .ok_or(malformed(0, "it doesn't start with the savegame header"))?;
+        match version.trim().parse() {
+            Ok(version) if version <= VERSION => {}
+            Ok(version) => return Err(SudokuError::UnsupportedSaveGame(version)),
+            Err(_) => return Err(malformed(0, "the version is not a number")),
+        }
+
+        let mut givens = Vec::new();
+        let mut entries = Vec::new();
+        let mut boxes = None;
+        let mut regions = None;
+        let mut diagonals = false;
+        let mut cages = Vec::new();
+        let mut marks = Vec::new();
+        let mut elapsed = Duration::ZERO;
+        for (line, text) in lines {
+            let (key, rest) = text.split_once(' ').unwrap_or((text, ""));
+            let row = || parse_row(rest).ok_or(malformed(line, "invalid value"));
+            match key {
+                "givens" => givens.push(row()?),
+                "entries" => entries.push(row()?),
+                "boxes" => boxes = Some(rest.parse()?),
+                "regions" => regions = Some(rest.parse::<Regions>()?),
+                "diagonals" => diagonals = true,
+                "cage" => cages.push(parse_cage(rest).ok_or(malformed(line, "invalid cage"))?),
+                "marks" => {
+                    let (cell, values) = rest.split_once(' ').unwrap_or((rest, ""));
+                    let cell = parse_cell(cell).ok_or(malformed(line, "invalid cell"))?;
+                    let values = values
+                        .split_whitespace()
+                        .map(|value| value.parse().ok())
+                        .collect::<Option<Vec<NonZeroU16>>>()
+                        .ok_or(malformed(line, "invalid value"))?;
+                    marks.push((line, cell, values));
+                }
+                "elapsed" => {
+                    let millis = rest.parse().map_err(|_| malformed(line, "invalid time"))?;
+                    elapsed = Duration::from_millis(millis);
+                }
+                _ => return Err(malformed(line, "unknown record")),
+            }
+        }
+
+        let order = givens.len();
+        if givens.iter().any(|row| row.len() != order) {
+            return Err(malformed(0, "the givens are not square"));
+        }
+        let mut sudoku = Sudoku::from_order_vec(
+            order,
+            givens.into_iter().flatten().map(SudokuValue).collect(),
+        )?;
+        if let Some(boxes) = boxes {
+            sudoku = sudoku.with_box_shape(boxes)?;
+        }
+        if let Some(regions) = regions {
+            sudoku = sudoku.with_regions(regions)?;
+        }
+        if diagonals {
+            sudoku = sudoku.with_diagonals()?;
+        }
+        if !cages.is_empty() {
+            sudoku = sudoku.with_cages(cages)?;
+        }
+
+        let mut game = Self::new(sudoku);
+        let entries: Vec<_> = entries.into_iter().flatten().collect();
+        if entries.len() != order * order {
+            return Err(malformed(0, "the entries don't cover the grid"));
+        }
+        if entries
+            .iter()
+            .flatten()
+            .any(|value| value.get() as usize > order)
+        {
+            return Err(malformed(0, "an entry is out of range"));
+        }
+        game.entries = entries;
+        for (line, (row, col), values) in marks {
+            if row >= order || col >= order {
+                return Err(malformed(line, "the cell is outside the grid"));
+            }
+            if values.iter().any(|value| value.get() as usize > order) {
+                return Err(malformed(line, "invalid value"));
+            }
+            game.marks[row * order + col] = values;
+        }
+        game.elapsed = elapsed;
+        Ok(game)
+    }
+}
+
+/// Parse a row of values separated by spaces, with `.` for the empty cells
+fn parse_row(text: &str) -> Option<Vec<Option<NonZeroU16>>> {
+    text.split_whitespace()
+        .map(|value| match value {
+            "." => Some(None),
+            value => value.parse().ok().map(Some),
+        })
+        .collect()
+}
+
+/// The character naming region `ix` in a layout
+fn region_id(ix: usize) -> char {
+    char::from_digit(ix as u32, 36).unwrap_or_else(|| {
+        char::from_u32(0xC0 + ix as u32).expect("the regions are fewer than the code points")
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::{num::NonZeroU16, time::Duration};
+
+    use super::SaveGame;
+    use crate::sudoku::{Sudoku, SudokuError};
+
+    #[test]
+    fn savegame_round_trip() {
+        let sudoku: Sudoku =
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79"
+                .parse()
+                .expect("Successful parse");
+        let sudoku = sudoku.with_diagonals().expect("valid diagonals");
+
+        let mut game = SaveGame::new(sudoku);
+        game.entries[2] = NonZeroU16::new(4);
+        game.entries[3] = NonZeroU16::new(5);
+        game.marks[7] = vec![NonZeroU16::MIN, NonZeroU16::new(3).expect("non zero")];
+        game.elapsed = Duration::from_millis(93125);
+
+        let saved = game.to_string();
+        let loaded: SaveGame = saved.parse().expect("valid savegame");
+        assert_eq!(loaded.givens.to_line(), game.givens.to_line());
+        assert_eq!(loaded.givens.rules(), game.givens.rules());
+        assert_eq!(loaded.entries, game.entries);
+        assert_eq!(loaded.marks, game.marks);
+        assert_eq!(loaded.elapsed, game.elapsed);
+        assert_eq!(loaded.to_string(), saved);
+
+        let newer = saved.replacen("savegame 1", "savegame 2", 1);
+        assert_eq!(
+            newer.parse::<SaveGame>().err(),
+            Some(SudokuError::UnsupportedSaveGame(2))
+        );
+        assert!("givens 1 2".parse::<SaveGame>().is_err());
+
+        let marked = saved.replacen("marks r1c8 1 3", "marks r1c8 10 3", 1);
+        assert!(matches!(
+            marked.parse::<SaveGame>(),
+            Err(SudokuError::MalformedSaveGame {
+                reason: "invalid value",
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,131 @@
+//! [`serde`] support for [`Sudoku`], enabled by the `serde` feature
+//!
+//! A Sudoku serializes as its single line representation, see [`Sudoku::to_line`]. It
+//! deserializes from either a line, parsed with [`Sudoku::parse_lenient`], or a nested array of
+//! rows with `0` for the empty cells. Use [`rows`] to serialize it as a nested array instead:
+//!
+//! ```
+//! # use nsudoku_solver::Sudoku;
+//! #[derive(serde::Serialize, serde::Deserialize)]
+//! struct Puzzle {
+//!     #[serde(with = "nsudoku_solver::serialize::rows")]
+//!     grid: Sudoku,
+//! }
+//! ```
+//!
+//! Only the grid is serialized, the rules of a variant are lost.
+
+use std::{fmt, num::NonZeroU16};
+
+use serde::{
+    de::{self, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use super::{Sudoku, SudokuValue};
+
+impl Serialize for Sudoku {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_line())
+    }
+}
+
+impl<'de> Deserialize<'de> for Sudoku {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(SudokuVisitor)
+    }
+}
+
+/// Serialize a [`Sudoku`] as a nested array of rows, with `0` for the empty cells
+pub mod rows {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::Sudoku;
+
+    pub fn serialize<S: Serializer>(sudoku: &Sudoku, serializer: S) -> Result<S::Ok, S::Error> {
+        let rows: Vec<Vec<u16>> = sudoku
+            .0
+            .rows()
+            .into_iter()
+            .map(|row| {
+                row.iter()
+                    .map(|value| value.map_or(0, |v| v.get()))
+                    .collect()
+            })
+            .collect();
+        rows.serialize(serializer)
+    }
+
+    /// Deserialize a [`Sudoku`] from either a nested array of rows or a line
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Sudoku, D::Error> {
+        Sudoku::deserialize(deserializer)
+    }
+}
+
+struct SudokuVisitor;
+
+impl<'de> Visitor<'de> for SudokuVisitor {
+    type Value = Sudoku;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a sudoku line or a nested array of rows")
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Self::Value, E> {
+        Sudoku::parse_lenient(s).map_err(E::custom)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut values = Vec::new();
+        let mut order = None;
+
+        while let Some(row) = seq.next_element::<Vec<u16>>()? {
+            if *order.get_or_insert(row.len()) != row.len() {
+                return Err(de::Error::custom("rows of different lengths"));
+            }
+            values.extend(row.into_iter().map(|v| SudokuValue(NonZeroU16::new(v))));
+        }
+
+        Sudoku::from_order_vec(order.unwrap_or(0), values).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde::{Deserialize, Serialize};
+
+    use crate::sudoku::Sudoku;
+
+    const LINE: &str =
+        "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+
+    #[derive(Serialize, Deserialize)]
+    struct Puzzle {
+        #[serde(with = "super::rows")]
+        grid: Sudoku,
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        let sudoku: Sudoku = LINE.parse().expect("Successful parse");
+
+        let json = serde_json::to_string(&sudoku).expect("serialize");
+        assert_eq!(json, format!("\"{LINE}\""));
+        let parsed: Sudoku = serde_json::from_str(&json).expect("deserialize");
+        assert_eq!(parsed.to_line(), LINE);
+
+        let json = serde_json::to_string(&Puzzle { grid: sudoku }).expect("serialize");
+        assert!(json.starts_with(r#"{"grid":[[5,3,0,0,7,0,0,0,0],[6,0,0,1,9,5"#));
+        let parsed: Puzzle = serde_json::from_str(&json).expect("deserialize");
+        assert_eq!(parsed.grid.to_line(), LINE);
+    }
+
+    #[test]
+    fn serde_rejects_invalid_grids() {
+        assert!(serde_json::from_str::<Sudoku>("\"123\"").is_err());
+        assert!(serde_json::from_str::<Sudoku>("[[1,2],[3]]").is_err());
+        assert!(
+            serde_json::from_str::<Sudoku>("[[1,1,0,0],[0,0,0,0],[0,0,0,0],[0,0,0,0]]").is_err()
+        );
+    }
+}
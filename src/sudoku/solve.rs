@@ -1,126 +1,736 @@
-use std::{collections::HashSet, fmt::Display, num::NonZeroU8, ops::ControlFlow};
+#[cfg(feature = "parallel")]
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::{
+    cmp::Reverse, fmt::Display, num::NonZeroU16, ops::ControlFlow, sync::Arc, time::Duration,
+};
 
 use ndarray::Array2;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
-use super::SudokuValue;
+use thiserror::Error;
+use tracing::debug;
 
-macro_rules! propagate_ok {
-    ($x:expr) => {
-        match $x {
-            Ok(value) => return Ok(value),
-            Err(err) => err,
+use self::{
+    backjump::{latest, level, up_to, Levels, Reasons, MAX_LEVEL},
+    buckets::Buckets,
+};
+use super::{
+    candidates::Candidates,
+    peers::Peers,
+    render::{self, Style},
+    rules::{sum_bounds, BoxShape, Cage, Rules},
+    techniques::{Cell, CellName, Deduction, Grid, Technique, Unit},
+    Conflict, SudokuError, SudokuValue,
+};
+
+mod backjump;
+mod buckets;
+mod dlx;
+#[cfg(feature = "parallel")]
+mod portfolio;
+mod registry;
+mod sat;
+mod stats;
+mod trace;
+
+pub use dlx::{dlx, ExactCover};
+#[cfg(feature = "parallel")]
+pub use portfolio::portfolio;
+#[cfg(feature = "parallel")]
+pub use registry::Portfolio;
+pub use registry::{
+    Dfs, Dlx, Logic, NaiveDfs, Propagate, Registry, Sat, SolveOutcome, Solver, SortedDfs,
+};
+pub use sat::{sat, Cnf};
+pub use stats::{
+    interrupt, interrupted, out_of_time, with_budget, with_progress, with_timeout, Budget,
+    Progress, SolveStats,
+};
+pub use trace::{with_observer, Event, Observer, Reason, Replay};
+
+/// The solved Sudoku, or why the solver returned without a solution
+pub type SudokuResult = Result<super::Sudoku, SolveError>;
+
+/// Why a solver returned without a solution
+#[derive(Debug, Clone, Error)]
+pub enum SolveError {
+    /// The givens contradict each other, found before searching
+    #[error("the sudoku has no solution, {reason}")]
+    Unsolvable { reason: Contradiction },
+    /// The solver found no solution, returning the original or partially solved Sudoku
+    #[error("no solution found for the sudoku")]
+    Unsolved(super::Sudoku),
+    /// The solver ran out of a budget before finding a solution
+    #[error("the solver gave up, {budget}")]
+    Aborted { budget: Budget },
+    /// The solver can't search the Sudoku, like one too large for its candidates
+    #[error(transparent)]
+    Unsupported(#[from] SudokuError),
+}
+
+impl SolveError {
+    /// The Sudoku the solver gave up on, `None` if the givens contradict each other, the solver
+    /// ran out of a budget or couldn't search the Sudoku
+    pub fn partial(&self) -> Option<&super::Sudoku> {
+        match self {
+            SolveError::Unsolvable { .. }
+            | SolveError::Aborted { .. }
+            | SolveError::Unsupported(_) => None,
+            SolveError::Unsolved(sudoku) => Some(sudoku),
         }
-    };
+    }
 }
 
-pub type SudokuResult = Result<super::Sudoku, super::Sudoku>;
+/// A contradiction in the givens of a Sudoku
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Contradiction {
+    /// Two givens repeat a value in a unit
+    Repeated(Conflict),
+    /// An empty cell has no candidates left
+    NoCandidates(Cell),
+    /// A value has no empty cell left in a unit
+    NoCell { unit: Unit, value: NonZeroU16 },
+    /// A killer cage can't reach its sum
+    Cage,
+}
 
 type InternalResult = ControlFlow<super::Sudoku, ()>;
 
-pub fn naive_dfs(mut sudoku: super::Sudoku) -> SudokuResult {
-    let order = sudoku.order();
-    let Some((ix, _)) = sudoku.0.indexed_iter().find(|(_, value)| value.is_none())
-    else {
-        if sudoku.solved() {
-            return Ok(sudoku);
+/// Try every value in every empty cell, checking the whole Sudoku after each guess
+pub fn naive_dfs(sudoku: super::Sudoku) -> (SudokuResult, SolveStats) {
+    SolveStats::record(|stats| naive_dfs_impl(sudoku, stats))
+}
+
+fn naive_dfs_impl(mut sudoku: super::Sudoku, stats: &mut SolveStats) -> SudokuResult {
+    let order = sudoku.order() as u16;
+    let empty: Vec<_> = sudoku
+        .0
+        .indexed_iter()
+        .filter(|(_, value)| value.is_none())
+        .map(|(ix, _)| ix)
+        .collect();
+
+    // The empty cells before `depth` hold the values being tried
+    let mut depth = 0;
+    while depth < empty.len() {
+        let ix = empty[depth];
+        let start = sudoku.0[ix].map_or(1, |value| value.get() + 1);
+
+        let next = (start..=order).find(|&value| {
+            sudoku.0[ix] = SudokuValue(NonZeroU16::new(value));
+            sudoku.valid()
+        });
+
+        if next.is_some() {
+            depth += 1;
+            stats.expand(depth);
+            if stats.cancelled() {
+                for &ix in &empty {
+                    sudoku.0[ix] = SudokuValue(None);
+                }
+                return Err(SolveError::Unsolved(sudoku));
+            }
         } else {
-            return Err(sudoku);
+            sudoku.0[ix] = SudokuValue(None);
+            if depth == 0 {
+                return Err(SolveError::Unsolved(sudoku));
+            }
+            depth -= 1;
+            stats.backtracks += 1;
         }
-    };
+    }
+
+    if sudoku.solved() {
+        Ok(sudoku)
+    } else {
+        Err(SolveError::Unsolved(sudoku))
+    }
+}
 
-    for value in 1..=order as u8 {
-        *sudoku.0.get_mut(ix).unwrap() = SudokuValue(Some(value.try_into().unwrap()));
+/// The logic applied before searching
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PropagationLevel {
+    /// Naked and hidden singles
+    #[default]
+    Singles,
+    /// Singles and locked candidates
+    Locked,
+    /// Singles, locked candidates, naked and hidden subsets up to quads and fish up to jellyfish
+    Subsets,
+}
 
-        if sudoku.valid() {
-            sudoku = propagate_ok!(naive_dfs(sudoku));
+impl PropagationLevel {
+    /// The hardest technique applied, `None` if only singles are propagated
+    fn hardest(self) -> Option<Technique> {
+        match self {
+            PropagationLevel::Singles => None,
+            PropagationLevel::Locked => Some(Technique::LockedCandidates),
+            PropagationLevel::Subsets => Some(Technique::HiddenQuad),
         }
     }
+}
 
-    *sudoku.0.get_mut(ix).unwrap() = SudokuValue(None);
+/// How the sorted search picks the cell to branch on
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Heuristic {
+    /// The first cell with the fewest candidates
+    Mrv,
+    /// The cell with the fewest candidates, breaking ties by the most empty peers
+    #[default]
+    MrvDegree,
+}
 
-    Err(sudoku)
+/// The order in which the candidates of the branching cell are tried
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ValueOrder {
+    /// From the smallest to the largest value
+    #[default]
+    Ascending,
+    /// Least constraining value first, the one with the fewest candidates to remove from its peers
+    Lcv,
+    /// Shuffled at every branch
+    Random,
 }
 
-pub fn dfs(sudoku: super::Sudoku) -> SudokuResult {
-    let orig = sudoku.clone();
-    let mut sudoku: AugmentedSudoku = sudoku.into();
+/// The settings of the [`dfs_with`] and [`sorted_dfs_with`] searches
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SearchOptions {
+    /// The logic applied before searching
+    pub level: PropagationLevel,
+    /// The cell to branch on, only used by the sorted search
+    pub heuristic: Heuristic,
+    /// The order of the values tried on the branching cell
+    pub value_order: ValueOrder,
+    /// Jump back to the latest guess behind each dead end instead of the previous one
+    pub backjump: bool,
+    /// Search the candidates of the first guess in parallel, only used by the sorted search
+    ///
+    /// Ignored without the `parallel` feature.
+    pub parallel: bool,
+    /// The seed of the random value order, a random one if `None`
+    pub seed: Option<u64>,
+    /// Start the search over after expanding this many nodes, doubling the budget every time
+    ///
+    /// Restarting is only useful with [`ValueOrder::Random`], which takes other paths every time.
+    pub restarts: Option<u64>,
+}
 
-    sudoku.prune_possible();
+/// The budgets and settings every [`Solver`] runs with, see [`Solver::solve_with`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SolveConfig {
+    /// Give up after expanding this many search nodes, counted separately by every thread of the
+    /// parallel searches
+    pub max_nodes: Option<u64>,
+    /// Give up after running for this long
+    pub max_time: Option<Duration>,
+    /// The seed of the randomized searches, replacing the one of the solver if set
+    pub random_seed: Option<u64>,
+    /// The logic applied before searching, replacing the one of the solver if set
+    pub propagation_level: Option<PropagationLevel>,
+}
 
-    match dfs_impl(sudoku) {
-        ControlFlow::Continue(_) => Err(orig),
-        ControlFlow::Break(solved) => Ok(solved),
+impl SolveConfig {
+    /// `options` with the seed and propagation level of the configuration
+    pub fn apply(&self, options: SearchOptions) -> SearchOptions {
+        SearchOptions {
+            level: self.propagation_level.unwrap_or(options.level),
+            seed: self.random_seed.or(options.seed),
+            ..options
+        }
     }
 }
 
-fn dfs_impl(sudoku: AugmentedSudoku) -> InternalResult {
-    let Some((ix, possible)) =
+/// Prepare the Sudoku for searching, applying the techniques of `level` first
+fn prepare(
+    sudoku: super::Sudoku,
+    level: PropagationLevel,
+    stats: &mut SolveStats,
+) -> Result<AugmentedSudoku, SolveError> {
+    let Some(hardest) = level.hardest() else {
+        return sudoku.try_into();
+    };
+
+    let mut grid = Grid::new(&sudoku)?;
+    let fixed = match trace::observer() {
+        Some(observer) => {
+            let mut fixed = 0;
+            while let Some(step) = grid.next_step_up_to(hardest) {
+                fixed += matches!(step.deduction, Deduction::Place { .. }) as usize;
+                observer.apply(&mut grid, &step, 0);
+            }
+            fixed
+        }
+        None => grid.solve_up_to(hardest),
+    };
+    debug!(?hardest, fixed, "applied the techniques before searching");
+    stats.propagations += fixed as u64;
+
+    let mut prepared: AugmentedSudoku = grid.sudoku().try_into()?;
+    for (ix, value) in prepared.data.indexed_iter_mut() {
+        if let AugmentedValue::Possible(possible) = value {
+            *possible = grid.candidate_set(ix);
+        }
+    }
+    Ok(prepared)
+}
+
+/// Try every candidate of each empty cell in order, pruning candidates after each guess
+pub fn dfs(sudoku: super::Sudoku) -> (SudokuResult, SolveStats) {
+    dfs_with(sudoku, SearchOptions::default())
+}
+
+/// [`dfs`], applying the techniques of the propagation level before searching
+pub fn dfs_with(sudoku: super::Sudoku, options: SearchOptions) -> (SudokuResult, SolveStats) {
+    SolveStats::record(|stats| {
+        let orig = sudoku.clone();
+        let mut sudoku = prepare(sudoku, options.level, stats)?;
+
+        match sudoku.prune_possible() {
+            Ok(fixed) => {
+                debug!(fixed, "propagated the clues");
+                stats.propagations += fixed as u64;
+            }
+            Err(reason) => {
+                debug!(%reason, "the clues contradict each other");
+                return Err(SolveError::Unsolvable { reason });
+            }
+        }
+
+        let search = search(sudoku, stats, options, |sudoku, _| {
+            if sudoku.prune_cages().is_none() {
+                return Branch::DeadEnd;
+            }
+
             sudoku
                 .data
                 .indexed_iter()
-                .find(|(_, value)| !value.is_fixed())
-        else {
-            return ControlFlow::Break(sudoku.into());
+                .find_map(|(ix, value)| match value {
+                    AugmentedValue::Fixed(_) => None,
+                    AugmentedValue::Possible(possible) => Some(Branch::Cell(ix, *possible)),
+                })
+                .unwrap_or(Branch::Solved)
+        });
+
+        match search {
+            ControlFlow::Continue(_) => Err(SolveError::Unsolved(orig)),
+            ControlFlow::Break(solved) => Ok(solved),
+        }
+    })
+}
+
+/// Propagate singles and branch on the cell with the fewest candidates
+pub fn sorted_dfs(sudoku: super::Sudoku) -> (SudokuResult, SolveStats) {
+    sorted_dfs_with(sudoku, SearchOptions::default())
+}
+
+/// [`sorted_dfs`], applying the techniques of the propagation level before searching and picking
+/// the cells with the heuristic
+pub fn sorted_dfs_with(
+    sudoku: super::Sudoku,
+    options: SearchOptions,
+) -> (SudokuResult, SolveStats) {
+    SolveStats::record(|stats| {
+        let orig = sudoku.clone();
+        let mut sudoku = prepare(sudoku, options.level, stats)?;
+
+        match sudoku.prune_possible() {
+            Ok(fixed) => {
+                debug!(fixed, "propagated the clues");
+                stats.propagations += fixed as u64;
+            }
+            Err(reason) => {
+                debug!(%reason, "the clues contradict each other");
+                return Err(SolveError::Unsolvable { reason });
+            }
+        }
+
+        sudoku.track_buckets();
+        let branch = |sudoku: &mut AugmentedSudoku, stats: &mut SolveStats| {
+            sorted_branch(sudoku, stats, options.level, options.heuristic)
+        };
+        #[cfg(feature = "parallel")]
+        let search = if options.parallel {
+            parallel_search(sudoku, stats, options, branch)
+        } else {
+            search(sudoku, stats, options, branch)
+        };
+        #[cfg(not(feature = "parallel"))]
+        let search = search(sudoku, stats, options, branch);
+        match search {
+            ControlFlow::Continue(_) => Err(SolveError::Unsolved(orig)),
+            ControlFlow::Break(solved) => Ok(solved),
+        }
+    })
+}
+
+/// Propagate singles, and locked candidates from [`PropagationLevel::Locked`] on, and select the
+/// cell to branch on with `heuristic`
+fn sorted_branch(
+    sudoku: &mut AugmentedSudoku,
+    stats: &mut SolveStats,
+    level: PropagationLevel,
+    heuristic: Heuristic,
+) -> Branch {
+    loop {
+        let Some(fixed) = sudoku.propagate() else {
+            return Branch::DeadEnd;
         };
+        stats.propagations += fixed as u64;
 
-    // println!("{sudoku}");
+        if level < PropagationLevel::Locked || sudoku.eliminate_locked() == 0 {
+            break;
+        }
+    }
 
-    let possible = if let AugmentedValue::Possible(possible) = possible {
-        possible.clone()
-    } else {
-        unreachable!()
+    // Propagation fixes every single, so all remaining cells have several candidates
+    let buckets = sudoku
+        .buckets
+        .as_ref()
+        .expect("the sorted search tracks buckets");
+    let Some(fewest) = buckets.fewest() else {
+        return Branch::Solved;
     };
+    let mut cells = buckets.cells(fewest);
+    let ix = match heuristic {
+        Heuristic::Mrv => cells.next(),
+        Heuristic::MrvDegree => {
+            // The earliest of the cells with the most empty peers
+            let mut best: Option<((usize, usize), usize)> = None;
+            for ix in cells {
+                let degree = sudoku.degree(ix);
+                if best.is_none_or(|(_, most)| degree > most) {
+                    best = Some((ix, degree));
+                }
+            }
+            best.map(|(ix, _)| ix)
+        }
+    }
+    .expect("the bucket is not empty");
 
-    for value in possible {
-        dfs_impl(sudoku.fix_value(ix, value))?;
+    match sudoku.data[ix] {
+        AugmentedValue::Possible(possible) => Branch::Cell(ix, possible),
+        AugmentedValue::Fixed(_) => unreachable!("only empty cells are bucketed"),
     }
+}
 
-    ControlFlow::Continue(())
+/// How the search should continue from a node
+enum Branch {
+    /// Every cell is fixed
+    Solved,
+    /// The node has no solution
+    DeadEnd,
+    /// Try each candidate of a cell
+    Cell((usize, usize), Candidates),
 }
 
-pub fn sorted_dfs(sudoku: super::Sudoku) -> SudokuResult {
-    let mut sudoku: AugmentedSudoku = sudoku.into();
+/// A node of the search whose remaining candidates have not been tried yet
+struct Frame {
+    ix: (usize, usize),
+    /// The values left to try, the next one last
+    remaining: Vec<NonZeroU16>,
+    /// The length of the trail when the node was expanded
+    checkpoint: usize,
+    /// The earlier levels behind the candidates ruled out of the cell and the failed values
+    conflicts: Levels,
+}
+
+/// Iterative depth first search starting from `sudoku`
+///
+/// `branch` prepares each node and selects the cell to branch on. The frames are kept in an
+/// explicit stack so large Sudokus don't overflow the call stack, and the search mutates a single
+/// board, undoing the changes of a failed guess through its trail.
+///
+/// With [`SearchOptions::backjump`], every frame collects the levels behind its failures and an
+/// exhausted frame jumps back to the latest of them, skipping the guesses that had no part in it.
+/// Past [`MAX_LEVEL`] the levels can't be told apart, and the search falls back to backtracking.
+///
+/// With [`SearchOptions::restarts`], the search undoes everything once it runs out of nodes and
+/// starts over from the root with twice the budget, so it still explores the whole tree.
+fn search(
+    mut sudoku: AugmentedSudoku,
+    stats: &mut SolveStats,
+    options: SearchOptions,
+    mut branch: impl FnMut(&mut AugmentedSudoku, &mut SolveStats) -> Branch,
+) -> InternalResult {
+    let mut stack: Vec<Frame> = Vec::new();
+    if options.backjump {
+        sudoku.reasons = Some(Reasons::new(sudoku.order));
+    }
+    let mut rng = StdRng::seed_from_u64(options.seed.unwrap_or_else(rand::random));
+    let root = sudoku.trail.len();
+    let mut budget = options.restarts;
+    let mut expanded = 0;
+
+    loop {
+        match branch(&mut sudoku, stats) {
+            Branch::Solved => return ControlFlow::Break(sudoku.into()),
+            Branch::DeadEnd => {
+                let depth = stack.len();
+                sudoku.notify(Event::DeadEnd { depth });
+                if let Some(frame) = stack.last_mut() {
+                    stats.backtracks += 1;
+                    if let Some(conflict) = sudoku.conflict() {
+                        // A dead end the last guess had no part in is reached with every value
+                        if conflict & level(depth) == 0 {
+                            frame.remaining.clear();
+                        }
+                        frame.conflicts |= conflict & !level(depth);
+                    }
+                }
+            }
+            Branch::Cell(ix, candidates) => {
+                stack.push(Frame {
+                    ix,
+                    remaining: sudoku.value_order(ix, candidates, options.value_order, &mut rng),
+                    checkpoint: sudoku.trail.len(),
+                    conflicts: sudoku.excluded(ix, candidates),
+                });
+                sudoku.notify(Event::Branch {
+                    cell: ix,
+                    depth: stack.len(),
+                });
+                if stack.len() > MAX_LEVEL {
+                    sudoku.reasons = None;
+                }
+            }
+        }
+
+        // Find the next value to try, backtracking out of exhausted frames
+        loop {
+            if let Some(limit) = budget.filter(|&limit| expanded >= limit && !stack.is_empty()) {
+                debug!(nodes = stats.nodes, limit, "restarting the search");
+                sudoku.undo(root);
+                sudoku.notify(Event::Restart);
+                sudoku.depth = 0;
+                if let Some(reasons) = &mut sudoku.reasons {
+                    reasons.depth = 0;
+                }
+                stack.clear();
+                budget = Some(limit.saturating_mul(2));
+                expanded = 0;
+                break;
+            }
+
+            let depth = stack.len();
+            let Some(frame) = stack.last_mut() else {
+                return ControlFlow::Continue(());
+            };
 
-    sudoku.prune_possible();
+            sudoku.undo(frame.checkpoint);
+            if let Some(value) = frame.remaining.pop() {
+                stats.expand(depth);
+                expanded += 1;
+                if stats.cancelled() {
+                    return ControlFlow::Continue(());
+                }
+                if let Some(reasons) = &mut sudoku.reasons {
+                    reasons.depth = depth;
+                }
+                sudoku.depth = depth;
+                sudoku.fix_value_inplace(frame.ix, value, level(depth), Reason::Guess);
+                break;
+            }
 
-    match sorted_dfs_impl(&mut sudoku) {
-        ControlFlow::Continue(_) => Err(sudoku.into()),
-        ControlFlow::Break(solved) => Ok(solved),
+            let exhausted = stack.pop().expect("the frame was just found");
+            if sudoku.reasons.is_some() {
+                // Every value failed because of the earlier levels in the conflicts, so only
+                // changing the latest of them can help
+                let target = latest(exhausted.conflicts);
+                if target + 1 < depth {
+                    debug!(from = depth, to = target, "backjumping");
+                }
+                stack.truncate(target);
+                if let Some(frame) = stack.last_mut() {
+                    frame.conflicts |= exhausted.conflicts & !level(target);
+                }
+            }
+            if !stack.is_empty() {
+                stats.backtracks += 1;
+            }
+        }
     }
 }
 
-fn sorted_dfs_impl(sudoku: &mut AugmentedSudoku) -> InternalResult {
-    let Some((ix, possible)) = sudoku.data.indexed_iter().filter_map(|(ix, value)| match value {
-        AugmentedValue::Fixed(_) => None,
-        AugmentedValue::Possible(set) => Some((ix, set)),
-    }).min_by_key(|(_, x)| {
-            x.len()
-    }) else {
-        return ControlFlow::Break(sudoku.clone().into());
+/// [`search`] trying the candidates of the first cell to branch on in parallel
+///
+/// Every candidate is searched on its own copy of the board by the rayon thread pool, and the
+/// first solution found cancels the other branches.
+#[cfg(feature = "parallel")]
+fn parallel_search(
+    mut sudoku: AugmentedSudoku,
+    stats: &mut SolveStats,
+    options: SearchOptions,
+    branch: impl Fn(&mut AugmentedSudoku, &mut SolveStats) -> Branch + Sync,
+) -> InternalResult {
+    let (ix, candidates) = match branch(&mut sudoku, stats) {
+        Branch::Solved => return ControlFlow::Break(sudoku.into()),
+        Branch::DeadEnd => return ControlFlow::Continue(()),
+        Branch::Cell(ix, candidates) => (ix, candidates),
     };
 
-    let possible = possible.clone();
+    let limits = stats::limits();
+    let progress = stats::progress();
+    let cancel = Arc::new(AtomicBool::new(false));
+    let mut rng = StdRng::seed_from_u64(options.seed.unwrap_or_else(rand::random));
+    let values: Vec<_> = sudoku
+        .value_order(ix, candidates, options.value_order, &mut rng)
+        .into_iter()
+        .rev()
+        .map(|value| (value, rng.gen()))
+        .collect();
+    let branches: Vec<_> = values
+        .into_par_iter()
+        .map(|(value, seed)| {
+            let options = SearchOptions {
+                seed: Some(seed),
+                ..options
+            };
+            let mut sudoku = sudoku.clone();
+            let mut stats = SolveStats::default();
+            stats.expand(1);
+            sudoku.depth = 1;
+            sudoku.fix_value_inplace(ix, value, 0, Reason::Guess);
 
-    // If it's the only possiblitiy then just fix it
-    if possible.len() == 1 {
-        let value = possible.into_iter().next().unwrap();
-        sudoku.fix_value_inplace(ix, value);
-        return sorted_dfs_impl(sudoku);
+            let result = stats::cancellable(limits, &progress, &cancel, || {
+                search(sudoku, &mut stats, options, &branch)
+            });
+            if result.is_break() {
+                cancel.store(true, Ordering::Relaxed);
+            }
+            stats.max_depth += 1;
+            (result, stats)
+        })
+        .collect();
+
+    // The branches cancelled by the solution didn't run out of time
+    let mut timed_out = false;
+    let mut solution = None;
+    for (result, branch) in branches {
+        timed_out |= branch.timed_out;
+        *stats += branch;
+        if let ControlFlow::Break(solved) = result {
+            solution.get_or_insert(solved);
+        }
     }
+    stats.timed_out = timed_out && solution.is_none();
+    solution.map_or(ControlFlow::Continue(()), ControlFlow::Break)
+}
 
-    // Clone for each possible value otherwise
-    for value in possible {
-        sorted_dfs_impl(&mut sudoku.fix_value(ix, value))?;
+/// Count the solutions of the Sudoku, stopping once `limit` solutions are found
+pub fn count_solutions(sudoku: &super::Sudoku, limit: usize) -> usize {
+    // Dancing Links can't prune killer cages, the sorted search can
+    if !sudoku.1.cages.is_empty() {
+        return count_sorted(sudoku, limit);
     }
 
-    ControlFlow::Continue(())
+    dlx::count(sudoku, limit)
+}
+
+fn count_sorted(sudoku: &super::Sudoku, limit: usize) -> usize {
+    let mut sudoku: AugmentedSudoku = sudoku
+        .clone()
+        .try_into()
+        .expect("cages fit in the candidates");
+    if sudoku.prune_possible().is_err() {
+        return 0;
+    }
+    sudoku.track_buckets();
+
+    // Treat every solution as a dead end until enough have been found
+    let mut found = 0;
+    let _ = search(
+        sudoku,
+        &mut SolveStats::default(),
+        SearchOptions::default(),
+        |sudoku, stats| {
+            // Nothing to keep to a propagation level, locked candidates prune the most dead ends
+            let branch = sorted_branch(
+                sudoku,
+                stats,
+                PropagationLevel::Locked,
+                Heuristic::default(),
+            );
+            if !matches!(branch, Branch::Solved) {
+                return branch;
+            }
+
+            found += 1;
+            if found >= limit {
+                Branch::Solved
+            } else {
+                Branch::DeadEnd
+            }
+        },
+    );
+
+    found
+}
+
+/// Whether the Sudoku has exactly one solution
+pub fn has_unique_solution(sudoku: &super::Sudoku) -> bool {
+    count_solutions(sudoku, 2) == 1
+}
+
+/// Solve the Sudoku using only naked and hidden singles
+///
+/// Returns the partially solved Sudoku if guessing would be required
+pub fn propagate(sudoku: super::Sudoku) -> (SudokuResult, SolveStats) {
+    SolveStats::record(|stats| {
+        let mut sudoku: AugmentedSudoku = sudoku.try_into()?;
+
+        match sudoku.prune_possible() {
+            Ok(fixed) => {
+                debug!(fixed, "propagated the clues");
+                stats.propagations += fixed as u64;
+            }
+            Err(reason) => {
+                debug!(%reason, "the clues contradict each other");
+                return Err(SolveError::Unsolvable { reason });
+            }
+        }
+
+        if sudoku.data.iter().all(AugmentedValue::is_fixed) {
+            Ok(sudoku.into())
+        } else {
+            Err(SolveError::Unsolved(sudoku.into()))
+        }
+    })
+}
+
+/// Solve the Sudoku using only the human-style [`techniques`](super::techniques), never guessing
+///
+/// Returns the partially solved Sudoku if guessing would be required
+pub fn logic(sudoku: super::Sudoku) -> (SudokuResult, SolveStats) {
+    SolveStats::record(|stats| {
+        let mut grid = Grid::new(&sudoku)?;
+        let observer = trace::observer();
+
+        while let Some(step) = grid.next_step() {
+            if matches!(step.deduction, Deduction::Place { .. }) {
+                stats.propagations += 1;
+            }
+            match &observer {
+                Some(observer) => observer.apply(&mut grid, &step, 0),
+                None => grid.apply(&step.deduction),
+            }
+        }
+
+        let sudoku = grid.sudoku();
+        if sudoku.solved() {
+            Ok(sudoku)
+        } else {
+            Err(SolveError::Unsolved(sudoku))
+        }
+    })
 }
 
 /// Augmented Sudoku Value
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy)]
 enum AugmentedValue {
-    Fixed(NonZeroU8),
-    Possible(HashSet<NonZeroU8>),
+    Fixed(NonZeroU16),
+    Possible(Candidates),
 }
 
 impl AugmentedValue {
@@ -131,10 +741,26 @@ impl AugmentedValue {
         }
     }
 
-    fn remove(&mut self, value: NonZeroU8) -> bool {
+    fn is_fixed_to(&self, value: NonZeroU16) -> bool {
+        matches!(self, AugmentedValue::Fixed(fixed) if *fixed == value)
+    }
+
+    fn contains(&self, value: NonZeroU16) -> bool {
+        matches!(self, AugmentedValue::Possible(possible) if possible.contains(value))
+    }
+
+    fn remove(&mut self, value: NonZeroU16) -> bool {
         match self {
             AugmentedValue::Fixed(_) => false,
-            AugmentedValue::Possible(possible) => possible.remove(&value),
+            AugmentedValue::Possible(possible) => possible.remove(value),
+        }
+    }
+
+    /// The bucket of the cell in [`Buckets`], `None` if it is fixed
+    fn bucket(&self) -> Option<usize> {
+        match self {
+            AugmentedValue::Fixed(_) => None,
+            AugmentedValue::Possible(possible) => Some(possible.len()),
         }
     }
 }
@@ -143,13 +769,33 @@ impl AugmentedValue {
 #[derive(Debug, Clone)]
 #[allow(unused)]
 struct AugmentedSudoku {
-    cell_size: usize,
+    boxes: BoxShape,
     order: usize,
     data: Array2<AugmentedValue>,
+    /// The previous value of every modified cell, in modification order
+    trail: Vec<((usize, usize), AugmentedValue)>,
+    rules: Rules,
+    /// The index of the killer cage containing each cell
+    cage_of: Array2<Option<usize>>,
+    /// The peers of every cell, shared by the copies of the board
+    peers: Arc<Peers>,
+    /// The empty cells by number of candidates, kept up to date by every change to `data` once
+    /// tracked, see [`AugmentedSudoku::track_buckets`]
+    buckets: Option<Buckets>,
+    /// The levels behind every removed candidate and fixed cell, only tracked when backjumping
+    reasons: Option<Reasons>,
+    /// Where the changes to the board are reported, see [`with_observer`]
+    observer: Option<Observer>,
+    /// The number of guesses behind the current changes to the board
+    depth: usize,
 }
 
 impl AugmentedSudoku {
-    fn prune_possible(&mut self) {
+    /// Remove the values of the givens from the candidates of their peers, then fix naked and
+    /// hidden singles until no more progress is made
+    ///
+    /// Returns the number of fixed cells, or the contradiction found if the Sudoku has no solution
+    fn prune_possible(&mut self) -> Result<usize, Contradiction> {
         let fixed_values = self
             .data
             .indexed_iter()
@@ -163,46 +809,503 @@ impl AugmentedSudoku {
             .collect::<Vec<_>>();
 
         for (ix, value) in fixed_values {
-            self.remove_value(ix, value);
+            self.remove_value(ix, value, 0);
+        }
+
+        if let Some(contradiction) = self.contradiction() {
+            return Err(contradiction);
+        }
+
+        // A failed propagation leaves an empty cell or a value without a cell, unless a cage broke
+        self.propagate()
+            .ok_or_else(|| self.contradiction().unwrap_or(Contradiction::Cage))
+    }
+
+    /// Find a repeated value, a cell without candidates or a value without a cell in some unit
+    fn contradiction(&self) -> Option<Contradiction> {
+        let empty = self
+            .data
+            .indexed_iter()
+            .find_map(|(ix, value)| match value {
+                AugmentedValue::Possible(possible) if possible.is_empty() => Some(ix),
+                _ => None,
+            });
+        if let Some(ix) = empty {
+            return Some(Contradiction::NoCandidates(ix));
         }
+
+        for unit in 0..self.units() {
+            let cells: Vec<_> = (0..self.order)
+                .map(|pos| self.unit_cell(unit, pos))
+                .collect();
+            let mut fixed = Candidates::default();
+            let mut possible = Candidates::default();
+
+            for (pos, &ix) in cells.iter().enumerate() {
+                match self.data[ix] {
+                    AugmentedValue::Fixed(value) if !fixed.insert(value) => {
+                        let first = cells[..pos]
+                            .iter()
+                            .copied()
+                            .find(|&other| self.data[other].is_fixed_to(value))
+                            .expect("the value was fixed before");
+                        return Some(Contradiction::Repeated(Conflict {
+                            unit: self.unit_name(unit),
+                            cells: (first, ix),
+                            value,
+                        }));
+                    }
+                    AugmentedValue::Fixed(_) => {}
+                    AugmentedValue::Possible(set) => possible = possible | set,
+                }
+            }
+
+            if let Some(value) = (Candidates::full(self.order) - fixed - possible).pop() {
+                return Some(Contradiction::NoCell {
+                    unit: self.unit_name(unit),
+                    value,
+                });
+            }
+        }
+
+        None
     }
 
-    fn remove_value(&mut self, ix: (usize, usize), value: NonZeroU8) {
-        let (row, col) = ix;
-        let chunk = (row / self.cell_size) * self.cell_size + col / self.cell_size;
+    /// Remove `value` from the peers of `ix`, a consequence of `levels`
+    fn remove_value(&mut self, ix: (usize, usize), value: NonZeroU16, levels: Levels) {
+        let peers = Arc::clone(&self.peers);
+        for &peer in peers.of(ix) {
+            self.remove_candidate(peer, value, levels, Reason::Peer);
+        }
+    }
 
-        self.data.row_mut(row).map_inplace(|val| {
-            val.remove(value);
-        });
-        self.data.column_mut(col).map_inplace(|val| {
-            val.remove(value);
-        });
-        self.data
-            .exact_chunks_mut((self.cell_size, self.cell_size))
-            .into_iter()
-            .nth(chunk)
-            .unwrap()
-            .map_inplace(|val| {
-                val.remove(value);
+    /// The levels that removed every candidate of `ix` but `values`, none unless backjumping
+    fn excluded(&self, ix: (usize, usize), values: Candidates) -> Levels {
+        let Some(reasons) = &self.reasons else {
+            return 0;
+        };
+        (Candidates::full(self.order) - values)
+            .iter()
+            .fold(0, |levels, value| levels | reasons.removed(ix, value))
+    }
+
+    /// The levels that ruled `value` out of the cells of `unit` but `except`, none unless
+    /// backjumping
+    fn excluded_from(&self, unit: usize, value: NonZeroU16, except: &[(usize, usize)]) -> Levels {
+        let Some(reasons) = &self.reasons else {
+            return 0;
+        };
+        (0..self.order)
+            .map(|pos| self.unit_cell(unit, pos))
+            .filter(|ix| !except.contains(ix))
+            .fold(0, |levels, ix| {
+                levels
+                    | match self.data[ix] {
+                        AugmentedValue::Fixed(_) => reasons.fixed(ix),
+                        AugmentedValue::Possible(_) => reasons.removed(ix, value),
+                    }
+            })
+    }
+
+    /// Every level so far, for the deductions that can't tell which guesses they depend on
+    fn all_levels(&self) -> Levels {
+        self.reasons
+            .as_ref()
+            .map_or(0, |reasons| up_to(reasons.depth))
+    }
+
+    /// The levels behind the contradiction of a dead end, `None` unless backjumping
+    fn conflict(&self) -> Option<Levels> {
+        let reasons = self.reasons.as_ref()?;
+        Some(match self.contradiction() {
+            Some(Contradiction::NoCandidates(ix)) => self.excluded(ix, Candidates::default()),
+            Some(Contradiction::NoCell { unit, value }) => {
+                let unit = match unit {
+                    Unit::Row(row) => row,
+                    Unit::Column(col) => self.order + col,
+                    Unit::Box(ix) => 2 * self.order + ix,
+                    Unit::Diagonal(ix) => 3 * self.order + ix,
+                };
+                self.excluded_from(unit, value, &[])
+            }
+            // Broken cages and repeated values aren't traced back to their guesses
+            _ => up_to(reasons.depth),
+        })
+    }
+
+    /// The number of empty peers of `ix`
+    fn degree(&self, ix: (usize, usize)) -> usize {
+        self.peers
+            .of(ix)
+            .iter()
+            .filter(|&&peer| !self.data[peer].is_fixed())
+            .count()
+    }
+
+    /// The candidates of a cell in the order they should be tried, the first one last
+    fn value_order(
+        &self,
+        ix: (usize, usize),
+        candidates: Candidates,
+        order: ValueOrder,
+        rng: &mut impl Rng,
+    ) -> Vec<NonZeroU16> {
+        let mut values: Vec<_> = candidates.iter().collect();
+        values.reverse();
+        match order {
+            ValueOrder::Ascending => {}
+            ValueOrder::Lcv => {
+                // Stable, so values removing as many candidates stay in ascending order
+                let removed = |value| {
+                    self.peers
+                        .of(ix)
+                        .iter()
+                        .filter(|&&peer| match self.data[peer] {
+                            AugmentedValue::Possible(possible) => possible.contains(value),
+                            AugmentedValue::Fixed(_) => false,
+                        })
+                        .count()
+                };
+                values.sort_by_key(|&value| Reverse(removed(value)));
+            }
+            ValueOrder::Random => values.shuffle(rng),
+        }
+        values
+    }
+
+    /// Remove a candidate from a cell as a consequence of `levels`, returns true if it was present
+    fn remove_candidate(
+        &mut self,
+        ix: (usize, usize),
+        value: NonZeroU16,
+        levels: Levels,
+        reason: Reason,
+    ) -> bool {
+        let cell = &mut self.data[ix];
+        let previous = *cell;
+        let removed = cell.remove(value);
+        if removed {
+            if let Some(buckets) = &mut self.buckets {
+                buckets.update(ix, previous.bucket(), cell.bucket());
+            }
+            if let Some(reasons) = &mut self.reasons {
+                reasons.remove(ix, value, levels);
+            }
+            self.trail.push((ix, previous));
+            self.notify(Event::Eliminate {
+                cell: ix,
+                value,
+                reason,
+                depth: self.depth,
             });
+        }
+        removed
+    }
+
+    /// Report `event` to the observer of the search, if there is one
+    fn notify(&self, event: Event) {
+        if let Some(observer) = &self.observer {
+            observer.notify(event);
+        }
+    }
+
+    /// Restore the cells modified since the trail had `checkpoint` entries
+    fn undo(&mut self, checkpoint: usize) {
+        for (ix, value) in self.trail.drain(checkpoint..).rev() {
+            if let Some(buckets) = &mut self.buckets {
+                buckets.update(ix, self.data[ix].bucket(), value.bucket());
+            }
+            self.data[ix] = value;
+        }
     }
 
-    fn fix_value_inplace(&mut self, ix: (usize, usize), value: NonZeroU8) {
-        *self.data.get_mut(ix).unwrap() = value.into();
+    /// Start bucketing the empty cells by their number of candidates, letting the sorted search
+    /// find the cells with the fewest candidates without scanning the grid
+    fn track_buckets(&mut self) {
+        let mut buckets = Buckets::new(self.order);
+        for (ix, value) in self.data.indexed_iter() {
+            buckets.update(ix, None, value.bucket());
+        }
+        self.buckets = Some(buckets);
+    }
+
+    /// Repeatedly fix naked and hidden singles until no more progress is made
+    ///
+    /// Returns the number of fixed cells, or `None` if the Sudoku has no solution: either a cell
+    /// has no candidates or a value has nowhere to go in a unit
+    fn propagate(&mut self) -> Option<usize> {
+        let mut fixed = 0;
+        loop {
+            let mut progress = false;
+
+            // Naked singles: cells with a single candidate
+            for row in 0..self.order {
+                for col in 0..self.order {
+                    let AugmentedValue::Possible(possible) = self.data[(row, col)] else {
+                        continue;
+                    };
+
+                    if possible.is_empty() {
+                        return None;
+                    }
+
+                    if let (1, Some(value)) = (possible.len(), possible.iter().next()) {
+                        let levels = self.excluded((row, col), possible);
+                        let reason = Reason::Technique(Technique::NakedSingle);
+                        self.fix_value_inplace((row, col), value, levels, reason);
+                        fixed += 1;
+                        progress = true;
+                    }
+                }
+            }
+
+            // Hidden singles: values with a single possible cell in a unit
+            for unit in 0..self.units() {
+                let mut unit_fixed = Candidates::default();
+                let mut once = Candidates::default();
+                let mut twice = Candidates::default();
+
+                for ix in (0..self.order).map(|pos| self.unit_cell(unit, pos)) {
+                    match self.data[ix] {
+                        AugmentedValue::Fixed(value) => {
+                            unit_fixed.insert(value);
+                        }
+                        AugmentedValue::Possible(possible) => {
+                            twice = twice | (once & possible);
+                            once = once | possible;
+                        }
+                    }
+                }
+
+                if !(Candidates::full(self.order) - unit_fixed - once).is_empty() {
+                    return None;
+                }
+
+                for value in once - twice - unit_fixed {
+                    let cell = (0..self.order)
+                        .map(|pos| self.unit_cell(unit, pos))
+                        .find(|&ix| match self.data[ix] {
+                            AugmentedValue::Fixed(_) => false,
+                            AugmentedValue::Possible(possible) => possible.contains(value),
+                        });
+
+                    // A previous single in this unit took the cell, the next pass will notice
+                    if let Some(ix) = cell {
+                        let levels = self.excluded_from(unit, value, &[ix]);
+                        let reason = Reason::Technique(Technique::HiddenSingle);
+                        self.fix_value_inplace(ix, value, levels, reason);
+                        fixed += 1;
+                        progress = true;
+                    }
+                }
+            }
+
+            if self.prune_cages()? > 0 {
+                progress = true;
+            }
 
-        self.remove_value(ix, value);
+            if !progress {
+                return Some(fixed);
+            }
+        }
     }
 
-    fn fix_value(&self, ix: (usize, usize), value: NonZeroU8) -> Self {
-        let mut new = self.clone();
-        new.fix_value_inplace(ix, value);
-        new
+    /// Remove the candidates confined to the intersection of two units from the rest of them
+    ///
+    /// If the cells of a box with a candidate all lie in one row (pointing), no other cell of the
+    /// row can take it, and the same holds the other way around (claiming). Returns the number of
+    /// removed candidates.
+    fn eliminate_locked(&mut self) -> usize {
+        let mut removed = 0;
+        let mut cells_with = vec![Vec::new(); self.order + 1];
+
+        for unit in 0..self.units() {
+            cells_with.iter_mut().for_each(Vec::clear);
+            for ix in (0..self.order).map(|pos| self.unit_cell(unit, pos)) {
+                if let AugmentedValue::Possible(possible) = self.data[ix] {
+                    for value in possible {
+                        cells_with[value.get() as usize].push(ix);
+                    }
+                }
+            }
+
+            for (value, cells) in cells_with.iter().enumerate().skip(1) {
+                if cells.len() < 2 {
+                    continue;
+                }
+                let value = NonZeroU16::new(value as u16).expect("values start at 1");
+                let mut levels = None;
+
+                for other in self.shared_units(cells).filter(|&other| other != unit) {
+                    for pos in 0..self.order {
+                        let ix = self.unit_cell(other, pos);
+                        if cells.contains(&ix) || !self.data[ix].contains(value) {
+                            continue;
+                        }
+                        let levels =
+                            *levels.get_or_insert_with(|| self.excluded_from(unit, value, cells));
+                        let reason = Reason::Technique(Technique::LockedCandidates);
+                        if self.remove_candidate(ix, value, levels, reason) {
+                            removed += 1;
+                        }
+                    }
+                }
+            }
+        }
+
+        removed
+    }
+
+    /// The indices of the units containing every cell, see [`AugmentedSudoku::unit_cell`]
+    fn shared_units(&self, cells: &[(usize, usize)]) -> impl Iterator<Item = usize> {
+        let order = self.order;
+        let (row, col) = cells[0];
+        let cell_box = self.rules.box_of(self.boxes, (row, col));
+        let all = |f: &dyn Fn(usize, usize) -> bool| cells.iter().all(|&(row, col)| f(row, col));
+        let diagonals = self.rules.diagonals;
+
+        [
+            all(&|other, _| other == row).then_some(row),
+            all(&|_, other| other == col).then_some(order + col),
+            all(&|row, col| self.rules.box_of(self.boxes, (row, col)) == cell_box)
+                .then_some(2 * order + cell_box),
+            (diagonals && all(&|row, col| row == col)).then_some(3 * order),
+            (diagonals && all(&|row, col| row + col == order - 1)).then_some(3 * order + 1),
+        ]
+        .into_iter()
+        .flatten()
+    }
+
+    /// Remove the candidates that would make a killer cage miss its sum
+    ///
+    /// Returns the number of removed candidates, or `None` if a cage can't reach its sum
+    fn prune_cages(&mut self) -> Option<usize> {
+        if self.rules.cages.is_empty() {
+            return Some(0);
+        }
+
+        let mut removed = 0;
+        let levels = self.all_levels();
+        let cages = Arc::clone(&self.rules.cages);
+        for Cage { sum, cells } in cages.iter() {
+            let mut remaining = *sum;
+            let mut empty = Vec::with_capacity(cells.len());
+            for &ix in cells {
+                match self.data[ix] {
+                    AugmentedValue::Fixed(value) => {
+                        remaining = remaining.checked_sub(value.get() as u32)?;
+                    }
+                    AugmentedValue::Possible(possible) => empty.push((ix, possible)),
+                }
+            }
+
+            if empty.is_empty() {
+                if remaining != 0 {
+                    return None;
+                }
+                continue;
+            }
+
+            for (pos, &(ix, possible)) in empty.iter().enumerate() {
+                let others = empty
+                    .iter()
+                    .enumerate()
+                    .filter(|&(other, _)| other != pos)
+                    .fold(Candidates::default(), |others, (_, &(_, set))| others | set);
+
+                for value in possible {
+                    let (min, max) =
+                        sum_bounds(others - [value].into_iter().collect(), empty.len() - 1);
+                    let reachable = remaining
+                        .checked_sub(value.get() as u32)
+                        .is_some_and(|rest| (min..=max).contains(&rest));
+
+                    if !reachable && self.remove_candidate(ix, value, levels, Reason::Cage) {
+                        removed += 1;
+                    }
+                }
+
+                if let AugmentedValue::Possible(possible) = self.data[ix] {
+                    if possible.is_empty() {
+                        return None;
+                    }
+                }
+            }
+        }
+
+        Some(removed)
+    }
+
+    /// The number of units, see [`AugmentedSudoku::unit_cell`]
+    fn units(&self) -> usize {
+        if self.rules.diagonals {
+            3 * self.order + 2
+        } else {
+            3 * self.order
+        }
+    }
+
+    /// The index of the `pos`th cell of a unit
+    ///
+    /// Units `0..order` are the rows, `order..2 * order` the columns, `2 * order..3 * order` the
+    /// boxes (or jigsaw regions) and the rest are the diagonals of a Sudoku X
+    fn unit_cell(&self, unit: usize, pos: usize) -> (usize, usize) {
+        let order = self.order;
+
+        if unit < order {
+            (unit, pos)
+        } else if unit < 2 * order {
+            (pos, unit - order)
+        } else if unit < 3 * order {
+            self.rules.box_cell(self.boxes, unit - 2 * order, pos)
+        } else if unit == 3 * order {
+            (pos, pos)
+        } else {
+            (pos, order - 1 - pos)
+        }
+    }
+
+    /// The unit with index `unit`, see [`AugmentedSudoku::unit_cell`]
+    fn unit_name(&self, unit: usize) -> Unit {
+        let order = self.order;
+        match unit / order {
+            0 => Unit::Row(unit),
+            1 => Unit::Column(unit - order),
+            2 => Unit::Box(unit - 2 * order),
+            _ => Unit::Diagonal(unit - 3 * order),
+        }
+    }
+
+    /// Fix a cell as a consequence of `levels`
+    fn fix_value_inplace(
+        &mut self,
+        ix: (usize, usize),
+        value: NonZeroU16,
+        levels: Levels,
+        reason: Reason,
+    ) {
+        self.trail.push((ix, self.data[ix]));
+        if let Some(buckets) = &mut self.buckets {
+            buckets.update(ix, self.data[ix].bucket(), None);
+        }
+        if let Some(reasons) = &mut self.reasons {
+            reasons.fix(ix, levels);
+        }
+        self.data[ix] = value.into();
+        self.notify(Event::Place {
+            cell: ix,
+            value,
+            reason,
+            depth: self.depth,
+        });
+
+        self.remove_value(ix, value, levels);
     }
 }
 
 impl From<AugmentedSudoku> for super::Sudoku {
     fn from(value: AugmentedSudoku) -> Self {
-        Self(value.data.mapv_into_any(|val| val.into()))
+        Self(value.data.mapv_into_any(|val| val.into()), value.rules)
     }
 }
 
@@ -215,47 +1318,68 @@ impl From<AugmentedValue> for SudokuValue {
     }
 }
 
-impl From<super::Sudoku> for AugmentedSudoku {
-    fn from(value: super::Sudoku) -> Self {
+impl TryFrom<super::Sudoku> for AugmentedSudoku {
+    type Error = SolveError;
+
+    fn try_from(value: super::Sudoku) -> Result<Self, Self::Error> {
         let order = value.order();
-        Self {
-            cell_size: value.cell_size(),
-            order,
-            data: Array2::from_shape_vec(
-                (order, order),
-                value
-                    .0
-                    .into_iter()
-                    .map(|val| {
-                        if let Some(val) = val.0 {
-                            val.into()
-                        } else {
-                            (1..=order as u8).filter_map(NonZeroU8::new).collect()
-                        }
-                    })
-                    .collect(),
-            )
-            .expect("valid sudoku"),
+        Candidates::check(order)?;
+        let mut cage_of = Array2::from_elem((order, order), None);
+        for (cage, Cage { cells, .. }) in value.1.cages.iter().enumerate() {
+            for &cell in cells {
+                cage_of[cell] = Some(cage);
+            }
         }
+
+        let boxes = value.box_shape();
+        let data = Array2::from_shape_vec(
+            (order, order),
+            value
+                .0
+                .into_iter()
+                .map(|val| {
+                    if let Some(val) = val.0 {
+                        val.into()
+                    } else {
+                        AugmentedValue::Possible(Candidates::full(order))
+                    }
+                })
+                .collect(),
+        )
+        .expect("valid sudoku");
+
+        Ok(Self {
+            boxes,
+            order,
+            buckets: None,
+            reasons: None,
+            observer: trace::observer(),
+            depth: 0,
+            peers: Peers::new(order, &value.1),
+            data,
+            trail: Vec::new(),
+            rules: value.1,
+            cage_of,
+        })
     }
 }
 
-impl From<NonZeroU8> for AugmentedValue {
-    fn from(value: NonZeroU8) -> Self {
+impl From<NonZeroU16> for AugmentedValue {
+    fn from(value: NonZeroU16) -> Self {
         Self::Fixed(value)
     }
 }
 
-impl TryFrom<u8> for AugmentedValue {
+impl TryFrom<u16> for AugmentedValue {
     type Error = std::num::TryFromIntError;
 
-    fn try_from(value: u8) -> Result<Self, Self::Error> {
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
         Ok(Self::Fixed(value.try_into()?))
     }
 }
 
-impl FromIterator<NonZeroU8> for AugmentedValue {
-    fn from_iter<T: IntoIterator<Item = NonZeroU8>>(iter: T) -> Self {
+impl FromIterator<NonZeroU16> for AugmentedValue {
+    fn from_iter<T: IntoIterator<Item = NonZeroU16>>(iter: T) -> Self {
         Self::Possible(iter.into_iter().collect())
     }
 }
@@ -266,7 +1390,7 @@ impl Display for AugmentedValue {
             AugmentedValue::Fixed(val) => write!(f, "{val}"),
             AugmentedValue::Possible(possible) => {
                 write!(f, "[")?;
-                for ele in possible {
+                for ele in possible.iter() {
                     write!(f, " {ele}")?;
                 }
                 write!(f, " ]")
@@ -278,34 +1402,51 @@ impl Display for AugmentedValue {
 impl Display for AugmentedSudoku {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let width = self.order * 2 + 3;
-        let horizontal_line = format!(
-            "+{}",
-            format!("{}+", "-".repeat(self.cell_size * (width + 1) + 1)).repeat(self.cell_size)
-        );
+        render::draw(
+            f,
+            self.order,
+            Style::Ascii,
+            |cell| self.rules.box_of(self.boxes, cell),
+            |cell| format!(" {:^width$}", format!("{}", self.data[cell])),
+        )
+    }
+}
 
-        for blocks in self.data.exact_chunks((self.cell_size, self.order)) {
-            writeln!(f, "{horizontal_line}")?;
-            for row in blocks.rows() {
-                write!(f, "|")?;
-                for cell in row.exact_chunks((self.cell_size,)) {
-                    for ele in cell {
-                        write!(f, " {:^width$}", format!("{ele}"))?;
-                    }
-                    write!(f, " |")?;
-                }
-                writeln!(f)?;
-            }
+impl Display for Budget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Budget::Nodes(nodes) => write!(f, "it expanded more than {nodes} nodes"),
+            Budget::Time => write!(f, "it ran out of time"),
+            Budget::Cancelled => write!(f, "it was cancelled"),
         }
+    }
+}
 
-        write!(f, "{horizontal_line}")
+impl Display for Contradiction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Contradiction::Repeated(conflict) => write!(f, "{conflict}"),
+            Contradiction::NoCandidates(cell) => write!(f, "{} has no candidates", CellName(*cell)),
+            Contradiction::NoCell { unit, value } => write!(f, "{value} has no place in {unit}"),
+            Contradiction::Cage => write!(f, "a killer cage can't reach its sum"),
+        }
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::sudoku::Sudoku;
+    use std::{sync::Arc, time::Duration};
 
-    use super::dfs;
+    use crate::sudoku::{
+        rules::{parse_cages, Regions},
+        Sudoku, SudokuError,
+    };
+
+    use super::{
+        count_solutions, dfs, dfs_with, dlx, logic, naive_dfs, propagate, sorted_dfs,
+        sorted_dfs_with, with_budget, with_progress, with_timeout, Budget, Contradiction,
+        Heuristic, PropagationLevel, Registry, SearchOptions, SolveConfig, SolveError, ValueOrder,
+    };
 
     #[test]
     fn puzzle54_solvable() {
@@ -314,19 +1455,279 @@ mod test {
                 .parse()
                 .expect("Successful parse");
 
-        assert!(dfs(sudoku).is_ok())
+        assert!(dfs(sudoku).0.is_ok())
+    }
+
+    #[test]
+    fn timeout_aborts_search() {
+        // Filling an empty 25x25 Sudoku takes more nodes than the timeout lets the search expand
+        let sudoku: Sudoku = ".".repeat(625).parse().expect("Successful parse");
+
+        for solver in [naive_dfs, dfs] {
+            let (solution, stats) = with_timeout(Duration::ZERO, || solver(sudoku.clone()));
+            assert!(matches!(
+                solution,
+                Err(SolveError::Aborted {
+                    budget: Budget::Time
+                })
+            ));
+            assert!(stats.timed_out);
+        }
+    }
+
+    #[test]
+    fn node_budget_aborts_search() {
+        let sudoku: Sudoku = ".".repeat(625).parse().expect("Successful parse");
+        let config = SolveConfig {
+            max_nodes: Some(100),
+            ..Default::default()
+        };
+
+        for solver in [naive_dfs, dfs, sorted_dfs, dlx] {
+            let (solution, stats) = with_budget(&config, || solver(sudoku.clone()));
+            assert!(matches!(
+                solution,
+                Err(SolveError::Aborted {
+                    budget: Budget::Nodes(100)
+                })
+            ));
+            assert_eq!(stats.nodes, 101);
+        }
+
+        let registry = Registry::with_builtins(SearchOptions::default());
+        let solver = registry.get("sorted-dfs").expect("builtin");
+        assert!(solver.solve_with(&sudoku, &config).0.is_err());
+        assert!(solver
+            .solve_with(&sudoku, &SolveConfig::default())
+            .0
+            .is_ok());
+    }
+
+    #[test]
+    fn progress_follows_search() {
+        // Every empty cell of the 25x25 Sudoku is filled by a guess
+        let sudoku: Sudoku = ".".repeat(625).parse().expect("Successful parse");
+        let progress = Arc::default();
+
+        let (solution, stats) = with_progress(&progress, || sorted_dfs(sudoku));
+        assert!(solution.is_ok());
+        assert!(progress.nodes() > 0);
+        assert!(progress.nodes() <= stats.nodes);
+        assert!(progress.max_depth() <= stats.max_depth);
+        assert!(progress.depth() <= progress.max_depth());
+    }
+
+    #[test]
+    fn contradictions_found_before_searching() {
+        let sudoku: Sudoku = format!("12345678.........9{}", ".".repeat(63))
+            .parse()
+            .expect("Successful parse");
+
+        for solver in [dfs, sorted_dfs, propagate] {
+            let (solution, stats) = solver(sudoku.clone());
+            assert!(matches!(
+                solution,
+                Err(SolveError::Unsolvable {
+                    reason: Contradiction::NoCandidates((0, 8))
+                })
+            ));
+            assert_eq!(stats.nodes, 0);
+        }
+    }
+
+    #[test]
+    fn searches_iteratively() {
+        // The search guesses a thousand levels deep, a frame per guess wouldn't fit in the stack
+        let solver = std::thread::Builder::new()
+            .stack_size(64 * 1024)
+            .spawn(|| {
+                sorted_dfs(Sudoku::from_order(36))
+                    .0
+                    .map(|solved| solved.solved())
+            })
+            .expect("spawned thread");
+        assert!(solver
+            .join()
+            .expect("the search doesn't panic")
+            .expect("solvable"));
+    }
+
+    #[test]
+    fn large_sudokus_are_unsupported() {
+        let sudoku: Sudoku = ".".repeat(81 * 81).parse().expect("Successful parse");
+
+        for solver in [dfs, sorted_dfs, propagate, logic] {
+            assert!(matches!(
+                solver(sudoku.clone()).0,
+                Err(SolveError::Unsupported(SudokuError::TooLarge(81)))
+            ));
+        }
+    }
+
+    #[test]
+    fn propagate_requires_no_guessing() {
+        let easy: Sudoku =
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79"
+                .parse()
+                .expect("Successful parse");
+        let hard: Sudoku =
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4.."
+                .parse()
+                .expect("Successful parse");
+
+        assert!(logic(easy.clone()).0.expect("solvable by singles").solved());
+        assert!(propagate(easy).0.expect("solvable by singles").solved());
+        assert!(propagate(hard.clone()).0.is_err());
+        assert!(logic(hard.clone()).0.is_err());
+
+        for level in [PropagationLevel::Locked, PropagationLevel::Subsets] {
+            let options = SearchOptions {
+                level,
+                ..Default::default()
+            };
+            let (solution, _) = sorted_dfs_with(hard.clone(), options);
+            assert!(solution.expect("solvable").solved());
+        }
+
+        let (solution, stats) = sorted_dfs(hard);
+        assert!(solution.expect("solvable").solved());
+        assert!(stats.nodes > 0 && stats.propagations > 0);
     }
 
-    // extern crate test;
-    // use test::Bencher;
-    //
-    // #[bench]
-    // fn sudoku_54(bench: &mut Bencher) {
-    //     let sudoku: Sudoku =
-    //         ".......16.4...5.......2.......6..43.2...1....3.....5.......37..1..8.......2......"
-    //             .parse()
-    //             .expect("Successful parse");
+    #[test]
+    fn search_orders_find_the_solution() {
+        let hard: Sudoku =
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4.."
+                .parse()
+                .expect("Successful parse");
+        let expected = sorted_dfs(hard.clone()).0.expect("solvable");
+
+        for heuristic in [Heuristic::Mrv, Heuristic::MrvDegree] {
+            for value_order in [ValueOrder::Ascending, ValueOrder::Lcv, ValueOrder::Random] {
+                let options = SearchOptions {
+                    heuristic,
+                    value_order,
+                    ..Default::default()
+                };
+                let (solution, _) = sorted_dfs_with(hard.clone(), options);
+                assert_eq!(solution.expect("solvable").to_line(), expected.to_line());
+            }
+        }
+    }
+
+    #[test]
+    fn backjumping_skips_unrelated_guesses() {
+        let hard: Sudoku =
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4.."
+                .parse()
+                .expect("Successful parse");
+        let options = SearchOptions {
+            backjump: true,
+            ..Default::default()
+        };
+
+        let (expected, backtracking) = dfs(hard.clone());
+        let (solution, backjumping) = dfs_with(hard.clone(), options);
+        assert_eq!(
+            solution.expect("solvable").to_line(),
+            expected.expect("solvable").to_line()
+        );
+        assert!(backjumping.nodes < backtracking.nodes);
+
+        let (solution, _) = sorted_dfs_with(hard, options);
+        assert!(solution.expect("solvable").solved());
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn parallel_search_finds_the_solution() {
+        let hard: Sudoku =
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4.."
+                .parse()
+                .expect("Successful parse");
+        let expected = sorted_dfs(hard.clone()).0.expect("solvable");
+        let options = SearchOptions {
+            parallel: true,
+            ..Default::default()
+        };
+
+        let (solution, stats) = sorted_dfs_with(hard, options);
+        assert_eq!(solution.expect("solvable").to_line(), expected.to_line());
+        assert!(!stats.timed_out);
 
-    //     bench.iter(|| dfs(sudoku.clone()).is_ok())
-    // }
+        let empty = Sudoku::from_order(16);
+        let (solution, _) = sorted_dfs_with(empty, options);
+        assert!(solution.expect("solvable").solved());
+    }
+
+    #[test]
+    fn seeded_restarts_are_reproducible() {
+        let hard: Sudoku =
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4.."
+                .parse()
+                .expect("Successful parse");
+        let expected = sorted_dfs(hard.clone()).0.expect("solvable");
+        let options = SearchOptions {
+            value_order: ValueOrder::Random,
+            seed: Some(7),
+            restarts: Some(10),
+            ..Default::default()
+        };
+
+        let (first, first_stats) = sorted_dfs_with(hard.clone(), options);
+        let (second, second_stats) = sorted_dfs_with(hard, options);
+        assert_eq!(first.expect("solvable").to_line(), expected.to_line());
+        assert_eq!(second.expect("solvable").to_line(), expected.to_line());
+        assert_eq!(first_stats.nodes, second_stats.nodes);
+    }
+
+    #[test]
+    fn killer_cages_are_enforced() {
+        let cages = parse_cages(
+            "4 r1c1 r2c1\n6 r1c2 r2c2\n4 r1c3 r2c3\n6 r1c4 r2c4\n\
+             6 r3c1 r4c1\n4 r3c2 r4c2\n6 r3c3 r4c3\n4 r3c4 r4c4",
+        )
+        .expect("valid cages");
+        let sudoku = "................"
+            .parse::<Sudoku>()
+            .expect("Successful parse")
+            .with_cages(cages)
+            .expect("valid cages");
+
+        for solver in [naive_dfs, dfs, sorted_dfs, dlx] {
+            let solution = solver(sudoku.clone()).0.expect("solvable");
+            assert!(solution.solved());
+        }
+        let classic = count_solutions(&Sudoku::from_order(4), usize::MAX);
+        assert!(count_solutions(&sudoku, usize::MAX) < classic);
+    }
+
+    #[test]
+    fn diagonals_are_enforced() {
+        let corners: Sudoku = "1..............1".parse().expect("Successful parse");
+        assert!(corners.with_diagonals().is_err());
+
+        let sudoku = Sudoku::from_order(4).with_diagonals().expect("no givens");
+        for solver in [naive_dfs, dfs, sorted_dfs, dlx] {
+            let solution = solver(sudoku.clone()).0.expect("solvable");
+            assert!(solution.solved());
+        }
+
+        let classic = count_solutions(&Sudoku::from_order(4), usize::MAX);
+        assert!(count_solutions(&sudoku, usize::MAX) < classic);
+    }
+
+    #[test]
+    fn regions_replace_boxes() {
+        let regions: Regions = "AAAB CABB CCDB CDDD".parse().expect("valid layout");
+        assert!(Sudoku::new().with_regions(regions.clone()).is_err());
+
+        let sudoku = Sudoku::from_order(4)
+            .with_regions(regions)
+            .expect("no givens");
+        for solver in [naive_dfs, dfs, sorted_dfs, dlx] {
+            let solution = solver(sudoku.clone()).0.expect("solvable");
+            assert!(solution.solved());
+        }
+    }
 }
@@ -0,0 +1,99 @@
+//! The guesses behind every deduction of the search, for conflict-directed backjumping
+//!
+//! Every removed candidate and fixed cell remembers the search levels whose guesses led to it, as
+//! a bitset over the first [`MAX_LEVEL`] levels. When a node fails, the levels behind the
+//! contradiction tell which guesses to blame, and the search jumps back to the latest of them
+//! instead of retrying the values of unrelated cells in between.
+
+use std::num::NonZeroU16;
+
+/// A set of search levels, level `depth` being bit `depth - 1`
+pub(super) type Levels = u128;
+
+/// The deepest level a [`Levels`] set can hold
+pub(super) const MAX_LEVEL: usize = Levels::BITS as usize;
+
+/// The set with only `depth`, empty for the root and the levels past [`MAX_LEVEL`]
+pub(super) fn level(depth: usize) -> Levels {
+    match depth {
+        1..=MAX_LEVEL => 1 << (depth - 1),
+        _ => 0,
+    }
+}
+
+/// The set of every level up to `depth`
+pub(super) fn up_to(depth: usize) -> Levels {
+    match depth {
+        0 => 0,
+        1..MAX_LEVEL => (1 << depth) - 1,
+        _ => Levels::MAX,
+    }
+}
+
+/// The latest level of the set, `0` if it is empty
+pub(super) fn latest(levels: Levels) -> usize {
+    (Levels::BITS - levels.leading_zeros()) as usize
+}
+
+/// The levels behind the state of every cell of an `order * order` board
+#[derive(Debug, Clone)]
+pub(super) struct Reasons {
+    order: usize,
+    /// The levels that removed each candidate, `order` per cell in row-major order
+    removed: Vec<Levels>,
+    /// The levels that fixed each cell
+    fixed: Vec<Levels>,
+    /// The level of the node being searched
+    pub(super) depth: usize,
+}
+
+impl Reasons {
+    /// No reasons yet, everything done so far is part of the root
+    pub(super) fn new(order: usize) -> Self {
+        Self {
+            order,
+            removed: vec![0; order * order * order],
+            fixed: vec![0; order * order],
+            depth: 0,
+        }
+    }
+
+    /// The levels that removed `value` from `cell`, only meaningful once removed
+    pub(super) fn removed(&self, cell: (usize, usize), value: NonZeroU16) -> Levels {
+        self.removed[self.candidate(cell, value)]
+    }
+
+    pub(super) fn remove(&mut self, cell: (usize, usize), value: NonZeroU16, levels: Levels) {
+        let ix = self.candidate(cell, value);
+        self.removed[ix] = levels;
+    }
+
+    /// The levels that fixed `cell`, only meaningful once fixed
+    pub(super) fn fixed(&self, (row, col): (usize, usize)) -> Levels {
+        self.fixed[row * self.order + col]
+    }
+
+    pub(super) fn fix(&mut self, (row, col): (usize, usize), levels: Levels) {
+        self.fixed[row * self.order + col] = levels;
+    }
+
+    fn candidate(&self, (row, col): (usize, usize), value: NonZeroU16) -> usize {
+        (row * self.order + col) * self.order + (value.get() as usize - 1)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{latest, level, up_to, MAX_LEVEL};
+
+    #[test]
+    fn level_sets() {
+        assert_eq!(level(0), 0);
+        assert_eq!(level(3), 0b100);
+        assert_eq!(level(MAX_LEVEL + 1), 0);
+        assert_eq!(up_to(3), 0b111);
+        assert_eq!(up_to(MAX_LEVEL).count_ones() as usize, MAX_LEVEL);
+        assert_eq!(latest(level(5) | level(2)), 5);
+        assert_eq!(latest(0), 0);
+    }
+}
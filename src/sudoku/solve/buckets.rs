@@ -0,0 +1,92 @@
+//! The empty cells of a grid grouped by how many candidates they have left
+//!
+//! Every bucket is a bitset over the cells in row-major order, so the cells of a bucket come out
+//! in the same order as a scan of the grid would find them.
+
+/// The empty cells of an `order * order` grid bucketed by their number of candidates
+#[derive(Debug, Clone)]
+pub(super) struct Buckets {
+    order: usize,
+    /// The number of words in the bitset of each bucket
+    words: usize,
+    /// The bitsets of every bucket, one after the other
+    bits: Vec<u64>,
+    /// The number of cells in each bucket
+    lens: Vec<usize>,
+}
+
+impl Buckets {
+    /// Empty buckets for cells with `0..=order` candidates
+    pub(super) fn new(order: usize) -> Self {
+        let words = (order * order).div_ceil(u64::BITS as usize);
+        Self {
+            order,
+            words,
+            bits: vec![0; words * (order + 1)],
+            lens: vec![0; order + 1],
+        }
+    }
+
+    /// Move a cell from bucket `from` to bucket `to`, `None` meaning the cell is filled
+    pub(super) fn update(&mut self, cell: (usize, usize), from: Option<usize>, to: Option<usize>) {
+        if from == to {
+            return;
+        }
+
+        let index = cell.0 * self.order + cell.1;
+        let (word, bit) = (
+            index / u64::BITS as usize,
+            1 << (index % u64::BITS as usize),
+        );
+        if let Some(from) = from {
+            self.bits[from * self.words + word] &= !bit;
+            self.lens[from] -= 1;
+        }
+        if let Some(to) = to {
+            self.bits[to * self.words + word] |= bit;
+            self.lens[to] += 1;
+        }
+    }
+
+    /// The smallest number of candidates of an empty cell, `None` if every cell is filled
+    pub(super) fn fewest(&self) -> Option<usize> {
+        self.lens.iter().position(|&len| len > 0)
+    }
+
+    /// The cells with `count` candidates in row-major order
+    pub(super) fn cells(&self, count: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let bucket = &self.bits[count * self.words..(count + 1) * self.words];
+        bucket.iter().enumerate().flat_map(move |(word, &bits)| {
+            let mut bits = bits;
+            std::iter::from_fn(move || {
+                if bits == 0 {
+                    return None;
+                }
+                let index = word * u64::BITS as usize + bits.trailing_zeros() as usize;
+                bits &= bits - 1;
+                Some((index / self.order, index % self.order))
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Buckets;
+
+    #[test]
+    fn cells_move_between_buckets() {
+        let mut buckets = Buckets::new(9);
+        assert_eq!(buckets.fewest(), None);
+
+        buckets.update((8, 8), None, Some(9));
+        buckets.update((0, 1), None, Some(9));
+        buckets.update((4, 2), None, Some(3));
+        assert_eq!(buckets.fewest(), Some(3));
+
+        buckets.update((4, 2), Some(3), None);
+        buckets.update((8, 8), Some(9), Some(9));
+        assert_eq!(buckets.fewest(), Some(9));
+        assert_eq!(buckets.cells(9).collect::<Vec<_>>(), [(0, 1), (8, 8)]);
+    }
+}
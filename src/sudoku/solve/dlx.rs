@@ -0,0 +1,488 @@
+//! Exact cover solver using Knuth's Dancing Links (Algorithm X)
+//!
+//! Every candidate placement `(row, col, value)` is a row of the exact cover matrix, and every
+//! constraint (a cell is filled, a row/column/box/diagonal contains a value) is a column. A solved
+//! Sudoku is a set of placements covering each constraint exactly once.
+
+use std::{fmt::Display, num::NonZeroU16, ops::ControlFlow};
+
+use super::{Contradiction, SolveError, SolveStats, SudokuResult};
+use crate::sudoku::{
+    rules::{BoxShape, Rules},
+    Sudoku, SudokuValue,
+};
+
+/// Solve the Sudoku as an exact cover problem with Dancing Links
+pub fn dlx(sudoku: Sudoku) -> (SudokuResult, SolveStats) {
+    SolveStats::record(|stats| dlx_impl(sudoku, stats))
+}
+
+fn dlx_impl(sudoku: Sudoku, stats: &mut SolveStats) -> SudokuResult {
+    let Some((mut matrix, mut solution)) = DancingLinks::with_givens(&sudoku) else {
+        // Two givens covered the same constraint, so they repeat a value in a unit
+        let reason = sudoku
+            .conflicts()
+            .into_iter()
+            .next()
+            .map_or(Contradiction::Cage, Contradiction::Repeated);
+        return Err(SolveError::Unsolvable { reason });
+    };
+
+    let search = matrix.search(&mut solution, stats, 0, &mut |solution| {
+        if satisfies_rules(&sudoku, solution) {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    });
+    if search.is_continue() || stats.timed_out {
+        return Err(SolveError::Unsolved(sudoku));
+    }
+
+    Ok(fill(&sudoku, &solution))
+}
+
+/// The Sudoku with the placements of `solution` filled in
+fn fill(sudoku: &Sudoku, solution: &[usize]) -> Sudoku {
+    let order = sudoku.order();
+    let mut solved = sudoku.clone();
+    for &placement in solution {
+        let Placement { row, col, value } = Placement::from_index(order, placement);
+        *solved.0.get_mut((row, col)).unwrap() = SudokuValue(Some(value));
+    }
+
+    solved
+}
+
+/// Whether an exact cover also satisfies the variant rules, which the matrix doesn't encode
+fn satisfies_rules(sudoku: &Sudoku, solution: &[usize]) -> bool {
+    sudoku.1.cages.is_empty() || fill(sudoku, solution).valid()
+}
+
+/// Count the solutions of the Sudoku, stopping once `limit` solutions are found
+pub(super) fn count(sudoku: &Sudoku, limit: usize) -> usize {
+    let Some((mut matrix, mut solution)) = DancingLinks::with_givens(sudoku) else {
+        return 0;
+    };
+
+    let mut found = 0;
+    let _ = matrix.search(
+        &mut solution,
+        &mut SolveStats::default(),
+        0,
+        &mut |solution| {
+            if !satisfies_rules(sudoku, solution) {
+                return ControlFlow::Continue(());
+            }
+
+            found += 1;
+            if found >= limit {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        },
+    );
+
+    found
+}
+
+/// The exact cover matrix of a Sudoku, as searched by [`dlx`]
+///
+/// The rows are the placements allowed by the givens: every value of an empty cell and only the
+/// given value of the others. The columns are the cells followed by the values of each row,
+/// column, box and diagonal. Killer cages are left out. [`Display`] writes one line per row, its
+/// placement as `r1c2=3` followed by the `0`/`1` entries of every column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExactCover {
+    order: usize,
+    columns: usize,
+    /// The placement of each row and the columns it covers
+    rows: Vec<(usize, Vec<usize>)>,
+}
+
+impl ExactCover {
+    /// The placements allowed by the givens and the constraints they cover
+    pub fn new(sudoku: &Sudoku) -> Self {
+        let order = sudoku.order();
+        let boxes = sudoku.box_shape();
+        let rows = (0..order * order * order)
+            .filter(|&index| {
+                let Placement { row, col, value } = Placement::from_index(order, index);
+                sudoku.0[(row, col)].0.is_none_or(|given| given == value)
+            })
+            .map(|index| {
+                let constraints =
+                    Placement::from_index(order, index).constraints(order, boxes, &sudoku.1);
+                (index, constraints)
+            })
+            .collect();
+
+        Self {
+            order,
+            columns: columns(order, &sudoku.1),
+            rows,
+        }
+    }
+
+    /// The number of constraints
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// The cell and value of every row, with the columns it covers in ascending order
+    pub fn rows(&self) -> impl Iterator<Item = ((usize, usize), NonZeroU16, &[usize])> {
+        self.rows.iter().map(|(index, constraints)| {
+            let Placement { row, col, value } = Placement::from_index(self.order, *index);
+            ((row, col), value, &constraints[..])
+        })
+    }
+}
+
+impl Display for ExactCover {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} {}", self.rows.len(), self.columns)?;
+        let mut line = vec![b'0'; self.columns];
+        for ((row, col), value, constraints) in self.rows() {
+            line.fill(b'0');
+            for &constraint in constraints {
+                line[constraint] = b'1';
+            }
+            let line = std::str::from_utf8(&line).expect("the entries are ascii");
+            writeln!(f, "r{}c{}={value} {line}", row + 1, col + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// The number of constraints of an `order * order` Sudoku following `rules`
+fn columns(order: usize, rules: &Rules) -> usize {
+    let diagonals = if rules.diagonals { 2 * order } else { 0 };
+    4 * order * order + diagonals
+}
+
+/// A candidate placement of `value` at (`row`, `col`)
+struct Placement {
+    row: usize,
+    col: usize,
+    value: NonZeroU16,
+}
+
+impl Placement {
+    fn index(order: usize, row: usize, col: usize, value: NonZeroU16) -> usize {
+        (row * order + col) * order + value.get() as usize - 1
+    }
+
+    fn from_index(order: usize, index: usize) -> Self {
+        let value = (index % order) as u16 + 1;
+        let cell = index / order;
+        Self {
+            row: cell / order,
+            col: cell % order,
+            value: NonZeroU16::new(value).expect("value is at least 1"),
+        }
+    }
+
+    /// The constraints satisfied by this placement
+    ///
+    /// Every placement fills a cell and puts a value in a row, column and box, placements on the
+    /// diagonals of a Sudoku X also put a value in their diagonals.
+    fn constraints(&self, order: usize, boxes: BoxShape, rules: &Rules) -> Vec<usize> {
+        let area = order * order;
+        let value = self.value.get() as usize - 1;
+        let cell_box = rules.box_of(boxes, (self.row, self.col));
+        let mut constraints = vec![
+            self.row * order + self.col,
+            area + self.row * order + value,
+            2 * area + self.col * order + value,
+            3 * area + cell_box * order + value,
+        ];
+
+        if rules.diagonals {
+            if self.row == self.col {
+                constraints.push(4 * area + value);
+            }
+            if self.row + self.col == order - 1 {
+                constraints.push(4 * area + order + value);
+            }
+        }
+
+        constraints
+    }
+}
+
+/// Toroidal doubly linked exact cover matrix stored as index arrays
+///
+/// Node `0` is the root, nodes `1..=columns` are the column headers and the remaining nodes are the
+/// ones in the matrix.
+struct DancingLinks {
+    left: Vec<usize>,
+    right: Vec<usize>,
+    up: Vec<usize>,
+    down: Vec<usize>,
+    /// The column header of each node
+    column: Vec<usize>,
+    /// The placement each node belongs to
+    placement: Vec<usize>,
+    /// The number of nodes in each column, indexed by header
+    size: Vec<usize>,
+    /// The first node of each placement, followed by the number of nodes
+    first: Vec<usize>,
+}
+
+impl DancingLinks {
+    fn new(order: usize, boxes: BoxShape, rules: &Rules) -> Self {
+        let columns = columns(order, rules);
+        let placements = order * order * order;
+        let nodes = 1 + columns + 4 * placements;
+
+        let mut links = Self {
+            left: Vec::with_capacity(nodes),
+            right: Vec::with_capacity(nodes),
+            up: Vec::with_capacity(nodes),
+            down: Vec::with_capacity(nodes),
+            column: Vec::with_capacity(nodes),
+            placement: Vec::with_capacity(nodes),
+            size: vec![0; columns + 1],
+            first: Vec::with_capacity(placements + 1),
+        };
+
+        for header in 0..=columns {
+            links
+                .left
+                .push(if header == 0 { columns } else { header - 1 });
+            links
+                .right
+                .push(if header == columns { 0 } else { header + 1 });
+            links.up.push(header);
+            links.down.push(header);
+            links.column.push(header);
+            links.placement.push(usize::MAX);
+        }
+
+        for index in 0..placements {
+            let first = links.left.len();
+            links.first.push(first);
+
+            let constraints = Placement::from_index(order, index).constraints(order, boxes, rules);
+            let last = constraints.len() - 1;
+            for (offset, constraint) in constraints.into_iter().enumerate() {
+                let node = first + offset;
+                let header = constraint + 1;
+
+                links
+                    .left
+                    .push(if offset == 0 { first + last } else { node - 1 });
+                links
+                    .right
+                    .push(if offset == last { first } else { node + 1 });
+                links.up.push(links.up[header]);
+                links.down.push(header);
+                links.column.push(header);
+                links.placement.push(index);
+
+                let last = links.up[header];
+                links.down[last] = node;
+                links.up[header] = node;
+                links.size[header] += 1;
+            }
+        }
+        links.first.push(links.left.len());
+
+        links
+    }
+
+    /// The matrix with the givens of the Sudoku already selected, `None` if the givens conflict
+    fn with_givens(sudoku: &Sudoku) -> Option<(Self, Vec<usize>)> {
+        let order = sudoku.order();
+        let mut matrix = Self::new(order, sudoku.box_shape(), &sudoku.1);
+        let mut solution = Vec::with_capacity(order * order);
+
+        for ((row, col), value) in sudoku.0.indexed_iter() {
+            if let Some(value) = value.0 {
+                let placement = Placement::index(order, row, col, value);
+                if !matrix.select(placement) {
+                    return None;
+                }
+                solution.push(placement);
+            }
+        }
+
+        Some((matrix, solution))
+    }
+
+    fn is_covered(&self, header: usize) -> bool {
+        self.right[self.left[header]] != header
+    }
+
+    fn cover(&mut self, header: usize) {
+        self.right[self.left[header]] = self.right[header];
+        self.left[self.right[header]] = self.left[header];
+
+        let mut row = self.down[header];
+        while row != header {
+            let mut node = self.right[row];
+            while node != row {
+                self.down[self.up[node]] = self.down[node];
+                self.up[self.down[node]] = self.up[node];
+                self.size[self.column[node]] -= 1;
+                node = self.right[node];
+            }
+            row = self.down[row];
+        }
+    }
+
+    fn uncover(&mut self, header: usize) {
+        let mut row = self.up[header];
+        while row != header {
+            let mut node = self.left[row];
+            while node != row {
+                self.size[self.column[node]] += 1;
+                self.down[self.up[node]] = node;
+                self.up[self.down[node]] = node;
+                node = self.left[node];
+            }
+            row = self.up[row];
+        }
+
+        self.right[self.left[header]] = header;
+        self.left[self.right[header]] = header;
+    }
+
+    /// Add a placement to the solution, returns false if it conflicts with the current selection
+    fn select(&mut self, placement: usize) -> bool {
+        let nodes = self.first[placement]..self.first[placement + 1];
+        if nodes.clone().any(|node| self.is_covered(self.column[node])) {
+            return false;
+        }
+
+        for node in nodes {
+            self.cover(self.column[node]);
+        }
+
+        true
+    }
+
+    /// Column with the fewest remaining nodes, or `None` if every column is covered
+    fn smallest_column(&self) -> Option<usize> {
+        let mut header = self.right[0];
+        let mut best = None;
+        while header != 0 {
+            if best.is_none_or(|best| self.size[header] < self.size[best]) {
+                best = Some(header);
+            }
+            header = self.right[header];
+        }
+        best
+    }
+
+    /// Search for the solutions of the matrix, calling `on_solution` with each one
+    ///
+    /// The search stops as soon as `on_solution` breaks, leaving the solution in `solution`, or
+    /// once the solver runs out of time
+    fn search(
+        &mut self,
+        solution: &mut Vec<usize>,
+        stats: &mut SolveStats,
+        depth: usize,
+        on_solution: &mut impl FnMut(&[usize]) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        let Some(header) = self.smallest_column() else {
+            return on_solution(solution);
+        };
+
+        if self.size[header] == 0 {
+            return ControlFlow::Continue(());
+        }
+
+        self.cover(header);
+
+        let mut row = self.down[header];
+        while row != header {
+            solution.push(self.placement[row]);
+            stats.expand(depth + 1);
+            if stats.cancelled() {
+                return ControlFlow::Break(());
+            }
+
+            let mut node = self.right[row];
+            while node != row {
+                self.cover(self.column[node]);
+                node = self.right[node];
+            }
+
+            self.search(solution, stats, depth + 1, on_solution)?;
+            stats.backtracks += 1;
+
+            let mut node = self.left[row];
+            while node != row {
+                self.uncover(self.column[node]);
+                node = self.left[node];
+            }
+
+            solution.pop();
+            row = self.down[row];
+        }
+
+        self.uncover(header);
+
+        ControlFlow::Continue(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::num::NonZeroU16;
+
+    use crate::sudoku::{Sudoku, SudokuValue};
+
+    use super::{count, dlx, Contradiction, ExactCover, SolveError};
+
+    #[test]
+    fn puzzle54_solvable() {
+        let sudoku: Sudoku =
+            ".......16.4...5.......2.......6..43.2...1....3.....5.......37..1..8.......2......"
+                .parse()
+                .expect("Successful parse");
+
+        assert!(dlx(sudoku).0.expect("solvable").solved())
+    }
+
+    #[test]
+    fn conflicting_givens() {
+        let mut sudoku = Sudoku::new();
+        sudoku.0[(0, 0)] = SudokuValue(NonZeroU16::new(1));
+        sudoku.0[(0, 1)] = SudokuValue(NonZeroU16::new(1));
+
+        assert!(matches!(
+            dlx(sudoku).0,
+            Err(SolveError::Unsolvable {
+                reason: Contradiction::Repeated(_)
+            })
+        ))
+    }
+
+    #[test]
+    fn count_empty_grid() {
+        let sudoku: Sudoku = "................".parse().expect("Successful parse");
+
+        assert_eq!(count(&sudoku, 1000), 288)
+    }
+
+    #[test]
+    fn exact_cover_matrix() {
+        let sudoku: Sudoku = "2...............".parse().expect("Successful parse");
+        let matrix = ExactCover::new(&sudoku);
+
+        assert_eq!(matrix.columns(), 64);
+        assert_eq!(matrix.rows().count(), 64 - 3);
+        assert!(matrix
+            .rows()
+            .all(|(_, _, constraints)| constraints.len() == 4));
+
+        let text = matrix.to_string();
+        let mut lines = text.lines();
+        assert_eq!(lines.next(), Some("61 64"));
+        assert!(lines.next().expect("a row").starts_with("r1c1=2 1000"));
+    }
+}
@@ -0,0 +1,109 @@
+//! Racing several solvers on the same puzzle
+//!
+//! No strategy is the fastest on every puzzle, and the slow cases of one are rarely the slow cases
+//! of another. The portfolio runs a few of them on separate threads and keeps whichever finishes
+//! first, cancelling the rest, which bounds the time spent on adversarial puzzles.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::Instant,
+};
+
+use super::{
+    dlx, sorted_dfs, sorted_dfs_with,
+    stats::{cancellable, limits, progress},
+    Heuristic, SearchOptions, SolveStats, SudokuResult, ValueOrder,
+};
+use crate::sudoku::Sudoku;
+
+/// A solver raced by the portfolio
+type Strategy = fn(Sudoku) -> (SudokuResult, SolveStats);
+
+/// The solvers raced by [`portfolio`]
+const STRATEGIES: [Strategy; 3] = [sorted_dfs, dlx, randomized];
+
+/// The nodes the randomized search expands before its first restart
+const RESTART_NODES: u64 = 1000;
+
+/// A sorted search trying the values in a random order, backjumping and restarting, so it gets
+/// stuck in other places than the deterministic solvers
+fn randomized(sudoku: Sudoku) -> (SudokuResult, SolveStats) {
+    let options = SearchOptions {
+        heuristic: Heuristic::Mrv,
+        value_order: ValueOrder::Random,
+        backjump: true,
+        restarts: Some(RESTART_NODES),
+        ..Default::default()
+    };
+    sorted_dfs_with(sudoku, options)
+}
+
+/// Race the sorted search, Dancing Links and a randomized search on separate threads
+///
+/// Returns the result of the first solver to finish, with its statistics. Every solver searches
+/// the whole tree, so finding no solution is as final as finding one. The solvers share the
+/// timeout of the calling thread, see [`with_timeout`](super::with_timeout).
+///
+/// Only as many solvers as the threads of the current rayon pool are raced, a single thread runs
+/// the sorted search alone.
+pub fn portfolio(sudoku: Sudoku) -> (SudokuResult, SolveStats) {
+    let start = Instant::now();
+    let limits = limits();
+    let progress = progress();
+    let cancel = Arc::new(AtomicBool::new(false));
+
+    let (result, mut stats) = thread::scope(|scope| {
+        let (sender, receiver) = mpsc::channel();
+        let racers = rayon::current_num_threads().clamp(1, STRATEGIES.len());
+        for &strategy in &STRATEGIES[..racers] {
+            let (sudoku, sender, cancel) = (sudoku.clone(), sender.clone(), Arc::clone(&cancel));
+            let progress = &progress;
+            scope.spawn(move || {
+                let solved = cancellable(limits, progress, &cancel, || strategy(sudoku));
+                // Nobody is listening once another solver has won
+                let _ = sender.send(solved);
+            });
+        }
+        drop(sender);
+
+        let mut last = None;
+        for (result, stats) in receiver {
+            if !stats.timed_out {
+                cancel.store(true, Ordering::Relaxed);
+                return (result, stats);
+            }
+            last = Some((result, stats));
+        }
+        last.expect("every solver reports back")
+    });
+
+    stats.elapsed = start.elapsed();
+    (result, stats)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::sudoku::Sudoku;
+
+    #[test]
+    fn first_solver_wins() {
+        let hard: Sudoku =
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4.."
+                .parse()
+                .expect("Successful parse");
+        let (solution, stats) = super::portfolio(hard);
+        assert!(solution.expect("solvable").solved());
+        assert!(!stats.timed_out);
+
+        let unsolvable: Sudoku = format!("12345678.........9{}", ".".repeat(63))
+            .parse()
+            .expect("Successful parse");
+        let (solution, stats) = super::portfolio(unsolvable);
+        assert!(solution.is_err());
+        assert!(!stats.timed_out);
+    }
+}
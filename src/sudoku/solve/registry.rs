@@ -0,0 +1,282 @@
+//! Solvers behind a common interface, looked up by name
+//!
+//! Every strategy of [`solve`](super) implements [`Solver`], and a [`Registry`] maps their names
+//! to them so the strategy can be picked at runtime, like from the command line. Crates depending
+//! on the library can register their own solvers next to the built-in ones.
+
+use std::fmt;
+
+#[cfg(feature = "parallel")]
+use super::portfolio;
+use super::{
+    dfs_with, dlx, logic, naive_dfs, propagate, sat, sorted_dfs_with, with_budget, SearchOptions,
+    SolveConfig, SolveStats, SudokuResult,
+};
+use crate::sudoku::Sudoku;
+
+/// The solution found by a [`Solver`], or why it returned without one, and how it searched
+pub type SolveOutcome = (SudokuResult, SolveStats);
+
+/// A strategy solving Sudokus
+pub trait Solver: Send + Sync {
+    /// Solve `sudoku`
+    fn solve(&self, sudoku: &Sudoku) -> SolveOutcome;
+
+    /// Solve `sudoku` within the budgets of `config`, giving up with
+    /// [`SolveError::Aborted`](super::SolveError::Aborted) once it runs out of one
+    ///
+    /// The searches also take their seed and propagation level from `config`.
+    fn solve_with(&self, sudoku: &Sudoku, config: &SolveConfig) -> SolveOutcome {
+        with_budget(config, || self.solve(sudoku))
+    }
+
+    /// The name of the solver in a [`Registry`], like `sorted-dfs`
+    fn name(&self) -> &str;
+
+    /// Whether the solver guesses when the logic runs out, instead of giving up with the partially
+    /// solved Sudoku in [`SolveError::Unsolved`](super::SolveError::Unsolved)
+    fn guesses(&self) -> bool {
+        true
+    }
+}
+
+/// [`naive_dfs`] as a [`Solver`]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NaiveDfs;
+
+impl Solver for NaiveDfs {
+    fn solve(&self, sudoku: &Sudoku) -> SolveOutcome {
+        naive_dfs(sudoku.clone())
+    }
+
+    fn name(&self) -> &str {
+        "naive-dfs"
+    }
+}
+
+/// [`dfs_with`] the options it holds, as a [`Solver`]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Dfs(pub SearchOptions);
+
+impl Solver for Dfs {
+    fn solve(&self, sudoku: &Sudoku) -> SolveOutcome {
+        dfs_with(sudoku.clone(), self.0)
+    }
+
+    fn solve_with(&self, sudoku: &Sudoku, config: &SolveConfig) -> SolveOutcome {
+        with_budget(config, || dfs_with(sudoku.clone(), config.apply(self.0)))
+    }
+
+    fn name(&self) -> &str {
+        "dfs"
+    }
+}
+
+/// [`sorted_dfs_with`] the options it holds, as a [`Solver`]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SortedDfs(pub SearchOptions);
+
+impl Solver for SortedDfs {
+    fn solve(&self, sudoku: &Sudoku) -> SolveOutcome {
+        sorted_dfs_with(sudoku.clone(), self.0)
+    }
+
+    fn solve_with(&self, sudoku: &Sudoku, config: &SolveConfig) -> SolveOutcome {
+        with_budget(config, || {
+            sorted_dfs_with(sudoku.clone(), config.apply(self.0))
+        })
+    }
+
+    fn name(&self) -> &str {
+        "sorted-dfs"
+    }
+}
+
+/// [`dlx`] as a [`Solver`]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Dlx;
+
+impl Solver for Dlx {
+    fn solve(&self, sudoku: &Sudoku) -> SolveOutcome {
+        dlx(sudoku.clone())
+    }
+
+    fn name(&self) -> &str {
+        "dlx"
+    }
+}
+
+/// [`propagate`] as a [`Solver`]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Propagate;
+
+impl Solver for Propagate {
+    fn solve(&self, sudoku: &Sudoku) -> SolveOutcome {
+        propagate(sudoku.clone())
+    }
+
+    fn name(&self) -> &str {
+        "propagate"
+    }
+
+    fn guesses(&self) -> bool {
+        false
+    }
+}
+
+/// [`logic`] as a [`Solver`]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Logic;
+
+impl Solver for Logic {
+    fn solve(&self, sudoku: &Sudoku) -> SolveOutcome {
+        logic(sudoku.clone())
+    }
+
+    fn name(&self) -> &str {
+        "logic"
+    }
+
+    fn guesses(&self) -> bool {
+        false
+    }
+}
+
+/// [`portfolio`] as a [`Solver`]
+#[cfg(feature = "parallel")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Portfolio;
+
+#[cfg(feature = "parallel")]
+impl Solver for Portfolio {
+    fn solve(&self, sudoku: &Sudoku) -> SolveOutcome {
+        portfolio(sudoku.clone())
+    }
+
+    fn name(&self) -> &str {
+        "portfolio"
+    }
+}
+
+/// [`sat`] as a [`Solver`], which ignores [`SolveConfig::max_nodes`]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Sat;
+
+impl Solver for Sat {
+    fn solve(&self, sudoku: &Sudoku) -> SolveOutcome {
+        sat(sudoku.clone())
+    }
+
+    fn name(&self) -> &str {
+        "sat"
+    }
+}
+
+/// The solvers available by name
+#[derive(Default)]
+pub struct Registry {
+    solvers: Vec<Box<dyn Solver>>,
+}
+
+impl Registry {
+    /// An empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry of every built-in solver, with the searches using `options`
+    pub fn with_builtins(options: SearchOptions) -> Self {
+        let mut registry = Self::new();
+        registry.register(NaiveDfs);
+        registry.register(Dfs(options));
+        registry.register(SortedDfs(options));
+        registry.register(Dlx);
+        registry.register(Propagate);
+        registry.register(Logic);
+        #[cfg(feature = "parallel")]
+        registry.register(Portfolio);
+        registry.register(Sat);
+        registry
+    }
+
+    /// Add `solver`, replacing the solver registered under the same name if there is one
+    pub fn register(&mut self, solver: impl Solver + 'static) {
+        let solver: Box<dyn Solver> = Box::new(solver);
+        match self.solvers.iter_mut().find(|s| s.name() == solver.name()) {
+            Some(registered) => *registered = solver,
+            None => self.solvers.push(solver),
+        }
+    }
+
+    /// The solver registered under `name`
+    pub fn get(&self, name: &str) -> Option<&dyn Solver> {
+        self.solvers
+            .iter()
+            .find(|solver| solver.name() == name)
+            .map(Box::as_ref)
+    }
+
+    /// The names of the registered solvers, in the order they were first registered
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.solvers.iter().map(|solver| solver.name())
+    }
+}
+
+impl fmt::Debug for Registry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Registry, SolveOutcome, Solver};
+    use crate::sudoku::{
+        solve::{SearchOptions, SolveError},
+        Sudoku,
+    };
+
+    /// A solver giving up on every puzzle
+    struct Quitter;
+
+    impl Solver for Quitter {
+        fn solve(&self, sudoku: &Sudoku) -> SolveOutcome {
+            (
+                Err(SolveError::Unsolved(sudoku.clone())),
+                Default::default(),
+            )
+        }
+
+        fn name(&self) -> &str {
+            "dlx"
+        }
+    }
+
+    #[test]
+    fn registry_lookup() {
+        let sudoku: Sudoku = "1.34..12.1..4..1".parse().expect("Successful parse");
+        let mut registry = Registry::with_builtins(SearchOptions::default());
+
+        for name in registry.names() {
+            let solver = registry.get(name).expect("registered solver");
+            let (solution, _) = solver.solve(&sudoku);
+            assert_eq!(
+                solution.expect("solvable sudoku").to_line(),
+                "1234341221434321",
+                "{name}"
+            );
+        }
+        assert!(!registry.get("logic").expect("builtin").guesses());
+        assert!(registry.get("simulated-annealing").is_none());
+
+        let count = registry.names().count();
+        registry.register(Quitter);
+        assert_eq!(registry.names().count(), count);
+        assert!(registry
+            .get("dlx")
+            .expect("replaced")
+            .solve(&sudoku)
+            .0
+            .is_err());
+    }
+}
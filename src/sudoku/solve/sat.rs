@@ -0,0 +1,284 @@
+//! Boolean satisfiability encoding, solved with an embedded SAT solver
+//!
+//! Every candidate placement `(row, col, value)` is a variable. The clauses make every cell hold
+//! exactly one value and every unit and killer cage hold each value at most once, every row,
+//! column, box and diagonal at least once, and each given is a clause of its own. Killer cage sums
+//! have no compact encoding: a model breaking one is ruled out with a clause forbidding the values
+//! of that cage, and the solver tries again.
+
+use std::{fmt::Display, io, num::NonZeroU16};
+
+use varisat::{ExtendFormula, Lit, ProofFormat, Solver};
+
+use super::{out_of_time, SolveError, SolveStats, SudokuResult};
+use crate::sudoku::{techniques::Unit, Sudoku, SudokuValue};
+
+/// A Sudoku in conjunctive normal form
+///
+/// Variables and literals follow the DIMACS convention: variables start at 1 and a negative
+/// literal is the negation of its variable. [`Display`] writes the formula in the DIMACS format
+/// read by most SAT solvers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cnf {
+    order: usize,
+    clauses: Vec<Vec<i32>>,
+    /// Whether the Sudoku has killer cages, whose sums are left out
+    cages: bool,
+}
+
+impl Cnf {
+    /// Encode the rules and the givens of the Sudoku
+    pub fn new(sudoku: &Sudoku) -> Self {
+        let order = sudoku.order();
+        let rules = &sudoku.1;
+        let mut cnf = Self {
+            order,
+            clauses: Vec::new(),
+            cages: !rules.cages.is_empty(),
+        };
+
+        let cells = (0..order).flat_map(|row| (0..order).map(move |col| (row, col)));
+        for cell in cells.clone() {
+            let values: Vec<_> = values(order)
+                .map(|value| cnf.variable(cell, value))
+                .collect();
+            cnf.exactly_one(&values);
+        }
+
+        for value in values(order) {
+            for unit in Unit::all(order, rules) {
+                let cells: Vec<_> = unit
+                    .cells(order, rules)
+                    .map(|cell| cnf.variable(cell, value))
+                    .collect();
+                cnf.exactly_one(&cells);
+            }
+            for cage in rules.cages.iter() {
+                let cells: Vec<_> = cage
+                    .cells
+                    .iter()
+                    .map(|&cell| cnf.variable(cell, value))
+                    .collect();
+                cnf.at_most_one(&cells);
+            }
+        }
+
+        for cell in cells {
+            if let Some(value) = sudoku.0[cell].0 {
+                cnf.clauses.push(vec![cnf.variable(cell, value)]);
+            }
+        }
+
+        cnf
+    }
+
+    /// The number of variables, `order³`
+    pub fn variables(&self) -> usize {
+        self.order.pow(3)
+    }
+
+    /// The clauses of the formula, as lists of DIMACS literals
+    pub fn clauses(&self) -> &[Vec<i32>] {
+        &self.clauses
+    }
+
+    /// The variable placing `value` on `cell`
+    pub fn variable(&self, (row, col): (usize, usize), value: NonZeroU16) -> i32 {
+        ((row * self.order + col) * self.order + value.get() as usize) as i32
+    }
+
+    /// The cell and value of a variable
+    fn placement(&self, variable: i32) -> ((usize, usize), NonZeroU16) {
+        let index = variable as usize - 1;
+        let value = NonZeroU16::new((index % self.order) as u16 + 1).expect("values start at 1");
+        let cell = index / self.order;
+        ((cell / self.order, cell % self.order), value)
+    }
+
+    fn at_most_one(&mut self, variables: &[i32]) {
+        for (ix, &first) in variables.iter().enumerate() {
+            for &second in &variables[ix + 1..] {
+                self.clauses.push(vec![-first, -second]);
+            }
+        }
+    }
+
+    fn exactly_one(&mut self, variables: &[i32]) {
+        self.clauses.push(variables.to_vec());
+        self.at_most_one(variables);
+    }
+}
+
+impl Display for Cnf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "c {0}x{0} sudoku, variable (row * {0} + col) * {0} + value",
+            self.order
+        )?;
+        if self.cages {
+            writeln!(f, "c the sums of the killer cages are left out")?;
+        }
+        writeln!(f, "p cnf {} {}", self.variables(), self.clauses.len())?;
+        for clause in &self.clauses {
+            for literal in clause {
+                write!(f, "{literal} ")?;
+            }
+            writeln!(f, "0")?;
+        }
+        Ok(())
+    }
+}
+
+/// The values of an `order * order` Sudoku
+fn values(order: usize) -> impl Iterator<Item = NonZeroU16> + Clone {
+    (1..=order as u16).map(|value| NonZeroU16::new(value).expect("values start at 1"))
+}
+
+/// Solve the Sudoku with a SAT solver
+///
+/// The solve gives up once it runs past [`with_timeout`](super::with_timeout) or is cancelled,
+/// checked every few kilobytes of learnt clauses. The SAT solver doesn't expand search nodes, so
+/// it ignores [`SolveConfig::max_nodes`](super::SolveConfig::max_nodes).
+pub fn sat(sudoku: Sudoku) -> (SudokuResult, SolveStats) {
+    SolveStats::record(|stats| sat_impl(sudoku, stats))
+}
+
+/// Throws away the proof of unsatisfiability written by the SAT solver, failing once the solve has
+/// to give up
+///
+/// Varisat can't be interrupted, but it stops solving as soon as it can't write its proof.
+struct Budget;
+
+impl io::Write for Budget {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if out_of_time() {
+            return Err(io::Error::other("the solve ran out of time"));
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn sat_impl(sudoku: Sudoku, stats: &mut SolveStats) -> SudokuResult {
+    let cnf = Cnf::new(&sudoku);
+    let mut solver = Solver::new();
+    solver.write_proof(Budget, ProofFormat::Varisat);
+    for clause in cnf.clauses() {
+        solver.add_clause(&literals(clause));
+    }
+
+    loop {
+        match solver.solve() {
+            Ok(true) => {}
+            Ok(false) => return Err(SolveError::Unsolved(sudoku)),
+            // Only the proof can fail, once the budget ran out
+            Err(_) => {
+                stats.timed_out = true;
+                return Err(SolveError::Unsolved(sudoku));
+            }
+        }
+
+        let mut solved = sudoku.clone();
+        for literal in solver.model().expect("the formula is satisfiable") {
+            if literal.is_positive() {
+                let (cell, value) = cnf.placement(literal.to_dimacs() as i32);
+                solved.0[cell] = SudokuValue(Some(value));
+            }
+        }
+
+        // Rule out the values of the cages missing their sum, the rest of the rules hold
+        let broken: Vec<_> = sudoku
+            .1
+            .cages
+            .iter()
+            .filter(|cage| {
+                let sum: u32 = cage
+                    .cells
+                    .iter()
+                    .filter_map(|&cell| solved.0[cell].0)
+                    .map(|value| value.get() as u32)
+                    .sum();
+                sum != cage.sum
+            })
+            .collect();
+        if broken.is_empty() {
+            return Ok(solved);
+        }
+        for cage in broken {
+            let clause: Vec<_> = cage
+                .cells
+                .iter()
+                .filter_map(|&cell| Some(-cnf.variable(cell, solved.0[cell].0?)))
+                .collect();
+            solver.add_clause(&literals(&clause));
+        }
+    }
+}
+
+fn literals(clause: &[i32]) -> Vec<Lit> {
+    clause
+        .iter()
+        .map(|&literal| Lit::from_dimacs(literal as isize))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::sudoku::{
+        rules::parse_cages,
+        solve::{with_timeout, Budget, SolveError},
+        Sudoku,
+    };
+
+    use super::Cnf;
+
+    #[test]
+    fn cnf_encoding() {
+        let sudoku: Sudoku = "1...............".parse().expect("Successful parse");
+        let cnf = Cnf::new(&sudoku);
+        assert_eq!(cnf.variables(), 64);
+        assert!(cnf.clauses().contains(&vec![1]));
+
+        let dimacs = cnf.to_string();
+        assert!(dimacs.contains(&format!("p cnf 64 {}\n", cnf.clauses().len())));
+        assert!(dimacs.ends_with(" 0\n"));
+    }
+
+    #[test]
+    fn sat_solves_killer_sudokus() {
+        let hard: Sudoku =
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4.."
+                .parse()
+                .expect("Successful parse");
+        let expected = crate::sudoku::solve::sorted_dfs(hard.clone()).0;
+        assert_eq!(
+            super::sat(hard).0.expect("solvable").to_line(),
+            expected.expect("solvable").to_line()
+        );
+
+        let cages = parse_cages("3 r1c1 r1c2\n7 r4c3 r4c4").expect("valid cages");
+        let killer = Sudoku::from_order(4)
+            .with_cages(cages)
+            .expect("valid cages");
+        assert!(super::sat(killer).0.expect("solvable").solved());
+    }
+
+    #[test]
+    fn sat_gives_up_on_timeouts() {
+        let empty = Sudoku::from_order(25);
+        let (result, stats) = with_timeout(Duration::ZERO, || super::sat(empty));
+        assert!(matches!(
+            result,
+            Err(SolveError::Aborted {
+                budget: Budget::Time
+            })
+        ));
+        assert!(stats.timed_out);
+    }
+}
@@ -0,0 +1,311 @@
+use std::{
+    cell::{Cell, RefCell},
+    fmt::Display,
+    iter::Sum,
+    ops::{Add, AddAssign},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time::Instant;
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+use web_time::Instant;
+
+use super::{SolveConfig, SolveError, SudokuResult};
+
+/// How many nodes are expanded between checks for a timeout or a cancellation
+const DEADLINE_INTERVAL: u64 = 256;
+
+/// The budgets of the solvers running on a thread
+#[derive(Debug, Default, Clone, Copy)]
+pub(super) struct Limits {
+    /// The instant after which the solvers give up
+    deadline: Option<Instant>,
+    /// The nodes a solver expands before giving up
+    nodes: Option<u64>,
+}
+
+thread_local! {
+    /// The budgets of the solvers running on this thread
+    static LIMITS: Cell<Limits> = const { Cell::new(Limits { deadline: None, nodes: None }) };
+    /// Set from another thread to make the solvers running on this thread give up
+    static CANCEL: RefCell<Option<Arc<AtomicBool>>> = const { RefCell::new(None) };
+    /// Where the solvers running on this thread report how far they got
+    static PROGRESS: RefCell<Option<Arc<Progress>>> = const { RefCell::new(None) };
+}
+
+/// Set by [`interrupt`] to make every solver of the process give up
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Statistics about the search performed by a solver
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SolveStats {
+    /// Search nodes expanded, one per value tried
+    pub nodes: u64,
+    /// Search nodes that led to a dead end
+    pub backtracks: u64,
+    /// The deepest level of nested guesses
+    pub max_depth: usize,
+    /// Values fixed through constraint propagation
+    pub propagations: u64,
+    /// Wall clock time spent solving
+    pub elapsed: Duration,
+    /// Whether the solver gave up because it ran out of a budget or was cancelled
+    pub timed_out: bool,
+}
+
+/// The budget a solver ran out of, see [`SolveError::Aborted`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Budget {
+    /// It expanded more than this many search nodes
+    Nodes(u64),
+    /// It ran past its deadline
+    Time,
+    /// It was cancelled with [`interrupt`], or by a faster solver of a race
+    Cancelled,
+}
+
+impl SolveStats {
+    /// Run a solver and record how long it took
+    pub(super) fn record(
+        solver: impl FnOnce(&mut SolveStats) -> SudokuResult,
+    ) -> (SudokuResult, SolveStats) {
+        let mut stats = Self::default();
+        let start = Instant::now();
+        let result = match solver(&mut stats) {
+            Err(SolveError::Unsolved(_)) if stats.timed_out => Err(SolveError::Aborted {
+                budget: stats.exhausted(),
+            }),
+            result => result,
+        };
+        stats.elapsed = start.elapsed();
+        tracing::debug!(
+            solved = result.is_ok(),
+            nodes = stats.nodes,
+            backtracks = stats.backtracks,
+            elapsed = ?stats.elapsed,
+            "finished solving"
+        );
+        (result, stats)
+    }
+
+    /// Record the expansion of a search node at `depth`, reported every few nodes
+    pub(super) fn expand(&mut self, depth: usize) {
+        self.nodes += 1;
+        self.max_depth = self.max_depth.max(depth);
+        if self.nodes.is_multiple_of(DEADLINE_INTERVAL) {
+            PROGRESS.with_borrow(|progress| {
+                if let Some(progress) = progress {
+                    progress
+                        .nodes
+                        .fetch_add(DEADLINE_INTERVAL, Ordering::Relaxed);
+                    progress.depth.store(depth, Ordering::Relaxed);
+                    progress
+                        .max_depth
+                        .fetch_max(self.max_depth, Ordering::Relaxed);
+                }
+            });
+        }
+    }
+
+    /// Whether the solver should give up, checked every few expanded nodes
+    pub(super) fn cancelled(&mut self) -> bool {
+        let limits = LIMITS.get();
+        if limits.nodes.is_some_and(|limit| self.nodes > limit) {
+            self.timed_out = true;
+        } else if self.nodes.is_multiple_of(DEADLINE_INTERVAL) {
+            self.timed_out = out_of_time();
+        }
+        self.timed_out
+    }
+
+    /// The budget the solver ran out of, once it gave up
+    fn exhausted(&self) -> Budget {
+        let limits = LIMITS.get();
+        match limits.nodes {
+            Some(limit) if self.nodes > limit => Budget::Nodes(limit),
+            _ if limits
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline) =>
+            {
+                Budget::Time
+            }
+            _ => Budget::Cancelled,
+        }
+    }
+}
+
+/// Whether the solvers running on this thread ran past their deadline or were cancelled
+///
+/// Checked on every call, for the solvers not expanding nodes and for callers of
+/// [`with_timeout`] telling a search that gave up from one finding nothing, like a rating or a
+/// count of solutions coming up empty.
+pub fn out_of_time() -> bool {
+    interrupted()
+        || LIMITS
+            .get()
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        || CANCEL.with_borrow(|cancel| {
+            cancel
+                .as_ref()
+                .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+        })
+}
+
+/// Run `f`, making every solver it calls on this thread give up after `timeout`
+///
+/// A solver that runs out of time returns [`SolveError::Aborted`], with [`SolveStats::timed_out`]
+/// set.
+pub fn with_timeout<T>(timeout: Duration, f: impl FnOnce() -> T) -> T {
+    let previous = LIMITS.get();
+    LIMITS.set(Limits {
+        deadline: Some(Instant::now() + timeout),
+        ..previous
+    });
+    let result = f();
+    LIMITS.set(previous);
+    result
+}
+
+/// Run `f`, making every solver it calls on this thread give up once it runs out of the budgets
+/// of `config`
+///
+/// Like [`with_timeout`] for [`SolveConfig::max_time`], and a solver expanding more than
+/// [`SolveConfig::max_nodes`] nodes gives up the same way. The budgets replace the ones `f` was
+/// already running with, except for an earlier deadline.
+pub fn with_budget<T>(config: &SolveConfig, f: impl FnOnce() -> T) -> T {
+    let previous = LIMITS.get();
+    let deadline = config.max_time.map(|max_time| Instant::now() + max_time);
+    LIMITS.set(Limits {
+        deadline: deadline.into_iter().chain(previous.deadline).min(),
+        nodes: config.max_nodes.or(previous.nodes),
+    });
+    let result = f();
+    LIMITS.set(previous);
+    result
+}
+
+/// How far the solvers watched by [`with_progress`] got, readable from other threads while they run
+#[derive(Debug, Default)]
+pub struct Progress {
+    nodes: AtomicU64,
+    depth: AtomicUsize,
+    max_depth: AtomicUsize,
+}
+
+impl Progress {
+    /// The search nodes expanded so far, counted a few hundred at a time
+    pub fn nodes(&self) -> u64 {
+        self.nodes.load(Ordering::Relaxed)
+    }
+
+    /// The level of nested guesses of the latest node reported
+    ///
+    /// With several threads searching it's the depth of whichever reported last.
+    pub fn depth(&self) -> usize {
+        self.depth.load(Ordering::Relaxed)
+    }
+
+    /// The deepest level of nested guesses reached so far
+    pub fn max_depth(&self) -> usize {
+        self.max_depth.load(Ordering::Relaxed)
+    }
+}
+
+/// Run `f`, making every solver it calls report how far it got to `progress`
+///
+/// The solvers report every few hundred nodes, so [`Progress`] lags a little behind the
+/// [`SolveStats`] they return. The SAT solver doesn't report anything.
+pub fn with_progress<T>(progress: &Arc<Progress>, f: impl FnOnce() -> T) -> T {
+    let previous = PROGRESS.replace(Some(Arc::clone(progress)));
+    let result = f();
+    PROGRESS.set(previous);
+    result
+}
+
+/// Make the solvers running on every thread give up, like on Ctrl-C
+///
+/// The solvers stop at their next check as if they ran out of time, the interruption lasts for
+/// the rest of the process.
+pub fn interrupt() {
+    INTERRUPTED.store(true, Ordering::Relaxed);
+}
+
+/// Whether [`interrupt`] was called
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+}
+
+/// The budgets of the solvers running on this thread, to pass on to other threads
+#[cfg(feature = "parallel")]
+pub(super) fn limits() -> Limits {
+    LIMITS.get()
+}
+
+/// Where the solvers running on this thread report their progress, to pass on to other threads
+#[cfg(feature = "parallel")]
+pub(super) fn progress() -> Option<Arc<Progress>> {
+    PROGRESS.with_borrow(Clone::clone)
+}
+
+/// Run `f` on a worker thread, giving up once it runs out of `limits` or `cancel` is set
+///
+/// The solvers report to `progress` like the ones of the thread that took it.
+#[cfg(feature = "parallel")]
+pub(super) fn cancellable<T>(
+    limits: Limits,
+    progress: &Option<Arc<Progress>>,
+    cancel: &Arc<AtomicBool>,
+    f: impl FnOnce() -> T,
+) -> T {
+    let previous = LIMITS.replace(limits);
+    let previous_cancel = CANCEL.replace(Some(Arc::clone(cancel)));
+    let previous_progress = PROGRESS.replace(progress.clone());
+    let result = f();
+    LIMITS.set(previous);
+    CANCEL.set(previous_cancel);
+    PROGRESS.set(previous_progress);
+    result
+}
+
+/// Aggregate the statistics of several solves, keeping the deepest `max_depth`
+impl Add for SolveStats {
+    type Output = Self;
+
+    fn add(mut self, rhs: Self) -> Self::Output {
+        self += rhs;
+        self
+    }
+}
+
+impl AddAssign for SolveStats {
+    fn add_assign(&mut self, rhs: Self) {
+        self.nodes += rhs.nodes;
+        self.backtracks += rhs.backtracks;
+        self.max_depth = self.max_depth.max(rhs.max_depth);
+        self.propagations += rhs.propagations;
+        self.elapsed += rhs.elapsed;
+        self.timed_out |= rhs.timed_out;
+    }
+}
+
+impl Sum for SolveStats {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), Add::add)
+    }
+}
+
+impl Display for SolveStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} nodes, {} backtracks, {} propagations, max depth {}",
+            self.nodes, self.backtracks, self.propagations, self.max_depth
+        )
+    }
+}
@@ -0,0 +1,351 @@
+//! The steps of a search, reported to a callback as they happen
+//!
+//! [`with_observer`] makes the solvers over candidates ([`dfs`](super::dfs),
+//! [`sorted_dfs`](super::sorted_dfs), [`propagate`](super::propagate) and their variants) and the
+//! [`logic`](super::logic) solver report every branch, guess, deduction and dead end as an
+//! [`Event`]. Replaying the events from the candidates of [`Grid::new`] rebuilds every state of
+//! the board the solver went through, see [`Event`].
+
+use std::{cell::RefCell, fmt::Display, num::NonZeroU16, str::FromStr, sync::Arc};
+
+use crate::sudoku::{
+    techniques::{Cell, Deduction, Grid, Step, Technique},
+    Sudoku, SudokuError,
+};
+
+thread_local! {
+    /// Where the solvers starting on this thread report their steps
+    static OBSERVER: RefCell<Option<Observer>> = const { RefCell::new(None) };
+}
+
+/// A step of a solver
+///
+/// The board of a replay starts with the candidates of [`Grid::new`]. A [`Event::Branch`] saves
+/// it as the node at its depth, and a guess, which is a [`Event::Place`] with [`Reason::Guess`],
+/// first goes back to the node of its depth. A [`Event::Restart`] goes back to the first node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// The search opened a node at `depth`, trying the candidates of `cell` next
+    Branch { cell: Cell, depth: usize },
+    /// `value` was placed in `cell`
+    Place {
+        cell: Cell,
+        value: NonZeroU16,
+        reason: Reason,
+        depth: usize,
+    },
+    /// `value` was removed from the candidates of `cell`
+    Eliminate {
+        cell: Cell,
+        value: NonZeroU16,
+        reason: Reason,
+        depth: usize,
+    },
+    /// The guesses up to `depth` led to a contradiction
+    DeadEnd { depth: usize },
+    /// The search started over from the first node, see
+    /// [`SearchOptions::restarts`](super::SearchOptions::restarts)
+    Restart,
+}
+
+/// Why a value was placed or removed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reason {
+    /// A guess of the search
+    Guess,
+    /// The value was placed in a peer of the cell
+    Peer,
+    /// The value can't reach the sum of a killer cage
+    Cage,
+    /// A deduction of a human-style technique
+    Technique(Technique),
+}
+
+/// The callback the solvers report their [`Event`]s to
+#[derive(Clone)]
+pub struct Observer(Arc<dyn Fn(&Event) + Send + Sync>);
+
+impl Observer {
+    /// Report the events to the `observer` callback
+    pub fn new(observer: impl Fn(&Event) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(observer))
+    }
+
+    pub(super) fn notify(&self, event: Event) {
+        (self.0)(&event)
+    }
+
+    /// Apply `step` to `grid`, reporting its deduction at `depth`, and the candidates a placed
+    /// value removes from its peers
+    pub(super) fn apply(&self, grid: &mut Grid, step: &Step, depth: usize) {
+        let reason = Reason::Technique(step.technique);
+        let Deduction::Place { cell, value } = step.deduction else {
+            grid.apply(&step.deduction);
+            if let Deduction::Eliminate(eliminations) = &step.deduction {
+                for &(cell, value) in eliminations {
+                    self.notify(Event::Eliminate {
+                        cell,
+                        value,
+                        reason,
+                        depth,
+                    });
+                }
+            }
+            return;
+        };
+
+        let order = grid.order();
+        let cells = (0..order).flat_map(|row| (0..order).map(move |col| (row, col)));
+        let before: Vec<_> = cells
+            .clone()
+            .map(|cell| grid.candidate_set(cell).contains(value))
+            .collect();
+        grid.apply(&step.deduction);
+        self.notify(Event::Place {
+            cell,
+            value,
+            reason,
+            depth,
+        });
+        for (peer, had) in cells.zip(before) {
+            if had && peer != cell && !grid.candidate_set(peer).contains(value) {
+                self.notify(Event::Eliminate {
+                    cell: peer,
+                    value,
+                    reason: Reason::Peer,
+                    depth,
+                });
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for Observer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Observer")
+    }
+}
+
+/// Run `f`, making every solver it starts on this thread report its steps to `observer`
+///
+/// The parallel searches report the steps of all their threads, interleaved, and the portfolio
+/// and the solvers not listed in the [module docs](self) don't report anything.
+pub fn with_observer<T>(observer: &Observer, f: impl FnOnce() -> T) -> T {
+    let previous = OBSERVER.replace(Some(observer.clone()));
+    let result = f();
+    OBSERVER.set(previous);
+    result
+}
+
+/// The observer of the solvers starting on this thread
+pub(super) fn observer() -> Option<Observer> {
+    OBSERVER.with_borrow(Clone::clone)
+}
+
+/// The boards a solver went through, rebuilt from its [`Event`]s
+#[derive(Debug, Clone)]
+pub struct Replay {
+    grid: Grid,
+    /// The board at every open node of the search, node `d` at index `d - 1`
+    nodes: Vec<Grid>,
+}
+
+impl Replay {
+    /// Start from the candidates of the givens of `sudoku`, which must fit in the candidates
+    pub fn new(sudoku: &Sudoku) -> Result<Self, SudokuError> {
+        Ok(Self {
+            grid: Grid::new(sudoku)?,
+            nodes: Vec::new(),
+        })
+    }
+
+    /// The board after the events applied so far
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    /// Apply `event` to the board
+    pub fn apply(&mut self, event: &Event) {
+        match *event {
+            Event::Branch { depth, .. } => {
+                self.nodes.truncate(depth.saturating_sub(1));
+                self.nodes.push(self.grid.clone());
+            }
+            Event::Place {
+                cell,
+                value,
+                reason,
+                depth,
+            } => {
+                if reason == Reason::Guess {
+                    if let Some(node) = depth.checked_sub(1).and_then(|ix| self.nodes.get(ix)) {
+                        self.grid = node.clone();
+                    }
+                }
+                self.grid.apply(&Deduction::Place { cell, value });
+            }
+            Event::Eliminate { cell, value, .. } => {
+                self.grid.apply(&Deduction::Eliminate(vec![(cell, value)]));
+            }
+            Event::DeadEnd { .. } => {}
+            Event::Restart => {
+                self.nodes.truncate(1);
+                if let Some(first) = self.nodes.first() {
+                    self.grid = first.clone();
+                }
+            }
+        }
+    }
+}
+
+impl Display for Reason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Reason::Guess => write!(f, "guess"),
+            Reason::Peer => write!(f, "peer"),
+            Reason::Cage => write!(f, "cage"),
+            Reason::Technique(technique) => write!(f, "{technique}"),
+        }
+    }
+}
+
+/// Describe the event like the steps of the techniques, `r1c2=3 guess at depth 1`
+impl Display for Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = |(row, col): Cell| format!("r{}c{}", row + 1, col + 1);
+        match *self {
+            Event::Branch { cell, depth } => write!(f, "branch on {} at depth {depth}", name(cell)),
+            Event::Place {
+                cell,
+                value,
+                reason,
+                depth,
+            } => write!(f, "{}={value} {reason} at depth {depth}", name(cell)),
+            Event::Eliminate {
+                cell,
+                value,
+                reason,
+                depth,
+            } => write!(f, "{}<>{value} {reason} at depth {depth}", name(cell)),
+            Event::DeadEnd { depth } => write!(f, "dead end at depth {depth}"),
+            Event::Restart => write!(f, "restart"),
+        }
+    }
+}
+
+/// Parse a reason from its name, like `peer` or `hidden single`
+impl FromStr for Reason {
+    type Err = SudokuError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "guess" => Ok(Reason::Guess),
+            "peer" => Ok(Reason::Peer),
+            "cage" => Ok(Reason::Cage),
+            name => Technique::ALL
+                .into_iter()
+                .find(|technique| technique.to_string() == name)
+                .map(Reason::Technique)
+                .ok_or_else(|| SudokuError::UnknownReason(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use super::{with_observer, Event, Observer, Reason, Replay};
+    use crate::sudoku::{
+        solve::{logic, sorted_dfs, sorted_dfs_with, SearchOptions},
+        techniques::Technique,
+        Sudoku,
+    };
+
+    fn trace(solve: impl FnOnce()) -> Vec<Event> {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let observer = Observer::new({
+            let events = events.clone();
+            move |event| events.lock().expect("not poisoned").push(*event)
+        });
+        with_observer(&observer, solve);
+        drop(observer);
+        Arc::into_inner(events)
+            .expect("the solver dropped the observer")
+            .into_inner()
+            .expect("not poisoned")
+    }
+
+    #[test]
+    fn events_match_stats() {
+        let hard: Sudoku =
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4.."
+                .parse()
+                .expect("Successful parse");
+        let mut stats = Default::default();
+        let events = trace(|| stats = sorted_dfs(hard).1);
+        let guesses = events
+            .iter()
+            .filter(|event| {
+                matches!(
+                    event,
+                    Event::Place {
+                        reason: Reason::Guess,
+                        ..
+                    }
+                )
+            })
+            .count();
+        assert_eq!(guesses as u64, stats.nodes);
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, Event::DeadEnd { .. })));
+
+        let easy: Sudoku = "1.34..12.1..4..1".parse().expect("Successful parse");
+        let mut stats = Default::default();
+        let events = trace(|| stats = logic(easy).1);
+        let placed = events
+            .iter()
+            .filter(|event| matches!(event, Event::Place { .. }))
+            .count();
+        assert_eq!(placed as u64, stats.propagations);
+        assert!(events
+            .iter()
+            .all(|event| !matches!(event, Event::Branch { .. })));
+    }
+
+    #[test]
+    fn replay_reaches_the_solution() {
+        let hard: Sudoku =
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4.."
+                .parse()
+                .expect("Successful parse");
+        let options = SearchOptions {
+            restarts: Some(20),
+            backjump: true,
+            ..Default::default()
+        };
+        let mut solution = None;
+        let events = trace(|| solution = sorted_dfs_with(hard.clone(), options).0.ok());
+        assert!(events.contains(&Event::Restart));
+
+        let mut replay = Replay::new(&hard).expect("small sudoku");
+        for event in &events {
+            replay.apply(event);
+        }
+        assert!(replay.grid().is_filled());
+        assert_eq!(
+            replay.grid().sudoku().to_line(),
+            solution.expect("solvable sudoku").to_line()
+        );
+
+        for reason in [
+            Reason::Guess,
+            Reason::Peer,
+            Reason::Technique(Technique::XWing),
+        ] {
+            assert_eq!(reason.to_string().parse::<Reason>().ok(), Some(reason));
+        }
+        assert!("hunch".parse::<Reason>().is_err());
+    }
+}
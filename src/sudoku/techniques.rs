@@ -0,0 +1,983 @@
+//! Human-style solving techniques
+//!
+//! Unlike the brute-force solvers in [`solve`](super::solve), every technique looks for a single
+//! logical deduction on a [`Grid`] of pencil marks and explains how it was found.
+
+use std::{fmt::Display, num::NonZeroU16};
+
+use ndarray::Array2;
+
+use super::{
+    candidates::Candidates,
+    rules::{BoxShape, Rules},
+    Sudoku, SudokuError, SudokuValue,
+};
+
+mod chains;
+mod uniqueness;
+
+/// A cell position as (row, column)
+pub type Cell = (usize, usize);
+
+/// A Sudoku with the candidates of every empty cell
+#[derive(Debug, Clone)]
+pub struct Grid {
+    boxes: BoxShape,
+    order: usize,
+    values: Array2<SudokuValue>,
+    /// The candidates of every cell, empty for filled cells
+    candidates: Array2<Candidates>,
+    rules: Rules,
+    /// The techniques that may be used
+    techniques: Vec<Technique>,
+}
+
+/// A row, column, box or diagonal of the Sudoku
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Row(usize),
+    Column(usize),
+    Box(usize),
+    /// The main diagonal (`0`) or the anti-diagonal (`1`) of a Sudoku X
+    Diagonal(usize),
+}
+
+impl Unit {
+    /// Every row, column, box and diagonal of a Sudoku of size `order`
+    pub(crate) fn all(order: usize, rules: &Rules) -> impl Iterator<Item = Unit> {
+        let diagonals = if rules.diagonals { 2 } else { 0 };
+        (0..order)
+            .map(Unit::Row)
+            .chain((0..order).map(Unit::Column))
+            .chain((0..order).map(Unit::Box))
+            .chain((0..diagonals).map(Unit::Diagonal))
+    }
+
+    /// The row, column, box and diagonals containing a cell of a Sudoku of size `order`
+    pub(crate) fn containing(order: usize, rules: &Rules, (row, col): Cell) -> Vec<Unit> {
+        let cell_box = rules.box_of(rules.box_shape(order), (row, col));
+        let mut units = vec![Unit::Row(row), Unit::Column(col), Unit::Box(cell_box)];
+
+        if rules.diagonals {
+            if row == col {
+                units.push(Unit::Diagonal(0));
+            }
+            if row + col == order - 1 {
+                units.push(Unit::Diagonal(1));
+            }
+        }
+
+        units
+    }
+
+    /// The cells of the unit in a Sudoku of size `order`
+    pub(crate) fn cells(self, order: usize, rules: &Rules) -> impl Iterator<Item = Cell> + '_ {
+        let boxes = rules.box_shape(order);
+        (0..order).map(move |pos| match self {
+            Unit::Row(row) => (row, pos),
+            Unit::Column(col) => (pos, col),
+            Unit::Box(cell_box) => rules.box_cell(boxes, cell_box, pos),
+            Unit::Diagonal(0) => (pos, pos),
+            Unit::Diagonal(_) => (pos, order - 1 - pos),
+        })
+    }
+}
+
+/// The techniques available to the human-style solver, from simplest to hardest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Technique {
+    /// A value with a single possible cell in a unit
+    HiddenSingle,
+    /// A cell with a single candidate
+    NakedSingle,
+    /// A value confined to the intersection of two units
+    LockedCandidates,
+    /// Two cells of a unit with the same two candidates
+    NakedPair,
+    /// A value confined to the same two columns in two rows, or the other way around
+    XWing,
+    /// Two values confined to the same two cells of a unit
+    HiddenPair,
+    /// Three cells of a unit sharing three candidates
+    NakedTriple,
+    /// A value confined to the same three columns in three rows, or the other way around
+    Swordfish,
+    /// Three values confined to the same three cells of a unit
+    HiddenTriple,
+    /// A rectangle of cells with the same two candidates but one, which can't take them
+    UniqueRectangle1,
+    /// A rectangle of cells with the same two candidates, two of them with the same extra one
+    UniqueRectangle2,
+    /// A rectangle of cells with the same two candidates, two of them forming a naked pair with
+    /// another cell through their extra candidates
+    UniqueRectangle3,
+    /// A rectangle of cells with the same two candidates, one of them confined to two of the cells
+    UniqueRectangle4,
+    /// Four cells of a unit sharing four candidates
+    NakedQuad,
+    /// A value confined to the same four columns in four rows, or the other way around
+    Jellyfish,
+    /// Four values confined to the same four cells of a unit
+    HiddenQuad,
+    /// Every empty cell has two candidates but one, which must take the value it has in excess
+    BugPlusOne,
+    /// A chain of cells with two candidates, each sharing one with the next, forcing one of its
+    /// ends to take a value
+    XYChain,
+}
+
+impl Technique {
+    /// Every technique, from simplest to hardest
+    pub const ALL: [Technique; 18] = [
+        Technique::HiddenSingle,
+        Technique::NakedSingle,
+        Technique::LockedCandidates,
+        Technique::NakedPair,
+        Technique::XWing,
+        Technique::HiddenPair,
+        Technique::NakedTriple,
+        Technique::Swordfish,
+        Technique::HiddenTriple,
+        Technique::UniqueRectangle1,
+        Technique::UniqueRectangle2,
+        Technique::UniqueRectangle3,
+        Technique::UniqueRectangle4,
+        Technique::NakedQuad,
+        Technique::Jellyfish,
+        Technique::HiddenQuad,
+        Technique::BugPlusOne,
+        Technique::XYChain,
+    ];
+
+    /// Whether the technique only holds on puzzles with a single solution
+    pub fn assumes_unique(self) -> bool {
+        matches!(
+            self,
+            Technique::UniqueRectangle1
+                | Technique::UniqueRectangle2
+                | Technique::UniqueRectangle3
+                | Technique::UniqueRectangle4
+                | Technique::BugPlusOne
+        )
+    }
+
+    /// How hard the technique is to spot, loosely following the Sudoku Explainer scale
+    pub fn difficulty(self) -> f32 {
+        match self {
+            Technique::HiddenSingle => 1.5,
+            Technique::NakedSingle => 2.3,
+            Technique::LockedCandidates => 2.8,
+            Technique::NakedPair => 3.0,
+            Technique::XWing => 3.2,
+            Technique::HiddenPair => 3.4,
+            Technique::NakedTriple => 3.6,
+            Technique::Swordfish => 3.8,
+            Technique::HiddenTriple => 4.0,
+            Technique::UniqueRectangle1
+            | Technique::UniqueRectangle2
+            | Technique::UniqueRectangle3
+            | Technique::UniqueRectangle4 => 4.5,
+            Technique::NakedQuad => 5.0,
+            Technique::Jellyfish => 5.2,
+            Technique::HiddenQuad => 5.4,
+            Technique::BugPlusOne => 5.6,
+            Technique::XYChain => 6.0,
+        }
+    }
+}
+
+/// The outcome of applying a technique
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Deduction {
+    /// Fill a cell with a value
+    Place { cell: Cell, value: NonZeroU16 },
+    /// Remove candidates from cells
+    Eliminate(Vec<(Cell, NonZeroU16)>),
+}
+
+/// A single logical step towards the solution
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub technique: Technique,
+    /// The units the pattern was found in
+    pub units: Vec<Unit>,
+    /// The cells forming the pattern
+    pub cells: Vec<Cell>,
+    /// The values forming the pattern
+    pub values: Vec<NonZeroU16>,
+    pub deduction: Deduction,
+}
+
+/// Look for a technique on a grid
+type Finder = fn(&Grid) -> Option<Step>;
+
+/// Every technique along with the function looking for it, from simplest to hardest
+const TECHNIQUES: [(Technique, Finder); 18] = [
+    (Technique::HiddenSingle, hidden_single),
+    (Technique::NakedSingle, naked_single),
+    (Technique::LockedCandidates, locked_candidates),
+    (Technique::NakedPair, naked_pair),
+    (Technique::XWing, x_wing),
+    (Technique::HiddenPair, hidden_pair),
+    (Technique::NakedTriple, naked_triple),
+    (Technique::Swordfish, swordfish),
+    (Technique::HiddenTriple, hidden_triple),
+    (Technique::UniqueRectangle1, uniqueness::rectangle_type_1),
+    (Technique::UniqueRectangle2, uniqueness::rectangle_type_2),
+    (Technique::UniqueRectangle3, uniqueness::rectangle_type_3),
+    (Technique::UniqueRectangle4, uniqueness::rectangle_type_4),
+    (Technique::NakedQuad, naked_quad),
+    (Technique::Jellyfish, jellyfish),
+    (Technique::HiddenQuad, hidden_quad),
+    (Technique::BugPlusOne, uniqueness::bug_plus_one),
+    (Technique::XYChain, chains::xy_chain),
+];
+
+/// Find the next logical step for the Sudoku, `None` if it is solved or guessing is required
+///
+/// Fails if the Sudoku is too large for the candidates of the techniques.
+pub fn hint(sudoku: &Sudoku) -> Result<Option<Step>, SudokuError> {
+    Ok(Grid::new(sudoku)?.next_step())
+}
+
+/// [`hint`], only using the given techniques
+pub fn hint_with(sudoku: &Sudoku, techniques: &[Technique]) -> Result<Option<Step>, SudokuError> {
+    Ok(Grid::new(sudoku)?.with_techniques(techniques).next_step())
+}
+
+impl Grid {
+    /// Fill in the candidates of every empty cell
+    ///
+    /// Fails for Sudokus with more values than the candidates can hold, see
+    /// [`SudokuError::TooLarge`].
+    pub fn new(sudoku: &Sudoku) -> Result<Self, SudokuError> {
+        let order = sudoku.order();
+        Candidates::check(order)?;
+        let mut grid = Self {
+            boxes: sudoku.box_shape(),
+            order,
+            values: sudoku.0.clone(),
+            candidates: sudoku.0.mapv(|value| {
+                if value.is_some() {
+                    Candidates::default()
+                } else {
+                    Candidates::full(order)
+                }
+            }),
+            rules: sudoku.1.clone(),
+            techniques: Technique::ALL
+                .into_iter()
+                .filter(|technique| !technique.assumes_unique())
+                .collect(),
+        };
+
+        for (cell, value) in sudoku.0.indexed_iter() {
+            if let Some(value) = value.0 {
+                grid.remove_from_peers(cell, value);
+            }
+        }
+
+        Ok(grid)
+    }
+
+    /// Allow the techniques relying on the puzzle having a single solution
+    ///
+    /// Unique rectangles and BUG+1 rule out the candidates that would leave several solutions, so
+    /// on puzzles with many solutions they can eliminate valid candidates. They are never used on
+    /// killer or Sudoku X puzzles, where swapping two values can break the extra constraints.
+    pub fn assuming_unique(mut self) -> Self {
+        self.techniques = Technique::ALL.to_vec();
+        self
+    }
+
+    /// Only use the given techniques, including the uniqueness ones if listed
+    pub fn with_techniques(mut self, techniques: &[Technique]) -> Self {
+        self.techniques = techniques.to_vec();
+        self
+    }
+
+    /// The Sudoku with the values filled so far
+    pub fn sudoku(&self) -> Sudoku {
+        Sudoku(self.values.clone(), self.rules.clone())
+    }
+
+    /// The number of rows, columns and values of the grid
+    pub(crate) fn order(&self) -> usize {
+        self.order
+    }
+
+    /// The value of a cell, `None` if it is empty
+    pub fn value(&self, cell: Cell) -> Option<NonZeroU16> {
+        self.values[cell].0
+    }
+
+    /// The remaining candidates of a cell in ascending order, empty if the cell is filled
+    pub fn candidates(&self, cell: Cell) -> impl Iterator<Item = NonZeroU16> {
+        self.candidates[cell].iter()
+    }
+
+    pub(crate) fn candidate_set(&self, cell: Cell) -> Candidates {
+        self.candidates[cell]
+    }
+
+    /// Whether every cell is filled
+    pub fn is_filled(&self) -> bool {
+        self.values.iter().all(|value| value.is_some())
+    }
+
+    /// Whether an empty cell ran out of candidates
+    pub fn is_broken(&self) -> bool {
+        self.values
+            .iter()
+            .zip(&self.candidates)
+            .any(|(value, candidates)| value.is_none() && candidates.is_empty())
+    }
+
+    /// Find the simplest applicable technique
+    pub fn next_step(&self) -> Option<Step> {
+        self.next_step_up_to(Technique::XYChain)
+    }
+
+    /// Find the simplest applicable technique no harder than `hardest`
+    pub fn next_step_up_to(&self, hardest: Technique) -> Option<Step> {
+        TECHNIQUES
+            .iter()
+            .take_while(|(technique, _)| *technique <= hardest)
+            .filter(|(technique, _)| self.techniques.contains(technique))
+            .find_map(|(_, find)| find(self))
+    }
+
+    /// Apply every technique no harder than `hardest` until none applies
+    ///
+    /// Returns the number of filled cells
+    pub fn solve_up_to(&mut self, hardest: Technique) -> usize {
+        let mut placed = 0;
+        while let Some(step) = self.next_step_up_to(hardest) {
+            if matches!(step.deduction, Deduction::Place { .. }) {
+                placed += 1;
+            }
+            self.apply(&step.deduction);
+        }
+        placed
+    }
+
+    /// Apply the deduction of a step
+    pub fn apply(&mut self, deduction: &Deduction) {
+        match deduction {
+            Deduction::Place { cell, value } => {
+                self.values[*cell] = SudokuValue(Some(*value));
+                self.candidates[*cell] = Candidates::default();
+                self.remove_from_peers(*cell, *value);
+            }
+            Deduction::Eliminate(eliminations) => {
+                for (cell, value) in eliminations {
+                    self.candidates[*cell].remove(*value);
+                }
+            }
+        }
+    }
+
+    fn remove_from_peers(&mut self, cell: Cell, value: NonZeroU16) {
+        let peers: Vec<_> = self
+            .units_of(cell)
+            .into_iter()
+            .flat_map(|unit| self.cells(unit))
+            .collect();
+
+        for peer in peers {
+            self.candidates[peer].remove(value);
+        }
+    }
+
+    /// Every row, column, box and diagonal
+    fn units(&self) -> impl Iterator<Item = Unit> {
+        Unit::all(self.order, &self.rules)
+    }
+
+    /// The row, column, box and diagonals containing a cell
+    fn units_of(&self, cell: Cell) -> Vec<Unit> {
+        Unit::containing(self.order, &self.rules, cell)
+    }
+
+    /// The cells of a unit
+    fn cells(&self, unit: Unit) -> impl Iterator<Item = Cell> + '_ {
+        unit.cells(self.order, &self.rules)
+    }
+
+    /// The empty cells of a unit that have `value` as a candidate
+    fn cells_with(&self, unit: Unit, value: NonZeroU16) -> Vec<Cell> {
+        self.cells(unit)
+            .filter(|&cell| self.candidates[cell].contains(value))
+            .collect()
+    }
+
+    fn values(&self) -> impl Iterator<Item = NonZeroU16> {
+        Candidates::full(self.order).into_iter()
+    }
+}
+
+fn naked_single(grid: &Grid) -> Option<Step> {
+    grid.candidates
+        .indexed_iter()
+        .find(|(_, candidates)| candidates.len() == 1)
+        .and_then(|(cell, candidates)| {
+            let value = candidates.iter().next()?;
+            Some(Step {
+                technique: Technique::NakedSingle,
+                units: vec![],
+                cells: vec![cell],
+                values: vec![value],
+                deduction: Deduction::Place { cell, value },
+            })
+        })
+}
+
+fn hidden_single(grid: &Grid) -> Option<Step> {
+    grid.units().find_map(|unit| {
+        grid.values()
+            .find_map(|value| match grid.cells_with(unit, value)[..] {
+                [cell] => Some(Step {
+                    technique: Technique::HiddenSingle,
+                    units: vec![unit],
+                    cells: vec![cell],
+                    values: vec![value],
+                    deduction: Deduction::Place { cell, value },
+                }),
+                _ => None,
+            })
+    })
+}
+
+/// Pointing (a box's candidates in one line) and claiming (a line's candidates in one box)
+///
+/// In a Sudoku X the diagonals take part too, as a diagonal's candidates can lie in a box or line.
+fn locked_candidates(grid: &Grid) -> Option<Step> {
+    grid.units().find_map(|unit| {
+        grid.values().find_map(|value| {
+            let cells = grid.cells_with(unit, value);
+            if cells.len() < 2 {
+                return None;
+            }
+
+            let mut shared = grid.units_of(cells[0]).into_iter().filter(|&other| {
+                other != unit
+                    && cells
+                        .iter()
+                        .all(|&cell| grid.units_of(cell).contains(&other))
+            });
+
+            shared.find_map(|shared| {
+                let eliminations: Vec<_> = grid
+                    .cells_with(shared, value)
+                    .into_iter()
+                    .filter(|cell| !cells.contains(cell))
+                    .map(|cell| (cell, value))
+                    .collect();
+
+                (!eliminations.is_empty()).then(|| Step {
+                    technique: Technique::LockedCandidates,
+                    units: vec![unit, shared],
+                    cells: cells.clone(),
+                    values: vec![value],
+                    deduction: Deduction::Eliminate(eliminations),
+                })
+            })
+        })
+    })
+}
+
+fn naked_pair(grid: &Grid) -> Option<Step> {
+    naked_subset(grid, 2, Technique::NakedPair)
+}
+
+fn hidden_pair(grid: &Grid) -> Option<Step> {
+    hidden_subset(grid, 2, Technique::HiddenPair)
+}
+
+fn naked_triple(grid: &Grid) -> Option<Step> {
+    naked_subset(grid, 3, Technique::NakedTriple)
+}
+
+fn hidden_triple(grid: &Grid) -> Option<Step> {
+    hidden_subset(grid, 3, Technique::HiddenTriple)
+}
+
+fn naked_quad(grid: &Grid) -> Option<Step> {
+    naked_subset(grid, 4, Technique::NakedQuad)
+}
+
+fn hidden_quad(grid: &Grid) -> Option<Step> {
+    hidden_subset(grid, 4, Technique::HiddenQuad)
+}
+
+/// `size` cells of a unit with only `size` candidates between them
+///
+/// The candidates must go in those cells, so the rest of the unit can't hold them.
+fn naked_subset(grid: &Grid, size: usize, technique: Technique) -> Option<Step> {
+    grid.units().find_map(|unit| {
+        let cells: Vec<_> = grid
+            .cells(unit)
+            .filter(|&cell| (2..=size).contains(&grid.candidates[cell].len()))
+            .collect();
+
+        subsets(&cells, size).into_iter().find_map(|subset| {
+            let values = subset.iter().fold(Candidates::default(), |values, &cell| {
+                values | grid.candidates[cell]
+            });
+            if values.len() != size {
+                return None;
+            }
+
+            let eliminations: Vec<_> = grid
+                .cells(unit)
+                .filter(|cell| !subset.contains(cell))
+                .flat_map(|cell| {
+                    (grid.candidates[cell] & values)
+                        .into_iter()
+                        .map(move |value| (cell, value))
+                })
+                .collect();
+
+            (!eliminations.is_empty()).then(|| Step {
+                technique,
+                units: vec![unit],
+                cells: subset,
+                values: values.iter().collect(),
+                deduction: Deduction::Eliminate(eliminations),
+            })
+        })
+    })
+}
+
+/// `size` values confined to the same `size` cells of a unit
+///
+/// Those cells must hold the values, so they can't hold any other candidate.
+fn hidden_subset(grid: &Grid, size: usize, technique: Technique) -> Option<Step> {
+    grid.units().find_map(|unit| {
+        let values: Vec<_> = grid
+            .values()
+            .filter(|&value| (2..=size).contains(&grid.cells_with(unit, value).len()))
+            .collect();
+
+        subsets(&values, size).into_iter().find_map(|subset| {
+            let mut cells: Vec<_> = subset
+                .iter()
+                .flat_map(|&value| grid.cells_with(unit, value))
+                .collect();
+            cells.sort_unstable();
+            cells.dedup();
+            if cells.len() != size {
+                return None;
+            }
+
+            let values: Candidates = subset.iter().copied().collect();
+            let eliminations: Vec<_> = cells
+                .iter()
+                .flat_map(|&cell| {
+                    (grid.candidates[cell] - values)
+                        .into_iter()
+                        .map(move |value| (cell, value))
+                })
+                .collect();
+
+            (!eliminations.is_empty()).then(|| Step {
+                technique,
+                units: vec![unit],
+                cells,
+                values: subset,
+                deduction: Deduction::Eliminate(eliminations),
+            })
+        })
+    })
+}
+
+fn x_wing(grid: &Grid) -> Option<Step> {
+    fish(grid, 2, Technique::XWing)
+}
+
+fn swordfish(grid: &Grid) -> Option<Step> {
+    fish(grid, 3, Technique::Swordfish)
+}
+
+fn jellyfish(grid: &Grid) -> Option<Step> {
+    fish(grid, 4, Technique::Jellyfish)
+}
+
+/// `size` rows where a value is confined to the same `size` columns, or the other way around
+///
+/// The value must go in the intersections, one in each row and column, so the rest of the columns
+/// (or rows) can't hold it. The step lists the defining rows (or columns) before the others.
+fn fish(grid: &Grid, size: usize, technique: Technique) -> Option<Step> {
+    [true, false].into_iter().find_map(|by_rows| {
+        let base = |ix| {
+            if by_rows {
+                Unit::Row(ix)
+            } else {
+                Unit::Column(ix)
+            }
+        };
+        let cover = |ix| {
+            if by_rows {
+                Unit::Column(ix)
+            } else {
+                Unit::Row(ix)
+            }
+        };
+        let cross = |(row, col): Cell| if by_rows { col } else { row };
+        let line = |(row, col): Cell| if by_rows { row } else { col };
+
+        grid.values().find_map(|value| {
+            let lines: Vec<_> = (0..grid.order)
+                .filter(|&ix| (2..=size).contains(&grid.cells_with(base(ix), value).len()))
+                .collect();
+
+            subsets(&lines, size).into_iter().find_map(|subset| {
+                let cells: Vec<_> = subset
+                    .iter()
+                    .flat_map(|&ix| grid.cells_with(base(ix), value))
+                    .collect();
+                let mut crosses: Vec<_> = cells.iter().map(|&cell| cross(cell)).collect();
+                crosses.sort_unstable();
+                crosses.dedup();
+                if crosses.len() != size {
+                    return None;
+                }
+
+                let eliminations: Vec<_> = crosses
+                    .iter()
+                    .flat_map(|&ix| grid.cells_with(cover(ix), value))
+                    .filter(|&cell| !subset.contains(&line(cell)))
+                    .map(|cell| (cell, value))
+                    .collect();
+
+                (!eliminations.is_empty()).then(|| Step {
+                    technique,
+                    units: subset
+                        .iter()
+                        .map(|&ix| base(ix))
+                        .chain(crosses.iter().map(|&ix| cover(ix)))
+                        .collect(),
+                    cells,
+                    values: vec![value],
+                    deduction: Deduction::Eliminate(eliminations),
+                })
+            })
+        })
+    })
+}
+
+/// Every way of picking `size` of the items, keeping their order
+fn subsets<T: Copy>(items: &[T], size: usize) -> Vec<Vec<T>> {
+    if size == 0 {
+        return vec![Vec::new()];
+    }
+
+    (0..items.len())
+        .flat_map(|ix| {
+            subsets(&items[ix + 1..], size - 1)
+                .into_iter()
+                .map(move |mut rest| {
+                    rest.insert(0, items[ix]);
+                    rest
+                })
+        })
+        .collect()
+}
+
+/// Format a cell as `r1c1`
+pub(crate) struct CellName(pub(crate) Cell);
+
+impl Display for CellName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (row, col) = self.0;
+        write!(f, "r{}c{}", row + 1, col + 1)
+    }
+}
+
+impl Display for Unit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Unit::Row(row) => write!(f, "row {}", row + 1),
+            Unit::Column(col) => write!(f, "column {}", col + 1),
+            Unit::Box(cell_box) => write!(f, "box {}", cell_box + 1),
+            Unit::Diagonal(0) => write!(f, "the main diagonal"),
+            Unit::Diagonal(_) => write!(f, "the anti-diagonal"),
+        }
+    }
+}
+
+impl Display for Technique {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Technique::NakedSingle => "naked single",
+            Technique::HiddenSingle => "hidden single",
+            Technique::LockedCandidates => "locked candidates",
+            Technique::NakedPair => "naked pair",
+            Technique::XWing => "x-wing",
+            Technique::HiddenPair => "hidden pair",
+            Technique::NakedTriple => "naked triple",
+            Technique::Swordfish => "swordfish",
+            Technique::HiddenTriple => "hidden triple",
+            Technique::UniqueRectangle1 => "unique rectangle type 1",
+            Technique::UniqueRectangle2 => "unique rectangle type 2",
+            Technique::UniqueRectangle3 => "unique rectangle type 3",
+            Technique::UniqueRectangle4 => "unique rectangle type 4",
+            Technique::NakedQuad => "naked quad",
+            Technique::Jellyfish => "jellyfish",
+            Technique::HiddenQuad => "hidden quad",
+            Technique::BugPlusOne => "bug+1",
+            Technique::XYChain => "xy-chain",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl Display for Step {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let units = self
+            .units
+            .iter()
+            .map(Unit::to_string)
+            .collect::<Vec<_>>()
+            .join(" and ");
+
+        match &self.deduction {
+            Deduction::Place { cell, value } => {
+                write!(f, "{}={value} {}", CellName(*cell), self.technique)?;
+                if !units.is_empty() {
+                    write!(f, " in {units}")?;
+                }
+                Ok(())
+            }
+            Deduction::Eliminate(eliminations) => {
+                let values = self
+                    .values
+                    .iter()
+                    .map(NonZeroU16::to_string)
+                    .collect::<Vec<_>>()
+                    .join(",");
+                let cells = self
+                    .cells
+                    .iter()
+                    .map(|&cell| CellName(cell).to_string())
+                    .collect::<Vec<_>>()
+                    .join(",");
+                let eliminations = eliminations
+                    .iter()
+                    .map(|&(cell, value)| format!("{}<>{value}", CellName(cell)))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                if self.technique == Technique::XYChain {
+                    return write!(
+                        f,
+                        "{} {} eliminates {eliminations}",
+                        self.technique,
+                        Chain(self)
+                    );
+                }
+
+                write!(f, "{} {{{values}}} at {cells}", self.technique)?;
+                if !units.is_empty() {
+                    write!(f, " in {units}")?;
+                }
+                write!(f, " eliminates {eliminations}")
+            }
+        }
+    }
+}
+
+/// The cells of a chain with the values linking them, like `r1c1(1=2)-r1c5(2=3)`
+struct Chain<'a>(&'a Step);
+
+impl Display for Chain<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Step { cells, values, .. } = self.0;
+        for (ix, (&cell, links)) in cells.iter().zip(values.windows(2)).enumerate() {
+            if ix > 0 {
+                write!(f, "-")?;
+            }
+            write!(f, "{}({}={})", CellName(cell), links[0], links[1])?;
+        }
+        Ok(())
+    }
+}
+
+/// Draw the pencil marks of every cell, each candidate in the same spot of its cell
+///
+/// Cells of different boxes are separated by `|` and `-`, and cells of the same box by `:` and
+/// `.`. Filled cells show their value in brackets.
+impl Display for Grid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (order, boxes) = (self.order, self.boxes);
+        let width = order.to_string().len();
+        let cell_width = boxes.cols * (width + 1) + 1;
+
+        let cell_box = |row: Option<usize>, col: Option<usize>| {
+            let (row, col) = (row?, col?);
+            (row < order && col < order).then(|| self.rules.box_of(boxes, (row, col)))
+        };
+        // Whether there is a box border left of `col` in `row`
+        let vertical = |row: Option<usize>, col: usize| {
+            row.is_some() && cell_box(row, col.checked_sub(1)) != cell_box(row, Some(col))
+        };
+
+        for row in 0..=order {
+            let above = row.checked_sub(1);
+            let below = (row < order).then_some(row);
+            let horizontal = |col: usize| cell_box(above, Some(col)) != cell_box(below, Some(col));
+
+            for col in 0..=order {
+                let corner = vertical(above, col)
+                    || vertical(below, col)
+                    || col.checked_sub(1).is_some_and(horizontal)
+                    || (col < order && horizontal(col));
+                write!(f, "{}", if corner { '+' } else { '.' })?;
+                if col < order {
+                    let line = if horizontal(col) { '-' } else { '.' };
+                    write!(f, "{}", line.to_string().repeat(cell_width))?;
+                }
+            }
+            writeln!(f)?;
+
+            let Some(row) = below else {
+                break;
+            };
+
+            for line in 0..boxes.rows {
+                for col in 0..=order {
+                    write!(f, "{}", if vertical(Some(row), col) { '|' } else { ':' })?;
+                    if col == order {
+                        break;
+                    }
+
+                    if let Some(value) = self.value((row, col)) {
+                        let value = if line == boxes.rows / 2 {
+                            format!("[{value}]")
+                        } else {
+                            String::new()
+                        };
+                        write!(f, "{value:^cell_width$}")?;
+                        continue;
+                    }
+
+                    for pos in 0..boxes.cols {
+                        match NonZeroU16::new((line * boxes.cols + pos + 1) as u16) {
+                            Some(value) if self.candidates[(row, col)].contains(value) => {
+                                write!(f, " {value:>width$}")?
+                            }
+                            _ => write!(f, " {:width$}", "")?,
+                        }
+                    }
+                    write!(f, " ")?;
+                }
+                writeln!(f)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::sudoku::Sudoku;
+
+    use super::{Deduction, Grid, Technique, Unit};
+
+    #[test]
+    fn singles_solve_easy_puzzle() {
+        let sudoku: Sudoku =
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79"
+                .parse()
+                .expect("Successful parse");
+        let mut grid = Grid::new(&sudoku).expect("small sudoku");
+
+        while let Some(step) = grid.next_step() {
+            assert!(matches!(step.deduction, Deduction::Place { .. }));
+            grid.apply(&step.deduction);
+        }
+
+        assert!(grid.sudoku().solved());
+    }
+
+    #[test]
+    fn naked_pair_eliminations() {
+        // Row 1 can only hold 1 and 2 in its first two cells
+        let sudoku: Sudoku =
+            "..3456789........................................................................"
+                .parse()
+                .expect("Successful parse");
+        let grid = Grid::new(&sudoku).expect("small sudoku");
+        let step = super::naked_pair(&grid).expect("naked pair");
+
+        assert_eq!(step.technique, Technique::NakedPair);
+        assert_eq!(step.cells, [(0, 0), (0, 1)]);
+        let Deduction::Eliminate(eliminations) = step.deduction else {
+            panic!("naked pairs eliminate candidates");
+        };
+        assert_eq!(eliminations.len(), 12);
+    }
+
+    #[test]
+    fn naked_triple_eliminations() {
+        // Row 1 can only hold 1, 2 and 3 in its first three cells, so the rest of box 1 can't
+        let sudoku: Sudoku =
+            "...456789........................................................................"
+                .parse()
+                .expect("Successful parse");
+        let grid = Grid::new(&sudoku).expect("small sudoku");
+        let step = super::naked_triple(&grid).expect("naked triple");
+
+        assert_eq!(step.technique, Technique::NakedTriple);
+        assert_eq!(step.units, [Unit::Box(0)]);
+        assert_eq!(step.cells, [(0, 0), (0, 1), (0, 2)]);
+        let Deduction::Eliminate(eliminations) = step.deduction else {
+            panic!("naked triples eliminate candidates");
+        };
+        assert_eq!(eliminations.len(), 18);
+    }
+
+    #[test]
+    fn x_wing_eliminations() {
+        let sudoku: Sudoku =
+            ".3.........1..4.98...27...4...8.5.292.......56.....43.853.........742......5....."
+                .parse()
+                .expect("Successful parse");
+        let solution = crate::sudoku::solve::sorted_dfs(sudoku.clone())
+            .0
+            .expect("solvable sudoku");
+        let mut grid = Grid::new(&sudoku).expect("small sudoku");
+        grid.solve_up_to(Technique::NakedPair);
+        let step = super::x_wing(&grid).expect("x-wing");
+
+        assert_eq!(step.technique, Technique::XWing);
+        assert_eq!(step.units.len(), 4);
+        let Deduction::Eliminate(eliminations) = step.deduction else {
+            panic!("fish eliminate candidates");
+        };
+        assert!(!eliminations.is_empty());
+        assert!(eliminations
+            .iter()
+            .all(|&(cell, value)| solution.0[cell].0 != Some(value)));
+    }
+
+    #[test]
+    fn technique_selection() {
+        let sudoku: Sudoku =
+            ".9...6....34....8.......1.5..2.........5.9..187......2.........5..1....8....84.23"
+                .parse()
+                .expect("Successful parse");
+        let singles = [Technique::HiddenSingle, Technique::NakedSingle];
+        let mut grid = Grid::new(&sudoku)
+            .expect("small sudoku")
+            .with_techniques(&singles);
+
+        while let Some(step) = grid.next_step() {
+            assert!(singles.contains(&step.technique));
+            grid.apply(&step.deduction);
+        }
+        assert!(!grid.is_filled());
+        assert_eq!(super::hint_with(&sudoku, &[]), Ok(None));
+    }
+}
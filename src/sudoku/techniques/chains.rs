@@ -0,0 +1,161 @@
+//! Chains of cells with two candidates
+//!
+//! In an XY-chain every cell has two candidates and sees the next one through a common candidate.
+//! If the first cell doesn't hold its other candidate `z`, it holds the common one, which rules it
+//! out of the next cell, forcing its other candidate and so on along the chain. When the last cell
+//! is forced to `z`, either end holds `z` and the cells seeing both can't.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    num::NonZeroU16,
+};
+
+use ndarray::Array2;
+
+use super::{Cell, Deduction, Grid, Step, Technique};
+
+/// The empty cells sharing a unit with `cell`
+fn peers(grid: &Grid, cell: Cell) -> Vec<Cell> {
+    let mut peers: Vec<_> = grid
+        .units_of(cell)
+        .into_iter()
+        .flat_map(|unit| grid.cells(unit))
+        .filter(|&peer| peer != cell && grid.values[peer].is_none())
+        .collect();
+    peers.sort_unstable();
+    peers.dedup();
+    peers
+}
+
+/// The candidate of a cell with two candidates other than `value`
+fn other(grid: &Grid, cell: Cell, value: NonZeroU16) -> Option<NonZeroU16> {
+    grid.candidates[cell].iter().find(|&other| other != value)
+}
+
+/// The shortest XY-chain eliminating a candidate
+///
+/// The cells of the step are the chain in order, and its values the candidates linking them: the
+/// first and last values are the candidate `z` of both ends, and every other value is shared by the
+/// cells before and after it.
+pub(super) fn xy_chain(grid: &Grid) -> Option<Step> {
+    let bivalue: Vec<_> = grid
+        .candidates
+        .indexed_iter()
+        .filter(|(_, candidates)| candidates.len() == 2)
+        .map(|(cell, _)| cell)
+        .collect();
+    if bivalue.len() < 3 {
+        return None;
+    }
+
+    let links = Array2::from_shape_fn((grid.order, grid.order), |cell| {
+        if grid.candidates[cell].len() == 2 {
+            peers(grid, cell)
+                .into_iter()
+                .filter(|&peer| grid.candidates[peer].len() == 2)
+                .collect()
+        } else {
+            Vec::new()
+        }
+    });
+
+    bivalue.iter().find_map(|&start| {
+        grid.candidates[start]
+            .iter()
+            .find_map(|end_value| chain_from(grid, &links, start, end_value))
+    })
+}
+
+/// Search the chains starting on `start` with `end_value` off, from the shortest
+fn chain_from(
+    grid: &Grid,
+    links: &Array2<Vec<Cell>>,
+    start: Cell,
+    end_value: NonZeroU16,
+) -> Option<Step> {
+    let start_peers = peers(grid, start);
+    let first = other(grid, start, end_value)?;
+
+    // The previous state of every (cell, value turned on) state reached
+    let mut previous = HashMap::new();
+    let mut queue = VecDeque::from([(start, first)]);
+    previous.insert((start, first), None);
+
+    while let Some((cell, on)) = queue.pop_front() {
+        if on == end_value && cell != start {
+            let eliminations: Vec<_> = peers(grid, cell)
+                .into_iter()
+                .filter(|peer| *peer != start && start_peers.contains(peer))
+                .filter(|&peer| grid.candidates[peer].contains(end_value))
+                .map(|peer| (peer, end_value))
+                .collect();
+
+            if !eliminations.is_empty() {
+                let mut chain = vec![(cell, on)];
+                while let Some(&Some(state)) = previous.get(chain.last().expect("never empty")) {
+                    chain.push(state);
+                }
+                chain.reverse();
+
+                return Some(Step {
+                    technique: Technique::XYChain,
+                    units: vec![],
+                    cells: chain.iter().map(|&(cell, _)| cell).collect(),
+                    values: std::iter::once(end_value)
+                        .chain(chain.iter().map(|&(_, on)| on))
+                        .collect(),
+                    deduction: Deduction::Eliminate(eliminations),
+                });
+            }
+        }
+
+        for &next in &links[cell] {
+            if !grid.candidates[next].contains(on) {
+                continue;
+            }
+            let Some(next_on) = other(grid, next, on) else {
+                continue;
+            };
+            if next == start || previous.contains_key(&(next, next_on)) {
+                continue;
+            }
+            previous.insert((next, next_on), Some((cell, on)));
+            queue.push_back((next, next_on));
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use crate::sudoku::{
+        techniques::{Deduction, Grid, Technique},
+        Sudoku,
+    };
+
+    #[test]
+    fn xy_chain_eliminations() {
+        let sudoku: Sudoku =
+            ".9...6....34....8.......1.5..2.........5.9..187......2.........5..1....8....84.23"
+                .parse()
+                .expect("Successful parse");
+        let solution = crate::sudoku::solve::sorted_dfs(sudoku.clone())
+            .0
+            .expect("solvable sudoku");
+        let mut grid = Grid::new(&sudoku).expect("small sudoku");
+        grid.solve_up_to(Technique::BugPlusOne);
+        let step = super::xy_chain(&grid).expect("xy-chain");
+
+        assert_eq!(step.technique, Technique::XYChain);
+        assert_eq!(step.values.len(), step.cells.len() + 1);
+        assert_eq!(step.values.first(), step.values.last());
+        let Deduction::Eliminate(eliminations) = step.deduction else {
+            panic!("chains eliminate candidates");
+        };
+        assert!(!eliminations.is_empty());
+        assert!(eliminations
+            .iter()
+            .all(|&(cell, value)| solution.0[cell].0 != Some(value)));
+    }
+}
@@ -0,0 +1,323 @@
+//! Techniques relying on the puzzle having a single solution
+//!
+//! Four cells on two rows, two columns and two boxes holding only the same two values form a
+//! deadly pattern: the values can be swapped without breaking any rule, so a puzzle with a single
+//! solution can't end up in it. The same goes for a grid where every empty cell is left with two
+//! candidates appearing twice in each unit.
+
+use std::num::NonZeroU16;
+
+use super::{Cell, Deduction, Grid, Step, Technique, Unit};
+use crate::sudoku::candidates::Candidates;
+
+/// Four cells on two rows, two columns and two boxes, all of them allowing both `pair` values
+struct Rectangle {
+    cells: [Cell; 4],
+    pair: Candidates,
+}
+
+impl Rectangle {
+    /// The cells with exactly the two values of the pair
+    fn floor(&self, grid: &Grid) -> Vec<Cell> {
+        self.cells
+            .into_iter()
+            .filter(|&cell| grid.candidates[cell] == self.pair)
+            .collect()
+    }
+
+    /// The cells with other candidates besides the pair
+    fn roof(&self, grid: &Grid) -> Vec<Cell> {
+        self.cells
+            .into_iter()
+            .filter(|&cell| grid.candidates[cell] != self.pair)
+            .collect()
+    }
+
+    fn step(&self, technique: Technique, deduction: Deduction) -> Step {
+        Step {
+            technique,
+            units: vec![],
+            cells: self.cells.to_vec(),
+            values: self.pair.iter().collect(),
+            deduction,
+        }
+    }
+}
+
+/// Whether the uniqueness techniques hold on the rules of the grid
+fn allowed(grid: &Grid) -> bool {
+    grid.rules.cages.is_empty() && !grid.rules.diagonals
+}
+
+/// Every rectangle of the grid that could become a deadly pattern
+fn rectangles(grid: &Grid) -> Vec<Rectangle> {
+    let order = grid.order;
+    let mut rectangles = Vec::new();
+
+    for top in 0..order {
+        for bottom in top + 1..order {
+            for left in 0..order {
+                for right in left + 1..order {
+                    let cells = [(top, left), (top, right), (bottom, left), (bottom, right)];
+                    let mut boxes: Vec<_> = cells
+                        .iter()
+                        .map(|&cell| grid.rules.box_of(grid.boxes, cell))
+                        .collect();
+                    boxes.sort_unstable();
+                    boxes.dedup();
+                    if boxes.len() != 2 {
+                        continue;
+                    }
+
+                    let common = cells.iter().fold(Candidates::full(order), |common, &cell| {
+                        common & grid.candidates[cell]
+                    });
+                    let values: Vec<_> = common.iter().collect();
+                    for (ix, &first) in values.iter().enumerate() {
+                        for &second in &values[ix + 1..] {
+                            rectangles.push(Rectangle {
+                                cells,
+                                pair: [first, second].into_iter().collect(),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    rectangles
+}
+
+/// The units containing both cells
+fn shared_units(grid: &Grid, first: Cell, second: Cell) -> Vec<Unit> {
+    let units = grid.units_of(second);
+    grid.units_of(first)
+        .into_iter()
+        .filter(|unit| units.contains(unit))
+        .collect()
+}
+
+/// The empty cells other than `exclude` of the units containing both cells
+fn common_peers(grid: &Grid, first: Cell, second: Cell, exclude: &[Cell]) -> Vec<Cell> {
+    let mut peers: Vec<_> = shared_units(grid, first, second)
+        .into_iter()
+        .flat_map(|unit| grid.cells(unit))
+        .filter(|cell| !exclude.contains(cell) && grid.values[*cell].is_none())
+        .collect();
+    peers.sort_unstable();
+    peers.dedup();
+    peers
+}
+
+/// The candidates of `cells` among `values`, as eliminations
+fn eliminate(grid: &Grid, cells: &[Cell], values: Candidates) -> Vec<(Cell, NonZeroU16)> {
+    cells
+        .iter()
+        .flat_map(|&cell| {
+            (grid.candidates[cell] & values)
+                .into_iter()
+                .map(move |value| (cell, value))
+        })
+        .collect()
+}
+
+/// Three cells of the rectangle hold only the pair, so the fourth can't take either value
+pub(super) fn rectangle_type_1(grid: &Grid) -> Option<Step> {
+    if !allowed(grid) {
+        return None;
+    }
+
+    rectangles(grid).into_iter().find_map(|rectangle| {
+        let [roof] = rectangle.roof(grid)[..] else {
+            return None;
+        };
+        let eliminations = eliminate(grid, &[roof], rectangle.pair);
+        Some(rectangle.step(
+            Technique::UniqueRectangle1,
+            Deduction::Eliminate(eliminations),
+        ))
+    })
+}
+
+/// The two roof cells of the rectangle have the same single extra candidate, so one of them holds
+/// it and their common peers can't
+pub(super) fn rectangle_type_2(grid: &Grid) -> Option<Step> {
+    if !allowed(grid) {
+        return None;
+    }
+
+    rectangles(grid).into_iter().find_map(|rectangle| {
+        let [first, second] = rectangle.roof(grid)[..] else {
+            return None;
+        };
+        let extra = grid.candidates[first] - rectangle.pair;
+        if extra.len() != 1 || grid.candidates[second] != grid.candidates[first] {
+            return None;
+        }
+
+        let peers = common_peers(grid, first, second, &rectangle.cells);
+        let eliminations = eliminate(grid, &peers, extra);
+        (!eliminations.is_empty()).then(|| {
+            rectangle.step(
+                Technique::UniqueRectangle2,
+                Deduction::Eliminate(eliminations),
+            )
+        })
+    })
+}
+
+/// The extra candidates of the two roof cells form a naked pair with another cell of a unit
+/// containing both, as one of the roof cells must hold one of them
+pub(super) fn rectangle_type_3(grid: &Grid) -> Option<Step> {
+    if !allowed(grid) {
+        return None;
+    }
+
+    rectangles(grid).into_iter().find_map(|rectangle| {
+        let [first, second] = rectangle.roof(grid)[..] else {
+            return None;
+        };
+        let extra = (grid.candidates[first] | grid.candidates[second]) - rectangle.pair;
+        if extra.len() != 2 {
+            return None;
+        }
+
+        shared_units(grid, first, second)
+            .into_iter()
+            .find_map(|unit| {
+                let partner = grid.cells(unit).find(|&cell| {
+                    !rectangle.cells.contains(&cell) && grid.candidates[cell] == extra
+                })?;
+                let others: Vec<_> = grid
+                    .cells(unit)
+                    .filter(|&cell| cell != partner && !rectangle.cells.contains(&cell))
+                    .collect();
+                let eliminations = eliminate(grid, &others, extra);
+
+                (!eliminations.is_empty()).then(|| {
+                    let mut step = rectangle.step(
+                        Technique::UniqueRectangle3,
+                        Deduction::Eliminate(eliminations),
+                    );
+                    step.units = vec![unit];
+                    step.cells.push(partner);
+                    step
+                })
+            })
+    })
+}
+
+/// One value of the pair is confined to the two roof cells in a unit containing both, so one of
+/// them holds it and neither can hold the other value
+pub(super) fn rectangle_type_4(grid: &Grid) -> Option<Step> {
+    if !allowed(grid) {
+        return None;
+    }
+
+    rectangles(grid).into_iter().find_map(|rectangle| {
+        let [first, second] = rectangle.roof(grid)[..] else {
+            return None;
+        };
+        if rectangle.floor(grid).len() != 2 {
+            return None;
+        }
+
+        shared_units(grid, first, second)
+            .into_iter()
+            .find_map(|unit| {
+                let locked = rectangle
+                    .pair
+                    .iter()
+                    .find(|&value| grid.cells_with(unit, value) == [first, second])?;
+                let other = rectangle.pair - [locked].into_iter().collect();
+                let eliminations = eliminate(grid, &[first, second], other);
+
+                let mut step = rectangle.step(
+                    Technique::UniqueRectangle4,
+                    Deduction::Eliminate(eliminations),
+                );
+                step.units = vec![unit];
+                Some(step)
+            })
+    })
+}
+
+/// Every empty cell has two candidates but one with three, which must hold the candidate it has in
+/// excess, otherwise every candidate would appear twice in each unit in a deadly pattern
+pub(super) fn bug_plus_one(grid: &Grid) -> Option<Step> {
+    if !allowed(grid) {
+        return None;
+    }
+
+    let mut extra = None;
+    for (cell, candidates) in grid.candidates.indexed_iter() {
+        match candidates.len() {
+            0 | 2 => {}
+            3 if extra.is_none() => extra = Some(cell),
+            _ => return None,
+        }
+    }
+    let cell = extra?;
+
+    // The value appearing three times in the row of the cell
+    let value = grid.candidates[cell]
+        .iter()
+        .find(|&value| grid.cells_with(Unit::Row(cell.0), value).len() == 3)?;
+
+    // Without it, every candidate must appear twice in each unit for the pattern to be deadly
+    let deadly = grid.units().all(|unit| {
+        grid.values().all(|other| {
+            let count = grid
+                .cells_with(unit, other)
+                .into_iter()
+                .filter(|&with| with != cell || other != value)
+                .count();
+            count == 0 || count == 2
+        })
+    });
+
+    deadly.then(|| Step {
+        technique: Technique::BugPlusOne,
+        units: vec![],
+        cells: vec![cell],
+        values: vec![value],
+        deduction: Deduction::Place { cell, value },
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::sudoku::{
+        techniques::{Deduction, Grid, Technique},
+        Sudoku,
+    };
+
+    #[test]
+    fn unique_rectangle_eliminations() {
+        let sudoku: Sudoku =
+            ".91..2.....5.9..26.4...8..14.2.6.5....8..9..2......6...27..5..4.3....8.....4.1.67"
+                .parse()
+                .expect("Successful parse");
+        let solution = crate::sudoku::solve::sorted_dfs(sudoku.clone())
+            .0
+            .expect("solvable sudoku");
+        let mut grid = Grid::new(&sudoku).expect("small sudoku").assuming_unique();
+        let step = loop {
+            let step = grid.next_step().expect("logical step");
+            if step.technique == Technique::UniqueRectangle1 {
+                break step;
+            }
+            grid.apply(&step.deduction);
+        };
+
+        assert_eq!(step.cells.len(), 4);
+        let Deduction::Eliminate(eliminations) = step.deduction else {
+            panic!("unique rectangles eliminate candidates");
+        };
+        assert!(!eliminations.is_empty());
+        assert!(eliminations
+            .iter()
+            .all(|&(cell, value)| solution.0[cell].0 != Some(value)));
+    }
+}
@@ -0,0 +1,211 @@
+//! Transformations of a grid keeping it a valid puzzle
+//!
+//! Rotating, reflecting and transposing the grid, relabeling its values and moving its bands,
+//! stacks, and the rows and columns inside them all keep the number of solutions of a puzzle. The
+//! transformed puzzles follow the classic rules, the constraints of a variant are dropped.
+
+use std::num::NonZeroU16;
+
+use ndarray::Array2;
+
+use super::{
+    rules::{BoxShape, Rules},
+    Sudoku, SudokuError, SudokuValue,
+};
+
+impl Sudoku {
+    /// Flip the grid over its main diagonal, rows become columns
+    pub fn transpose(&self) -> Self {
+        self.remap(self.turned_boxes(), |(row, col)| (col, row))
+    }
+
+    /// Rotate the grid a quarter turn clockwise
+    pub fn rotate90(&self) -> Self {
+        let last = self.order() - 1;
+        self.remap(self.turned_boxes(), |(row, col)| (last - col, row))
+    }
+
+    /// Rotate the grid a half turn
+    pub fn rotate180(&self) -> Self {
+        let last = self.order() - 1;
+        self.remap(self.1.boxes, |(row, col)| (last - row, last - col))
+    }
+
+    /// Flip the grid left to right
+    pub fn reflect_horizontal(&self) -> Self {
+        let last = self.order() - 1;
+        self.remap(self.1.boxes, |(row, col)| (row, last - col))
+    }
+
+    /// Flip the grid top to bottom
+    pub fn reflect_vertical(&self) -> Self {
+        let last = self.order() - 1;
+        self.remap(self.1.boxes, |(row, col)| (last - row, col))
+    }
+
+    /// Relabel the values, `values[value - 1]` replacing `value`
+    ///
+    /// The new values must be a permutation of `1..=order`.
+    pub fn permute_values(&self, values: &[u16]) -> Result<Self, SudokuError> {
+        let order = self.order();
+        let mut sorted = values.to_vec();
+        sorted.sort_unstable();
+        if !sorted.iter().copied().eq(1..=order as u16) {
+            return Err(SudokuError::InvalidTransformation(
+                "the values must be a permutation of every value",
+            ));
+        }
+
+        let mut sudoku = self.remap(self.1.boxes, |cell| cell);
+        for value in sudoku.0.iter_mut() {
+            value.0 = value
+                .0
+                .and_then(|value| NonZeroU16::new(values[value.get() as usize - 1]));
+        }
+        Ok(sudoku)
+    }
+
+    /// Swap two rows of the same band, counting from 0
+    pub fn swap_rows_in_band(&self, first: usize, second: usize) -> Result<Self, SudokuError> {
+        let band = self.box_shape().rows;
+        self.check_lines(first, second)?;
+        if first / band != second / band {
+            return Err(SudokuError::InvalidTransformation(
+                "only rows of the same band can be swapped",
+            ));
+        }
+        Ok(self.remap(self.1.boxes, |(row, col)| {
+            (swapped(row, first, second), col)
+        }))
+    }
+
+    /// Swap two columns of the same stack, counting from 0
+    pub fn swap_cols_in_stack(&self, first: usize, second: usize) -> Result<Self, SudokuError> {
+        let stack = self.box_shape().cols;
+        self.check_lines(first, second)?;
+        if first / stack != second / stack {
+            return Err(SudokuError::InvalidTransformation(
+                "only columns of the same stack can be swapped",
+            ));
+        }
+        Ok(self.remap(self.1.boxes, |(row, col)| {
+            (row, swapped(col, first, second))
+        }))
+    }
+
+    /// Swap two bands of rows, counting from 0
+    pub fn swap_bands(&self, first: usize, second: usize) -> Result<Self, SudokuError> {
+        let band = self.box_shape().rows;
+        self.check_lines(first * band, second * band)?;
+        Ok(self.remap(self.1.boxes, |(row, col)| {
+            (swapped(row / band, first, second) * band + row % band, col)
+        }))
+    }
+
+    /// Swap two stacks of columns, counting from 0
+    pub fn swap_stacks(&self, first: usize, second: usize) -> Result<Self, SudokuError> {
+        let stack = self.box_shape().cols;
+        self.check_lines(first * stack, second * stack)?;
+        Ok(self.remap(self.1.boxes, |(row, col)| {
+            (
+                row,
+                swapped(col / stack, first, second) * stack + col % stack,
+            )
+        }))
+    }
+
+    fn check_lines(&self, first: usize, second: usize) -> Result<(), SudokuError> {
+        let order = self.order();
+        if first >= order || second >= order {
+            return Err(SudokuError::InvalidTransformation(
+                "the lines must be inside the grid",
+            ));
+        }
+        Ok(())
+    }
+
+    /// The boxes once rows become columns, rectangular boxes turn on their side
+    fn turned_boxes(&self) -> Option<BoxShape> {
+        let boxes = self.box_shape();
+        let turned = BoxShape {
+            rows: boxes.cols,
+            cols: boxes.rows,
+        };
+        (Some(turned) != BoxShape::of_order(self.order())).then_some(turned)
+    }
+
+    /// The grid with the value of cell `source(cell)` in every cell, following the classic rules
+    fn remap(
+        &self,
+        boxes: Option<BoxShape>,
+        source: impl Fn((usize, usize)) -> (usize, usize),
+    ) -> Self {
+        let order = self.order();
+        let grid: Array2<SudokuValue> =
+            Array2::from_shape_fn((order, order), |cell| self.0[source(cell)]);
+        Self(
+            grid,
+            Rules {
+                boxes,
+                ..Rules::default()
+            },
+        )
+    }
+}
+
+/// The line swapped with `line`, itself unless it is one of the pair
+fn swapped(line: usize, first: usize, second: usize) -> usize {
+    match line {
+        line if line == first => second,
+        line if line == second => first,
+        line => line,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::num::NonZeroU16;
+
+    use crate::sudoku::{rules::BoxShape, Sudoku};
+
+    #[test]
+    fn transformations_keep_puzzles_valid() {
+        let sudoku: Sudoku =
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79"
+                .parse()
+                .expect("Successful parse");
+
+        assert_eq!(
+            sudoku.rotate90().rotate90().to_line(),
+            sudoku.rotate180().to_line()
+        );
+        assert_eq!(
+            sudoku.rotate90().to_line(),
+            sudoku.transpose().reflect_horizontal().to_line()
+        );
+        assert_eq!(sudoku.rotate90().get(0, 8), sudoku.get(0, 0));
+        assert_eq!(sudoku.reflect_vertical().get(8, 0), sudoku.get(0, 0));
+
+        let moved = sudoku
+            .swap_rows_in_band(0, 2)
+            .and_then(|sudoku| sudoku.swap_cols_in_stack(3, 5))
+            .and_then(|sudoku| sudoku.swap_bands(0, 2))
+            .and_then(|sudoku| sudoku.swap_stacks(1, 2))
+            .and_then(|sudoku| sudoku.permute_values(&[9, 8, 7, 6, 5, 4, 3, 2, 1]))
+            .expect("valid transformations");
+        assert!(moved.valid());
+        let flipped = sudoku
+            .get(2, 0)
+            .and_then(|value| NonZeroU16::new(10 - value.get()));
+        assert_eq!(moved.get(6, 0), flipped);
+        assert!(sudoku.swap_rows_in_band(0, 3).is_err());
+        assert!(sudoku.permute_values(&[1, 1, 2, 3, 4, 5, 6, 7, 8]).is_err());
+
+        let six: Sudoku = "1.......6..44...1..2.......243......"
+            .parse()
+            .expect("Successful parse");
+        let turned = six.rotate90();
+        assert_eq!(turned.box_shape(), BoxShape { rows: 3, cols: 2 });
+        assert!(turned.valid());
+    }
+}
@@ -0,0 +1,172 @@
+//! Recording the steps of a solve to a file, one JSON object per line
+//!
+//! The first line describes the solve, `{"puzzle": "...", "solver": "sorted-dfs"}`, and every
+//! other line is an [`Event`] of the solver:
+//!
+//! - `{"event": "branch", "cell": "r1c2", "depth": 1}`
+//! - `{"event": "place", "cell": "r1c2", "value": 3, "reason": "guess", "depth": 1}`
+//! - `{"event": "eliminate", "cell": "r4c2", "value": 3, "reason": "peer", "depth": 1}`
+//! - `{"event": "dead-end", "depth": 1}`
+//! - `{"event": "restart"}`
+//!
+//! [`load`] reads the trace back, for `replay`.
+
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    num::NonZeroU16,
+    path::Path,
+    sync::mpsc,
+};
+
+use nsudoku_solver::{
+    solve::{with_observer, Event, Observer, Reason},
+    techniques::Cell,
+    Sudoku,
+};
+use serde_json::{json, Value};
+
+/// Run `solve`, writing the events of the solvers it calls to `path`
+pub fn record<T>(
+    path: &Path,
+    puzzle: &Sudoku,
+    solver: &str,
+    solve: impl FnOnce() -> T,
+) -> color_eyre::Result<T> {
+    let mut out = BufWriter::new(File::create(path)?);
+    writeln!(
+        out,
+        "{}",
+        json!({ "puzzle": puzzle.to_line(), "solver": solver })
+    )?;
+
+    let (sender, events) = mpsc::channel();
+    let observer = Observer::new(move |event| {
+        // The writer only hangs up after failing, its error is reported below
+        let _ = sender.send(*event);
+    });
+    std::thread::scope(|scope| {
+        let writer = scope.spawn(move || -> std::io::Result<()> {
+            for event in events {
+                writeln!(out, "{}", to_json(&event))?;
+            }
+            out.flush()
+        });
+        let result = with_observer(&observer, solve);
+        // Hang up, so the writer stops once it wrote every event
+        drop(observer);
+        writer.join().expect("the writer doesn't panic")?;
+        Ok(result)
+    })
+}
+
+/// A solve recorded by [`record`]
+#[derive(Debug)]
+pub struct Trace {
+    pub puzzle: Sudoku,
+    pub solver: String,
+    pub events: Vec<Event>,
+}
+
+/// Read the trace [`record`] wrote to `path`
+pub fn load(path: &Path) -> color_eyre::Result<Trace> {
+    let mut lines = BufReader::new(File::open(path)?).lines();
+    let Some(header) = lines.next() else {
+        color_eyre::eyre::bail!("the trace is empty");
+    };
+    let header: Value = serde_json::from_str(&header?)?;
+    let (Some(puzzle), Some(solver)) = (header["puzzle"].as_str(), header["solver"].as_str())
+    else {
+        color_eyre::eyre::bail!(
+            "malformed trace on line 1, expected {{\"puzzle\": \"...\", \"solver\": \"...\"}}"
+        );
+    };
+
+    let mut events = Vec::new();
+    for (ix, line) in lines.enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match from_json(&serde_json::from_str(&line)?) {
+            Some(event) => events.push(event),
+            None => color_eyre::eyre::bail!("malformed event on line {}", ix + 2),
+        }
+    }
+    Ok(Trace {
+        puzzle: puzzle.parse()?,
+        solver: solver.to_string(),
+        events,
+    })
+}
+
+/// The event of a JSON object written by [`to_json`]
+fn from_json(event: &Value) -> Option<Event> {
+    let depth = || Some(event["depth"].as_u64()? as usize);
+    let cell = || -> Option<Cell> {
+        let (row, col) = event["cell"].as_str()?.strip_prefix('r')?.split_once('c')?;
+        Some((
+            row.parse::<usize>().ok()?.checked_sub(1)?,
+            col.parse::<usize>().ok()?.checked_sub(1)?,
+        ))
+    };
+    let value = || NonZeroU16::new(event["value"].as_u64()?.try_into().ok()?);
+    let reason = || event["reason"].as_str()?.parse::<Reason>().ok();
+    Some(match event["event"].as_str()? {
+        "branch" => Event::Branch {
+            cell: cell()?,
+            depth: depth()?,
+        },
+        "place" => Event::Place {
+            cell: cell()?,
+            value: value()?,
+            reason: reason()?,
+            depth: depth()?,
+        },
+        "eliminate" => Event::Eliminate {
+            cell: cell()?,
+            value: value()?,
+            reason: reason()?,
+            depth: depth()?,
+        },
+        "dead-end" => Event::DeadEnd { depth: depth()? },
+        "restart" => Event::Restart,
+        _ => return None,
+    })
+}
+
+/// The JSON object of an event
+fn to_json(event: &Event) -> Value {
+    let cell = |(row, col): (usize, usize)| format!("r{}c{}", row + 1, col + 1);
+    match *event {
+        Event::Branch { cell: ix, depth } => {
+            json!({ "event": "branch", "cell": cell(ix), "depth": depth })
+        }
+        Event::Place {
+            cell: ix,
+            value,
+            reason,
+            depth,
+        } => json!({
+            "event": "place",
+            "cell": cell(ix),
+            "value": value.get(),
+            "reason": reason.to_string(),
+            "depth": depth,
+        }),
+        Event::Eliminate {
+            cell: ix,
+            value,
+            reason,
+            depth,
+        } => json!({
+            "event": "eliminate",
+            "cell": cell(ix),
+            "value": value.get(),
+            "reason": reason.to_string(),
+            "depth": depth,
+        }),
+        Event::DeadEnd { depth } => json!({ "event": "dead-end", "depth": depth }),
+        Event::Restart => json!({ "event": "restart" }),
+    }
+}
@@ -0,0 +1,337 @@
+//! Moving the values and lines of puzzles around, for `canonicalize`, `equivalent`, `transform`
+//! and `dedup`
+
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{BufWriter, Write},
+    ops::Range,
+    path::PathBuf,
+};
+
+use clap::{Args, ValueEnum};
+use nsudoku_solver::{canonical, Sudoku, SudokuError};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use rayon::prelude::*;
+
+use crate::{
+    read_puzzles, status::Status, FormatArgs, GridFormat, GridStyle, InputArgs, VariantArgs,
+};
+
+/// The clockwise rotations of `transform`
+#[derive(Debug, ValueEnum, Clone, Copy)]
+enum Rotation {
+    /// A quarter turn
+    #[value(name = "90")]
+    Quarter,
+    /// A half turn
+    #[value(name = "180")]
+    Half,
+    /// Three quarter turns
+    #[value(name = "270")]
+    ThreeQuarters,
+}
+
+/// The reflections of `transform`
+#[derive(Debug, ValueEnum, Clone, Copy)]
+enum Reflection {
+    /// Left to right
+    Horizontal,
+    /// Top to bottom
+    Vertical,
+}
+
+/// The relabeling of the values by `transform`
+#[derive(Debug, Clone)]
+enum Digits {
+    /// A random permutation
+    Random,
+    /// The new value of each value
+    Given(Vec<u16>),
+}
+
+fn parse_digits(s: &str) -> Result<Digits, String> {
+    if s.trim() == "random" {
+        return Ok(Digits::Random);
+    }
+    s.split(',')
+        .map(|value| {
+            value.trim().parse().map_err(|_| {
+                format!("invalid value `{value}`, expected `random` or a list like `2,1,3`")
+            })
+        })
+        .collect::<Result<_, _>>()
+        .map(Digits::Given)
+}
+
+/// Shuffle `lines` with random swaps
+fn shuffle_lines(
+    mut sudoku: Sudoku,
+    lines: Range<usize>,
+    rng: &mut impl Rng,
+    swap: impl Fn(&Sudoku, usize, usize) -> Result<Sudoku, SudokuError>,
+) -> Result<Sudoku, SudokuError> {
+    let lines: Vec<_> = lines.collect();
+    for ix in (1..lines.len()).rev() {
+        let other = rng.gen_range(0..=ix);
+        if other != ix {
+            sudoku = swap(&sudoku, lines[ix], lines[other])?;
+        }
+    }
+    Ok(sudoku)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn transform(
+    mut sudoku: Sudoku,
+    rotate: Option<Rotation>,
+    transpose: bool,
+    reflect: Option<Reflection>,
+    [swap_bands, swap_stacks, swap_rows, swap_cols]: [bool; 4],
+    digits: Option<&Digits>,
+    rng: &mut impl Rng,
+) -> Result<Sudoku, SudokuError> {
+    let boxes = sudoku.box_shape();
+    sudoku = match rotate {
+        Some(Rotation::Quarter) => sudoku.rotate90(),
+        Some(Rotation::Half) => sudoku.rotate180(),
+        Some(Rotation::ThreeQuarters) => sudoku.rotate180().rotate90(),
+        None => sudoku,
+    };
+    if transpose {
+        sudoku = sudoku.transpose();
+    }
+    sudoku = match reflect {
+        Some(Reflection::Horizontal) => sudoku.reflect_horizontal(),
+        Some(Reflection::Vertical) => sudoku.reflect_vertical(),
+        None => sudoku,
+    };
+    // The outputs don't record the shape of the boxes, they would be read back unturned
+    if sudoku.box_shape() != boxes {
+        return Err(SudokuError::InvalidTransformation(
+            "rectangular boxes can't be turned on their side, rotate by 180 degrees or reflect instead",
+        ));
+    }
+
+    let order = sudoku.order();
+    if swap_bands {
+        sudoku = shuffle_lines(sudoku, 0..order / boxes.rows, rng, Sudoku::swap_bands)?;
+    }
+    if swap_stacks {
+        sudoku = shuffle_lines(sudoku, 0..order / boxes.cols, rng, Sudoku::swap_stacks)?;
+    }
+    if swap_rows {
+        for band in (0..order).step_by(boxes.rows) {
+            let rows = band..band + boxes.rows;
+            sudoku = shuffle_lines(sudoku, rows, rng, Sudoku::swap_rows_in_band)?;
+        }
+    }
+    if swap_cols {
+        for stack in (0..order).step_by(boxes.cols) {
+            let cols = stack..stack + boxes.cols;
+            sudoku = shuffle_lines(sudoku, cols, rng, Sudoku::swap_cols_in_stack)?;
+        }
+    }
+
+    match digits {
+        Some(Digits::Random) => {
+            let mut values: Vec<_> = (1..=order as u16).collect();
+            values.shuffle(rng);
+            sudoku.permute_values(&values)
+        }
+        Some(Digits::Given(values)) => sudoku.permute_values(values),
+        None => Ok(sudoku),
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct CanonicalizeArgs {
+    #[command(flatten)]
+    input: InputArgs,
+    /// How to print the canonical puzzles
+    #[arg(long, value_enum, default_value_t = GridFormat::Line)]
+    format: GridFormat,
+    #[command(flatten)]
+    variant: VariantArgs,
+}
+
+impl CanonicalizeArgs {
+    /// Turn sudokus into the canonical form shared by every equivalent puzzle
+    pub fn run(self, style: GridStyle) -> color_eyre::Result<Status> {
+        let Self {
+            input,
+            format,
+            variant,
+        } = self;
+        let puzzles = input.read(&variant)?;
+
+        let forms = puzzles
+            .par_iter()
+            .map(|(_, puzzle)| canonical::canonical(puzzle))
+            .collect::<Result<Vec<_>, SudokuError>>()?;
+
+        let mut out = std::io::stdout().lock();
+        for form in forms {
+            format.write(&mut out, &form, style)?;
+        }
+        Ok(Status::Success)
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct EquivalentArgs {
+    /// The string representation of the first Sudoku
+    first: Sudoku,
+    /// The string representation of the second Sudoku
+    second: Sudoku,
+}
+
+impl EquivalentArgs {
+    /// Check whether two sudokus are the same puzzle with its values and lines moved around
+    pub fn run(self) -> color_eyre::Result<Status> {
+        let Self { first, second } = self;
+        match canonical::equivalence(&first, &second)? {
+            Some(transformation) => {
+                println!("The sudokus are equivalent, the second one is the first after:");
+                println!("{transformation}");
+            }
+            None => println!("The sudokus are not equivalent"),
+        }
+        Ok(Status::Success)
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct TransformArgs {
+    #[command(flatten)]
+    input: InputArgs,
+    /// Rotate the grid clockwise by this many degrees, only by 180 for rectangular boxes
+    #[arg(long, value_enum)]
+    rotate: Option<Rotation>,
+    /// Flip the grid over its main diagonal, only for square boxes unless rotated a quarter
+    /// turn too
+    #[arg(long)]
+    transpose: bool,
+    /// Flip the grid
+    #[arg(long, value_enum)]
+    reflect: Option<Reflection>,
+    /// Shuffle the bands of rows
+    #[arg(long)]
+    swap_bands: bool,
+    /// Shuffle the stacks of columns
+    #[arg(long)]
+    swap_stacks: bool,
+    /// Shuffle the rows inside every band
+    #[arg(long)]
+    swap_rows: bool,
+    /// Shuffle the columns inside every stack
+    #[arg(long)]
+    swap_cols: bool,
+    /// Relabel the values, `random` or the new value of each one like `9,8,7,6,5,4,3,2,1`
+    #[arg(long, value_parser = parse_digits)]
+    permute_digits: Option<Digits>,
+    /// The seed of the random swaps and digits
+    #[arg(long)]
+    seed: Option<u64>,
+    /// How to print the transformed puzzles
+    #[arg(long, value_enum, default_value_t = GridFormat::Line)]
+    format: GridFormat,
+    #[command(flatten)]
+    variant: VariantArgs,
+}
+
+impl TransformArgs {
+    /// Turn, flip and relabel sudokus into equivalent puzzles
+    pub fn run(self, style: GridStyle) -> color_eyre::Result<Status> {
+        let Self {
+            input,
+            rotate,
+            transpose,
+            reflect,
+            swap_bands,
+            swap_stacks,
+            swap_rows,
+            swap_cols,
+            permute_digits,
+            seed,
+            format,
+            variant,
+        } = self;
+        let puzzles = input.read(&variant)?;
+
+        let seed = seed.unwrap_or_else(rand::random);
+        let mut out = std::io::stdout().lock();
+        for (ix, puzzle) in puzzles {
+            let mut rng = StdRng::seed_from_u64(seed.wrapping_add(ix as u64));
+            let transformed = transform(
+                puzzle,
+                rotate,
+                transpose,
+                reflect,
+                [swap_bands, swap_stacks, swap_rows, swap_cols],
+                permute_digits.as_ref(),
+                &mut rng,
+            )?;
+            format.write(&mut out, &transformed, style)?;
+        }
+        Ok(Status::Success)
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct DedupArgs {
+    /// A file with one sudoku per line, `-` reads from stdin
+    #[arg(short, long)]
+    file: PathBuf,
+    #[command(flatten)]
+    input: FormatArgs,
+    /// How to print the puzzles kept
+    #[arg(long, value_enum, default_value_t = GridFormat::Line)]
+    format: GridFormat,
+    /// Write the puzzles kept to a file instead of stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    #[command(flatten)]
+    variant: VariantArgs,
+}
+
+impl DedupArgs {
+    /// Drop the sudokus equivalent to an earlier one, keeping the first of each
+    pub fn run(self, style: GridStyle) -> color_eyre::Result<Status> {
+        let Self {
+            file,
+            input,
+            format,
+            output,
+            variant,
+        } = self;
+        let puzzles = read_puzzles(None, Some(file), &input, &variant)?;
+
+        let forms = puzzles
+            .par_iter()
+            .map(|(_, puzzle)| Ok(canonical::canonical(puzzle)?.to_line()))
+            .collect::<Result<Vec<_>, SudokuError>>()?;
+
+        let mut out: Box<dyn Write> = if let Some(output) = output {
+            Box::new(BufWriter::new(File::create(output)?))
+        } else {
+            Box::new(std::io::stdout().lock())
+        };
+
+        let mut seen = HashSet::new();
+        for ((_, puzzle), form) in puzzles.iter().zip(forms) {
+            if seen.insert(form) {
+                format.write(&mut out, puzzle, style)?;
+            }
+        }
+        out.flush()?;
+
+        eprintln!(
+            "Kept {} of {} puzzles, removed {} duplicates",
+            seen.len(),
+            puzzles.len(),
+            puzzles.len() - seen.len()
+        );
+        Ok(Status::Success)
+    }
+}
@@ -0,0 +1,180 @@
+//! Cross-checking the solvers against each other, for `verify`
+//!
+//! Every puzzle is solved by every solver, and flagged when a solver answers with a grid that
+//! isn't a solution, or when one finds a solution while another says there is none. Different
+//! solutions are fine, both being valid means the puzzle has several.
+
+use std::fmt::Display;
+
+use clap::Args;
+use nsudoku_solver::{
+    solve::{Registry, SolveConfig, SolveError, Solver},
+    Sudoku,
+};
+use rayon::prelude::*;
+use tracing::warn;
+
+use crate::{status::Status, BudgetArgs, FileArgs, SearchArgs, SudokuSolver, VariantArgs};
+
+/// What a solver answered on a puzzle
+#[derive(Debug)]
+enum Answer {
+    Solution(Sudoku),
+    /// A grid that doesn't solve the puzzle
+    Wrong(Sudoku),
+    NoSolution,
+    /// It ran out of its budget, can't search puzzles this large, or needed to guess without
+    /// being able to
+    GaveUp,
+}
+
+impl Answer {
+    fn of(solver: &dyn Solver, puzzle: &Sudoku, config: &SolveConfig) -> Self {
+        match solver.solve_with(puzzle, config).0 {
+            Ok(solution) if solution.solves(puzzle) => Answer::Solution(solution),
+            Ok(solution) => Answer::Wrong(solution),
+            Err(SolveError::Aborted { .. } | SolveError::Unsupported(_)) => Answer::GaveUp,
+            Err(SolveError::Unsolved(_)) if !solver.guesses() => Answer::GaveUp,
+            Err(_) => Answer::NoSolution,
+        }
+    }
+}
+
+impl Display for Answer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Answer::Solution(solution) => write!(f, "{}", solution.to_line()),
+            Answer::Wrong(grid) => write!(f, "{}, which doesn't solve the puzzle", grid.to_line()),
+            Answer::NoSolution => write!(f, "no solution"),
+            Answer::GaveUp => write!(f, "gave up"),
+        }
+    }
+}
+
+/// The totals of a verification
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Verification {
+    pub puzzles: usize,
+    /// The puzzles the solvers disagree on
+    pub disagreements: usize,
+    /// The puzzles only one solver or none answered, which can't be checked
+    pub unchecked: usize,
+}
+
+/// Solve every puzzle with every solver, printing the answers on the puzzles they disagree on
+pub fn verify(
+    puzzles: &[(usize, Sudoku)],
+    solvers: &[&dyn Solver],
+    config: &SolveConfig,
+) -> Verification {
+    let answers: Vec<Vec<Answer>> = puzzles
+        .par_iter()
+        .map(|(_, puzzle)| {
+            solvers
+                .iter()
+                .map(|solver| Answer::of(*solver, puzzle, config))
+                .collect()
+        })
+        .collect();
+
+    let width = solvers
+        .iter()
+        .map(|solver| solver.name().len())
+        .max()
+        .unwrap_or_default();
+    let mut verification = Verification {
+        puzzles: puzzles.len(),
+        ..Default::default()
+    };
+    for ((line, _), answers) in puzzles.iter().zip(&answers) {
+        let answered = answers
+            .iter()
+            .filter(|answer| !matches!(answer, Answer::GaveUp))
+            .count();
+        let solved = answers
+            .iter()
+            .any(|answer| matches!(answer, Answer::Solution(_)));
+        let disagree = answers.iter().any(|answer| match answer {
+            Answer::Wrong(_) => true,
+            Answer::NoSolution => solved,
+            Answer::Solution(_) | Answer::GaveUp => false,
+        });
+
+        if disagree {
+            verification.disagreements += 1;
+            println!("Line {}:", line + 1);
+            for (solver, answer) in solvers.iter().zip(answers) {
+                println!("  {:<width$} {answer}", solver.name());
+            }
+        } else if answered < 2 {
+            verification.unchecked += 1;
+        }
+    }
+    verification
+}
+
+#[derive(Debug, Args)]
+pub struct VerifyArgs {
+    /// The solver strategies to check against each other
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_values_t = [SudokuSolver::SortedDfs, SudokuSolver::Dlx]
+    )]
+    solvers: Vec<SudokuSolver>,
+    #[command(flatten)]
+    search: SearchArgs,
+    #[command(flatten)]
+    input: FileArgs,
+    #[command(flatten)]
+    variant: VariantArgs,
+    #[command(flatten)]
+    budget: BudgetArgs,
+}
+
+impl VerifyArgs {
+    /// Solve sudokus with several solvers and report the puzzles they disagree on
+    pub fn run(self) -> color_eyre::Result<Status> {
+        let Self {
+            solvers,
+            search,
+            input,
+            variant,
+            budget,
+        } = self;
+        if solvers.len() < 2 {
+            color_eyre::eyre::bail!("--solvers takes at least two solvers to check");
+        }
+        let puzzles = input.read(&variant)?;
+        let registry = Registry::with_builtins(search.options());
+        let config = budget.config(&search, &solvers);
+        let strategies: Vec<_> = solvers
+            .iter()
+            .map(|solver| solver.solver(&registry))
+            .collect();
+
+        let verification = verify(&puzzles, &strategies, &config);
+        let names = strategies
+            .iter()
+            .map(|solver| solver.name())
+            .collect::<Vec<_>>()
+            .join(", ");
+        if verification.unchecked > 0 {
+            warn!(
+                "{} sudokus were answered by fewer than two solvers and couldn't be checked",
+                verification.unchecked
+            );
+        }
+        if verification.disagreements == 0 {
+            println!("{names} agree on all {} sudokus", verification.puzzles);
+            Ok(Status::Success)
+        } else {
+            println!(
+                "{names} disagree on {} of {} sudokus",
+                verification.disagreements, verification.puzzles
+            );
+            Ok(Status::Incorrect)
+        }
+    }
+}
@@ -0,0 +1,101 @@
+//! Drawing the board in place on stderr while a solver searches it
+//!
+//! The solver reports its steps through an [`Observer`], and a thread applies them to a
+//! [`Replay`] of the board, redrawing its pencil marks every [`FRAME`] at most so the terminal
+//! keeps up with searches placing millions of values.
+
+use std::{
+    io::{self, IsTerminal, Write},
+    sync::mpsc::{self, RecvTimeoutError},
+    time::{Duration, Instant},
+};
+
+use nsudoku_solver::{
+    solve::{with_observer, Event, Observer, Replay},
+    Sudoku,
+};
+use ratatui::crossterm::{
+    cursor::{MoveToColumn, MoveUp},
+    queue,
+    terminal::{self, Clear, ClearType},
+};
+
+/// How often the board is redrawn at most
+const FRAME: Duration = Duration::from_millis(50);
+
+/// Run `solve`, drawing the board of `puzzle` on stderr as the solvers it calls change it
+pub fn visualize<T>(puzzle: &Sudoku, solve: impl FnOnce() -> T) -> color_eyre::Result<T> {
+    if !io::stderr().is_terminal() {
+        color_eyre::eyre::bail!("--visualize needs a terminal");
+    }
+    // The board and the line describing the last event
+    let replay = Replay::new(puzzle)?;
+    let lines = replay.grid().to_string().lines().count() + 1;
+    let (_, rows) = terminal::size()?;
+    if lines > rows as usize {
+        color_eyre::eyre::bail!(
+            "the board doesn't fit in the terminal, it needs {lines} lines and there are {rows}"
+        );
+    }
+
+    let (sender, events) = mpsc::channel();
+    let observer = Observer::new(move |event| {
+        // The drawer only hangs up after failing, its error is reported below
+        let _ = sender.send(*event);
+    });
+    std::thread::scope(|scope| {
+        let drawer = scope.spawn(move || -> io::Result<()> {
+            let mut out = io::stderr().lock();
+            let mut replay = replay;
+            let (mut count, mut last) = (0, None);
+            let mut drawn = None::<Instant>;
+            loop {
+                match events.recv_timeout(FRAME) {
+                    Ok(event) => {
+                        replay.apply(&event);
+                        count += 1;
+                        last = Some(event);
+                    }
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+                if drawn.is_none_or(|drawn| drawn.elapsed() >= FRAME) {
+                    draw(&mut out, &replay, count, last, drawn.is_some())?;
+                    drawn = Some(Instant::now());
+                }
+            }
+            draw(&mut out, &replay, count, last, drawn.is_some())
+        });
+        let result = with_observer(&observer, solve);
+        // Hang up, so the drawer stops once it drew the last board
+        drop(observer);
+        drawer.join().expect("the drawer doesn't panic")?;
+        Ok(result)
+    })
+}
+
+/// Draw the board of `replay` and its last event, over the previous board if there is one
+fn draw(
+    out: &mut impl Write,
+    replay: &Replay,
+    count: usize,
+    last: Option<Event>,
+    redraw: bool,
+) -> io::Result<()> {
+    let board = replay.grid().to_string();
+    if redraw {
+        let lines = board.lines().count() + 1;
+        queue!(
+            out,
+            MoveUp(lines as u16),
+            MoveToColumn(0),
+            Clear(ClearType::FromCursorDown)
+        )?;
+    }
+    write!(out, "{board}")?;
+    match last {
+        Some(event) => writeln!(out, "{count} events, {event}")?,
+        None => writeln!(out, "givens")?,
+    }
+    out.flush()
+}
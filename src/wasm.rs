@@ -0,0 +1,83 @@
+//! JavaScript bindings for the solver, built with `wasm-bindgen`
+//!
+//! Puzzles go in and out as their single line representation, grids are accepted as well:
+//!
+//! ```js
+//! import init, { solve, hint, generate } from "./nsudoku_solver.js";
+//!
+//! await init();
+//! solve("1.34..12.1..4..1"); // "1234341221434321"
+//! hint("1.34..12.1..4..1"); // { technique: "hidden single", description: "r1c2=2 ...", ... }
+//! generate("hard");
+//! ```
+//!
+//! Invalid puzzles, puzzles without a solution and unknown difficulties throw an `Error`.
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    generate::{self, Symmetry},
+    rate::Difficulty,
+    solve::sorted_dfs,
+    techniques::{self, Deduction},
+    Sudoku,
+};
+
+/// A step found by [`hint`]
+#[derive(Serialize)]
+struct Hint {
+    technique: String,
+    description: String,
+    /// The cell filled by the step, like `r1c2`, unless it only removes candidates
+    cell: Option<String>,
+    value: Option<u16>,
+}
+
+/// Solve a puzzle, answering its solution
+#[wasm_bindgen]
+pub fn solve(puzzle: &str) -> Result<String, JsError> {
+    let puzzle = Sudoku::parse_lenient(puzzle)?;
+    Ok(sorted_dfs(puzzle).0?.to_line())
+}
+
+/// The next logical step towards the solution, `null` when the puzzle is solved or needs guessing
+#[wasm_bindgen]
+pub fn hint(puzzle: &str) -> Result<JsValue, JsError> {
+    let puzzle = Sudoku::parse_lenient(puzzle)?;
+    if puzzle.solved() {
+        return Ok(JsValue::NULL);
+    }
+    let Some(step) = techniques::hint(&puzzle)? else {
+        return Ok(JsValue::NULL);
+    };
+    let (cell, value) = match step.deduction {
+        Deduction::Place {
+            cell: (row, col),
+            value,
+        } => (Some(format!("r{}c{}", row + 1, col + 1)), Some(value.get())),
+        Deduction::Eliminate(_) => (None, None),
+    };
+    let hint = Hint {
+        technique: step.technique.to_string(),
+        description: step.to_string(),
+        cell,
+        value,
+    };
+    Ok(serde_wasm_bindgen::to_value(&hint)?)
+}
+
+/// Generate a 9x9 puzzle of `difficulty`: easy, medium, hard, expert or diabolical
+#[wasm_bindgen]
+pub fn generate(difficulty: &str) -> Result<String, JsError> {
+    let difficulty: Difficulty = difficulty.parse()?;
+    let rng = &mut rand::thread_rng();
+    let attempts = generate::ATTEMPTS;
+    generate::puzzle_of_difficulty(9, None, Symmetry::default(), difficulty, attempts, rng)?
+        .map(|(puzzle, _)| puzzle.to_line())
+        .ok_or_else(|| {
+            JsError::new(&format!(
+                "no {difficulty} puzzle found in {attempts} attempts"
+            ))
+        })
+}